@@ -1,4 +1,10 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use indexed_priority_queue::builder::IpqBuilder;
+use indexed_priority_queue::coalesce::MinCoalescingIpq;
+use indexed_priority_queue::dary::{MinAutoDaryIndexedPriorityQueue, MinDaryIndexedPriorityQueue};
+use indexed_priority_queue::ipq::IndexedPriorityQueue;
+use indexed_priority_queue::split::MinPayloadIndexedPriorityQueue;
+use indexed_priority_queue::tuning::{BulkTuning, MapLayout, RebuildThreshold};
 use indexed_priority_queue::MinIndexedPriorityQueue;
 use std::ops::Range;
 
@@ -10,12 +16,276 @@ fn ten_million_sequential_integers_benchmark(c: &mut Criterion) {
                 end: 10i32.pow(7),
             }
                 .rev()
-                .map(|i| i)
                 .collect::<Vec<i32>>();
-            MinIndexedPriorityQueue::from(black_box(&mut v));
+            black_box(MinIndexedPriorityQueue::from(black_box(&mut v)));
         });
     });
 }
 
-criterion_group!(benches, ten_million_sequential_integers_benchmark);
+fn large_payload_polling_benchmark(c: &mut Criterion) {
+    let n = 10u32.pow(4);
+    let mut group = c.benchmark_group("10K polls of 256 byte payloads");
+
+    group.bench_function("payload stored with its priority", |b| {
+        b.iter(|| {
+            let mut v = Range { start: 0, end: n }
+                .rev()
+                .map(|i| (i, [0u8; 256]))
+                .collect::<Vec<(u32, [u8; 256])>>();
+            let mut ipq = MinIndexedPriorityQueue::from(&mut v);
+            while let Some(entry) = ipq.poll_min_value() {
+                black_box(entry);
+            }
+        });
+    });
+
+    group.bench_function("payload split from its priority", |b| {
+        b.iter(|| {
+            let mut ipq = Range { start: 0, end: n }
+                .rev()
+                .map(|i| (i, [0u8; 256]))
+                .collect::<MinPayloadIndexedPriorityQueue<u32, [u8; 256]>>();
+            while let Some(entry) = ipq.poll_min() {
+                black_box(entry);
+            }
+        });
+    });
+
+    group.finish();
+}
+
+fn keyed_operation_overhead_benchmark(c: &mut Criterion) {
+    let n = 10usize.pow(4);
+    let mut group = c.benchmark_group("10K keyed operations");
+    let queue = || {
+        let values = Range { start: 0, end: n }.rev().collect::<Vec<usize>>();
+        IpqBuilder::new().values(values).build().unwrap()
+    };
+
+    group.bench_function("contains", |b| {
+        let ipq = queue();
+        b.iter(|| {
+            for k in 0..2 * n {
+                black_box(ipq.contains(black_box(k)));
+            }
+        });
+    });
+
+    group.bench_function("update to the same rank", |b| {
+        let mut ipq = queue();
+        b.iter(|| {
+            for k in 0..n {
+                black_box(ipq.update(k, n - k));
+            }
+        });
+    });
+
+    group.bench_function("delete then insert", |b| {
+        let mut ipq = queue();
+        b.iter(|| {
+            for k in 0..n {
+                let value = ipq.delete(k).unwrap();
+                ipq.insert(k, value);
+            }
+        });
+    });
+
+    group.finish();
+}
+
+fn map_layout_benchmark(c: &mut Criterion) {
+    let n = 10usize.pow(5);
+    let mut group = c.benchmark_group("100K keyed decreases and polls by map layout");
+
+    fn workload<Q: IndexedPriorityQueue<usize>>(ipq: &mut Q, n: usize) {
+        (0..n).for_each(|k| ipq.insert(k, 2 * n + k));
+        (0..n).step_by(3).for_each(|k| ipq.decrease(k, n - k));
+        while let Some(value) = ipq.poll_min_value() {
+            black_box(value);
+        }
+    }
+
+    group.bench_function("paged position map and inverse vector", |b| {
+        b.iter(|| workload(&mut IpqBuilder::new().build().unwrap(), n));
+    });
+
+    group.bench_function("interleaved slots", |b| {
+        let builder = || IpqBuilder::new().map_layout(MapLayout::Interleaved);
+        b.iter(|| workload(&mut builder().build().unwrap(), n));
+    });
+
+    group.finish();
+}
+
+fn sorted_construction_benchmark(c: &mut Criterion) {
+    let n = 10i32.pow(6);
+    let mut group = c.benchmark_group("1M ascending i32's construction");
+
+    group.bench_function("heapified from a vector", |b| {
+        b.iter(|| {
+            let mut v = Range { start: 0, end: n }.collect::<Vec<i32>>();
+            black_box(MinIndexedPriorityQueue::from(black_box(&mut v)));
+        });
+    });
+
+    group.bench_function("from_sorted_iter", |b| {
+        b.iter(|| {
+            black_box(MinIndexedPriorityQueue::from_sorted_iter(black_box(0..n)));
+        });
+    });
+
+    group.finish();
+}
+
+fn bulk_strategy_benchmark(c: &mut Criterion) {
+    let n = 10usize.pow(5);
+    let mut group = c.benchmark_group("bulk strategies on 100K scattered u64's");
+    let scattered = |k: usize| (k as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 16;
+    let strategies = [
+        ("sift", RebuildThreshold::Ratio(f64::INFINITY)),
+        ("rebuild", RebuildThreshold::Ratio(0.0)),
+    ];
+
+    for percent in [1, 10, 50, 90] {
+        let k = n * percent / 100;
+        for (name, threshold) in strategies {
+            let tuning = BulkTuning {
+                append: threshold,
+                remove: threshold,
+                decrease: threshold,
+            };
+            let queue = || {
+                let values = (0..n).map(scattered).collect::<Vec<u64>>();
+                IpqBuilder::new().values(values).bulk_tuning(tuning).build().unwrap()
+            };
+
+            group.bench_function(format!("append {}% by {}", percent, name), |b| {
+                b.iter_batched(
+                    || (queue(), (n..n + k).map(scattered).collect::<Vec<u64>>()),
+                    |(mut ipq, mut extra)| ipq.append(&mut extra),
+                    BatchSize::LargeInput,
+                );
+            });
+            group.bench_function(format!("remove_all {}% by {}", percent, name), |b| {
+                b.iter_batched(
+                    queue,
+                    |mut ipq| black_box(ipq.remove_all((0..n).filter(|k| k % 100 < percent))),
+                    BatchSize::LargeInput,
+                );
+            });
+            group.bench_function(format!("decrease_many {}% by {}", percent, name), |b| {
+                b.iter_batched(
+                    queue,
+                    |mut ipq| {
+                        let updates = (0..n).filter(|k| k % 100 < percent);
+                        black_box(ipq.decrease_many(updates.map(|k| (k, scattered(k) / 2))))
+                    },
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn coalesced_update_benchmark(c: &mut Criterion) {
+    let n = 10usize.pow(4);
+    let hot_keys = 64;
+    let mut group = c.benchmark_group("100K rescores of 64 hot keys among 10K, polled every 1K");
+    let score = |tick: usize| (tick as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 16;
+    let queue = || MinIndexedPriorityQueue::from_sorted_iter((0..n).map(|k| k as u64 + (1 << 50)));
+
+    group.bench_function("update", |b| {
+        b.iter_batched(
+            queue,
+            |mut ipq| {
+                for tick in 0..100_000 {
+                    ipq.update(tick % hot_keys, score(tick));
+                    if tick % 1_000 == 999 {
+                        black_box(ipq.peek_min_key_index());
+                    }
+                }
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.bench_function("coalesced update", |b| {
+        b.iter_batched(
+            || MinCoalescingIpq::new(queue()),
+            |mut ipq| {
+                for tick in 0..100_000 {
+                    ipq.update(tick % hot_keys, score(tick));
+                    if tick % 1_000 == 999 {
+                        black_box(ipq.peek_min());
+                    }
+                }
+            },
+            BatchSize::LargeInput,
+        );
+    });
+
+    group.finish();
+}
+
+fn dary_heap_benchmark(c: &mut Criterion) {
+    fn decrease_heavy<Q: IndexedPriorityQueue<u64>>(mut ipq: Q, n: usize) {
+        for step in 0..n * 8 {
+            let key_index = step.wrapping_mul(7919) % n;
+            if let Some(value) = ipq.value_of(key_index) {
+                ipq.decrease(key_index, value - value / 16);
+            }
+            if step % 8 == 7 {
+                black_box(ipq.poll_min_key_index());
+            }
+        }
+    }
+
+    fn poll_all<Q: IndexedPriorityQueue<u64>>(mut ipq: Q) {
+        while let Some(key_index) = ipq.poll_min_key_index() {
+            black_box(key_index);
+        }
+    }
+
+    let n = 10usize.pow(5);
+    let mut group = c.benchmark_group("d-ary heaps on 100K scattered u64's");
+    let scattered = || (0..n as u64).map(|k| k.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 16);
+
+    macro_rules! bench_arity {
+        ($d:literal) => {
+            let queue = || scattered().collect::<MinDaryIndexedPriorityQueue<u64, $d>>();
+            group.bench_function(concat!("decrease-heavy, D = ", $d), |b| {
+                b.iter_batched(queue, |ipq| decrease_heavy(ipq, n), BatchSize::LargeInput);
+            });
+            group.bench_function(concat!("poll all, D = ", $d), |b| {
+                b.iter_batched(queue, poll_all, BatchSize::LargeInput);
+            });
+        };
+    }
+    bench_arity!(2);
+    bench_arity!(4);
+    bench_arity!(8);
+
+    let auto_tuned = || MinAutoDaryIndexedPriorityQueue::auto_tuned_from(scattered().collect());
+    group.bench_function("decrease-heavy, auto-tuned", |b| {
+        b.iter_batched(auto_tuned, |ipq| decrease_heavy(ipq, n), BatchSize::LargeInput);
+    });
+    group.bench_function("poll all, auto-tuned", |b| {
+        b.iter_batched(auto_tuned, poll_all, BatchSize::LargeInput);
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    ten_million_sequential_integers_benchmark,
+    large_payload_polling_benchmark,
+    keyed_operation_overhead_benchmark,
+    map_layout_benchmark,
+    sorted_construction_benchmark,
+    bulk_strategy_benchmark,
+    coalesced_update_benchmark,
+    dary_heap_benchmark
+);
 criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Soaks a queue with random operations, printing its size and footprint at every check.
+//!
+//! `cargo run --release --example stress -- [operations] [key space] [seed]`
+
+use indexed_priority_queue::soak::{soak, SoakConfig};
+use std::env;
+use std::process;
+use std::time::Instant;
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let arg = |index: usize, default: u64| {
+        args.get(index).map_or(default, |arg| {
+            arg.parse().unwrap_or_else(|_| {
+                eprintln!("Expected a number; received: {}", arg);
+                process::exit(2);
+            })
+        })
+    };
+    let operations = arg(0, 50_000_000);
+    let config = SoakConfig {
+        operations,
+        key_space: arg(1, 1 << 16) as usize,
+        sample_every: (operations / 50).max(1),
+        seed: arg(2, 1),
+    };
+
+    let start = Instant::now();
+    let outcome = soak(&config, |sample| {
+        println!(
+            "{:>12} ops {:>8.1?} size {:>8} values {:>8} mapping {:>8} pages {:>4}",
+            sample.operation,
+            start.elapsed(),
+            sample.size,
+            sample.footprint.values_capacity,
+            sample.footprint.mapping_capacity,
+            sample.footprint.position_pages
+        );
+    });
+
+    if let Err(e) = outcome {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,405 @@
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of, MaybeUninit};
+use std::panic::{self, AssertUnwindSafe};
+
+/// Bumped whenever the layout of [`AbiIpq`] or [`AbiVTable`] changes.
+pub const ABI_VERSION: u32 = 3;
+
+/// Outcome of an [`AbiVTable`] function, `Ok` being `0`.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbiStatus {
+    Ok = 0,
+    KeyInUse = 1,
+    KeyTooLarge = 2,
+    KeyNotFound = 3,
+    Empty = 4,
+    InvalidRange = 5,
+    Incomparable = 6,
+    CapacityExceeded = 7,
+    /// A `PartialOrd` or `Clone` of the value type panicked; the panic was caught on the side
+    /// that built the queue instead of unwinding across the C ABI, and the queue may be left
+    /// out of order.
+    Panicked = 8,
+    /// The queue was built for another [`ABI_VERSION`] or value layout, see
+    /// [`AbiIpq::is_compatible`]; it was not called.
+    Incompatible = 9,
+    NoShards = 10,
+}
+
+impl From<Result<(), IpqError>> for AbiStatus {
+    fn from(result: Result<(), IpqError>) -> Self {
+        match result {
+            Ok(()) => AbiStatus::Ok,
+            Err(IpqError::KeyInUse(_)) => AbiStatus::KeyInUse,
+            Err(IpqError::KeyTooLarge { .. }) => AbiStatus::KeyTooLarge,
+            Err(IpqError::KeyNotFound(_)) => AbiStatus::KeyNotFound,
+            Err(IpqError::Empty) => AbiStatus::Empty,
+            Err(IpqError::InvalidRange { .. }) => AbiStatus::InvalidRange,
+            Err(IpqError::Incomparable(_)) => AbiStatus::Incomparable,
+            Err(IpqError::CapacityExceeded { .. }) => AbiStatus::CapacityExceeded,
+            Err(IpqError::NoShards) => AbiStatus::NoShards,
+        }
+    }
+}
+
+/// The operations of an [`AbiIpq`], as `extern "C"` functions of the binary that built it.
+///
+/// Each function takes the queue's opaque pointer first and returns an [`AbiStatus`], writing
+/// its results through the pointers that follow. Values travel by pointer and are copied,
+/// which is why the value type must be `Copy`; `value_size` and `value_align` let the other
+/// side check that both binaries agree on it. No function unwinds: a panic of the value
+/// type's `PartialOrd` or `Clone` is reported as [`AbiStatus::Panicked`].
+#[repr(C)]
+pub struct AbiVTable<T> {
+    pub abi_version: u32,
+    pub value_size: usize,
+    pub value_align: usize,
+    pub size: unsafe extern "C" fn(*const c_void, *mut usize) -> AbiStatus,
+    pub contains: unsafe extern "C" fn(*const c_void, usize, *mut bool) -> AbiStatus,
+    pub try_insert: unsafe extern "C" fn(*mut c_void, usize, *const T) -> AbiStatus,
+    /// Writes the removed value to its last argument, returning `KeyNotFound` for a missing
+    /// key.
+    pub delete: unsafe extern "C" fn(*mut c_void, usize, *mut T) -> AbiStatus,
+    /// Writes the key index and value ranked first, returning `Empty` for an empty queue.
+    pub peek_min: unsafe extern "C" fn(*const c_void, *mut usize, *mut T) -> AbiStatus,
+    /// Same as `peek_min`, removing the entry.
+    pub poll_min: unsafe extern "C" fn(*mut c_void, *mut usize, *mut T) -> AbiStatus,
+    pub drop: unsafe extern "C" fn(*mut c_void) -> AbiStatus,
+}
+
+/// An owned queue behind an opaque pointer and a [`AbiVTable`], for passing across
+/// dynamically loaded plugin boundaries.
+///
+/// [`OrderedIndexedPriorityQueue`] has no stable layout, so two binaries built separately, e.g.
+/// an application and a plugin opened with `libloading`, cannot share it. An `AbiIpq` is
+/// `#[repr(C)]` and only ever calls back into the code of the binary that created it, so either
+/// side may own, use and drop it, provided both agree on [`ABI_VERSION`] and the value type.
+/// Every method checks [`is_compatible`](Self::is_compatible) first and fails with
+/// [`AbiStatus::Incompatible`] otherwise; an incompatible queue is leaked rather than dropped
+/// through a vtable of unknown layout.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::abi::{AbiMinIpq, AbiStatus, ABI_VERSION};
+///
+/// // e.g. returned by a plugin's `extern "C" fn new_queue() -> AbiMinIpq<u64>`
+/// let mut timers = AbiMinIpq::<u64>::new();
+/// assert!(timers.is_compatible());
+/// assert_eq!(timers.vtable().abi_version, ABI_VERSION);
+///
+/// assert_eq!(timers.try_insert(3, 300), AbiStatus::Ok);
+/// assert_eq!(timers.try_insert(1, 100), AbiStatus::Ok);
+/// assert_eq!(timers.try_insert(1, 50), AbiStatus::KeyInUse);
+/// assert_eq!(timers.poll_min(), Ok(Some((1, 100))));
+/// assert_eq!(timers.delete(3), Ok(Some(300)));
+/// assert_eq!(timers.is_empty(), Ok(true));
+/// ```
+#[repr(C)]
+pub struct AbiIpq<T: 'static, O> {
+    queue: *mut c_void,
+    vtable: &'static AbiVTable<T>,
+    order: PhantomData<O>,
+}
+
+pub type AbiMinIpq<T> = AbiIpq<T, Min>;
+
+pub type AbiMaxIpq<T> = AbiIpq<T, Max>;
+
+impl<T, O> AbiIpq<T, O>
+where
+    T: Copy + PartialOrd + 'static,
+    O: Order + 'static,
+{
+    const VTABLE: AbiVTable<T> = AbiVTable {
+        abi_version: ABI_VERSION,
+        value_size: size_of::<T>(),
+        value_align: align_of::<T>(),
+        size: size::<T, O>,
+        contains: contains::<T, O>,
+        try_insert: try_insert::<T, O>,
+        delete: delete::<T, O>,
+        peek_min: peek_min::<T, O>,
+        poll_min: poll_min::<T, O>,
+        drop: drop_queue::<T, O>,
+    };
+
+    pub fn new() -> Self {
+        Self::from_queue(OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())))
+    }
+
+    /// Moves an owned queue behind the stable interface, keys and settings included.
+    pub fn from_queue(queue: OrderedIndexedPriorityQueue<'static, T, O>) -> Self {
+        Self {
+            queue: Box::into_raw(Box::new(queue)) as *mut c_void,
+            vtable: &Self::VTABLE,
+            order: PhantomData,
+        }
+    }
+}
+
+impl<T, O> Default for AbiIpq<T, O>
+where
+    T: Copy + PartialOrd + 'static,
+    O: Order + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: 'static, O> AbiIpq<T, O> {
+    pub fn vtable(&self) -> &AbiVTable<T> {
+        self.vtable
+    }
+
+    /// Returns `true` if the binary that built this queue uses this crate's [`ABI_VERSION`]
+    /// and the same size and alignment for `T`; the other methods refuse to call into the
+    /// vtable otherwise.
+    pub fn is_compatible(&self) -> bool {
+        self.vtable.abi_version == ABI_VERSION
+            && self.vtable.value_size == size_of::<T>()
+            && self.vtable.value_align == align_of::<T>()
+    }
+
+    pub fn size(&self) -> Result<usize, AbiStatus> {
+        self.check_compatible()?;
+        let mut size = 0;
+        match unsafe { (self.vtable.size)(self.queue, &mut size) } {
+            AbiStatus::Ok => Ok(size),
+            status => Err(status),
+        }
+    }
+
+    pub fn is_empty(&self) -> Result<bool, AbiStatus> {
+        Ok(self.size()? == 0)
+    }
+
+    pub fn contains(&self, key_index: usize) -> Result<bool, AbiStatus> {
+        self.check_compatible()?;
+        let mut contained = false;
+        match unsafe { (self.vtable.contains)(self.queue, key_index, &mut contained) } {
+            AbiStatus::Ok => Ok(contained),
+            status => Err(status),
+        }
+    }
+
+    pub fn try_insert(&mut self, key_index: usize, value: T) -> AbiStatus {
+        if !self.is_compatible() {
+            return AbiStatus::Incompatible;
+        }
+
+        unsafe { (self.vtable.try_insert)(self.queue, key_index, &value) }
+    }
+
+    pub fn delete(&mut self, key_index: usize) -> Result<Option<T>, AbiStatus> {
+        self.check_compatible()?;
+        let mut value = MaybeUninit::uninit();
+        match unsafe { (self.vtable.delete)(self.queue, key_index, value.as_mut_ptr()) } {
+            AbiStatus::Ok => Ok(Some(unsafe { value.assume_init() })),
+            AbiStatus::KeyNotFound => Ok(None),
+            status => Err(status),
+        }
+    }
+
+    pub fn peek_min(&self) -> Result<Option<(usize, T)>, AbiStatus> {
+        self.check_compatible()?;
+        let (mut key_index, mut value) = (0, MaybeUninit::uninit());
+        match unsafe { (self.vtable.peek_min)(self.queue, &mut key_index, value.as_mut_ptr()) } {
+            AbiStatus::Ok => Ok(Some((key_index, unsafe { value.assume_init() }))),
+            AbiStatus::Empty => Ok(None),
+            status => Err(status),
+        }
+    }
+
+    pub fn poll_min(&mut self) -> Result<Option<(usize, T)>, AbiStatus> {
+        self.check_compatible()?;
+        let (mut key_index, mut value) = (0, MaybeUninit::uninit());
+        match unsafe { (self.vtable.poll_min)(self.queue, &mut key_index, value.as_mut_ptr()) } {
+            AbiStatus::Ok => Ok(Some((key_index, unsafe { value.assume_init() }))),
+            AbiStatus::Empty => Ok(None),
+            status => Err(status),
+        }
+    }
+
+    fn check_compatible(&self) -> Result<(), AbiStatus> {
+        match self.is_compatible() {
+            true => Ok(()),
+            false => Err(AbiStatus::Incompatible),
+        }
+    }
+}
+
+impl<T: 'static, O> Drop for AbiIpq<T, O> {
+    fn drop(&mut self) {
+        if self.is_compatible() {
+            unsafe { (self.vtable.drop)(self.queue) };
+        }
+    }
+}
+
+type Queue<T, O> = OrderedIndexedPriorityQueue<'static, T, O>;
+
+unsafe fn queue_ref<'q, T: Clone + 'static, O>(queue: *const c_void) -> &'q Queue<T, O> {
+    &*(queue as *const Queue<T, O>)
+}
+
+unsafe fn queue_mut<'q, T: Clone + 'static, O>(queue: *mut c_void) -> &'q mut Queue<T, O> {
+    &mut *(queue as *mut Queue<T, O>)
+}
+
+/// Runs the body of a vtable function, reporting a panic as [`AbiStatus::Panicked`] since
+/// unwinding out of an `extern "C"` function aborts the process.
+fn catch_panic<F: FnOnce() -> AbiStatus>(body: F) -> AbiStatus {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(AbiStatus::Panicked)
+}
+
+unsafe extern "C" fn size<T: Copy + PartialOrd + 'static, O: Order>(
+    queue: *const c_void,
+    size: *mut usize,
+) -> AbiStatus {
+    catch_panic(|| {
+        *size = queue_ref::<T, O>(queue).size();
+        AbiStatus::Ok
+    })
+}
+
+unsafe extern "C" fn contains<T: Copy + PartialOrd + 'static, O: Order>(
+    queue: *const c_void,
+    key_index: usize,
+    contained: *mut bool,
+) -> AbiStatus {
+    catch_panic(|| {
+        *contained = queue_ref::<T, O>(queue).contains(key_index);
+        AbiStatus::Ok
+    })
+}
+
+unsafe extern "C" fn try_insert<T: Copy + PartialOrd + 'static, O: Order>(
+    queue: *mut c_void,
+    key_index: usize,
+    value: *const T,
+) -> AbiStatus {
+    catch_panic(|| queue_mut::<T, O>(queue).try_insert(key_index, *value).into())
+}
+
+unsafe extern "C" fn delete<T: Copy + PartialOrd + 'static, O: Order>(
+    queue: *mut c_void,
+    key_index: usize,
+    value: *mut T,
+) -> AbiStatus {
+    catch_panic(|| match queue_mut::<T, O>(queue).delete(key_index) {
+        Some(v) => write_out(v, value),
+        None => AbiStatus::KeyNotFound,
+    })
+}
+
+unsafe extern "C" fn peek_min<T: Copy + PartialOrd + 'static, O: Order>(
+    queue: *const c_void,
+    key_index: *mut usize,
+    value: *mut T,
+) -> AbiStatus {
+    catch_panic(|| {
+        let queue = queue_ref::<T, O>(queue);
+        match queue.peek_min_key_index().zip(queue.peek_min_value()) {
+            Some((k, v)) => {
+                *key_index = k;
+                write_out(v, value)
+            }
+            None => AbiStatus::Empty,
+        }
+    })
+}
+
+unsafe extern "C" fn poll_min<T: Copy + PartialOrd + 'static, O: Order>(
+    queue: *mut c_void,
+    key_index: *mut usize,
+    value: *mut T,
+) -> AbiStatus {
+    catch_panic(|| match queue_mut::<T, O>(queue).swap_remove_min() {
+        Some((k, v)) => {
+            *key_index = k;
+            write_out(v, value)
+        }
+        None => AbiStatus::Empty,
+    })
+}
+
+unsafe extern "C" fn drop_queue<T: Copy + PartialOrd + 'static, O: Order>(
+    queue: *mut c_void,
+) -> AbiStatus {
+    catch_panic(|| {
+        drop(Box::from_raw(queue as *mut Queue<T, O>));
+        AbiStatus::Ok
+    })
+}
+
+unsafe fn write_out<T>(value: T, out: *mut T) -> AbiStatus {
+    out.write(value);
+    AbiStatus::Ok
+}
+
+#[cfg(test)]
+mod abi_tests {
+    use crate::abi::{AbiMaxIpq, AbiMinIpq, AbiStatus, AbiVTable};
+    use crate::MaxIndexedPriorityQueue;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn abi_ipq_should_dispatch_every_operation_through_its_vtable() {
+        let mut values = vec![2.5, 9.0, 4.0];
+        let queue = MaxIndexedPriorityQueue::from_sorted_iter(values.drain(..));
+        let mut ipq = AbiMaxIpq::from_queue(queue);
+
+        assert!(ipq.is_compatible());
+        assert_eq!(ipq.size(), Ok(3));
+        assert_eq!(ipq.peek_min(), Ok(Some((1, 9.0))));
+        assert_eq!(ipq.try_insert(7, 12.0), AbiStatus::Ok);
+        assert_eq!(ipq.poll_min(), Ok(Some((7, 12.0))));
+        assert_eq!(ipq.delete(7), Ok(None));
+        assert_eq!(ipq.contains(0), Ok(true));
+        assert_eq!(ipq.contains(7), Ok(false));
+        assert_eq!(ipq.try_insert(8, f64::NAN), AbiStatus::Incomparable);
+        assert_eq!(ipq.vtable().value_size, 8);
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Touchy(u32);
+
+    impl PartialOrd for Touchy {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            assert!(self.0 != 13 && other.0 != 13, "unlucky value");
+            self.0.partial_cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn panics_of_the_value_type_should_be_reported_instead_of_unwinding() {
+        let mut ipq = AbiMinIpq::new();
+        assert_eq!(ipq.try_insert(0, Touchy(1)), AbiStatus::Ok);
+
+        assert_eq!(ipq.try_insert(1, Touchy(13)), AbiStatus::Panicked);
+        assert_eq!(ipq.poll_min(), Ok(Some((0, Touchy(1)))));
+    }
+
+    #[test]
+    fn incompatible_queues_should_be_refused_and_leaked() {
+        let mut ipq = AbiMinIpq::<u32>::new();
+        let vtable = ipq.vtable();
+        ipq.vtable = Box::leak(Box::new(AbiVTable {
+            abi_version: vtable.abi_version + 1,
+            ..*vtable
+        }));
+
+        assert!(!ipq.is_compatible());
+        assert_eq!(ipq.try_insert(0, 1), AbiStatus::Incompatible);
+        assert_eq!(ipq.size(), Err(AbiStatus::Incompatible));
+        assert_eq!(ipq.poll_min(), Err(AbiStatus::Incompatible));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::storage::Storage;
+use crate::MinIndexedPriorityQueue;
+
+/// Returns the length of each symbol's Huffman code, `0` for symbols of frequency `0`.
+///
+/// The two lightest subtrees are repeatedly polled and merged back in as one, ties going to
+/// the subtree built first, so equal inputs always yield equal lengths. A lone symbol still
+/// gets a one bit code.
+pub fn huffman_code_lengths(frequencies: &[u64]) -> Vec<usize> {
+    let symbols = frequencies.len();
+    let mut lengths = vec![0; symbols];
+    let mut subtrees: MinIndexedPriorityQueue<(u64, usize)> =
+        MinIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new()));
+    frequencies
+        .iter()
+        .enumerate()
+        .filter(|(_, &frequency)| frequency > 0)
+        .for_each(|(symbol, &frequency)| subtrees.insert(symbol, (frequency, symbol)));
+    if subtrees.size() == 1 {
+        lengths[subtrees.peek_min_key_index().unwrap()] = 1;
+        return lengths;
+    }
+
+    // Leaves are the nodes `0..symbols`, merged subtrees get the following ones.
+    let mut parents = vec![usize::MAX; symbols];
+    while subtrees.size() > 1 {
+        let (a_weight, a) = subtrees.poll_min_value().unwrap();
+        let (b_weight, b) = subtrees.poll_min_value().unwrap();
+        let merged = parents.len();
+        parents.push(usize::MAX);
+        parents[a] = merged;
+        parents[b] = merged;
+        subtrees.insert(merged, (a_weight.saturating_add(b_weight), merged));
+    }
+
+    // Parents always come after their children, so depths can be filled from the root down.
+    let mut depths = vec![0; parents.len()];
+    (0..parents.len()).rev().for_each(|node| {
+        if parents[node] != usize::MAX {
+            depths[node] = depths[parents[node]] + 1;
+        }
+    });
+    lengths
+        .iter_mut()
+        .zip(frequencies)
+        .enumerate()
+        .filter(|(_, (_, &frequency))| frequency > 0)
+        .for_each(|(symbol, (length, _))| *length = depths[symbol]);
+
+    lengths
+}
+
+/// Builds a Huffman code for `frequencies`, returning each symbol's code as bits, first bit
+/// first, or an empty code for symbols of frequency `0`.
+///
+/// The codes are canonical: they only depend on the lengths from [`huffman_code_lengths`],
+/// shorter codes and then lower symbols counting up from all zeros, so a decoder can rebuild
+/// them from the lengths alone.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::algorithms::huffman;
+///
+/// let codes = huffman(&[45, 13, 12, 16, 9, 5]);
+///
+/// assert_eq!(codes[0], vec![false]);
+/// assert_eq!(codes[5], vec![true, true, true, true]);
+/// assert_eq!(codes.iter().map(Vec::len).collect::<Vec<usize>>(), vec![1, 3, 3, 3, 4, 4]);
+/// ```
+pub fn huffman(frequencies: &[u64]) -> Vec<Vec<bool>> {
+    let lengths = huffman_code_lengths(frequencies);
+    let mut symbols = (0..lengths.len())
+        .filter(|&symbol| lengths[symbol] > 0)
+        .collect::<Vec<usize>>();
+    symbols.sort_by_key(|&symbol| (lengths[symbol], symbol));
+
+    let mut codes = vec![Vec::new(); lengths.len()];
+    let mut code: Vec<bool> = Vec::new();
+    for symbol in symbols {
+        if let Some(last_zero) = code.iter().rposition(|&bit| !bit) {
+            code.truncate(last_zero);
+            code.push(true);
+        }
+        code.resize(lengths[symbol], false);
+        codes[symbol] = code.clone();
+    }
+
+    codes
+}
+
+#[cfg(test)]
+mod algorithms_tests {
+    use crate::algorithms::{huffman, huffman_code_lengths};
+
+    fn weighted_length(frequencies: &[u64], lengths: &[usize]) -> u64 {
+        frequencies.iter().zip(lengths).map(|(&f, &l)| f * l as u64).sum()
+    }
+
+    /// Huffman's cost by merging the two lightest weights of a sorted vector, no heap involved.
+    fn reference_cost(frequencies: &[u64]) -> u64 {
+        let mut weights = frequencies.iter().copied().filter(|&f| f > 0).collect::<Vec<u64>>();
+        let mut cost = 0;
+        while weights.len() > 1 {
+            weights.sort_unstable_by(|a, b| b.cmp(a));
+            let merged = weights.pop().unwrap() + weights.pop().unwrap();
+            cost += merged;
+            weights.push(merged);
+        }
+
+        cost
+    }
+
+    #[test]
+    fn huffman_should_match_the_optimal_cost_on_random_frequencies() {
+        let mut state = 11u64;
+        for symbols in 1..60 {
+            let frequencies = (0..symbols)
+                .map(|_| {
+                    state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    (state >> 54) % 3 * (state >> 44) % 1_000
+                })
+                .collect::<Vec<u64>>();
+
+            let lengths = huffman_code_lengths(&frequencies);
+            let used = frequencies.iter().filter(|&&f| f > 0).count();
+            if used > 1 {
+                assert_eq!(weighted_length(&frequencies, &lengths), reference_cost(&frequencies));
+            }
+
+            let codes = huffman(&frequencies);
+            for (i, a) in codes.iter().enumerate().filter(|(_, a)| !a.is_empty()) {
+                assert_eq!(a.len(), lengths[i]);
+                assert!(codes
+                    .iter()
+                    .enumerate()
+                    .all(|(j, b)| i == j || b.is_empty() || !b.starts_with(a)));
+            }
+        }
+    }
+
+    #[test]
+    fn huffman_should_handle_degenerate_inputs() {
+        assert!(huffman(&[]).is_empty());
+        assert_eq!(huffman(&[0, 7, 0]), vec![vec![], vec![false], vec![]]);
+        assert_eq!(huffman_code_lengths(&[1, 1]), vec![1, 1]);
+        assert_eq!(huffman_code_lengths(&[u64::MAX, u64::MAX, 1]), vec![2, 1, 2]);
+    }
+}
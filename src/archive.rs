@@ -0,0 +1,217 @@
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// An owned copy of a queue's values and mappings that can be archived with
+/// [rkyv](https://docs.rs/rkyv).
+///
+/// The archived form, [`ArchivedIpqSnapshot`], answers the read-only queue queries directly
+/// from the serialized bytes, so a large snapshot can be memory-mapped and queried without
+/// deserializing it first.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::archive::{ArchivedIpqSnapshot, IpqSnapshot};
+/// use indexed_priority_queue::MinIndexedPriorityQueue;
+///
+/// let mut values = vec![9, 8, 0, 4];
+/// let ipq = MinIndexedPriorityQueue::from(&mut values);
+///
+/// let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&IpqSnapshot::from(&ipq)).unwrap();
+/// let archived = rkyv::access::<ArchivedIpqSnapshot<i32>, rkyv::rancor::Error>(&bytes).unwrap();
+///
+/// assert_eq!(archived.peek_min_key_index(), Some(2));
+/// assert_eq!(archived.peek_min_value().map(|v| v.to_native()), Some(0));
+/// assert!(archived.contains(3));
+/// ```
+#[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+pub struct IpqSnapshot<T> {
+    values: Vec<T>,
+    inverse_map: Vec<usize>,
+    sorted_key_indexes: Vec<usize>,
+    sorted_node_indexes: Vec<usize>,
+}
+
+impl<'q, 'a, T, O> From<&'q OrderedIndexedPriorityQueue<'a, T, O>> for IpqSnapshot<T>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn from(ipq: &'q OrderedIndexedPriorityQueue<'a, T, O>) -> Self {
+        let (sorted_key_indexes, sorted_node_indexes) = ipq
+            .maps
+            .iter()
+            .map(|(key_index, node_index)| (key_index.0, node_index.0))
+            .unzip();
+
+        Self {
+            values: ipq.values.to_vec(),
+            inverse_map: ipq.maps.keys_by_node(ipq.values.len()).map(|k| k.0).collect(),
+            sorted_key_indexes,
+            sorted_node_indexes,
+        }
+    }
+}
+
+impl<T> IpqSnapshot<T> {
+    /// Hands back the snapshotted `(key_index, value)` pairs, by ascending key index.
+    pub fn into_entries(self) -> Vec<(usize, T)> {
+        let mut entries = self
+            .inverse_map
+            .into_iter()
+            .zip(self.values)
+            .collect::<Vec<(usize, T)>>();
+        entries.sort_by_key(|(key_index, _)| *key_index);
+
+        entries
+    }
+}
+
+impl<T> ArchivedIpqSnapshot<T>
+where
+    T: Archive,
+{
+    pub fn size(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.node_index_of(key_index).is_some()
+    }
+
+    pub fn peek_min_key_index(&self) -> Option<usize> {
+        self.inverse_map.first().map(|k| k.to_native() as usize)
+    }
+
+    pub fn peek_min_value(&self) -> Option<&T::Archived> {
+        self.values.first()
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Option<&T::Archived> {
+        self.values.get(self.node_index_of(key_index)?)
+    }
+
+    /// Returns the node index holding `key_index` in the archived heap, found by binary search
+    /// over the archived keys.
+    ///
+    /// `rkyv` validates each archived vector on its own, so bytes that pass validation may
+    /// still hold key and node vectors of different lengths, or node indexes past the values;
+    /// such keys are reported as missing rather than panicking.
+    pub fn node_index_of(&self, key_index: usize) -> Option<usize> {
+        let i = self
+            .sorted_key_indexes
+            .binary_search_by_key(&key_index, |k| k.to_native() as usize)
+            .ok()?;
+        let node_index = self.sorted_node_indexes.get(i)?.to_native() as usize;
+
+        (node_index < self.values.len()).then(|| node_index)
+    }
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Copies the queue into an [`IpqSnapshot`], ready to be archived.
+    pub fn snapshot(&self) -> IpqSnapshot<T> {
+        IpqSnapshot::from(self)
+    }
+}
+
+#[cfg(test)]
+mod archive_tests {
+    use crate::archive::{ArchivedIpqSnapshot, IpqSnapshot};
+    use crate::MinIndexedPriorityQueue;
+    use rkyv::rancor::Error;
+
+    #[test]
+    fn archived_snapshot_should_answer_queries_like_the_live_queue() {
+        let mut values: Vec<i64> = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let bytes = rkyv::to_bytes::<Error>(&ipq.snapshot()).unwrap();
+        let archived = rkyv::access::<ArchivedIpqSnapshot<i64>, Error>(&bytes).unwrap();
+
+        assert_eq!(archived.size(), ipq.size());
+        assert_eq!(archived.peek_min_key_index(), ipq.peek_min_key_index());
+        assert_eq!(
+            archived.peek_min_value().map(|v| v.to_native()),
+            ipq.peek_min_value()
+        );
+        (0..ipq.size()).for_each(|k| {
+            assert!(archived.contains(k));
+            assert_eq!(archived.value_of(k).map(|v| v.to_native()), ipq.value_of(k));
+        });
+        assert!(!archived.contains(12));
+        assert_eq!(archived.node_index_of(11), Some(0));
+    }
+
+    #[test]
+    fn archived_snapshot_should_treat_inconsistent_mappings_as_missing_keys() {
+        let snapshot = IpqSnapshot {
+            values: vec![1i64, 2],
+            inverse_map: vec![0, 1],
+            sorted_key_indexes: vec![0, 1, 2],
+            sorted_node_indexes: vec![0, 7],
+        };
+
+        let bytes = rkyv::to_bytes::<Error>(&snapshot).unwrap();
+        let archived = rkyv::access::<ArchivedIpqSnapshot<i64>, Error>(&bytes).unwrap();
+
+        assert_eq!(archived.value_of(0).map(|v| v.to_native()), Some(1));
+        assert!(!archived.contains(1));
+        assert_eq!(archived.value_of(1), None);
+        assert!(!archived.contains(2));
+        assert_eq!(archived.node_index_of(2), None);
+    }
+
+    #[test]
+    fn snapshot_should_round_trip_through_deserialization() {
+        let mut values = vec![1u8, 2, 2, 2, 0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+        let snapshot = ipq.snapshot();
+
+        let bytes = rkyv::to_bytes::<Error>(&snapshot).unwrap();
+        let restored = rkyv::from_bytes::<IpqSnapshot<u8>, Error>(&bytes).unwrap();
+        assert_eq!(restored, snapshot);
+
+        assert_eq!(
+            restored.into_entries(),
+            vec![(0, 1), (1, 2), (2, 2), (3, 2), (4, 0)]
+        );
+    }
+
+    #[test]
+    fn archived_snapshot_should_look_up_sparse_keys() {
+        let mut values = vec![5u16, 3];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        ipq.insert(10_000_000, 1);
+        ipq.delete(0);
+
+        let bytes = rkyv::to_bytes::<Error>(&ipq.snapshot()).unwrap();
+        let archived = rkyv::access::<ArchivedIpqSnapshot<u16>, Error>(&bytes).unwrap();
+
+        assert!(!archived.contains(0));
+        assert_eq!(archived.peek_min_key_index(), Some(10_000_000));
+        assert_eq!(archived.value_of(1).map(|v| v.to_native()), Some(3));
+        assert_eq!(archived.value_of(10_000_000).map(|v| v.to_native()), Some(1));
+    }
+
+    #[test]
+    fn empty_archived_snapshot_should_have_no_minimum() {
+        let mut values: Vec<u32> = Vec::new();
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let bytes = rkyv::to_bytes::<Error>(&ipq.snapshot()).unwrap();
+        let archived = rkyv::access::<ArchivedIpqSnapshot<u32>, Error>(&bytes).unwrap();
+
+        assert!(archived.is_empty());
+        assert_eq!(archived.peek_min_key_index(), None);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+
+/// Priority given to the `attempt`-th retry of an entry, usually a delay or a due time that
+/// grows with the number of failures.
+///
+/// Closures `Fn(u32) -> T` are policies too.
+pub trait BackoffPolicy<T> {
+    fn delay(&self, attempt: u32) -> T;
+}
+
+impl<T, F> BackoffPolicy<T> for F
+where
+    F: Fn(u32) -> T,
+{
+    fn delay(&self, attempt: u32) -> T {
+        self(attempt)
+    }
+}
+
+/// `base + step · attempt`, capped at `max`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinearBackoff {
+    pub base: u64,
+    pub step: u64,
+    pub max: u64,
+}
+
+impl BackoffPolicy<u64> for LinearBackoff {
+    fn delay(&self, attempt: u32) -> u64 {
+        self.step
+            .saturating_mul(attempt as u64)
+            .saturating_add(self.base)
+            .min(self.max)
+    }
+}
+
+/// `base · factorᵃᵗᵗᵉᵐᵖᵗ`, capped at `max`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExponentialBackoff {
+    pub base: u64,
+    pub factor: u64,
+    pub max: u64,
+}
+
+impl BackoffPolicy<u64> for ExponentialBackoff {
+    fn delay(&self, attempt: u32) -> u64 {
+        self.factor
+            .saturating_pow(attempt)
+            .saturating_mul(self.base)
+            .min(self.max)
+    }
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Puts `key_index` back in the queue with the priority `policy` gives to its `attempt`-th
+    /// retry, returning the value it replaces if the key was still queued.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::backoff::ExponentialBackoff;
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let policy = ExponentialBackoff { base: 100, factor: 2, max: 1_000 };
+    /// let mut delays = vec![0, 250];
+    /// let mut retries = MinIndexedPriorityQueue::from(&mut delays);
+    ///
+    /// let failed = retries.poll_min_key_index().unwrap();
+    /// retries.requeue_with_backoff(failed, 2, &policy);
+    /// assert_eq!(retries.value_of(failed), Some(400));
+    /// assert_eq!(retries.poll_min_key_index(), Some(1));
+    /// ```
+    pub fn requeue_with_backoff<P>(
+        &mut self,
+        key_index: usize,
+        attempt: u32,
+        policy: &P,
+    ) -> Option<T>
+    where
+        P: BackoffPolicy<T>,
+    {
+        let priority = policy.delay(attempt);
+        if self.contains(key_index) {
+            return Some(self.update(key_index, priority));
+        }
+        self.insert(key_index, priority);
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod backoff_tests {
+    use crate::backoff::{BackoffPolicy, ExponentialBackoff, LinearBackoff};
+    use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue};
+
+    #[test]
+    fn policies_should_grow_and_saturate_at_their_cap() {
+        let linear = LinearBackoff { base: 5, step: 10, max: 40 };
+        let exponential = ExponentialBackoff { base: 3, factor: 10, max: u64::MAX };
+
+        assert_eq!((0..5).map(|a| linear.delay(a)).collect::<Vec<u64>>(), vec![5, 15, 25, 35, 40]);
+        assert_eq!(exponential.delay(2), 300);
+        assert_eq!(exponential.delay(64), u64::MAX);
+    }
+
+    #[test]
+    fn requeue_should_reprioritize_queued_keys_and_reinsert_polled_ones() {
+        let mut values = vec![1.0, 2.0];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        let policy = |attempt: u32| 2f64.powi(attempt as i32);
+
+        assert_eq!(ipq.requeue_with_backoff(1, 3, &policy), Some(2.0));
+        let polled = ipq.poll_min_key_index().unwrap();
+        assert_eq!(ipq.requeue_with_backoff(polled, 4, &policy), None);
+        assert_eq!(ipq.poll_min_value(), Some(8.0));
+        assert_eq!(ipq.poll_min_value(), Some(16.0));
+    }
+
+    #[test]
+    fn requeue_should_follow_the_queue_order() {
+        let mut values = vec![50u64, 60];
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+        ipq.requeue_with_backoff(1, 1, &LinearBackoff { base: 0, step: 10, max: 100 });
+
+        assert_eq!(ipq.poll_min_key_index(), Some(0));
+    }
+}
@@ -0,0 +1,315 @@
+use crate::index::{KeyIdx, NodeIdx};
+use crate::limit::{IpqError, DEFAULT_MAX_KEY_BOUND};
+use crate::order::{Min, Order};
+use crate::paged::PagedPositionMap;
+use crate::shrink::ShrinkPolicy;
+use crate::tuning::{BulkTuning, MapLayout};
+use crate::OrderedIndexedPriorityQueue;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+
+/// Reason an [`IpqBuilder`] refused to build a queue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    HeapInvariantViolated,
+    Key(IpqError),
+    LengthMismatch { values: usize, key_indexes: usize },
+    NextKeyIndexTooSmall { next_key_index: usize, greatest_key_index: usize },
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::HeapInvariantViolated => {
+                write!(f, "Values are not in heap order")
+            }
+            BuildError::Key(e) => e.fmt(f),
+            BuildError::LengthMismatch {
+                values,
+                key_indexes,
+            } => write!(
+                f,
+                "Got {} values but {} key indexes",
+                values, key_indexes
+            ),
+            BuildError::NextKeyIndexTooSmall {
+                next_key_index,
+                greatest_key_index,
+            } => write!(
+                f,
+                "Next key index {} does not exceed key index {}",
+                next_key_index, greatest_key_index
+            ),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+impl From<IpqError> for BuildError {
+    fn from(e: IpqError) -> Self {
+        BuildError::Key(e)
+    }
+}
+
+/// Checked construction of an owned queue from state that cannot be trusted, such as a file
+/// or a network message.
+///
+/// Every input is validated when [`build`](Self::build) is called: key indexes must be
+/// distinct and within the key space, there must be one per value, and the optional next key
+/// index must exceed all of them. Unless told the values are already in heap order, they are
+/// heapified in `O(n)`; when they are, that order is verified rather than assumed.
+///
+/// The queue built is binary; d-ary heaps of other arities are built by
+/// [`with_arity`](crate::dary::AutoDaryIndexedPriorityQueue::with_arity) instead.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::builder::{BuildError, IpqBuilder};
+/// use indexed_priority_queue::limit::IpqError;
+/// use indexed_priority_queue::order::Max;
+///
+/// let mut ipq = IpqBuilder::new()
+///     .entries(vec![(10, 'a'), (42, 'z'), (7, 'm')])
+///     .ordered::<Max>()
+///     .build()
+///     .unwrap();
+/// assert_eq!(ipq.poll_min_key_index(), Some(42));
+///
+/// let duplicate = IpqBuilder::<char>::new().entries(vec![(1, 'a'), (1, 'b')]).build();
+/// assert_eq!(duplicate.err(), Some(BuildError::Key(IpqError::KeyInUse(1))));
+/// ```
+pub struct IpqBuilder<T, O = Min> {
+    values: Vec<T>,
+    key_indexes: Option<Vec<usize>>,
+    next_key_index: Option<usize>,
+    heap_ordered: bool,
+    shrink_policy: ShrinkPolicy,
+    bulk_tuning: BulkTuning,
+    map_layout: MapLayout,
+    max_key_bound: usize,
+    order: PhantomData<O>,
+}
+
+impl<T> Default for IpqBuilder<T, Min>
+where
+    T: Clone + PartialOrd + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IpqBuilder<T, Min>
+where
+    T: Clone + PartialOrd + 'static,
+{
+    /// Starts an empty minimum queue; see [`ordered`](Self::ordered) for other orders.
+    pub fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            key_indexes: None,
+            next_key_index: None,
+            heap_ordered: false,
+            shrink_policy: ShrinkPolicy::default(),
+            bulk_tuning: BulkTuning::default(),
+            map_layout: MapLayout::default(),
+            max_key_bound: DEFAULT_MAX_KEY_BOUND,
+            order: PhantomData,
+        }
+    }
+}
+
+impl<T, O> IpqBuilder<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    /// Sets the values, stored under the key indexes `0..values.len()` unless
+    /// [`key_indexes`](Self::key_indexes) says otherwise.
+    pub fn values(mut self, values: Vec<T>) -> Self {
+        self.values = values;
+        self
+    }
+
+    /// Sets the key index of each value, by position.
+    pub fn key_indexes(mut self, key_indexes: Vec<usize>) -> Self {
+        self.key_indexes = Some(key_indexes);
+        self
+    }
+
+    /// Sets the values and their key indexes from `(key_index, value)` pairs.
+    pub fn entries<I>(self, entries: I) -> Self
+    where
+        I: IntoIterator<Item = (usize, T)>,
+    {
+        let (key_indexes, values) = entries.into_iter().unzip();
+
+        self.values(values).key_indexes(key_indexes)
+    }
+
+    /// Sets the key index [`push`](crate::ipq::IndexedPriorityQueue::push) uses next, which
+    /// defaults to one past the greatest key index.
+    pub fn next_key_index(mut self, next_key_index: usize) -> Self {
+        self.next_key_index = Some(next_key_index);
+        self
+    }
+
+    /// Declares the values already laid out in heap order, as nodes `0..n`; the build then
+    /// verifies that order instead of heapifying.
+    pub fn heap_ordered(mut self, heap_ordered: bool) -> Self {
+        self.heap_ordered = heap_ordered;
+        self
+    }
+
+    pub fn shrink_policy(mut self, shrink_policy: ShrinkPolicy) -> Self {
+        self.shrink_policy = shrink_policy;
+        self
+    }
+
+    pub fn bulk_tuning(mut self, bulk_tuning: BulkTuning) -> Self {
+        self.bulk_tuning = bulk_tuning;
+        self
+    }
+
+    pub fn map_layout(mut self, map_layout: MapLayout) -> Self {
+        self.map_layout = map_layout;
+        self
+    }
+
+    pub fn max_key_bound(mut self, max_key_bound: usize) -> Self {
+        self.max_key_bound = max_key_bound;
+        self
+    }
+
+    /// Switches the order the built queue ranks its values in.
+    pub fn ordered<P: Order>(self) -> IpqBuilder<T, P> {
+        IpqBuilder {
+            values: self.values,
+            key_indexes: self.key_indexes,
+            next_key_index: self.next_key_index,
+            heap_ordered: self.heap_ordered,
+            shrink_policy: self.shrink_policy,
+            bulk_tuning: self.bulk_tuning,
+            map_layout: self.map_layout,
+            max_key_bound: self.max_key_bound,
+            order: PhantomData,
+        }
+    }
+
+    pub fn build(self) -> Result<OrderedIndexedPriorityQueue<'static, T, O>, BuildError> {
+        let key_indexes = self
+            .key_indexes
+            .unwrap_or_else(|| (0..self.values.len()).collect());
+        if key_indexes.len() != self.values.len() {
+            return Err(BuildError::LengthMismatch {
+                values: self.values.len(),
+                key_indexes: key_indexes.len(),
+            });
+        }
+
+        if self.heap_ordered
+            && (1..self.values.len())
+                .any(|i| O::precedes(&self.values[i], &self.values[NodeIdx(i).parent().0]))
+        {
+            return Err(BuildError::HeapInvariantViolated);
+        }
+
+        let mut mapped = PagedPositionMap::default();
+        for (i, &key_index) in key_indexes.iter().enumerate() {
+            if key_index >= self.max_key_bound {
+                return Err(IpqError::KeyTooLarge {
+                    key_index,
+                    max_key_bound: self.max_key_bound,
+                }
+                .into());
+            }
+            if mapped.set(KeyIdx(key_index), NodeIdx(i)).is_some() {
+                return Err(IpqError::KeyInUse(key_index).into());
+            }
+        }
+        let greatest_key_index = mapped.key_bound().checked_sub(1);
+        if let (Some(next_key_index), Some(greatest_key_index)) =
+            (self.next_key_index, greatest_key_index)
+        {
+            if next_key_index <= greatest_key_index {
+                return Err(BuildError::NextKeyIndexTooSmall {
+                    next_key_index,
+                    greatest_key_index,
+                });
+            }
+        }
+
+        let mut ipq = OrderedIndexedPriorityQueue::try_from_entries(
+            key_indexes.into_iter().zip(self.values).collect(),
+        )?
+        .with_max_key_bound(self.max_key_bound)
+        .with_shrink_policy(self.shrink_policy)
+        .with_bulk_tuning(self.bulk_tuning)
+        .with_map_layout(self.map_layout);
+        ipq.maps.raise_key_bound(self.next_key_index.unwrap_or(0));
+
+        Ok(ipq)
+    }
+}
+
+#[cfg(test)]
+mod builder_tests {
+    use crate::builder::{BuildError, IpqBuilder};
+    use crate::limit::IpqError;
+    use crate::order::Max;
+    use crate::tuning::MapLayout;
+
+    #[test]
+    fn build_should_map_explicit_keys_and_heapify() {
+        let ipq = IpqBuilder::new()
+            .values(vec![3, 1, 2])
+            .key_indexes(vec![7, 100, 5])
+            .next_key_index(500)
+            .map_layout(MapLayout::Interleaved)
+            .build()
+            .unwrap();
+
+        assert_eq!(ipq.next_key_index(), 500);
+        assert_eq!(ipq.keys().collect::<Vec<usize>>(), vec![5, 7, 100]);
+        assert_eq!(ipq.map_layout(), MapLayout::Interleaved);
+        assert!(ipq.has_consistent_mappings());
+        assert!(ipq.satisfies_heap_invariant());
+    }
+
+    #[test]
+    fn build_should_reject_every_kind_of_inconsistent_input() {
+        let build = |builder: IpqBuilder<i32, Max>| builder.build().err();
+
+        assert_eq!(
+            build(IpqBuilder::new().values(vec![1, 2]).key_indexes(vec![0]).ordered()),
+            Some(BuildError::LengthMismatch { values: 2, key_indexes: 1 })
+        );
+        assert_eq!(
+            build(IpqBuilder::new().entries(vec![(9, 1)]).max_key_bound(9).ordered()),
+            Some(BuildError::Key(IpqError::KeyTooLarge { key_index: 9, max_key_bound: 9 }))
+        );
+        assert_eq!(
+            build(IpqBuilder::new().entries(vec![(4, 1)]).next_key_index(4).ordered()),
+            Some(BuildError::NextKeyIndexTooSmall { next_key_index: 4, greatest_key_index: 4 })
+        );
+        assert_eq!(
+            build(IpqBuilder::new().values(vec![1, 2]).heap_ordered(true).ordered()),
+            Some(BuildError::HeapInvariantViolated)
+        );
+    }
+
+    #[test]
+    fn heap_ordered_values_should_keep_their_layout() {
+        let ipq = IpqBuilder::new()
+            .values(vec![1, 4, 2, 8])
+            .heap_ordered(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(ipq.iter().map(|(_, &v)| v).collect::<Vec<i32>>(), vec![1, 4, 2, 8]);
+        assert_eq!(ipq.size(), 4);
+    }
+}
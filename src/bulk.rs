@@ -0,0 +1,607 @@
+use crate::cursor::PriorityCursor;
+use crate::ipq::IndexedBinaryHeap;
+use crate::order::Order;
+use crate::index::NodeIdx;
+use crate::OrderedIndexedPriorityQueue;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Removes every key of `keys` still in the queue and returns the removed
+    /// `(key_index, value)` pairs by ascending key index; unmapped and repeated keys are
+    /// ignored.
+    ///
+    /// Keys are deleted one by one in `O(k log n)`. Once [`BulkTuning::remove`] says so, the
+    /// survivors are compacted and heapified in a single `O(n)` rebuild instead, which by
+    /// default never happens.
+    ///
+    /// [`BulkTuning::remove`]: crate::tuning::BulkTuning::remove
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![5, 1, 4, 2, 3];
+    /// let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+    ///
+    /// assert_eq!(ipq.remove_all([3, 1, 9, 1]), vec![(1, 1), (3, 2)]);
+    /// assert_eq!(ipq.poll_min_value(), Some(3));
+    /// ```
+    pub fn remove_all<I>(&mut self, keys: I) -> Vec<(usize, T)>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut keys = keys
+            .into_iter()
+            .filter(|&key_index| self.contains(key_index))
+            .collect::<Vec<usize>>();
+        keys.sort_unstable();
+        keys.dedup();
+
+        if !self.bulk_tuning.remove.should_rebuild(keys.len(), self.size()) {
+            return keys
+                .into_iter()
+                .map(|key_index| (key_index, self.delete(key_index).unwrap()))
+                .collect();
+        }
+
+        let mut removed =
+            self.remove_and_rebuild(|key_index, _| keys.binary_search(&key_index).is_ok());
+        removed.sort_by_key(|(key_index, _)| *key_index);
+
+        removed
+    }
+
+    /// Lowers the value of each key of `updates` to the paired value if it is smaller, as
+    /// [`decrease`](IndexedPriorityQueue::decrease) would, and returns how many were lowered.
+    /// Keys not in the queue are ignored, and a key given twice keeps the smaller value.
+    ///
+    /// A few lowered entries are sifted one by one, while many are written in place and
+    /// heapified in a single `O(n)` pass, as
+    /// [`BulkTuning::decrease`](crate::tuning::BulkTuning::decrease) decides.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut distances = vec![9, 4, 7, 8];
+    /// let mut frontier = MinIndexedPriorityQueue::from(&mut distances);
+    ///
+    /// assert_eq!(frontier.decrease_many([(2, 3), (1, 5), (3, 1), (6, 0)]), 2);
+    /// assert_eq!(frontier.poll_min_key_index(), Some(3));
+    /// assert_eq!(frontier.poll_min_key_index(), Some(2));
+    /// ```
+    pub fn decrease_many<I>(&mut self, updates: I) -> usize
+    where
+        I: IntoIterator<Item = (usize, T)>,
+    {
+        let updates = updates
+            .into_iter()
+            .filter(|(key_index, value)| self.value_ref(*key_index).map_or(false, |v| value < v))
+            .collect::<Vec<(usize, T)>>();
+        let rebuild = self.bulk_tuning.decrease.should_rebuild(updates.len(), self.size());
+
+        let mut lowered = 0;
+        for (key_index, value) in updates {
+            let i = self.node_index(key_index);
+            if value < self.values[i.0] {
+                let rises = O::precedes(&value, &self.values[i.0]);
+                self.values[i.0] = value;
+                if !rebuild {
+                    self.sift(i, rises);
+                }
+                lowered += 1;
+            }
+        }
+
+        if rebuild {
+            self.fix_heap_invariant();
+        }
+        self.publish_min();
+
+        lowered
+    }
+
+    /// Removes every entry whose value satisfies `predicate` and returns the removed
+    /// `(key_index, value)` pairs by ascending key index.
+    ///
+    /// Every value is tested once and the survivors are heapified in a single `O(n)` pass.
+    /// When the predicate is monotone in the queue's order, prefer
+    /// [`remove_where_monotone`](Self::remove_where_monotone).
+    pub fn remove_where<F>(&mut self, mut predicate: F) -> Vec<(usize, T)>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut removed = self.remove_and_rebuild(|_, value| predicate(value));
+        removed.sort_by_key(|(key_index, _)| *key_index);
+
+        removed
+    }
+
+    /// Keeps only the entries for which `keep(key_index, &value)` holds, like `Vec::retain`.
+    ///
+    /// Every entry is tested once and the survivors are heapified in a single `O(n)` pass,
+    /// rather than sifting after each removal as a [`delete`](IndexedPriorityQueue::delete)
+    /// per entry would.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let mut sessions = OwnedMinIpq::from(vec![30, 5, 12, 48]);
+    /// sessions.retain(|key_index, &idle| key_index != 0 && idle < 40);
+    ///
+    /// assert_eq!(sessions.poll_min_value(), Some(5));
+    /// assert_eq!(sessions.poll_min_value(), Some(12));
+    /// assert_eq!(sessions.poll_min_value(), None);
+    /// ```
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(usize, &T) -> bool,
+    {
+        self.remove_and_rebuild(|key_index, value| !keep(key_index, value));
+    }
+
+    /// Removes every entry for which `predicate(key_index, &value)` holds and returns the
+    /// removed `(key_index, value)` pairs by ascending key index.
+    ///
+    /// Unlike `Vec::extract_if`, the entries are taken out before the iterator is returned,
+    /// so that the heap is rebuilt once, in `O(n)`, and dropping the iterator early still
+    /// removes them all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::OwnedMaxIpq;
+    ///
+    /// let mut jobs = OwnedMaxIpq::from(vec![3, 9, 4, 7]);
+    /// let urgent = jobs.extract_if(|_, &priority| priority > 5).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(urgent, vec![(1, 9), (3, 7)]);
+    /// assert_eq!(jobs.size(), 2);
+    /// ```
+    pub fn extract_if<F>(&mut self, predicate: F) -> std::vec::IntoIter<(usize, T)>
+    where
+        F: FnMut(usize, &T) -> bool,
+    {
+        let mut removed = self.remove_and_rebuild(predicate);
+        removed.sort_by_key(|(key_index, _)| *key_index);
+
+        removed.into_iter()
+    }
+
+    /// Same as [`remove_where`](Self::remove_where), for predicates that keep holding for every
+    /// value ranked after one satisfying them, such as `|v| *v > cutoff` in a minimum queue.
+    ///
+    /// As a node's descendants all rank after it, the first match found along a path takes its
+    /// whole subtree with it untested, and only the nodes ranked before the cutoff and their
+    /// direct children are ever evaluated. The matches are then handed to
+    /// [`remove_all`](Self::remove_all).
+    ///
+    /// A predicate breaking that promise leaves some matching entries in the queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut deadlines = vec![40, 10, 90, 20, 70];
+    /// let mut timers = MinIndexedPriorityQueue::from(&mut deadlines);
+    ///
+    /// let expired = timers.remove_where_monotone(|&deadline| deadline > 50);
+    /// assert_eq!(expired, vec![(2, 90), (4, 70)]);
+    /// assert_eq!(timers.size(), 3);
+    /// ```
+    pub fn remove_where_monotone<F>(&mut self, mut predicate: F) -> Vec<(usize, T)>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut keys = Vec::new();
+        let mut pending = Vec::new();
+        if !self.is_empty() {
+            pending.push((NodeIdx::ROOT, false));
+        }
+
+        while let Some((i, matched)) = pending.pop() {
+            let matched = matched || predicate(self.priority_sequenced_value(i));
+            if matched {
+                keys.push(self.maps.key_at(i).unwrap().0);
+            }
+            [0, 1]
+                .into_iter()
+                .filter_map(|nth| i.child(nth))
+                .filter(|&child| child.0 < self.size())
+                .for_each(|child| pending.push((child, matched)));
+        }
+
+        self.remove_all(keys)
+    }
+
+    /// Moves the `k` entries ranked first into a new queue that owns them, keys included,
+    /// leaving the rest of the entries in this one.
+    ///
+    /// The entries to move are found with a side frontier in `O(k log k)` without disturbing
+    /// the heap, then removed as [`remove_all`](Self::remove_all) would, their metadata and
+    /// holds moving along. The new queue shares this one's settings, key space included, and
+    /// next key index, so pushing to either never reuses a key of the other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut jobs = vec![7, 3, 9, 1, 5];
+    /// let mut backlog = MinIndexedPriorityQueue::from(&mut jobs);
+    ///
+    /// let mut batch = backlog.split_k_smallest(2);
+    /// assert_eq!(batch.poll_min_key_index(), Some(3));
+    /// assert_eq!(batch.poll_min_key_index(), Some(1));
+    /// assert_eq!(backlog.size(), 3);
+    /// assert_eq!(backlog.peek_min_value(), Some(5));
+    /// ```
+    pub fn split_k_smallest(&mut self, k: usize) -> Self {
+        let size = self.size();
+        let mut cursor = PriorityCursor::new(size);
+        let mut keys = Vec::with_capacity(k.min(size));
+        while keys.len() < k {
+            match cursor.next(size, |i, j| self.less(i, j)) {
+                Some(i) => keys.push(self.maps.key_at(i).unwrap().0),
+                None => break,
+            }
+        }
+
+        let metadata = keys
+            .iter()
+            .filter_map(|key_index| self.metadata.remove_entry(key_index))
+            .collect();
+        let held = keys
+            .iter()
+            .filter(|key_index| self.held.remove(key_index))
+            .copied()
+            .collect();
+        let map_layout = self.map_layout();
+        let mut batch = Self::from_entries_in(self.remove_all(keys), map_layout);
+        batch.maps.raise_key_bound(self.next_key_index());
+        batch.metadata = metadata;
+        batch.held = held;
+        batch.shrink_policy = self.shrink_policy;
+        batch.bulk_tuning = self.bulk_tuning;
+        batch.max_key_bound = self.max_key_bound;
+        batch.dedup = self.dedup;
+
+        batch
+    }
+
+    /// Moves up to `max` of the entries `victim` would poll last into this queue, for a worker
+    /// whose queue ran dry to take load off a busier one, and returns the
+    /// `(victim_key_index, new_key_index)` pair of each moved entry by ascending victim key.
+    ///
+    /// Stolen entries are pushed under this queue's own next key indexes, their metadata
+    /// moving along, since both queues have key spaces of their own. Held entries are never
+    /// stolen, and stealing stops early once this queue's key space is exhausted. The entries
+    /// to move are selected in `O(n)` over the victim's size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let (mut busy_jobs, mut idle_jobs) = (vec![5, 1, 9, 7], vec![3]);
+    /// let mut busy = MinIndexedPriorityQueue::from(&mut busy_jobs);
+    /// let mut idle = MinIndexedPriorityQueue::from(&mut idle_jobs);
+    ///
+    /// assert_eq!(idle.steal_batch(&mut busy, 2), vec![(2, 1), (3, 2)]);
+    /// assert_eq!(idle.value_of(1), Some(9));
+    /// assert_eq!(busy.size(), 2);
+    /// assert_eq!(busy.peek_min_value(), Some(1));
+    /// ```
+    pub fn steal_batch(
+        &mut self,
+        victim: &mut OrderedIndexedPriorityQueue<'_, T, O>,
+        max: usize,
+    ) -> Vec<(usize, usize)> {
+        let capacity = self.max_key_bound.saturating_sub(self.next_key_index());
+        let mut candidates = (0..victim.size())
+            .map(NodeIdx)
+            .filter(|&i| !victim.held.contains(&victim.maps.key_at(i).unwrap().0))
+            .collect::<Vec<NodeIdx>>();
+        let count = max.min(capacity).min(candidates.len());
+        if count == 0 {
+            return Vec::new();
+        }
+
+        let ranked_last_first = |&i: &NodeIdx, &j: &NodeIdx| {
+            if victim.less(j, i) {
+                Ordering::Less
+            } else if victim.less(i, j) {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        };
+        candidates.select_nth_unstable_by(count - 1, ranked_last_first);
+        let keys = candidates[..count]
+            .iter()
+            .map(|&i| victim.maps.key_at(i).unwrap().0)
+            .collect::<Vec<usize>>();
+        let mut metadata = keys
+            .iter()
+            .filter_map(|key_index| victim.metadata.remove_entry(key_index))
+            .collect::<HashMap<_, _>>();
+
+        victim
+            .remove_all(keys)
+            .into_iter()
+            .map(|(key_index, value)| {
+                let new_key_index = self.next_key_index();
+                self.insert(new_key_index, value);
+                if let Some(meta) = metadata.remove(&key_index) {
+                    self.metadata.insert(new_key_index, meta);
+                }
+
+                (key_index, new_key_index)
+            })
+            .collect()
+    }
+
+    /// Polls entries best-first for as long as `step` lets it, threading an accumulator
+    /// through, and returns the polled `(key_index, value)` pairs with the final accumulator.
+    ///
+    /// `step` sees the accumulator and the value about to be polled. `Continue` polls the
+    /// entry and carries on with the new accumulator, while `Break` leaves the entry in the
+    /// queue and stops, e.g. once the next item no longer fits a budget. Held entries are
+    /// skipped, as [`poll_min_value`](IndexedPriorityQueue::poll_min_value) would. Each polled
+    /// entry costs `O(log n)`, and nothing past the first refused one is looked at.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    /// use std::ops::ControlFlow;
+    ///
+    /// let mut weights = vec![4, 1, 7, 3, 2];
+    /// let mut items = MinIndexedPriorityQueue::from(&mut weights);
+    ///
+    /// let (taken, load) = items.select_until(0, |load, &weight| {
+    ///     if load + weight <= 8 {
+    ///         ControlFlow::Continue(load + weight)
+    ///     } else {
+    ///         ControlFlow::Break(load)
+    ///     }
+    /// });
+    /// assert_eq!(taken, vec![(1, 1), (4, 2), (3, 3)]);
+    /// assert_eq!(load, 6);
+    /// assert_eq!(items.peek_min_value(), Some(4));
+    /// ```
+    pub fn select_until<A, F>(&mut self, init: A, mut step: F) -> (Vec<(usize, T)>, A)
+    where
+        F: FnMut(A, &T) -> ControlFlow<A, A>,
+    {
+        let mut taken = Vec::new();
+        let mut acc = init;
+        while let Some(i) = self.first_unheld_node() {
+            match step(acc, self.priority_sequenced_value(i)) {
+                ControlFlow::Continue(next) => {
+                    acc = next;
+                    taken.extend(self.swap_remove(i));
+                }
+                ControlFlow::Break(last) => return (taken, last),
+            }
+        }
+
+        (taken, acc)
+    }
+}
+
+#[cfg(test)]
+mod bulk_tests {
+    use crate::tuning::{BulkTuning, MapLayout, RebuildThreshold};
+    use crate::MinIndexedPriorityQueue;
+    use std::ops::ControlFlow;
+
+    #[test]
+    fn remove_all_should_delete_few_keys_one_by_one() {
+        let mut values = (0..64).rev().collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.remove_all([63, 0]), vec![(0, 63), (63, 0)]);
+        assert_eq!(ipq.size(), 62);
+        assert!(ipq.has_consistent_mappings());
+        assert!(ipq.satisfies_heap_invariant());
+        assert_eq!(ipq.poll_min_value(), Some(1));
+    }
+
+    #[test]
+    fn remove_all_should_rebuild_once_for_large_key_sets() {
+        let mut values = (0..64).rev().collect::<Vec<i32>>();
+        let tuning = BulkTuning { remove: RebuildThreshold::SiftCost, ..BulkTuning::default() };
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values).with_bulk_tuning(tuning);
+
+        let removed = ipq.remove_all((0..64).filter(|k| k % 2 == 1));
+        assert_eq!(removed.len(), 32);
+        assert_eq!(removed[0], (1, 62));
+        assert_eq!(ipq.size(), 32);
+        assert!(ipq.has_consistent_mappings());
+        assert!(!ipq.contains(1));
+        assert_eq!(ipq.value_of(2), Some(61));
+
+        let mut polled = Vec::new();
+        while let Some(v) = ipq.poll_min_value() {
+            polled.push(v);
+        }
+        assert_eq!(polled, (0..64).rev().step_by(2).rev().collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn remove_where_should_test_every_value() {
+        let mut values = vec![1, 2, 3, 4, 5, 6];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.remove_where(|v| v % 2 == 0), vec![(1, 2), (3, 4), (5, 6)]);
+        assert!(ipq.satisfies_heap_invariant());
+        assert_eq!(ipq.poll_min_value(), Some(1));
+        assert_eq!(ipq.poll_min_value(), Some(3));
+    }
+
+    #[test]
+    fn retain_and_extract_if_should_see_keys_and_rebuild_once() {
+        let mut values = (0..100).map(|i| (i * 7919) % 211).collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let mut tested = 0;
+        ipq.retain(|key_index, _| {
+            tested += 1;
+            key_index % 3 != 0
+        });
+        assert_eq!(tested, 100);
+        assert_eq!(ipq.size(), 66);
+        assert!(ipq.satisfies_heap_invariant() && ipq.has_consistent_mappings());
+
+        let extracted = ipq.extract_if(|_, &value| value < 50).collect::<Vec<(usize, i32)>>();
+        assert!(extracted.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(extracted.iter().all(|&(key_index, value)| key_index % 3 != 0 && value < 50));
+        assert_eq!(ipq.size() + extracted.len(), 66);
+        assert!(ipq.peek_min_value().map_or(true, |min| min >= 50));
+        assert!(ipq.has_consistent_mappings());
+    }
+
+    #[test]
+    fn remove_where_monotone_should_skip_testing_pruned_subtrees() {
+        let mut values = (0..63).collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let mut tested = 0;
+        let removed = ipq.remove_where_monotone(|&v| {
+            tested += 1;
+            v > 2
+        });
+
+        assert_eq!(removed.len(), 60);
+        assert_eq!(tested, 7);
+        assert_eq!(ipq.size(), 3);
+        assert!(ipq.has_consistent_mappings());
+        assert_eq!(ipq.poll_min_value(), Some(0));
+    }
+
+    #[test]
+    fn split_k_smallest_should_keep_keys_and_order_on_both_sides() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        ipq.try_set_meta(11, 'a').unwrap();
+        ipq.try_set_meta(0, 'b').unwrap();
+
+        let mut batch = ipq.split_k_smallest(5);
+        assert_eq!(batch.meta::<char>(11), Ok(&'a'));
+        assert_eq!(ipq.meta::<char>(0), Ok(&'b'));
+        assert_eq!(batch.size(), 5);
+        assert_eq!(ipq.size(), 7);
+        assert_eq!(batch.value_of(11), Some(0));
+        assert_eq!(batch.next_key_index(), ipq.next_key_index());
+        assert!(!ipq.contains(11));
+
+        let mut polled = Vec::new();
+        while let Some(v) = batch.poll_min_value() {
+            polled.push(v);
+        }
+        assert_eq!(polled, vec![0, 1, 2, 2, 2]);
+        assert_eq!(ipq.poll_min_value(), Some(3));
+    }
+
+    #[test]
+    fn split_k_smallest_should_take_everything_when_k_exceeds_the_size() {
+        let mut values = vec![2, 1];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let batch = ipq.split_k_smallest(10);
+        assert_eq!(batch.size(), 2);
+        assert!(ipq.is_empty());
+        assert!(ipq.split_k_smallest(1).is_empty());
+    }
+
+    #[test]
+    fn split_k_smallest_should_keep_the_map_layout() {
+        let mut values = vec![4, 3, 2, 1];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        ipq.set_map_layout(MapLayout::Interleaved);
+
+        let batch = ipq.split_k_smallest(2);
+        assert_eq!(batch.map_layout(), MapLayout::Interleaved);
+        assert!(batch.has_consistent_mappings());
+        assert_eq!(batch.peek_min_key_index(), Some(3));
+    }
+
+    #[test]
+    fn steal_batch_should_take_the_entries_ranked_last_with_fresh_keys() {
+        let mut victim_values = (0..20).collect::<Vec<i32>>();
+        let mut victim = MinIndexedPriorityQueue::from(&mut victim_values);
+        victim.try_set_meta(19, "last").unwrap();
+        victim.hold(18);
+        let mut thief_values = vec![-1, -2];
+        let mut thief = MinIndexedPriorityQueue::from(&mut thief_values);
+
+        let moved = thief.steal_batch(&mut victim, 3);
+        assert_eq!(moved, vec![(16, 2), (17, 3), (19, 4)]);
+        assert_eq!(thief.meta::<&str>(4), Ok(&"last"));
+        assert_eq!(victim.size(), 17);
+        assert!(victim.contains(18));
+        assert!(victim.has_consistent_mappings());
+        assert!(thief.satisfies_heap_invariant());
+        assert_eq!(thief.value_of(3), Some(17));
+    }
+
+    #[test]
+    fn steal_batch_should_stop_at_the_thief_key_space() {
+        let mut victim_values = vec![1, 2, 3];
+        let mut victim = MinIndexedPriorityQueue::from(&mut victim_values);
+        let mut thief_values = vec![0];
+        let mut thief = MinIndexedPriorityQueue::from(&mut thief_values).with_max_key_bound(2);
+
+        assert_eq!(thief.steal_batch(&mut victim, 10), vec![(2, 1)]);
+        assert_eq!(thief.steal_batch(&mut victim, 10), Vec::new());
+        assert_eq!(victim.size(), 2);
+    }
+
+    #[test]
+    fn remove_all_should_ignore_unmapped_keys() {
+        let mut values = vec![1, 2];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.remove_all([5, 7]), Vec::new());
+        assert_eq!(ipq.size(), 2);
+    }
+
+    #[test]
+    fn select_until_should_drain_the_queue_when_never_stopped() {
+        let mut values = vec![3, 1, 2];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        ipq.hold(1);
+
+        let (taken, count) = ipq.select_until(0, |count, _| ControlFlow::Continue(count + 1));
+        assert_eq!(taken, vec![(2, 2), (0, 3)]);
+        assert_eq!(count, 2);
+        assert_eq!(ipq.size(), 1);
+        assert_eq!(ipq.select_until((), |_, _| ControlFlow::Break(())), (Vec::new(), ()));
+    }
+
+    #[test]
+    fn decrease_many_should_poll_the_same_whichever_strategy_it_picks() {
+        for threshold in [RebuildThreshold::Ratio(0.0), RebuildThreshold::Ratio(f64::INFINITY)] {
+            let tuning = BulkTuning { decrease: threshold, ..BulkTuning::default() };
+            let mut ipq = MinIndexedPriorityQueue::from_sorted_iter(0..32).with_bulk_tuning(tuning);
+
+            let updates = (0..32).rev().step_by(3).map(|k: i32| (k as usize, -k));
+            assert_eq!(ipq.decrease_many(updates.chain([(31, -40), (99, 0)])), 12);
+            assert_eq!(ipq.check_invariants(), Ok(()));
+            assert_eq!(ipq.poll_min_key_index(), Some(31));
+            assert_eq!(ipq.poll_min_key_index(), Some(28));
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::order::Min;
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// How an entry of a [`PriorityCache`] has been used, in ticks of the cache's access clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccessStats {
+    /// Number of [`get`](PriorityCache::get)s since the entry was inserted.
+    pub hits: u64,
+    pub inserted_at: u64,
+    pub last_access: u64,
+}
+
+/// Least recently used first.
+pub fn lru(stats: &AccessStats) -> u64 {
+    stats.last_access
+}
+
+/// Least frequently used first, the least recently used among those.
+pub fn lfu(stats: &AccessStats) -> (u64, u64) {
+    (stats.hits, stats.last_access)
+}
+
+struct Slot<K, V> {
+    key: K,
+    value: V,
+    stats: AccessStats,
+}
+
+/// A bounded map that evicts its lowest scored entry when full, and drops entries whose time
+/// to live ran out.
+///
+/// Every insertion and [`get`](Self::get) ticks an access clock and rescores the entry with
+/// the scorer, [`lru`] unless built otherwise, in `O(log n)`. Expiry deadlines live in a
+/// second queue, in whatever unit the caller measures time; nothing expires until
+/// [`purge_expired`](Self::purge_expired) is called with the current time.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::cache::PriorityCache;
+///
+/// let mut cache = PriorityCache::new(2);
+/// cache.insert("a", 1);
+/// cache.insert_with_ttl("b", 2, 100);
+/// cache.get(&"a");
+///
+/// assert_eq!(cache.insert("c", 3), Some(("b", 2)));
+/// assert_eq!(cache.purge_expired(1_000), vec![]);
+/// assert_eq!(cache.peek(&"a"), Some(&1));
+/// ```
+pub struct PriorityCache<K, V, S = u64, F = fn(&AccessStats) -> S>
+where
+    S: Clone + 'static,
+{
+    capacity: usize,
+    slots: HashMap<K, usize>,
+    entries: HashMap<usize, Slot<K, V>>,
+    scores: OrderedIndexedPriorityQueue<'static, S, Min>,
+    deadlines: OrderedIndexedPriorityQueue<'static, u64, Min>,
+    free_key_indexes: Vec<usize>,
+    clock: u64,
+    scorer: F,
+}
+
+impl<K, V> PriorityCache<K, V>
+where
+    K: Clone + Eq + Hash,
+{
+    /// A least recently used cache of `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_scorer(capacity, lru)
+    }
+}
+
+impl<K, V> PriorityCache<K, V, (u64, u64)>
+where
+    K: Clone + Eq + Hash,
+{
+    /// A least frequently used cache of `capacity` entries.
+    pub fn lfu(capacity: usize) -> Self {
+        Self::with_scorer(capacity, lfu)
+    }
+}
+
+impl<K, V, S, F> PriorityCache<K, V, S, F>
+where
+    K: Clone + Eq + Hash,
+    S: Clone + PartialOrd + 'static,
+    F: Fn(&AccessStats) -> S,
+{
+    /// A cache of `capacity` entries evicting the one `scorer` ranks lowest first.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is `0`.
+    pub fn with_scorer(capacity: usize, scorer: F) -> Self {
+        if capacity == 0 {
+            panic!("Cache capacity must be positive; received: 0");
+        }
+
+        Self {
+            capacity,
+            slots: HashMap::new(),
+            entries: HashMap::new(),
+            scores: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+            deadlines: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+            free_key_indexes: Vec::new(),
+            clock: 0,
+            scorer,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.slots.contains_key(key)
+    }
+
+    /// Returns `key`'s value without counting it as an access.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        Some(&self.entries[self.slots.get(key)?].value)
+    }
+
+    pub fn stats(&self, key: &K) -> Option<AccessStats> {
+        Some(self.entries[self.slots.get(key)?].stats)
+    }
+
+    /// Returns `key`'s value, counting a hit and rescoring the entry.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let key_index = *self.slots.get(key)?;
+        let now = self.tick();
+        let slot = self.entries.get_mut(&key_index)?;
+        slot.stats.hits += 1;
+        slot.stats.last_access = now;
+        self.scores.update(key_index, (self.scorer)(&slot.stats));
+
+        Some(&slot.value)
+    }
+
+    /// Stores `value` under `key` without expiry, returning the entry it pushed out: `key`'s
+    /// previous value, or the lowest scored entry if the cache was full.
+    pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.insert_entry(key, value, None)
+    }
+
+    /// Like [`insert`](Self::insert), the entry being dropped by the first
+    /// [`purge_expired`](Self::purge_expired) at or after `expires_at`.
+    pub fn insert_with_ttl(&mut self, key: K, value: V, expires_at: u64) -> Option<(K, V)> {
+        self.insert_entry(key, value, Some(expires_at))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let key_index = *self.slots.get(key)?;
+
+        Some(self.remove_key_index(key_index).1)
+    }
+
+    /// Evicts the lowest scored entry.
+    pub fn pop_lowest(&mut self) -> Option<(K, V)> {
+        let key_index = self.scores.peek_min_key_index()?;
+
+        Some(self.remove_key_index(key_index))
+    }
+
+    /// Removes every entry whose deadline is `now` or earlier, soonest deadline first.
+    pub fn purge_expired(&mut self, now: u64) -> Vec<(K, V)> {
+        let mut expired = Vec::new();
+        while let Some(key_index) = self.deadlines.peek_min_key_index() {
+            match self.deadlines.peek_min_value() {
+                Some(deadline) if deadline <= now => {
+                    expired.push(self.remove_key_index(key_index));
+                }
+                _ => break,
+            }
+        }
+
+        expired
+    }
+
+    fn insert_entry(&mut self, key: K, value: V, expires_at: Option<u64>) -> Option<(K, V)> {
+        let pushed_out = match self.slots.get(&key) {
+            Some(&key_index) => Some(self.remove_key_index(key_index)),
+            None if self.len() == self.capacity => self.pop_lowest(),
+            None => None,
+        };
+
+        let now = self.tick();
+        let stats = AccessStats {
+            hits: 0,
+            inserted_at: now,
+            last_access: now,
+        };
+        let key_index = self
+            .free_key_indexes
+            .pop()
+            .unwrap_or_else(|| self.scores.next_key_index());
+        self.scores.insert(key_index, (self.scorer)(&stats));
+        if let Some(expires_at) = expires_at {
+            self.deadlines.insert(key_index, expires_at);
+        }
+        self.slots.insert(key.clone(), key_index);
+        self.entries.insert(key_index, Slot { key, value, stats });
+
+        pushed_out
+    }
+
+    fn remove_key_index(&mut self, key_index: usize) -> (K, V) {
+        self.scores.delete(key_index);
+        self.deadlines.delete(key_index);
+        self.free_key_indexes.push(key_index);
+        let slot = self.entries.remove(&key_index).unwrap();
+        self.slots.remove(&slot.key);
+
+        (slot.key, slot.value)
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+
+        self.clock
+    }
+}
+
+#[cfg(test)]
+mod priority_cache_tests {
+    use crate::cache::PriorityCache;
+
+    #[test]
+    fn lru_should_evict_the_entry_left_unused_the_longest() {
+        let mut cache = PriorityCache::new(3);
+        (0..3).for_each(|key| {
+            cache.insert(key, key * 10);
+        });
+        cache.get(&0);
+        cache.get(&1);
+
+        assert_eq!(cache.insert(3, 30), Some((2, 20)));
+        assert_eq!(cache.insert(4, 40), Some((0, 0)));
+        assert_eq!(cache.insert(1, 11), Some((1, 10)));
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.pop_lowest(), Some((3, 30)));
+    }
+
+    #[test]
+    fn lfu_should_evict_the_entry_used_the_least() {
+        let mut cache = PriorityCache::lfu(2);
+        cache.insert('a', ());
+        cache.insert('b', ());
+        cache.get(&'a');
+        cache.get(&'a');
+        cache.get(&'b');
+
+        assert_eq!(cache.insert('c', ()), Some(('b', ())));
+        assert_eq!(cache.stats(&'a').unwrap().hits, 2);
+    }
+
+    #[test]
+    fn purge_expired_should_only_drop_entries_past_their_deadline() {
+        let mut cache = PriorityCache::new(4);
+        cache.insert_with_ttl("short", 1, 10);
+        cache.insert_with_ttl("long", 2, 50);
+        cache.insert("forever", 3);
+
+        assert_eq!(cache.purge_expired(9), vec![]);
+        assert_eq!(cache.purge_expired(10), vec![("short", 1)]);
+        assert_eq!(cache.remove(&"long"), Some(2));
+        assert_eq!(cache.purge_expired(u64::MAX), vec![]);
+        assert_eq!(cache.len(), 1);
+    }
+}
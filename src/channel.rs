@@ -0,0 +1,459 @@
+use crate::limit::DEFAULT_MAX_KEY_BOUND;
+use crate::order::{Max, Min, Order};
+use crate::split::PayloadIndexedPriorityQueue;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+/// Error returned by [`Sender::send`] once the receiver is gone or for a priority the buffer
+/// cannot order, handing the entry back.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SendError<T> {
+    Closed(T),
+    Incomparable(T),
+}
+
+impl<T> Display for SendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Closed(_) => write!(f, "Channel closed; the receiver was dropped"),
+            SendError::Incomparable(_) => write!(f, "{}", INCOMPARABLE_PRIORITY),
+        }
+    }
+}
+
+impl<T: Debug> Error for SendError<T> {}
+
+/// Error returned by [`Sender::try_send`], handing the entry back.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Closed(T),
+    Incomparable(T),
+}
+
+impl<T> Display for TrySendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "Channel full; no capacity left"),
+            TrySendError::Closed(_) => write!(f, "Channel closed; the receiver was dropped"),
+            TrySendError::Incomparable(_) => write!(f, "{}", INCOMPARABLE_PRIORITY),
+        }
+    }
+}
+
+impl<T: Debug> Error for TrySendError<T> {}
+
+const INCOMPARABLE_PRIORITY: &str =
+    "Priority is not comparable with itself, e.g. a NaN; wrap it in order::Total";
+
+/// Error returned by [`Receiver::try_recv`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Closed,
+}
+
+impl Display for TryRecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "Channel empty"),
+            TryRecvError::Closed => write!(f, "Channel closed; every sender was dropped"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+struct State<P, V, O>
+where
+    P: Clone + 'static,
+{
+    buffer: PayloadIndexedPriorityQueue<P, V, O>,
+    capacity: usize,
+    senders: usize,
+    receiver_alive: bool,
+    waiting_senders: Vec<Waker>,
+    waiting_receiver: Option<Waker>,
+}
+
+type Shared<P, V, O> = Arc<Mutex<State<P, V, O>>>;
+
+fn lock<P, V, O>(shared: &Shared<P, V, O>) -> MutexGuard<'_, State<P, V, O>>
+where
+    P: Clone + 'static,
+{
+    shared.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Creates a channel buffering up to `capacity` entries, a capacity of `0` counting as `1` and
+/// one past [`DEFAULT_MAX_KEY_BOUND`] as that bound, and handing them to the receiver by
+/// priority, the one ranked first by `O` first.
+///
+/// Like a bounded `tokio::sync::mpsc` channel, [`Sender::send`] waits for room instead of
+/// failing, so fast producers are slowed down to the pace of the consumer, while
+/// [`Sender::try_send`] reports a full buffer at once. The futures only rely on
+/// `std::task`, so any executor drives them.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::channel::{max_bounded, TrySendError};
+///
+/// let (tx, mut rx) = max_bounded(2);
+/// tx.try_send(1, "bulk").unwrap();
+/// tx.try_send(9, "urgent").unwrap();
+/// assert_eq!(tx.try_send(5, "normal"), Err(TrySendError::Full((5, "normal"))));
+///
+/// assert_eq!(rx.try_recv(), Ok((9, "urgent")));
+/// tx.try_send(5, "normal").unwrap();
+/// assert_eq!(rx.try_recv(), Ok((5, "normal")));
+/// ```
+pub fn bounded<P, V, O>(capacity: usize) -> (Sender<P, V, O>, Receiver<P, V, O>)
+where
+    P: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    let shared = Arc::new(Mutex::new(State {
+        buffer: PayloadIndexedPriorityQueue::new(),
+        capacity: capacity.clamp(1, DEFAULT_MAX_KEY_BOUND),
+        senders: 1,
+        receiver_alive: true,
+        waiting_senders: Vec::new(),
+        waiting_receiver: None,
+    }));
+
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+/// [`bounded`] channel receiving the smallest priority first.
+pub fn min_bounded<P, V>(capacity: usize) -> (Sender<P, V, Min>, Receiver<P, V, Min>)
+where
+    P: Clone + PartialOrd + 'static,
+{
+    bounded(capacity)
+}
+
+/// [`bounded`] channel receiving the greatest priority first.
+pub fn max_bounded<P, V>(capacity: usize) -> (Sender<P, V, Max>, Receiver<P, V, Max>)
+where
+    P: Clone + PartialOrd + 'static,
+{
+    bounded(capacity)
+}
+
+/// Sending half of a [`bounded`] channel; clone it for every producer.
+pub struct Sender<P, V, O>
+where
+    P: Clone + 'static,
+{
+    shared: Shared<P, V, O>,
+}
+
+impl<P, V, O> Clone for Sender<P, V, O>
+where
+    P: Clone + 'static,
+{
+    fn clone(&self) -> Self {
+        lock(&self.shared).senders += 1;
+
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<P, V, O> Drop for Sender<P, V, O>
+where
+    P: Clone + 'static,
+{
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared);
+        state.senders -= 1;
+        if state.senders == 0 {
+            if let Some(waker) = state.waiting_receiver.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<P, V, O> Sender<P, V, O>
+where
+    P: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    /// Buffers `value` with `priority`, waiting for room while the buffer is full. Fails only
+    /// once the receiver is dropped or for a priority not comparable with itself, such as a
+    /// NaN, handing the entry back.
+    pub fn send(&self, priority: P, value: V) -> SendFuture<'_, P, V, O> {
+        SendFuture {
+            sender: self,
+            entry: Some((priority, value)),
+        }
+    }
+
+    /// Same as [`send`](Self::send), failing at once instead of waiting when the buffer is
+    /// full.
+    pub fn try_send(&self, priority: P, value: V) -> Result<(), TrySendError<(P, V)>> {
+        let mut state = lock(&self.shared);
+        if !state.receiver_alive {
+            return Err(TrySendError::Closed((priority, value)));
+        }
+        if priority.partial_cmp(&priority).is_none() {
+            return Err(TrySendError::Incomparable((priority, value)));
+        }
+        if state.buffer.size() >= state.capacity {
+            return Err(TrySendError::Full((priority, value)));
+        }
+
+        state
+            .buffer
+            .try_push(priority, value)
+            .expect("the priority and the room left were both checked, so the buffer takes it");
+        if let Some(waker) = state.waiting_receiver.take() {
+            waker.wake();
+        }
+
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !lock(&self.shared).receiver_alive
+    }
+}
+
+/// Future returned by [`Sender::send`].
+pub struct SendFuture<'s, P, V, O>
+where
+    P: Clone + 'static,
+{
+    sender: &'s Sender<P, V, O>,
+    entry: Option<(P, V)>,
+}
+
+// The entry is only ever moved out, never pinned.
+impl<'s, P, V, O> Unpin for SendFuture<'s, P, V, O> where P: Clone + 'static {}
+
+impl<'s, P, V, O> Future for SendFuture<'s, P, V, O>
+where
+    P: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    type Output = Result<(), SendError<(P, V)>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let (priority, value) = match this.entry.take() {
+            Some(entry) => entry,
+            None => return Poll::Ready(Ok(())),
+        };
+
+        match this.sender.try_send(priority, value) {
+            Ok(()) => Poll::Ready(Ok(())),
+            Err(TrySendError::Closed(entry)) => Poll::Ready(Err(SendError::Closed(entry))),
+            Err(TrySendError::Incomparable(entry)) => {
+                Poll::Ready(Err(SendError::Incomparable(entry)))
+            }
+            Err(TrySendError::Full(entry)) => {
+                let mut state = lock(&this.sender.shared);
+                if state.buffer.size() < state.capacity || !state.receiver_alive {
+                    cx.waker().wake_by_ref();
+                } else if !state
+                    .waiting_senders
+                    .iter()
+                    .any(|waker| waker.will_wake(cx.waker()))
+                {
+                    state.waiting_senders.push(cx.waker().clone());
+                }
+                this.entry = Some(entry);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Receiving half of a [`bounded`] channel.
+pub struct Receiver<P, V, O>
+where
+    P: Clone + 'static,
+{
+    shared: Shared<P, V, O>,
+}
+
+impl<P, V, O> Drop for Receiver<P, V, O>
+where
+    P: Clone + 'static,
+{
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared);
+        state.receiver_alive = false;
+        state.waiting_senders.drain(..).for_each(Waker::wake);
+    }
+}
+
+impl<P, V, O> Receiver<P, V, O>
+where
+    P: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    /// Waits for an entry and returns the one ranked first, or `None` once the buffer is
+    /// empty and every sender is dropped.
+    pub fn recv(&mut self) -> RecvFuture<'_, P, V, O> {
+        RecvFuture { receiver: self }
+    }
+
+    /// Same as [`recv`](Self::recv), failing at once instead of waiting when the buffer is
+    /// empty.
+    pub fn try_recv(&mut self) -> Result<(P, V), TryRecvError> {
+        let mut state = lock(&self.shared);
+        match state.buffer.poll_min() {
+            Some((_, priority, value)) => {
+                state.waiting_senders.drain(..).for_each(Waker::wake);
+                Ok((priority, value))
+            }
+            None if state.senders == 0 => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Number of entries buffered.
+    pub fn len(&self) -> usize {
+        lock(&self.shared).buffer.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        lock(&self.shared).capacity
+    }
+}
+
+/// Future returned by [`Receiver::recv`].
+pub struct RecvFuture<'r, P, V, O>
+where
+    P: Clone + 'static,
+{
+    receiver: &'r mut Receiver<P, V, O>,
+}
+
+impl<'r, P, V, O> Future for RecvFuture<'r, P, V, O>
+where
+    P: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    type Output = Option<(P, V)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let receiver = &mut *self.get_mut().receiver;
+        match receiver.try_recv() {
+            Ok(entry) => Poll::Ready(Some(entry)),
+            Err(TryRecvError::Closed) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => {
+                let mut state = lock(&receiver.shared);
+                if !state.buffer.is_empty() || state.senders == 0 {
+                    cx.waker().wake_by_ref();
+                } else {
+                    state.waiting_receiver = Some(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod channel_tests {
+    use crate::channel::{
+        lock, max_bounded, min_bounded, SendError, TryRecvError, TrySendError,
+    };
+    use crate::testing::{block_on, thread_waker};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::Context;
+    use std::thread;
+
+    #[test]
+    fn send_should_wait_for_room_instead_of_failing() {
+        let (tx, mut rx) = min_bounded::<u32, usize>(4);
+        let producers = (0..3)
+            .map(|producer| {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    for i in 0..200 {
+                        block_on(tx.send((i % 7) as u32, producer * 200 + i)).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        drop(tx);
+
+        let mut received = Vec::new();
+        while let Some((priority, value)) = block_on(rx.recv()) {
+            assert!(rx.len() <= rx.capacity());
+            received.push((priority, value));
+        }
+        producers.into_iter().for_each(|producer| producer.join().unwrap());
+
+        received.sort_unstable_by_key(|&(_, value)| value);
+        assert!(received.iter().map(|&(_, value)| value).eq(0..600));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Closed));
+    }
+
+    #[test]
+    fn try_send_and_try_recv_should_report_full_empty_and_closed() {
+        let (tx, mut rx) = max_bounded(0);
+        assert_eq!(rx.capacity(), 1);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(tx.try_send(1, 'a'), Ok(()));
+        assert_eq!(tx.try_send(2, 'b'), Err(TrySendError::Full((2, 'b'))));
+
+        let waker = thread_waker();
+        let mut pending = Box::pin(tx.send(2, 'b'));
+        for _ in 0..3 {
+            let polled = Pin::as_mut(&mut pending).poll(&mut Context::from_waker(&waker));
+            assert!(polled.is_pending());
+        }
+        assert_eq!(lock(&tx.shared).waiting_senders.len(), 1);
+        drop(pending);
+
+        let blocked = thread::spawn(move || {
+            let sent = block_on(tx.send(3, 'c'));
+            (sent, block_on(tx.send(4, 'd')))
+        });
+        assert_eq!(block_on(rx.recv()), Some((1, 'a')));
+        assert_eq!(block_on(rx.recv()), Some((3, 'c')));
+        drop(rx);
+        assert_eq!(blocked.join().unwrap().0, Ok(()));
+
+        let (tx, rx) = min_bounded::<u8, u8>(1);
+        drop(rx);
+        assert!(tx.is_closed());
+        assert_eq!(block_on(tx.send(0, 0)), Err(SendError::Closed((0, 0))));
+    }
+
+    #[test]
+    fn priorities_not_comparable_with_themselves_should_be_handed_back() {
+        let (tx, mut rx) = min_bounded(1);
+        let refused = tx.try_send(f64::NAN, 'a');
+        assert!(matches!(refused, Err(TrySendError::Incomparable((_, 'a')))));
+        let refused = block_on(tx.send(f64::NAN, 'b'));
+        assert!(matches!(refused, Err(SendError::Incomparable((_, 'b')))));
+
+        assert_eq!(tx.try_send(1.0, 'c'), Ok(()));
+        let refused = tx.try_send(f64::NAN, 'd');
+        assert!(matches!(refused, Err(TrySendError::Incomparable((_, 'd')))));
+        assert_eq!(rx.try_recv(), Ok((1.0, 'c')));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+}
@@ -0,0 +1,295 @@
+use crate::index::KeyIdx;
+use crate::ipq::{IndexedBinaryHeap, IndexedPriorityQueue};
+use crate::limit::{BulkOutcome, IpqError};
+use crate::order::Order;
+use crate::{check_comparable, OrderedIndexedPriorityQueue};
+use std::ops::RangeBounds;
+use std::vec::IntoIter;
+
+/// The part of the queue's API that never panics.
+///
+/// The reads and removals mirror [`IndexedPriorityQueue`]'s, so they stay callable when the
+/// `deny-panics` feature is enabled without `panic-on-misuse`: the [`ipq`](crate::ipq) traits,
+/// whose insertions and updates panic on misuse, are then private to the crate and only these
+/// methods and the `try_*` ones remain. The guarantee covers the queue's keyed operations,
+/// not allocation failures or a panicking `PartialOrd` implementation.
+///
+/// Every `try_*` method reports its failure as an [`IpqError`]: those taking a key refuse
+/// a missing or out of range key, or a value not comparable with itself, and those that can
+/// also meet an empty queue report [`IpqError::Empty`]. [`KeyError`](crate::limit::KeyError)
+/// is only a former name of the same type, so no conversion is involved.
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    pub fn size(&self) -> usize {
+        IndexedBinaryHeap::size(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        IndexedBinaryHeap::is_empty(self)
+    }
+
+    pub fn contains(&self, key_index: impl Into<KeyIdx>) -> bool {
+        IndexedPriorityQueue::contains(self, key_index)
+    }
+
+    pub fn value_of(&self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        IndexedPriorityQueue::value_of(self, key_index)
+    }
+
+    pub fn peek_min_key_index(&self) -> Option<usize> {
+        IndexedPriorityQueue::peek_min_key_index(self)
+    }
+
+    pub fn peek_min_value(&self) -> Option<T> {
+        IndexedPriorityQueue::peek_min_value(self)
+    }
+
+    pub fn poll_min_key_index(&mut self) -> Option<usize> {
+        IndexedPriorityQueue::poll_min_key_index(self)
+    }
+
+    pub fn poll_min_value(&mut self) -> Option<T> {
+        IndexedPriorityQueue::poll_min_value(self)
+    }
+
+    /// Removes and returns the key index and value ranked first, or `None` if the queue is
+    /// empty.
+    pub fn poll_min(&mut self) -> Option<(usize, T)> {
+        self.swap_remove_min()
+    }
+
+    pub fn delete(&mut self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        IndexedPriorityQueue::delete(self, key_index)
+    }
+
+    /// Same as [`update`](IndexedPriorityQueue::update), returning an error instead of
+    /// panicking when `key_index` is not in the queue or `value` is not comparable with itself.
+    pub fn try_update(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<T, IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        self.check_used_key(key_index)?;
+        check_comparable(key_index, &value)?;
+
+        Ok(IndexedPriorityQueue::update(self, key_index, value))
+    }
+
+    /// Same as [`decrease`](IndexedPriorityQueue::decrease), returning an error instead of
+    /// panicking when `key_index` is not in the queue.
+    pub fn try_decrease(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<(), IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        self.check_used_key(key_index)?;
+        IndexedPriorityQueue::decrease(self, key_index, value);
+
+        Ok(())
+    }
+
+    /// Same as [`increase`](IndexedPriorityQueue::increase), returning an error instead of
+    /// panicking when `key_index` is not in the queue.
+    pub fn try_increase(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<(), IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        self.check_used_key(key_index)?;
+        IndexedPriorityQueue::increase(self, key_index, value);
+
+        Ok(())
+    }
+
+    /// Same as [`delete`](Self::delete), reporting a missing key as an error.
+    pub fn try_delete(&mut self, key_index: impl Into<KeyIdx>) -> Result<T, IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        self.delete(key_index).ok_or(IpqError::KeyNotFound(key_index))
+    }
+
+    /// Returns the key index and value ranked first, or [`IpqError::Empty`].
+    pub fn try_peek_min(&self) -> Result<(usize, T), IpqError> {
+        let key_index = self.peek_min_key_index().ok_or(IpqError::Empty)?;
+
+        Ok((key_index, self.value_of(key_index).unwrap()))
+    }
+
+    /// Same as [`try_peek_min`](Self::try_peek_min), removing the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::limit::IpqError;
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// fn serve(queue: &mut OwnedMinIpq<u32>, key_index: usize) -> Result<u32, IpqError> {
+    ///     queue.try_insert(key_index, 7)?;
+    ///     let (_, first) = queue.try_poll_min()?;
+    ///     queue.try_delete(key_index)?;
+    ///     Ok(first)
+    /// }
+    ///
+    /// let mut queue = OwnedMinIpq::from(vec![3]);
+    /// assert_eq!(serve(&mut queue, 0), Err(IpqError::KeyInUse(0)));
+    /// assert_eq!(serve(&mut queue, 1), Ok(3));
+    /// assert_eq!(queue.try_poll_min(), Err(IpqError::Empty));
+    /// assert_eq!(queue.try_delete(1), Err(IpqError::KeyNotFound(1)));
+    /// ```
+    pub fn try_poll_min(&mut self) -> Result<(usize, T), IpqError> {
+        self.swap_remove_min().ok_or(IpqError::Empty)
+    }
+
+    /// Same as [`drain`](IndexedPriorityQueue::drain), returning [`IpqError::InvalidRange`]
+    /// and leaving the queue untouched when the range is reversed or reaches past
+    /// [`next_key_index`](Self::next_key_index).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::limit::IpqError;
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let mut ipq = OwnedMinIpq::from(vec![4, 1, 3]);
+    ///
+    /// let invalid = IpqError::InvalidRange { start: 2, end: 4, len: 3 };
+    /// assert_eq!(ipq.try_drain(2..=3).err(), Some(invalid));
+    /// assert_eq!(ipq.try_drain(1..).unwrap().collect::<Vec<i32>>(), vec![1, 3]);
+    /// ```
+    pub fn try_drain<R: RangeBounds<usize>>(
+        &mut self,
+        key_indexes: R,
+    ) -> Result<IntoIter<T>, IpqError> {
+        let range = self.try_key_range(key_indexes)?;
+
+        Ok(IndexedPriorityQueue::drain(self, range))
+    }
+
+    /// Inserts each of `entries` as [`try_insert`](Self::try_insert) would, going on past
+    /// the ones that fail, and reports which keys were inserted and why the others were not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::limit::IpqError;
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let mut ipq = OwnedMinIpq::from(vec![5]).with_max_key_bound(4);
+    /// let outcome = ipq.try_insert_all(vec![(2, 8), (0, 1), (9, 3), (3, 6)]);
+    ///
+    /// assert_eq!(outcome.succeeded, vec![2, 3]);
+    /// assert_eq!(
+    ///     outcome.failed,
+    ///     vec![IpqError::KeyInUse(0), IpqError::KeyTooLarge { key_index: 9, max_key_bound: 4 }]
+    /// );
+    /// assert_eq!(ipq.size(), 3);
+    /// ```
+    pub fn try_insert_all<I>(&mut self, entries: I) -> BulkOutcome
+    where
+        I: IntoIterator<Item = (usize, T)>,
+    {
+        let mut outcome = BulkOutcome::default();
+        for (key_index, value) in entries {
+            match self.try_insert(key_index, value) {
+                Ok(()) => outcome.succeeded.push(key_index),
+                Err(e) => outcome.failed.push(e),
+            }
+        }
+
+        outcome
+    }
+
+    /// Deletes each of `key_indexes` as [`try_delete`](Self::try_delete) would, going on past
+    /// the missing ones, and reports which keys were deleted and which were not found.
+    pub fn try_delete_all<I>(&mut self, key_indexes: I) -> (Vec<(usize, T)>, BulkOutcome)
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        let mut deleted = Vec::new();
+        let mut outcome = BulkOutcome::default();
+        for key_index in key_indexes {
+            match self.try_delete(key_index) {
+                Ok(value) => {
+                    deleted.push((key_index, value));
+                    outcome.succeeded.push(key_index);
+                }
+                Err(e) => outcome.failed.push(e),
+            }
+        }
+
+        (deleted, outcome)
+    }
+
+    fn check_used_key(&self, key_index: usize) -> Result<(), IpqError> {
+        if !self.contains(key_index) {
+            return Err(IpqError::KeyNotFound(key_index));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod checked_tests {
+    use crate::limit::IpqError;
+    use crate::MaxIndexedPriorityQueue;
+    use std::ops::Bound;
+
+    #[test]
+    fn try_methods_should_report_missing_keys_instead_of_panicking() {
+        let mut values = vec![3, 8];
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.try_update(0, 9), Ok(3));
+        assert_eq!(ipq.try_decrease(1, 1), Ok(()));
+        assert_eq!(ipq.try_increase(2, 1), Err(IpqError::KeyNotFound(2)));
+        assert_eq!(ipq.try_update(7, 1), Err(IpqError::KeyNotFound(7)));
+        assert_eq!(ipq.delete(5), None);
+        assert_eq!(ipq.poll_min_value(), Some(9));
+        assert_eq!(ipq.peek_min_key_index(), Some(1));
+    }
+
+    #[test]
+    fn ipq_errors_should_cover_key_errors_and_empty_queues() {
+        let mut ipq = MaxIndexedPriorityQueue::from_entries(vec![(4, 'x')]).with_max_key_bound(8);
+
+        assert_eq!(ipq.try_peek_min(), Ok((4, 'x')));
+        assert_eq!(ipq.try_insert(4, 'y'), Err(IpqError::KeyInUse(4)));
+        assert_eq!(
+            ipq.try_insert(8, 'y'),
+            Err(IpqError::KeyTooLarge { key_index: 8, max_key_bound: 8 })
+        );
+        assert_eq!(ipq.try_delete(4), Ok('x'));
+        assert_eq!(ipq.try_peek_min(), Err(IpqError::Empty));
+        assert_eq!(IpqError::KeyNotFound(4).to_string(), "Index does not exist; received: 4");
+    }
+
+    #[test]
+    fn try_drain_and_bulk_deletes_should_report_bad_ranges_and_missing_keys() {
+        let mut values = vec![3, 8, 5, 1];
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+        let (start, end) = (3, 2);
+
+        assert_eq!(
+            ipq.try_drain(start..end).err(),
+            Some(IpqError::InvalidRange { start: 3, end: 2, len: 4 })
+        );
+        assert_eq!(
+            ipq.try_drain((Bound::Excluded(usize::MAX), Bound::Unbounded)).err(),
+            Some(IpqError::InvalidRange { start: usize::MAX, end: 4, len: 4 })
+        );
+        assert_eq!(ipq.size(), 4);
+
+        let (deleted, outcome) = ipq.try_delete_all([1, 6, 1, 3]);
+        assert_eq!(deleted, vec![(1, 8), (3, 1)]);
+        assert_eq!(outcome.succeeded, vec![1, 3]);
+        assert_eq!(outcome.failed, vec![IpqError::KeyNotFound(6), IpqError::KeyNotFound(1)]);
+        assert!(!outcome.is_complete());
+        assert_eq!(ipq.try_drain(..).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn try_methods_should_refuse_values_not_comparable_with_themselves() {
+        let mut values = vec![2.0, 1.0];
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.try_insert(2, f64::NAN), Err(IpqError::Incomparable(2)));
+        assert_eq!(ipq.try_update(1, f64::NAN), Err(IpqError::Incomparable(1)));
+        assert_eq!(ipq.try_push(f64::NAN), Err(IpqError::Incomparable(2)));
+        assert_eq!(ipq.size(), 2);
+        assert_eq!(ipq.try_poll_min(), Ok((0, 2.0)));
+    }
+}
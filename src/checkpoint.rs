@@ -0,0 +1,152 @@
+use crate::limit::DEFAULT_MAX_KEY_BOUND;
+use crate::order::Order;
+use crate::{OrderedIndexedPriorityQueue, OwnedIpq};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+
+/// Serialized form of a queue: its values and the key index of each, in node order, and the
+/// next key index.
+#[derive(Serialize)]
+#[serde(rename = "IndexedPriorityQueue")]
+struct Checkpoint<'q, T> {
+    values: &'q [T],
+    key_indexes: Vec<usize>,
+    next_key_index: usize,
+}
+
+/// Same as [`Checkpoint`], where a missing `key_indexes` keys the values by position and a
+/// missing `next_key_index` follows the largest key index.
+#[derive(Deserialize)]
+#[serde(rename = "IndexedPriorityQueue")]
+struct Restored<T> {
+    values: Vec<T>,
+    #[serde(default)]
+    key_indexes: Option<Vec<usize>>,
+    #[serde(default)]
+    next_key_index: Option<usize>,
+}
+
+/// Writes the values, the key index of each and the next key index, so that a queue can be
+/// checkpointed, e.g. to disk, and restored with the same keys. Settings, holds, metadata and
+/// the observer are left out.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::OwnedMinIpq;
+///
+/// let mut jobs = OwnedMinIpq::new();
+/// jobs.insert(4, 40);
+/// jobs.insert(9, 10);
+///
+/// let checkpoint = serde_json::to_string(&jobs).unwrap();
+/// let mut restored: OwnedMinIpq<u32> = serde_json::from_str(&checkpoint).unwrap();
+/// assert_eq!(restored.poll_min_key_index(), Some(9));
+///
+/// let by_position: OwnedMinIpq<u32> = serde_json::from_str(r#"{"values":[3,1,2]}"#).unwrap();
+/// assert_eq!(by_position.peek_min_key_index(), Some(1));
+/// ```
+impl<'a, T, O> Serialize for OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd + Serialize,
+    O: Order,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Checkpoint {
+            values: &self.values,
+            key_indexes: self.iter().map(|(key_index, _)| key_index).collect(),
+            next_key_index: self.next_key_index(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Reads a queue written by its `Serialize` implementation, or from its values alone, keyed
+/// `0..n` by position.
+///
+/// The stored node order is not trusted: the heap is rebuilt in `O(n)`, so a checkpoint edited
+/// by hand or written by another order still restores a valid queue. Key indexes that repeat,
+/// reach the next key index or pass the [`DEFAULT_MAX_KEY_BOUND`] are rejected, as are values
+/// not comparable with themselves.
+impl<'de, T, O> Deserialize<'de> for OwnedIpq<T, O>
+where
+    T: Clone + PartialOrd + Deserialize<'de>,
+    O: Order,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let restored = Restored::<T>::deserialize(deserializer)?;
+        let key_indexes = match restored.key_indexes {
+            Some(key_indexes) if key_indexes.len() != restored.values.len() => {
+                return Err(D::Error::invalid_length(key_indexes.len(), &"one per value"));
+            }
+            Some(key_indexes) => key_indexes,
+            None => (0..restored.values.len()).collect(),
+        };
+
+        let mut seen = HashSet::with_capacity(key_indexes.len());
+        if let Some(&key_index) = key_indexes.iter().find(|&&k| !seen.insert(k)) {
+            return Err(D::Error::custom(format!("Key index {} repeats", key_index)));
+        }
+        if let Some(&key_index) = key_indexes.iter().find(|&&k| k >= DEFAULT_MAX_KEY_BOUND) {
+            return Err(D::Error::custom(format!(
+                "Key index {} is past the bound {}",
+                key_index, DEFAULT_MAX_KEY_BOUND
+            )));
+        }
+        let key_bound = key_indexes
+            .iter()
+            .max()
+            .and_then(|&max| max.checked_add(1))
+            .unwrap_or(0);
+        let next_key_index = restored.next_key_index.unwrap_or(key_bound);
+        if next_key_index < key_bound || next_key_index > DEFAULT_MAX_KEY_BOUND {
+            return Err(D::Error::custom(format!(
+                "Next key index {} is below a key index in use or past the bound {}",
+                next_key_index, DEFAULT_MAX_KEY_BOUND
+            )));
+        }
+
+        let entries = key_indexes.into_iter().zip(restored.values).collect();
+        let mut ipq = Self::try_from_entries(entries).map_err(D::Error::custom)?;
+        ipq.maps.raise_key_bound(next_key_index);
+
+        Ok(ipq)
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_tests {
+    use crate::{OwnedMaxIpq, OwnedMinIpq};
+
+    #[test]
+    fn a_restored_queue_should_keep_its_keys_and_poll_the_same() {
+        let mut ipq = (0..40).map(|i| i * 13 % 29).collect::<OwnedMaxIpq<i64>>();
+        ipq.delete(7);
+        ipq.update(12, 100);
+        ipq.poll_min_value();
+
+        let json = serde_json::to_string(&ipq).unwrap();
+        let mut restored: OwnedMaxIpq<i64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.next_key_index(), 40);
+        assert_eq!(restored.fingerprint(), ipq.fingerprint());
+        assert!(restored.drain_sorted().eq(ipq.drain_sorted()));
+
+        let reordered: OwnedMinIpq<i64> = serde_json::from_str(&json).unwrap();
+        assert!(reordered.satisfies_heap_invariant());
+        assert_eq!(reordered.value_of(12), None);
+    }
+
+    #[test]
+    fn inconsistent_checkpoints_should_be_rejected() {
+        let restore = |json: &str| serde_json::from_str::<OwnedMinIpq<u8>>(json).map(|_| ());
+
+        assert!(restore(r#"{"values":[1,2],"key_indexes":[0]}"#).is_err());
+        assert!(restore(r#"{"values":[1,2],"key_indexes":[3,3]}"#).is_err());
+        assert!(restore(r#"{"values":[1,2],"key_indexes":[3,4],"next_key_index":4}"#).is_err());
+        assert!(restore(r#"{"values":[],"next_key_index":5000000000}"#).is_err());
+        assert!(restore(r#"{"values":[1],"key_indexes":[18446744073709551615]}"#).is_err());
+        assert!(restore(r#"{"values":[1],"key_indexes":[1073741824]}"#).is_err());
+        assert!(restore(r#"{"values":[1,2],"key_indexes":[3,4],"next_key_index":9}"#).is_ok());
+    }
+}
@@ -0,0 +1,347 @@
+use crate::index::KeyIdx;
+use crate::limit::IpqError;
+use crate::maintain::{Budget, Meter};
+use crate::order::{Max, Min, Order};
+use crate::paged::{PAGE_BITS, PAGE_LEN, PAGE_MASK};
+use crate::shrink::ShrinkPolicy;
+use crate::{check_comparable, OrderedIndexedPriorityQueue};
+
+/// A queue whose [`update`](Self::update)s are held aside and only sifted in at the next
+/// structural operation, so that a key rescored many times in between costs a single sift.
+///
+/// Each key carries the version of the flush it was last marked dirty for, and the slot of
+/// its pending value: an update to a key already stamped with the current version only
+/// rewrites that value, in `O(1)` and without hashing. The stamps are paged like the queue's
+/// own position map, so updating a lone key in the millions allocates a single page.
+/// Peeking, polling, inserting, deleting or calling [`flush`](Self::flush) then repairs every
+/// dirty key in one pass and starts a new version, while [`maintain`](Self::maintain) sifts
+/// pending updates in a few at a time.
+/// Values read through [`value_of`](Self::value_of) are always the latest ones.
+///
+/// The wrapped queue's [`ShrinkPolicy`] is deferred too: removals only note that the index
+/// maps should shrink, and the reallocation is left to [`maintain`](Self::maintain), or to
+/// [`into_inner`](Self::into_inner) which hands the policy back.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::coalesce::MaxCoalescingIpq;
+/// use indexed_priority_queue::MaxIndexedPriorityQueue;
+///
+/// let mut scores = MaxCoalescingIpq::new(MaxIndexedPriorityQueue::from_sorted_iter([0; 4]));
+/// for tick in 0..1_000 {
+///     scores.try_update(tick % 4, tick).unwrap();
+/// }
+///
+/// assert_eq!(scores.pending_updates(), 4);
+/// assert_eq!(scores.poll_min(), Some((3, 999)));
+/// assert_eq!(scores.pending_updates(), 0);
+/// ```
+pub struct CoalescingIpq<'a, T, O>
+where
+    T: Clone,
+{
+    heap: OrderedIndexedPriorityQueue<'a, T, O>,
+    stamps: Vec<Option<StampPage>>,
+    version: u64,
+    dirty: Vec<(usize, T)>,
+    shrink_policy: ShrinkPolicy,
+}
+
+/// The `(version, slot)` stamps of [`PAGE_LEN`] consecutive keys.
+type StampPage = Box<[(u64, usize)]>;
+
+pub type MinCoalescingIpq<'a, T> = CoalescingIpq<'a, T, Min>;
+
+pub type MaxCoalescingIpq<'a, T> = CoalescingIpq<'a, T, Max>;
+
+impl<'a, T, O> CoalescingIpq<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    pub fn new(mut heap: OrderedIndexedPriorityQueue<'a, T, O>) -> Self {
+        let shrink_policy = heap.shrink_policy();
+        heap.set_shrink_policy(ShrinkPolicy::Never);
+
+        Self {
+            heap,
+            stamps: Vec::new(),
+            version: 1,
+            dirty: Vec::new(),
+            shrink_policy,
+        }
+    }
+
+    /// Flushes the pending updates and hands the underlying queue back, with its shrink policy
+    /// restored and applied.
+    pub fn into_inner(mut self) -> OrderedIndexedPriorityQueue<'a, T, O> {
+        self.flush();
+        self.heap.set_shrink_policy(self.shrink_policy);
+        self.heap
+    }
+
+    /// The underlying queue, with every pending update flushed. Its own shrink policy reads
+    /// [`ShrinkPolicy::Never`] while the coalescing queue defers it.
+    pub fn queue(&mut self) -> &OrderedIndexedPriorityQueue<'a, T, O> {
+        self.flush();
+        &self.heap
+    }
+
+    pub fn size(&self) -> usize {
+        self.heap.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.heap.contains(key_index)
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Option<&T> {
+        match self.stamp(key_index) {
+            Some((stamp, slot)) if stamp == self.version => Some(&self.dirty[slot].1),
+            _ => self.heap.value_ref(key_index),
+        }
+    }
+
+    /// Number of distinct keys updated since the last flush.
+    pub fn pending_updates(&self) -> usize {
+        self.dirty.len()
+    }
+
+    /// Returns `true` if removals left the index maps sparse enough for the deferred
+    /// [`ShrinkPolicy`] to shrink them at the next [`maintain`](Self::maintain).
+    pub fn pending_shrink(&self) -> bool {
+        self.shrink_policy
+            .should_shrink(self.heap.size(), self.heap.mapping_capacity())
+    }
+
+    /// Replaces `key_index`'s value without sifting it, returning the previous one, or `None`
+    /// if the key is not in the queue.
+    ///
+    /// # Panics
+    ///
+    /// If `value` is not comparable with itself, e.g. a NaN, see
+    /// [`try_update`](Self::try_update).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn update(&mut self, key_index: usize, value: T) -> Option<T> {
+        self.try_update(key_index, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`update`](Self::update), returning an error instead of panicking. A refused
+    /// value is never held aside, so it cannot break the heap order at the next flush.
+    pub fn try_update(&mut self, key_index: usize, value: T) -> Result<Option<T>, IpqError> {
+        let i = match self.heap.maps.node_of(KeyIdx(key_index)) {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+        check_comparable(key_index, &value)?;
+
+        let version = self.version;
+        let pending = self.dirty.len();
+        let (stamp, slot) = *self.stamp_mut(key_index);
+        if stamp == version {
+            return Ok(Some(std::mem::replace(&mut self.dirty[slot].1, value)));
+        }
+        *self.stamp_mut(key_index) = (version, pending);
+        self.dirty.push((key_index, value));
+
+        Ok(Some(self.heap.values[i.0].clone()))
+    }
+
+    /// Restores the heap order for every key updated since the last flush, sifting each of
+    /// them once however many times it was updated.
+    pub fn flush(&mut self) {
+        if self.dirty.is_empty() {
+            return;
+        }
+
+        let mut touched = Vec::with_capacity(self.dirty.len());
+        for (key_index, value) in self.dirty.drain(..) {
+            if let Some(i) = self.heap.maps.node_of(KeyIdx(key_index)) {
+                self.heap.values[i.0] = value;
+                touched.push(i);
+            }
+        }
+        self.heap.repair_nodes(&touched);
+        self.version = self.version.wrapping_add(1);
+    }
+
+    /// Sifts pending updates in one at a time, then shrinks the index maps if the deferred
+    /// [`ShrinkPolicy`] asks for it, until no work is left or `budget` is spent, and returns
+    /// `Ok(true)` once no work is left.
+    ///
+    /// The heap stays ordered between calls, so a frame loop can call this with whatever time
+    /// it has left and keep the pause of the next peek or poll short, rather than letting
+    /// that one [`flush`](Self::flush) every update at once.
+    ///
+    /// # Errors
+    ///
+    /// If a pending update can no longer be sifted in, which the checks of
+    /// [`try_update`](Self::try_update) and the flushes before every removal rule out; the
+    /// update is dropped and the remaining work left to the next call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::coalesce::MinCoalescingIpq;
+    /// use indexed_priority_queue::maintain::Budget;
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut agents = MinCoalescingIpq::new(MinIndexedPriorityQueue::from_sorted_iter(0..100));
+    /// for agent in 0..100 {
+    ///     agents.try_update(agent, 100 - agent).unwrap();
+    /// }
+    ///
+    /// assert_eq!(agents.maintain(Budget::Ops(60)), Ok(false));
+    /// assert_eq!(agents.pending_updates(), 40);
+    /// assert_eq!(agents.maintain(Budget::Ops(60)), Ok(true));
+    /// assert_eq!(agents.peek_min(), Some((99, &1)));
+    /// ```
+    pub fn maintain(&mut self, budget: Budget) -> Result<bool, IpqError> {
+        let mut meter = Meter::start(budget);
+        while !self.dirty.is_empty() && meter.spend() {
+            if let Some((key_index, value)) = self.dirty.pop() {
+                self.stamp_mut(key_index).0 = 0;
+                self.heap.try_update(key_index, value)?;
+            }
+        }
+        if self.dirty.is_empty() && self.pending_shrink() && meter.spend() {
+            self.heap.shrink_to_fit();
+        }
+
+        Ok(self.dirty.is_empty() && !self.pending_shrink())
+    }
+
+    pub fn peek_min(&mut self) -> Option<(usize, &T)> {
+        self.flush();
+        let key_index = self.heap.peek_min_key_index()?;
+
+        Some((key_index, self.heap.value_ref(key_index)?))
+    }
+
+    pub fn poll_min(&mut self) -> Option<(usize, T)> {
+        self.flush();
+        self.heap.swap_remove_min()
+    }
+
+    pub fn try_insert(&mut self, key_index: usize, value: T) -> Result<(), IpqError> {
+        self.flush();
+        self.heap.try_insert(key_index, value)
+    }
+
+    pub fn delete(&mut self, key_index: usize) -> Option<T> {
+        self.flush();
+        self.heap.delete(key_index)
+    }
+
+    fn stamp(&self, key_index: usize) -> Option<(u64, usize)> {
+        match self.stamps.get(key_index >> PAGE_BITS) {
+            Some(Some(page)) => Some(page[key_index & PAGE_MASK]),
+            _ => None,
+        }
+    }
+
+    fn stamp_mut(&mut self, key_index: usize) -> &mut (u64, usize) {
+        let page_index = key_index >> PAGE_BITS;
+        if page_index >= self.stamps.len() {
+            self.stamps.resize_with(page_index + 1, || None);
+        }
+        let page = self.stamps[page_index]
+            .get_or_insert_with(|| vec![(0, 0); PAGE_LEN].into_boxed_slice());
+
+        &mut page[key_index & PAGE_MASK]
+    }
+}
+
+#[cfg(test)]
+mod coalescing_ipq_tests {
+    use crate::coalesce::MinCoalescingIpq;
+    use crate::limit::IpqError;
+    use crate::maintain::Budget;
+    use crate::shrink::ShrinkPolicy;
+    use crate::MinIndexedPriorityQueue;
+    use std::time::Duration;
+
+    #[test]
+    fn repeated_updates_should_be_sifted_once_at_the_next_structural_operation() {
+        let mut ipq = MinCoalescingIpq::new(MinIndexedPriorityQueue::from_sorted_iter(0..8));
+
+        for round in 0..50 {
+            assert!(ipq.try_update(0, 100 + round).unwrap().is_some());
+            ipq.try_update(5, 60 - round).unwrap();
+        }
+        assert_eq!(ipq.try_update(9, 0), Ok(None));
+        assert_eq!(ipq.pending_updates(), 2);
+        assert_eq!(ipq.value_of(5), Some(&11));
+
+        assert_eq!(ipq.peek_min(), Some((1, &1)));
+        assert_eq!(ipq.delete(1), Some(1));
+        ipq.try_update(7, -1).unwrap();
+        assert_eq!(ipq.try_insert(9, 3), Ok(()));
+        assert_eq!(ipq.pending_updates(), 0);
+
+        let polled = std::iter::from_fn(|| ipq.poll_min()).map(|(_, value)| value);
+        assert_eq!(polled.collect::<Vec<i32>>(), vec![-1, 2, 3, 3, 4, 6, 11, 149]);
+        assert!(ipq.queue().check_invariants().is_ok());
+    }
+
+    #[test]
+    fn budgeted_maintenance_should_keep_the_heap_ordered_between_calls() {
+        let mut ipq = MinCoalescingIpq::new(MinIndexedPriorityQueue::from_sorted_iter(0..500));
+        for frame in 0..40 {
+            for step in 0..50 {
+                let key_index = (frame * 50 + step) * 7 % 500;
+                ipq.try_update(key_index, (key_index * 31 + frame) % 1_000).unwrap();
+            }
+            assert!(ipq.maintain(Budget::Ops(30)).is_ok());
+            assert!(ipq.heap.satisfies_heap_invariant());
+        }
+        assert!(ipq.pending_updates() > 0);
+        assert_eq!(ipq.value_of(7), Some(&(7 * 31 + 30)));
+        assert_eq!(ipq.maintain(Budget::Time(Duration::from_secs(60))), Ok(true));
+
+        let polled = std::iter::from_fn(|| ipq.poll_min()).map(|(_, value)| value);
+        let polled = polled.collect::<Vec<usize>>();
+        assert!(polled.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(polled.len(), 500);
+    }
+
+    #[test]
+    fn a_far_key_should_stamp_a_single_page_and_nans_should_not_be_held_aside() {
+        let mut heap = MinIndexedPriorityQueue::new();
+        assert_eq!(heap.try_insert(10_000_000, 1.0), Ok(()));
+        let mut ipq = MinCoalescingIpq::new(heap);
+
+        assert_eq!(ipq.try_update(10_000_000, 0.5), Ok(Some(1.0)));
+        assert_eq!(ipq.stamps.iter().flatten().count(), 1);
+        let refused = ipq.try_update(10_000_000, f64::NAN);
+        assert_eq!(refused, Err(IpqError::Incomparable(10_000_000)));
+        assert_eq!(ipq.value_of(10_000_000), Some(&0.5));
+        assert_eq!(ipq.poll_min(), Some((10_000_000, 0.5)));
+    }
+
+    #[test]
+    fn shrinks_should_wait_for_maintenance() {
+        let heap = MinIndexedPriorityQueue::from_sorted_iter(0..1_000);
+        let mut ipq = MinCoalescingIpq::new(heap.with_shrink_policy(ShrinkPolicy::QUARTER));
+        (0..990).for_each(|_| {
+            ipq.poll_min();
+        });
+        assert_eq!(ipq.queue().mapping_capacity(), 1_024);
+        assert!(ipq.pending_shrink());
+
+        assert_eq!(ipq.try_update(995, 0), Ok(Some(995)));
+        assert_eq!(ipq.maintain(Budget::Ops(1)), Ok(false));
+        assert!(ipq.pending_shrink());
+        assert_eq!(ipq.maintain(Budget::Ops(1)), Ok(true));
+        assert_eq!(ipq.queue().mapping_capacity(), 16);
+
+        let heap = ipq.into_inner();
+        assert_eq!(heap.shrink_policy(), ShrinkPolicy::QUARTER);
+        assert!(heap.check_invariants().is_ok());
+    }
+}
@@ -0,0 +1,431 @@
+use crate::index::{KeyIdx, NodeIdx};
+use crate::limit::DEFAULT_MAX_KEY_BOUND;
+use crate::maps::IndexMaps;
+use crate::meta::MetaMap;
+use crate::order::Order;
+use crate::shrink::ShrinkPolicy;
+use crate::storage::Storage;
+use crate::tuning::{BulkTuning, MapLayout};
+use crate::{check_comparable, OrderedIndexedPriorityQueue};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+const MAGIC: [u8; 4] = *b"IPQB";
+const HEADER_LEN: usize = MAGIC.len() + 2 + 8;
+const CHECKSUM_LEN: usize = 4;
+
+/// Version of the layout written by [`OrderedIndexedPriorityQueue::to_bytes`].
+///
+/// Every version up to this one stays readable by [`OrderedIndexedPriorityQueue::from_bytes`];
+/// anything newer is rejected with [`CodecError::UnsupportedVersion`].
+///
+/// * `1`: values by key index, then the dense position and inverse maps.
+/// * `2`: values by node index, the key index held by each node, then the next key index.
+pub const FORMAT_VERSION: u16 = 2;
+
+#[derive(Debug)]
+pub enum CodecError {
+    BadMagic,
+    ChecksumMismatch { expected: u32, found: u32 },
+    HeapInvariantViolated,
+    /// The value decoded under this key index is not comparable with itself, e.g. a NaN.
+    Incomparable(usize),
+    InconsistentMappings,
+    KeyTooLarge(usize),
+    LengthMismatch { expected: usize, found: usize },
+    Payload(bincode::Error),
+    UnsupportedVersion(u16),
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodecError::BadMagic => write!(
+                f,
+                "Bytes do not start with an indexed priority queue header"
+            ),
+            CodecError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "Checksum mismatch; expected: {:#010x}, found: {:#010x}",
+                expected, found
+            ),
+            CodecError::HeapInvariantViolated => {
+                write!(f, "Decoded values violate the heap invariant")
+            }
+            CodecError::Incomparable(k) => write!(
+                f,
+                "Decoded value of key index {} is not comparable with itself",
+                k
+            ),
+            CodecError::InconsistentMappings => {
+                write!(f, "Decoded position and inverse maps disagree")
+            }
+            CodecError::KeyTooLarge(k) => write!(
+                f,
+                "Decoded key indexes reach {}, past the supported bound {}",
+                k, DEFAULT_MAX_KEY_BOUND
+            ),
+            CodecError::LengthMismatch { expected, found } => write!(
+                f,
+                "Byte length mismatch; expected: {}, found: {}",
+                expected, found
+            ),
+            CodecError::Payload(e) => write!(f, "Payload could not be encoded or decoded: {}", e),
+            CodecError::UnsupportedVersion(v) => write!(
+                f,
+                "Format version {} is newer than the supported version {}",
+                v, FORMAT_VERSION
+            ),
+        }
+    }
+}
+
+impl Error for CodecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CodecError::Payload(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<bincode::Error> for CodecError {
+    fn from(e: bincode::Error) -> Self {
+        CodecError::Payload(e)
+    }
+}
+
+/// CRC-32 (IEEE 802.3) of `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(u32::MAX, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+/// Wraps an encoded payload between the format header and its checksum.
+fn frame(version: u16, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len() + CHECKSUM_LEN);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes.extend_from_slice(&crc32(payload).to_le_bytes());
+
+    bytes
+}
+
+/// Reorders a version `1` payload, whose dense keys index the values directly, into the
+/// node ordered parts of the current layout.
+fn upgrade_v1<T>(
+    values: Vec<T>,
+    position_map: Vec<Option<usize>>,
+    inverse_map: Vec<Option<usize>>,
+) -> Result<(Vec<T>, Vec<usize>, usize), CodecError> {
+    let size = values.len();
+    if position_map.len() != inverse_map.len() || inverse_map.len() < size {
+        return Err(CodecError::InconsistentMappings);
+    }
+
+    let key_indexes = Range { start: 0, end: size }
+        .map(|i| match inverse_map[i] {
+            Some(k) if k < size && position_map[k] == Some(i) => Ok(k),
+            _ => Err(CodecError::InconsistentMappings),
+        })
+        .collect::<Result<Vec<usize>, CodecError>>()?;
+    let padding_is_unmapped = Range {
+        start: size,
+        end: inverse_map.len(),
+    }
+        .all(|i| inverse_map[i].is_none() && position_map[i].is_none());
+    if !padding_is_unmapped {
+        return Err(CodecError::InconsistentMappings);
+    }
+
+    let mut by_key = values.into_iter().map(Some).collect::<Vec<Option<T>>>();
+    let values = key_indexes
+        .iter()
+        .map(|&k| by_key[k].take().unwrap())
+        .collect();
+
+    Ok((values, key_indexes, size))
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd + Serialize,
+    O: Order,
+{
+    /// Encodes the queue, mappings included, as a versioned and checksummed byte vector.
+    ///
+    /// The layout is a `b"IPQB"` magic, the little endian [`FORMAT_VERSION`] and payload
+    /// length, the [bincode](https://docs.rs/bincode) payload and finally its CRC-32.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CodecError> {
+        let key_indexes = self
+            .maps
+            .keys_by_node(self.values.len())
+            .map(|k| k.0)
+            .collect::<Vec<usize>>();
+        let payload = bincode::serialize(&(&**self.values, &key_indexes, self.next_key_index()))?;
+
+        Ok(frame(FORMAT_VERSION, &payload))
+    }
+}
+
+impl<T, O> OrderedIndexedPriorityQueue<'static, T, O>
+where
+    T: Clone + PartialOrd + DeserializeOwned,
+    O: Order,
+{
+    /// Decodes bytes produced by [`to_bytes`](OrderedIndexedPriorityQueue::to_bytes) into a queue
+    /// owning its values.
+    ///
+    /// The header, checksum, mappings and heap invariant are all verified, and every value must
+    /// be comparable with itself, so a damaged or foreign input is reported as a [`CodecError`]
+    /// instead of producing a corrupt queue. Key indexes must fit the [`DEFAULT_MAX_KEY_BOUND`] of the restored queue.
+    /// Bytes written by older versions of the format are upgraded on the fly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![9, 8, 0, 4];
+    /// let bytes = MinIndexedPriorityQueue::from(&mut values).to_bytes().unwrap();
+    ///
+    /// let mut restored = MinIndexedPriorityQueue::<i32>::from_bytes(&bytes).unwrap();
+    /// assert_eq!(restored.poll_min_value(), Some(0));
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(CodecError::LengthMismatch {
+                expected: HEADER_LEN + CHECKSUM_LEN,
+                found: bytes.len(),
+            });
+        }
+        if bytes[..MAGIC.len()] != MAGIC {
+            return Err(CodecError::BadMagic);
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(CodecError::UnsupportedVersion(version));
+        }
+
+        let mut payload_len = [0; 8];
+        payload_len.copy_from_slice(&bytes[6..HEADER_LEN]);
+        let payload_len = u64::from_le_bytes(payload_len) as usize;
+        let expected = HEADER_LEN
+            .saturating_add(payload_len)
+            .saturating_add(CHECKSUM_LEN);
+        if bytes.len() != expected {
+            return Err(CodecError::LengthMismatch {
+                expected,
+                found: bytes.len(),
+            });
+        }
+
+        let payload = &bytes[HEADER_LEN..HEADER_LEN + payload_len];
+        let mut checksum = [0; CHECKSUM_LEN];
+        checksum.copy_from_slice(&bytes[HEADER_LEN + payload_len..]);
+        let expected = u32::from_le_bytes(checksum);
+        let found = crc32(payload);
+        if expected != found {
+            return Err(CodecError::ChecksumMismatch { expected, found });
+        }
+
+        let (values, key_indexes, next_key_index): (Vec<T>, Vec<usize>, usize) = match version {
+            1 => {
+                let (values, position_map, inverse_map) = bincode::deserialize(payload)?;
+                upgrade_v1(values, position_map, inverse_map)?
+            }
+            _ => bincode::deserialize(payload)?,
+        };
+        if key_indexes.len() != values.len() {
+            return Err(CodecError::InconsistentMappings);
+        }
+        if next_key_index > DEFAULT_MAX_KEY_BOUND {
+            return Err(CodecError::KeyTooLarge(next_key_index));
+        }
+
+        let node_capacity = values.len().next_power_of_two();
+        let mut maps = IndexMaps::with_node_capacity(MapLayout::default(), node_capacity);
+        for (i, &k) in key_indexes.iter().enumerate() {
+            if k >= next_key_index || maps.node_of(KeyIdx(k)).is_some() {
+                return Err(CodecError::InconsistentMappings);
+            }
+            check_comparable(k, &values[i]).map_err(|_| CodecError::Incomparable(k))?;
+            maps.map(KeyIdx(k), NodeIdx(i));
+        }
+        maps.raise_key_bound(next_key_index);
+
+        let ipq = Self {
+            values: Storage::Owned(values),
+            maps,
+            shrink_policy: ShrinkPolicy::default(),
+            bulk_tuning: BulkTuning::default(),
+            min_publisher: None,
+            observer: None,
+            metadata: MetaMap::new(),
+            held: HashSet::new(),
+            max_key_bound: DEFAULT_MAX_KEY_BOUND,
+            dedup: false,
+            version: 0,
+            order: PhantomData,
+        };
+
+        if !ipq.has_consistent_mappings() {
+            return Err(CodecError::InconsistentMappings);
+        }
+        if !ipq.satisfies_heap_invariant() {
+            return Err(CodecError::HeapInvariantViolated);
+        }
+
+        Ok(ipq)
+    }
+}
+
+#[cfg(test)]
+mod codec_tests {
+    use crate::codec::{crc32, frame, CodecError, FORMAT_VERSION, HEADER_LEN};
+    use crate::MinIndexedPriorityQueue;
+
+    fn encoded_sample() -> Vec<u8> {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        MinIndexedPriorityQueue::from(&mut values)
+            .to_bytes()
+            .unwrap()
+    }
+
+    #[test]
+    fn crc32_should_match_the_ieee_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn from_bytes_should_restore_values_and_mappings() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        ipq.poll_min_value();
+        ipq.update(3, -1);
+
+        let mut restored =
+            MinIndexedPriorityQueue::<i32>::from_bytes(&ipq.to_bytes().unwrap()).unwrap();
+
+        assert!(restored.maps.iter().eq(ipq.maps.iter()));
+        assert!(restored.structurally_eq(&ipq));
+        assert_eq!(restored.mapping_capacity(), ipq.mapping_capacity());
+        assert_eq!(restored.next_key_index(), 12);
+        assert_eq!(restored.size(), 11);
+        assert_eq!(restored.poll_min_value(), Some(-1));
+        assert_eq!(restored.poll_min_value(), Some(1));
+    }
+
+    #[test]
+    fn from_bytes_should_reject_a_damaged_payload() {
+        let mut bytes = encoded_sample();
+        bytes[HEADER_LEN + 3] ^= 0xFF;
+
+        assert!(matches!(
+            MinIndexedPriorityQueue::<i32>::from_bytes(&bytes),
+            Err(CodecError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn from_bytes_should_reject_foreign_or_truncated_input() {
+        let bytes = encoded_sample();
+
+        let mut foreign = bytes.clone();
+        foreign[0] = b'X';
+        assert!(matches!(
+            MinIndexedPriorityQueue::<i32>::from_bytes(&foreign),
+            Err(CodecError::BadMagic)
+        ));
+
+        assert!(matches!(
+            MinIndexedPriorityQueue::<i32>::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(CodecError::LengthMismatch { .. })
+        ));
+        assert!(matches!(
+            MinIndexedPriorityQueue::<i32>::from_bytes(&[]),
+            Err(CodecError::LengthMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn from_bytes_should_reject_newer_format_versions() {
+        let mut bytes = encoded_sample();
+        bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+
+        assert!(matches!(
+            MinIndexedPriorityQueue::<i32>::from_bytes(&bytes),
+            Err(CodecError::UnsupportedVersion(v)) if v == FORMAT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn from_bytes_should_reject_payloads_breaking_the_heap_invariant() {
+        let payload = bincode::serialize(&(vec![3, 1, 2], vec![0usize, 1, 2], 3usize)).unwrap();
+
+        assert!(matches!(
+            MinIndexedPriorityQueue::<i32>::from_bytes(&frame(FORMAT_VERSION, &payload)),
+            Err(CodecError::HeapInvariantViolated)
+        ));
+    }
+
+    #[test]
+    fn from_bytes_should_reject_incomparable_values() {
+        let payload = bincode::serialize(&(vec![0.5, f64::NAN], vec![3usize, 8], 9usize)).unwrap();
+
+        assert!(matches!(
+            MinIndexedPriorityQueue::<f64>::from_bytes(&frame(FORMAT_VERSION, &payload)),
+            Err(CodecError::Incomparable(8))
+        ));
+    }
+
+    #[test]
+    fn from_bytes_should_reject_inconsistent_mappings() {
+        let repeated_key = bincode::serialize(&(vec![1, 2, 3], vec![0usize, 0, 2], 3usize)).unwrap();
+        let key_out_of_bound = bincode::serialize(&(vec![1, 2], vec![0usize, 7], 3usize)).unwrap();
+
+        [repeated_key, key_out_of_bound].iter().for_each(|payload| {
+            assert!(matches!(
+                MinIndexedPriorityQueue::<i32>::from_bytes(&frame(FORMAT_VERSION, payload)),
+                Err(CodecError::InconsistentMappings)
+            ))
+        });
+
+        let hostile_bound = bincode::serialize(&(vec![1], vec![usize::MAX - 1], usize::MAX)).unwrap();
+        assert!(matches!(
+            MinIndexedPriorityQueue::<i32>::from_bytes(&frame(FORMAT_VERSION, &hostile_bound)),
+            Err(CodecError::KeyTooLarge(usize::MAX))
+        ));
+    }
+
+    #[test]
+    fn from_bytes_should_upgrade_version_1_payloads() {
+        let position_map: Vec<Option<usize>> = vec![Some(1), Some(2), Some(0), None];
+        let inverse_map: Vec<Option<usize>> = vec![Some(2), Some(0), Some(1), None];
+        let payload = bincode::serialize(&(vec![5, 7, 1], &position_map, &inverse_map)).unwrap();
+
+        let mut ipq = MinIndexedPriorityQueue::<i32>::from_bytes(&frame(1, &payload)).unwrap();
+        assert_eq!(ipq.value_of(0), Some(5));
+        assert_eq!(ipq.next_key_index(), 3);
+        assert_eq!(ipq.poll_min_key_index(), Some(2));
+        assert_eq!(ipq.poll_min_key_index(), Some(0));
+
+        let position_map: Vec<Option<usize>> = vec![Some(0), Some(0), Some(2), None];
+        let payload = bincode::serialize(&(vec![1, 2, 3], &position_map, &inverse_map)).unwrap();
+        assert!(matches!(
+            MinIndexedPriorityQueue::<i32>::from_bytes(&frame(1, &payload)),
+            Err(CodecError::InconsistentMappings)
+        ));
+    }
+}
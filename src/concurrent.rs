@@ -0,0 +1,1092 @@
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+/// An indexed priority queue shared between threads, split into shards that each sit behind
+/// their own lock so that writers to different shards never wait for one another.
+///
+/// Keys go to shard `key_index % shard_count`, so every keyed operation locks a single shard.
+/// Polls combine the shards instead: each head is looked at in turn, one lock at a time, and
+/// the shard whose head ranks first is polled.
+///
+/// # Relaxed ordering
+///
+/// Without concurrent writers, polls come out in exactly the order of a single queue. With
+/// them, a poll only promises to return an entry that was first in its shard when removed,
+/// and that ranked first among the heads seen while scanning: an entry inserted into a shard
+/// already scanned, or a head replaced in between, can let a poll return an entry ranked
+/// after another one still queued. No entry is ever returned twice or lost.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::concurrent::MinShardedIndexedPriorityQueue;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let jobs = Arc::new(MinShardedIndexedPriorityQueue::try_new(4).unwrap());
+/// let writers = (0..4)
+///     .map(|t| {
+///         let jobs = Arc::clone(&jobs);
+///         thread::spawn(move || (0..100).for_each(|i| jobs.try_insert(t * 100 + i, i).unwrap()))
+///     })
+///     .collect::<Vec<_>>();
+/// writers.into_iter().for_each(|writer| writer.join().unwrap());
+///
+/// assert_eq!(jobs.size(), 400);
+/// assert_eq!(jobs.poll_min().map(|(_, priority)| priority), Some(0));
+/// ```
+pub struct ShardedIndexedPriorityQueue<T, O>
+where
+    T: Clone + 'static,
+{
+    shards: Vec<Mutex<Shard<T, O>>>,
+}
+
+type Shard<T, O> = OrderedIndexedPriorityQueue<'static, T, O>;
+
+pub type MinShardedIndexedPriorityQueue<T> = ShardedIndexedPriorityQueue<T, Min>;
+
+pub type MaxShardedIndexedPriorityQueue<T> = ShardedIndexedPriorityQueue<T, Max>;
+
+impl<T, O> ShardedIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    /// # Panics
+    ///
+    /// If `shard_count` is `0`, see [`try_new`](Self::try_new).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn new(shard_count: usize) -> Self {
+        Self::try_new(shard_count).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`new`](Self::new), reporting a `shard_count` of `0` as
+    /// [`IpqError::NoShards`].
+    pub fn try_new(shard_count: usize) -> Result<Self, IpqError> {
+        if shard_count == 0 {
+            return Err(IpqError::NoShards);
+        }
+
+        let empty_shard = || OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new()));
+        Ok(Self {
+            shards: (0..shard_count).map(|_| Mutex::new(empty_shard())).collect(),
+        })
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Number of entries across all shards; only a snapshot while other threads write.
+    pub fn size(&self) -> usize {
+        (0..self.shards.len()).map(|shard| self.lock(shard).size()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|shard| self.lock(shard).is_empty())
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.shard(key_index).contains(key_index)
+    }
+
+    /// Adds `value` under `key_index`.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is already in use or out of the key space, see
+    /// [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&self, key_index: usize, value: T) {
+        if let Err(e) = self.try_insert(key_index, value) {
+            panic!("{}", e);
+        }
+    }
+
+    pub fn try_insert(&self, key_index: usize, value: T) -> Result<(), IpqError> {
+        self.shard(key_index).try_insert(key_index, value)
+    }
+
+    pub fn delete(&self, key_index: usize) -> Option<T> {
+        self.shard(key_index).delete(key_index)
+    }
+
+    /// Replaces the value of `key_index` and returns the previous one, or returns `None` if
+    /// the key is not in the queue, e.g. because another thread polled it first.
+    ///
+    /// # Panics
+    ///
+    /// If `value` is not comparable with itself, e.g. a NaN, see
+    /// [`try_update`](Self::try_update).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn update(&self, key_index: usize, value: T) -> Option<T> {
+        self.try_update(key_index, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`update`](Self::update), returning an error instead of panicking. The shard
+    /// is left untouched, and its lock unpoisoned, when `value` is refused.
+    pub fn try_update(&self, key_index: usize, value: T) -> Result<Option<T>, IpqError> {
+        let mut shard = self.shard(key_index);
+        if !shard.contains(key_index) {
+            return Ok(None);
+        }
+
+        shard.try_update(key_index, value).map(Some)
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Option<T> {
+        self.shard(key_index).value_of(key_index)
+    }
+
+    /// Returns the `(key_index, value)` pair ranked first among the shard heads.
+    pub fn peek_min(&self) -> Option<(usize, T)> {
+        let shard = self.first_shard()?;
+        let shard = self.lock(shard);
+        let key_index = shard.peek_min_key_index()?;
+
+        Some((key_index, shard.value_of(key_index)?))
+    }
+
+    /// Removes and returns the head of the shard whose head ranked first when scanned; see
+    /// the type's documentation for how far this may stray from the global order.
+    pub fn poll_min(&self) -> Option<(usize, T)> {
+        loop {
+            let mut shard = self.lock(self.first_shard()?);
+            if let Some(key_index) = shard.peek_min_key_index() {
+                return Some((key_index, shard.delete(key_index)?));
+            }
+            // The shard was emptied since it was scanned; look again.
+        }
+    }
+
+    /// Index of the shard whose head ranks first, each shard being locked in turn.
+    fn first_shard(&self) -> Option<usize> {
+        let mut first: Option<(usize, T)> = None;
+        for shard in 0..self.shards.len() {
+            let head = self.lock(shard).peek_min_value();
+            if let Some(head) = head {
+                if first.as_ref().map_or(true, |(_, value)| O::precedes(&head, value)) {
+                    first = Some((shard, head));
+                }
+            }
+        }
+
+        first.map(|(shard, _)| shard)
+    }
+
+    fn shard(&self, key_index: usize) -> MutexGuard<'_, Shard<T, O>> {
+        self.lock(key_index % self.shards.len())
+    }
+
+    fn lock(&self, shard: usize) -> MutexGuard<'_, Shard<T, O>> {
+        self.shards[shard]
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A queue with a single writer and any number of readers, e.g. a scheduler's hot queue
+/// watched by telemetry, where each read sees one consistent state of the queue.
+///
+/// The writer mutates its own copy through [`write`](Self::write), which then publishes a frozen
+/// snapshot of the result. Readers load the latest snapshot, a plain shared queue to `peek`,
+/// `contains` or `iter` at leisure. Loads are wait-free: a reader announces itself on one of
+/// two counters, clones the `Arc` behind an atomic pointer and leaves, without ever waiting
+/// for the writer or for other readers. Publishing swaps the pointer and retires the snapshot
+/// it replaced until both counters have been seen empty since, which a later write checks
+/// without waiting either. A snapshot is freed once it is reclaimed and its last reader
+/// drops it.
+///
+/// Publishing copies the queue in `O(n)`, so group changes into as few writes as possible.
+/// Metadata stays with the writer and is not part of snapshots.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::concurrent::SnapshotIpq;
+/// use indexed_priority_queue::order::Min;
+/// use std::thread;
+///
+/// let mut scheduler = SnapshotIpq::<u32, Min>::new();
+/// let dashboard = scheduler.reader();
+/// let before = dashboard.load();
+///
+/// scheduler.write(|ipq| ipq.try_insert_all((0..10).rev().enumerate()).is_complete());
+/// let busiest = thread::spawn(move || dashboard.load().peek_min_key_index());
+///
+/// assert_eq!(busiest.join().unwrap(), Some(9));
+/// assert!(before.is_empty());
+/// ```
+pub struct SnapshotIpq<T, O>
+where
+    T: Clone + 'static,
+{
+    queue: Shard<T, O>,
+    published: Arc<Published<T, O>>,
+}
+
+/// Reading end of a [`SnapshotIpq`], which can be cloned and sent to other threads.
+pub struct SnapshotReader<T, O>
+where
+    T: Clone + 'static,
+{
+    published: Arc<Published<T, O>>,
+}
+
+/// The snapshot shared by a [`SnapshotIpq`] and its readers, behind a pointer obtained from
+/// [`Arc::into_raw`] that owns one strong count.
+///
+/// Each swapped out snapshot is retired with the epoch, i.e. the number of writes, at which it
+/// was swapped out. A reader may still be cloning it only if it loaded the pointer before the
+/// swap, so announced itself on a reader counter before the swap too: the snapshot can be
+/// reclaimed once both counters have been seen at zero after it. Readers announce themselves
+/// on the counter of the current epoch's parity, which leaves the other one to drain.
+struct Published<T, O>
+where
+    T: Clone + 'static,
+{
+    current: AtomicPtr<Shard<T, O>>,
+    epoch: AtomicUsize,
+    readers: [AtomicUsize; 2],
+    // Only ever locked by the writer, never by loads.
+    retired: Mutex<Retired<T, O>>,
+}
+
+struct Retired<T, O>
+where
+    T: Clone + 'static,
+{
+    snapshots: Vec<(usize, Arc<Shard<T, O>>)>,
+    // One past the last epoch after whose swap each reader counter was seen at zero.
+    drained: [usize; 2],
+}
+
+impl<T, O> Published<T, O>
+where
+    T: Clone + 'static,
+{
+    fn new(snapshot: Arc<Shard<T, O>>) -> Self {
+        Self {
+            current: AtomicPtr::new(Arc::into_raw(snapshot) as *mut Shard<T, O>),
+            epoch: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            retired: Mutex::new(Retired {
+                snapshots: Vec::new(),
+                drained: [0; 2],
+            }),
+        }
+    }
+
+    fn load(&self) -> Arc<Shard<T, O>> {
+        let readers = &self.readers[self.epoch.load(Ordering::SeqCst) & 1];
+        readers.fetch_add(1, Ordering::SeqCst);
+        let snapshot = self.current.load(Ordering::SeqCst);
+        // The snapshot cannot be reclaimed before this counter is seen back at zero, and its
+        // pointer came from `Arc::into_raw` in `new` or `publish`.
+        unsafe { Arc::increment_strong_count(snapshot) };
+        readers.fetch_sub(1, Ordering::SeqCst);
+
+        unsafe { Arc::from_raw(snapshot) }
+    }
+
+    /// Swaps `snapshot` in, then reclaims the retired snapshots no load can still be cloning.
+    /// Must only be called by the single writer.
+    fn publish(&self, snapshot: Arc<Shard<T, O>>) {
+        let swapped = Arc::into_raw(snapshot) as *mut Shard<T, O>;
+        let swapped_out = self.current.swap(swapped, Ordering::SeqCst);
+        let epoch = self.epoch.load(Ordering::SeqCst);
+
+        let mut retired = self.retired.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // The swapped out pointer owned the strong count `Arc::into_raw` left behind.
+        retired.snapshots.push((epoch, unsafe { Arc::from_raw(swapped_out) }));
+        for (counter, readers) in self.readers.iter().enumerate() {
+            if readers.load(Ordering::SeqCst) == 0 {
+                retired.drained[counter] = epoch + 1;
+            }
+        }
+        let drained = retired.drained;
+        retired
+            .snapshots
+            .retain(|&(retired_at, _)| drained.iter().any(|&after| after <= retired_at));
+        self.epoch.store(epoch + 1, Ordering::SeqCst);
+    }
+
+    #[cfg(test)]
+    fn retired(&self) -> usize {
+        self.retired.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).snapshots.len()
+    }
+}
+
+impl<T, O> Drop for Published<T, O>
+where
+    T: Clone + 'static,
+{
+    fn drop(&mut self) {
+        // No reader is left to load the pointer, which owns the strong count of `publish`.
+        drop(unsafe { Arc::from_raw(*self.current.get_mut()) });
+    }
+}
+
+impl<T, O> Clone for SnapshotReader<T, O>
+where
+    T: Clone + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            published: Arc::clone(&self.published),
+        }
+    }
+}
+
+impl<T, O> SnapshotReader<T, O>
+where
+    T: Clone + 'static,
+{
+    /// Returns the snapshot published last, without waiting.
+    pub fn load(&self) -> Arc<OrderedIndexedPriorityQueue<'static, T, O>> {
+        self.published.load()
+    }
+}
+
+impl<T, O> Default for SnapshotIpq<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, O> SnapshotIpq<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    pub fn new() -> Self {
+        let queue = OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new()));
+        let snapshot = Arc::new(queue.detached_copy());
+
+        Self {
+            queue,
+            published: Arc::new(Published::new(snapshot)),
+        }
+    }
+
+    pub fn reader(&self) -> SnapshotReader<T, O> {
+        SnapshotReader {
+            published: Arc::clone(&self.published),
+        }
+    }
+
+    /// The writer's own, always current, view of the queue.
+    pub fn queue(&self) -> &OrderedIndexedPriorityQueue<'static, T, O> {
+        &self.queue
+    }
+
+    /// Returns the snapshot published last, as readers see it.
+    pub fn load(&self) -> Arc<OrderedIndexedPriorityQueue<'static, T, O>> {
+        self.published.load()
+    }
+
+    /// Applies `f` to the queue, then publishes the outcome to readers in one piece.
+    pub fn write<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut OrderedIndexedPriorityQueue<'static, T, O>) -> R,
+    {
+        let result = f(&mut self.queue);
+        self.published.publish(Arc::new(self.queue.detached_copy()));
+
+        result
+    }
+}
+
+/// Reason a [`ReadMostlyIpq`] operation failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LockError {
+    Key(IpqError),
+    /// A thread panicked while holding the write lock, so the queue may be left half-updated.
+    Poisoned,
+}
+
+impl Display for LockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockError::Key(e) => e.fmt(f),
+            LockError::Poisoned => write!(f, "Queue lock poisoned by a panicking writer"),
+        }
+    }
+}
+
+impl Error for LockError {}
+
+impl From<IpqError> for LockError {
+    fn from(e: IpqError) -> Self {
+        LockError::Key(e)
+    }
+}
+
+/// A queue behind an `RwLock`, for many threads that mostly read it: reads share the lock,
+/// writes take it exclusively.
+///
+/// No operation panics on bad input while holding the lock, so the lock is only poisoned by a
+/// panic in a closure given to [`read`](Self::read) or [`write`](Self::write). Every operation
+/// then fails with [`LockError::Poisoned`] instead of propagating the panic.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::concurrent::{LockError, MinReadMostlyIpq};
+/// use indexed_priority_queue::limit::IpqError;
+///
+/// let ipq = MinReadMostlyIpq::new();
+/// ipq.insert(4, 40).unwrap();
+/// ipq.insert(2, 20).unwrap();
+///
+/// assert_eq!(ipq.insert(4, 1), Err(LockError::Key(IpqError::KeyInUse(4))));
+/// assert_eq!(ipq.peek_min_key_index(), Ok(Some(2)));
+/// assert_eq!(ipq.iter().unwrap().collect::<Vec<i32>>(), vec![20, 40]);
+/// ```
+pub struct ReadMostlyIpq<T, O>
+where
+    T: Clone + 'static,
+{
+    queue: RwLock<Shard<T, O>>,
+}
+
+pub type MinReadMostlyIpq<T> = ReadMostlyIpq<T, Min>;
+
+pub type MaxReadMostlyIpq<T> = ReadMostlyIpq<T, Max>;
+
+/// Iterator over the values of a [`ReadMostlyIpq`] in heap order, holding the read lock until
+/// dropped.
+pub struct ReadMostlyIter<'q, T, O>
+where
+    T: Clone + 'static,
+{
+    queue: RwLockReadGuard<'q, Shard<T, O>>,
+    node_index: usize,
+}
+
+impl<'q, T, O> Iterator for ReadMostlyIter<'q, T, O>
+where
+    T: Clone + 'static,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.queue.values.get(self.node_index)?.clone();
+        self.node_index += 1;
+
+        Some(value)
+    }
+}
+
+impl<T, O> Default for ReadMostlyIpq<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, O> ReadMostlyIpq<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    pub fn new() -> Self {
+        let queue = OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new()));
+        Self {
+            queue: RwLock::new(queue),
+        }
+    }
+
+    /// Runs `f` under the read lock, for reads not covered by the other methods.
+    pub fn read<F, R>(&self, f: F) -> Result<R, LockError>
+    where
+        F: FnOnce(&OrderedIndexedPriorityQueue<'static, T, O>) -> R,
+    {
+        Ok(f(&*self.read_lock()?))
+    }
+
+    /// Runs `f` under the write lock, for writes not covered by the other methods.
+    pub fn write<F, R>(&self, f: F) -> Result<R, LockError>
+    where
+        F: FnOnce(&mut OrderedIndexedPriorityQueue<'static, T, O>) -> R,
+    {
+        Ok(f(&mut *self.write_lock()?))
+    }
+
+    pub fn size(&self) -> Result<usize, LockError> {
+        self.read(|ipq| ipq.size())
+    }
+
+    pub fn is_empty(&self) -> Result<bool, LockError> {
+        self.read(|ipq| ipq.is_empty())
+    }
+
+    pub fn contains(&self, key_index: usize) -> Result<bool, LockError> {
+        self.read(|ipq| ipq.contains(key_index))
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Result<Option<T>, LockError> {
+        self.read(|ipq| ipq.value_of(key_index))
+    }
+
+    pub fn peek_min_key_index(&self) -> Result<Option<usize>, LockError> {
+        self.read(|ipq| ipq.peek_min_key_index())
+    }
+
+    pub fn peek_min_value(&self) -> Result<Option<T>, LockError> {
+        self.read(|ipq| ipq.peek_min_value())
+    }
+
+    /// Iterates over clones of the values in heap order, starting with the one ranked first.
+    pub fn iter(&self) -> Result<ReadMostlyIter<'_, T, O>, LockError> {
+        Ok(ReadMostlyIter {
+            queue: self.read_lock()?,
+            node_index: 0,
+        })
+    }
+
+    pub fn insert(&self, key_index: usize, value: T) -> Result<(), LockError> {
+        Ok(self.write_lock()?.try_insert(key_index, value)?)
+    }
+
+    /// Adds `value` under the next key index and returns it.
+    pub fn push(&self, value: T) -> Result<usize, LockError> {
+        Ok(self.write_lock()?.try_push(value)?)
+    }
+
+    pub fn delete(&self, key_index: usize) -> Result<Option<T>, LockError> {
+        self.write(|ipq| ipq.delete(key_index))
+    }
+
+    /// Replaces the value of `key_index` and returns the previous one, or `None` if the key is
+    /// not in the queue. A value not comparable with itself, e.g. a NaN, is refused with
+    /// [`LockError::Key`] without poisoning the lock.
+    pub fn update(&self, key_index: usize, value: T) -> Result<Option<T>, LockError> {
+        let mut ipq = self.write_lock()?;
+        if !ipq.contains(key_index) {
+            return Ok(None);
+        }
+
+        Ok(Some(ipq.try_update(key_index, value)?))
+    }
+
+    pub fn poll_min_key_index(&self) -> Result<Option<usize>, LockError> {
+        self.write(|ipq| ipq.poll_min_key_index())
+    }
+
+    pub fn poll_min_value(&self) -> Result<Option<T>, LockError> {
+        self.write(|ipq| ipq.poll_min_value())
+    }
+
+    fn read_lock(&self) -> Result<RwLockReadGuard<'_, Shard<T, O>>, LockError> {
+        self.queue.read().map_err(|_| LockError::Poisoned)
+    }
+
+    fn write_lock(&self) -> Result<RwLockWriteGuard<'_, Shard<T, O>>, LockError> {
+        self.queue.write().map_err(|_| LockError::Poisoned)
+    }
+}
+
+/// A single queue behind a `Mutex`, shared by worker threads as their work queue: producers
+/// insert, and workers either [`try_poll_min`](Self::try_poll_min) or wait for an entry with
+/// [`poll_min_blocking`](Self::poll_min_blocking).
+///
+/// Unlike a [`ShardedIndexedPriorityQueue`], polls follow the exact order of the queue, at the
+/// cost of every operation taking the one lock. No operation panics while holding it, so a
+/// lock poisoned by a panicking `Clone` or `PartialOrd` of `T` is recovered rather than
+/// propagated. [`close`](Self::close) lets waiting workers return once the queue is drained.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::concurrent::MaxSyncIndexedPriorityQueue;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let jobs = Arc::new(MaxSyncIndexedPriorityQueue::new());
+/// let worker = {
+///     let jobs = Arc::clone(&jobs);
+///     thread::spawn(move || std::iter::from_fn(|| jobs.poll_min_blocking()).count())
+/// };
+///
+/// (0..50).for_each(|job| jobs.try_insert(job, job % 5).unwrap());
+/// jobs.close();
+/// assert_eq!(worker.join().unwrap(), 50);
+/// assert_eq!(jobs.try_poll_min(), None);
+/// ```
+pub struct SyncIndexedPriorityQueue<T, O>
+where
+    T: Clone + 'static,
+{
+    queue: Mutex<SyncState<T, O>>,
+    inserted: Condvar,
+}
+
+struct SyncState<T, O>
+where
+    T: Clone + 'static,
+{
+    queue: Shard<T, O>,
+    closed: bool,
+}
+
+pub type MinSyncIndexedPriorityQueue<T> = SyncIndexedPriorityQueue<T, Min>;
+
+pub type MaxSyncIndexedPriorityQueue<T> = SyncIndexedPriorityQueue<T, Max>;
+
+impl<T, O> Default for SyncIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, O> SyncIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    pub fn new() -> Self {
+        let queue = OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new()));
+        Self {
+            queue: Mutex::new(SyncState {
+                queue,
+                closed: false,
+            }),
+            inserted: Condvar::new(),
+        }
+    }
+
+    /// Number of entries; only a snapshot while other threads write.
+    pub fn size(&self) -> usize {
+        self.lock().queue.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().queue.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.lock().queue.contains(key_index)
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Option<T> {
+        self.lock().queue.value_of(key_index)
+    }
+
+    /// Adds `value` under `key_index`, waking one waiting worker.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is already in use or out of the key space, see
+    /// [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&self, key_index: usize, value: T) {
+        if let Err(e) = self.try_insert(key_index, value) {
+            panic!("{}", e);
+        }
+    }
+
+    pub fn try_insert(&self, key_index: usize, value: T) -> Result<(), IpqError> {
+        self.lock().queue.try_insert(key_index, value)?;
+        self.inserted.notify_one();
+
+        Ok(())
+    }
+
+    /// Adds `value` under the next key index and returns it, waking one waiting worker.
+    pub fn try_push(&self, value: T) -> Result<usize, IpqError> {
+        let key_index = self.lock().queue.try_push(value)?;
+        self.inserted.notify_one();
+
+        Ok(key_index)
+    }
+
+    pub fn delete(&self, key_index: usize) -> Option<T> {
+        self.lock().queue.delete(key_index)
+    }
+
+    /// Replaces the value of `key_index` and returns the previous one, or returns `None` if
+    /// the key is not in the queue, e.g. because a worker polled it first.
+    ///
+    /// # Panics
+    ///
+    /// If `value` is not comparable with itself, e.g. a NaN, see
+    /// [`try_update`](Self::try_update).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn update(&self, key_index: usize, value: T) -> Option<T> {
+        self.try_update(key_index, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`update`](Self::update), returning an error instead of panicking, after the
+    /// lock is released.
+    pub fn try_update(&self, key_index: usize, value: T) -> Result<Option<T>, IpqError> {
+        let mut state = self.lock();
+        if !state.queue.contains(key_index) {
+            return Ok(None);
+        }
+
+        state.queue.try_update(key_index, value).map(Some)
+    }
+
+    /// Removes and returns the entry ranked first, or `None` at once if the queue is empty.
+    pub fn try_poll_min(&self) -> Option<(usize, T)> {
+        self.lock().queue.swap_remove_min()
+    }
+
+    /// Removes and returns the entry ranked first, waiting for one while the queue is empty.
+    /// Returns `None` once the queue is [closed](Self::close) and empty.
+    pub fn poll_min_blocking(&self) -> Option<(usize, T)> {
+        let mut state = self.lock();
+        loop {
+            if let Some(entry) = state.queue.swap_remove_min() {
+                return Some(entry);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .inserted
+                .wait(state)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Same as [`poll_min_blocking`](Self::poll_min_blocking), giving up with `None` after
+    /// `timeout`.
+    pub fn poll_min_timeout(&self, timeout: Duration) -> Option<(usize, T)> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock();
+        loop {
+            if let Some(entry) = state.queue.swap_remove_min() {
+                return Some(entry);
+            }
+            let now = Instant::now();
+            if state.closed || now >= deadline {
+                return None;
+            }
+            state = self
+                .inserted
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+    }
+
+    /// Wakes every waiting worker and makes blocking polls return `None` once the queue is
+    /// empty. Entries can still be inserted and polled.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.inserted.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Copies the `(key_index, value)` pairs out, in heap order, under a single short lock
+    /// and returns them as an iterator that no longer needs it, so that a dashboard can
+    /// enumerate and render thousands of entries while workers keep polling.
+    ///
+    /// The snapshot is stamped with the queue's [`version`](OrderedIndexedPriorityQueue::version)
+    /// at the time of the copy; [`IterSnapshot::is_current`] tells whether the queue changed
+    /// since, e.g. to skip redrawing an unchanged view.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::concurrent::MinSyncIndexedPriorityQueue;
+    ///
+    /// let jobs = MinSyncIndexedPriorityQueue::new();
+    /// (0..3).for_each(|job| jobs.try_insert(job, 10 - job).unwrap());
+    ///
+    /// let snapshot = jobs.iter_snapshot();
+    /// assert!(snapshot.is_current(&jobs));
+    /// assert_eq!(jobs.try_poll_min(), Some((2, 8)));
+    /// assert!(!snapshot.is_current(&jobs));
+    ///
+    /// let mut rows = snapshot.collect::<Vec<_>>();
+    /// rows.sort_unstable();
+    /// assert_eq!(rows, [(0, 10), (1, 9), (2, 8)]);
+    /// ```
+    pub fn iter_snapshot(&self) -> IterSnapshot<T> {
+        let state = self.lock();
+        let entries = state
+            .queue
+            .iter()
+            .map(|(key_index, value)| (key_index, value.clone()))
+            .collect::<Vec<(usize, T)>>();
+
+        IterSnapshot {
+            generation: state.queue.version(),
+            entries: entries.into_iter(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SyncState<T, O>> {
+        self.queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Entries copied out of a [`SyncIndexedPriorityQueue`] by
+/// [`iter_snapshot`](SyncIndexedPriorityQueue::iter_snapshot), iterated without its lock.
+pub struct IterSnapshot<T> {
+    generation: u64,
+    entries: std::vec::IntoIter<(usize, T)>,
+}
+
+impl<T> IterSnapshot<T> {
+    /// Version of the queue the entries were copied from.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns `true` if `queue`, the one the snapshot was taken from, was not changed since.
+    pub fn is_current<O>(&self, queue: &SyncIndexedPriorityQueue<T, O>) -> bool
+    where
+        T: Clone + PartialOrd + 'static,
+        O: Order,
+    {
+        queue.lock().queue.version() == self.generation
+    }
+}
+
+impl<T> Iterator for IterSnapshot<T> {
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for IterSnapshot<T> {}
+
+#[cfg(test)]
+mod sharded_indexed_pq_tests {
+    use crate::concurrent::{
+        LockError, MaxReadMostlyIpq, MaxShardedIndexedPriorityQueue,
+        MinShardedIndexedPriorityQueue, MinSyncIndexedPriorityQueue, SnapshotIpq,
+    };
+    use crate::limit::IpqError;
+    use crate::order::Max;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn try_new_should_refuse_a_queue_without_shards() {
+        let ipq = MinShardedIndexedPriorityQueue::<u32>::try_new(0);
+        assert_eq!(ipq.err(), Some(IpqError::NoShards));
+        assert_eq!(MinShardedIndexedPriorityQueue::<u32>::try_new(2).unwrap().shard_count(), 2);
+    }
+
+    #[test]
+    fn polls_should_follow_the_global_order_without_concurrent_writers() {
+        let ipq = MaxShardedIndexedPriorityQueue::try_new(3).unwrap();
+        (0..30).for_each(|key| ipq.try_insert(key, (key * 7) % 30).unwrap());
+        assert_eq!(ipq.try_update(4, 100), Ok(Some(28)));
+        assert_eq!(ipq.delete(5), Some(5));
+
+        assert_eq!(ipq.peek_min(), Some((4, 100)));
+        let polled = (0..29).map(|_| ipq.poll_min().unwrap().1).collect::<Vec<usize>>();
+        let mut expected = polled.clone();
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(polled, expected);
+        assert!(ipq.is_empty());
+    }
+
+    #[test]
+    fn concurrent_polls_should_return_each_entry_exactly_once() {
+        let ipq = Arc::new(MinShardedIndexedPriorityQueue::try_new(4).unwrap());
+        (0..1_000).for_each(|key| ipq.try_insert(key, key).unwrap());
+
+        let pollers = (0..4)
+            .map(|_| {
+                let ipq = Arc::clone(&ipq);
+                thread::spawn(move || {
+                    let mut polled = Vec::new();
+                    while let Some((key_index, _)) = ipq.poll_min() {
+                        polled.push(key_index);
+                    }
+                    polled
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut polled = pollers
+            .into_iter()
+            .flat_map(|poller| poller.join().unwrap())
+            .collect::<Vec<usize>>();
+
+        polled.sort_unstable();
+        assert_eq!(polled, (0..1_000).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn keys_should_be_global_across_shards() {
+        let ipq = MinShardedIndexedPriorityQueue::try_new(2).unwrap();
+        assert_eq!(ipq.try_insert(3, 'a'), Ok(()));
+
+        assert!(ipq.try_insert(3, 'b').is_err());
+        assert_eq!(ipq.try_update(8, 'c'), Ok(None));
+        assert_eq!(ipq.value_of(3), Some('a'));
+        assert_eq!(ipq.shard_count(), 2);
+    }
+
+    #[test]
+    fn readers_should_keep_their_snapshot_while_the_writer_moves_on() {
+        let mut ipq = SnapshotIpq::<char, Max>::new();
+        let reader = ipq.reader();
+        ipq.write(|ipq| {
+            ipq.insert(3, 'c');
+            ipq.insert(1, 'a');
+        });
+
+        let snapshot = reader.clone().load();
+        let polled = ipq.write(|ipq| ipq.poll_min_key_index());
+        assert_eq!(polled, Some(3));
+        assert_eq!(snapshot.peek_min_key_index(), Some(3));
+        assert_eq!(snapshot.iter().count(), 2);
+        assert_eq!(reader.load().peek_min_key_index(), Some(1));
+        assert!(!ipq.load().contains(3));
+        assert_eq!(ipq.queue().size(), 1);
+    }
+
+    #[test]
+    fn loads_racing_writes_should_see_whole_snapshots_and_leave_few_retired() {
+        let mut ipq = SnapshotIpq::<usize, Max>::new();
+        let readers = (0..4)
+            .map(|_| {
+                let reader = ipq.reader();
+                thread::spawn(move || {
+                    (0..2_000).all(|_| {
+                        let snapshot = reader.load();
+                        snapshot.size() == snapshot.peek_min_value().map_or(0, |max| max + 1)
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for value in 0..500 {
+            ipq.write(|ipq| ipq.try_push(value).unwrap());
+        }
+        assert!(readers.into_iter().all(|reader| reader.join().unwrap()));
+
+        ipq.write(|_| ());
+        ipq.write(|_| ());
+        assert_eq!(ipq.published.retired(), 0);
+        assert_eq!(ipq.load().size(), 500);
+    }
+
+    #[test]
+    fn read_mostly_ipq_should_report_poisoning_as_an_error() {
+        let ipq = Arc::new(MaxReadMostlyIpq::new());
+        assert_eq!(ipq.push('x'), Ok(0));
+        assert_eq!(ipq.update(0, 'y'), Ok(Some('x')));
+        assert_eq!(ipq.update(5, 'z'), Ok(None));
+
+        let writer = Arc::clone(&ipq);
+        let outcome = thread::spawn(move || writer.write(|_| panic!("writer failed"))).join();
+        assert!(outcome.is_err());
+
+        assert_eq!(ipq.peek_min_value(), Err(LockError::Poisoned));
+        assert_eq!(ipq.insert(1, 'a'), Err(LockError::Poisoned));
+        assert!(ipq.iter().is_err());
+    }
+
+    #[test]
+    fn updates_to_a_nan_should_be_refused_without_poisoning_any_lock() {
+        let sharded = MinShardedIndexedPriorityQueue::try_new(2).unwrap();
+        sharded.try_insert(1, 1.0).unwrap();
+        assert_eq!(sharded.try_update(1, f64::NAN), Err(IpqError::Incomparable(1)));
+        assert_eq!(sharded.try_update(1, 0.5), Ok(Some(1.0)));
+
+        let read_mostly = MaxReadMostlyIpq::new();
+        read_mostly.insert(1, 1.0).unwrap();
+        let refused = read_mostly.update(1, f64::NAN);
+        assert_eq!(refused, Err(LockError::Key(IpqError::Incomparable(1))));
+        assert_eq!(read_mostly.update(1, 0.5), Ok(Some(1.0)));
+
+        let sync = MinSyncIndexedPriorityQueue::new();
+        sync.try_insert(1, 1.0).unwrap();
+        assert_eq!(sync.try_update(1, f64::NAN), Err(IpqError::Incomparable(1)));
+        assert_eq!(sync.try_poll_min(), Some((1, 1.0)));
+    }
+
+    #[test]
+    fn sync_ipq_workers_should_wait_for_entries_and_stop_once_closed() {
+        let ipq = Arc::new(MinSyncIndexedPriorityQueue::new());
+        assert_eq!(ipq.try_poll_min(), None);
+        assert_eq!(ipq.poll_min_timeout(Duration::from_millis(1)), None);
+
+        let workers = (0..3)
+            .map(|_| {
+                let ipq = Arc::clone(&ipq);
+                thread::spawn(move || {
+                    std::iter::from_fn(|| ipq.poll_min_blocking()).collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        (0..300).for_each(|key| ipq.try_insert(key, key % 17).unwrap());
+        assert_eq!(ipq.try_push(0), Ok(300));
+        ipq.close();
+
+        let mut polled = workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .map(|(key_index, _)| key_index)
+            .collect::<Vec<usize>>();
+        polled.sort_unstable();
+        assert_eq!(polled, (0..301).collect::<Vec<usize>>());
+        assert!(ipq.is_closed() && ipq.is_empty());
+
+        assert_eq!(ipq.try_insert(4, 1), Ok(()));
+        assert_eq!(ipq.try_insert(2, 0), Ok(()));
+        assert_eq!(ipq.try_update(4, 0), Ok(Some(1)));
+        assert_eq!(ipq.poll_min_blocking().map(|(_, value)| value), Some(0));
+        assert_eq!(ipq.try_poll_min().map(|(_, value)| value), Some(0));
+        assert_eq!(ipq.poll_min_blocking(), None);
+    }
+
+    #[test]
+    fn iter_snapshots_should_outlive_concurrent_polls() {
+        let ipq = Arc::new(MinSyncIndexedPriorityQueue::new());
+        (0..2_000).for_each(|key| ipq.try_insert(key, key % 97).unwrap());
+        let snapshot = ipq.iter_snapshot();
+        let generation = snapshot.generation();
+
+        let worker = {
+            let ipq = Arc::clone(&ipq);
+            thread::spawn(move || std::iter::from_fn(|| ipq.try_poll_min()).count())
+        };
+        let mut rows = snapshot.collect::<Vec<_>>();
+        assert_eq!(worker.join().unwrap(), 2_000);
+
+        rows.sort_unstable();
+        assert!(rows.iter().enumerate().all(|(key, &row)| row == (key, key % 97)));
+        let empty = ipq.iter_snapshot();
+        assert_ne!(empty.generation(), generation);
+        assert!(empty.is_current(&ipq) && empty.len() == 0);
+    }
+}
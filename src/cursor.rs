@@ -0,0 +1,79 @@
+use crate::index::NodeIdx;
+use crate::ipq::IndexedBinaryHeap;
+
+/// Walks a heap's nodes in priority order without touching the heap itself.
+///
+/// The cursor keeps its own small heap of frontier node indexes: popping a node pushes its
+/// children, so yielding the first `k` nodes costs `O(k log k)` comparisons and never more
+/// than `k + 1` indexes of memory.
+pub(crate) struct PriorityCursor {
+    frontier: Vec<NodeIdx>,
+}
+
+impl PriorityCursor {
+    pub(crate) fn new(size: usize) -> Self {
+        let mut frontier = Vec::new();
+        if size > 0 {
+            frontier.push(NodeIdx::ROOT);
+        }
+
+        Self { frontier }
+    }
+
+    /// Returns the next node index in priority order, `less(i, j)` comparing heap nodes.
+    pub(crate) fn next<F>(&mut self, size: usize, less: F) -> Option<NodeIdx>
+    where
+        F: Fn(NodeIdx, NodeIdx) -> bool,
+    {
+        if self.frontier.is_empty() {
+            return None;
+        }
+        let mut frontier = Frontier {
+            nodes: &mut self.frontier,
+            less: &less,
+        };
+        let last = NodeIdx(frontier.size() - 1);
+        frontier.swap(NodeIdx::ROOT, last);
+        let node = frontier.nodes.pop()?;
+        frontier.sink(NodeIdx::ROOT);
+
+        [0, 1]
+            .into_iter()
+            .filter_map(|nth| node.child(nth))
+            .filter(|&child| child.0 < size)
+            .for_each(|child| {
+                frontier.nodes.push(child);
+                frontier.swim(NodeIdx(frontier.size() - 1));
+            });
+
+        Some(node)
+    }
+}
+
+/// The cursor's frontier seen as a heap of its own, ranking the node indexes it holds by
+/// `less`.
+struct Frontier<'c, F> {
+    nodes: &'c mut Vec<NodeIdx>,
+    less: &'c F,
+}
+
+impl<'c, F> IndexedBinaryHeap for Frontier<'c, F>
+where
+    F: Fn(NodeIdx, NodeIdx) -> bool,
+{
+    fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn less(&self, i: NodeIdx, j: NodeIdx) -> bool {
+        (self.less)(self.nodes[i.0], self.nodes[j.0])
+    }
+
+    fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn swap(&mut self, i: NodeIdx, j: NodeIdx) {
+        self.nodes.swap(i.0, j.0);
+    }
+}
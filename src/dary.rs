@@ -0,0 +1,740 @@
+use crate::check_comparable;
+use crate::index::{KeyIdx, NodeIdx};
+use crate::ipq::{IndexedBinaryHeap, IndexedPriorityQueue};
+use crate::limit::{IpqError, DEFAULT_MAX_KEY_BOUND};
+use crate::order::{Max, Min, Order};
+use crate::paged::PagedPositionMap;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::{Bound, RangeBounds};
+use std::vec::IntoIter;
+
+/// An indexed priority queue over a `D`-ary heap, whose nodes have up to `D` children.
+///
+/// A wider heap is shallower, `log_D n` levels instead of `log_2 n`, so swimming, which is all
+/// a [`decrease`](IndexedPriorityQueue::decrease) does in a minimum queue, moves fewer entries,
+/// while each sink step compares `D` children that sit next to each other in memory. In the
+/// `d-ary heaps` benchmarks on 100K entries, `D = 4` polls a whole queue about 10% faster than
+/// a binary heap, and `D = 8` overtakes it once decreases outnumber polls eight to one, as in
+/// Dijkstra's algorithm on dense graphs, running that mix about 12% faster than `D = 2`.
+///
+/// `D` must be at least `2`, which is checked at compile time. Keys map to their nodes through
+/// pages allocated as keys fall into them, so a lone far key costs a page, not a slot per key
+/// before it.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::dary::MinDaryIndexedPriorityQueue;
+///
+/// let mut ipq = MinDaryIndexedPriorityQueue::<u32, 4>::new();
+/// (0..10).for_each(|key_index| ipq.try_insert(key_index, 100 - key_index as u32).unwrap());
+/// ipq.try_decrease(3, 1).unwrap();
+///
+/// assert_eq!(ipq.poll_min_key_index(), Some(3));
+/// assert_eq!(ipq.peek_min_value(), Some(91));
+/// ```
+#[derive(Clone, Debug)]
+pub struct DaryIndexedPriorityQueue<T, const D: usize, O> {
+    values: Vec<T>,
+    key_of_node: Vec<usize>,
+    node_of_key: PagedPositionMap,
+    order: PhantomData<O>,
+}
+
+pub type MinDaryIndexedPriorityQueue<T, const D: usize> = DaryIndexedPriorityQueue<T, D, Min>;
+
+pub type MaxDaryIndexedPriorityQueue<T, const D: usize> = DaryIndexedPriorityQueue<T, D, Max>;
+
+impl<T, const D: usize, O> Default for DaryIndexedPriorityQueue<T, D, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const D: usize, O> FromIterator<T> for DaryIndexedPriorityQueue<T, D, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Keys the values `0..n` in iteration order and heapifies them in `O(n)`.
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        let mut ipq = Self::new();
+        ipq.append(&mut values.into_iter().collect());
+
+        ipq
+    }
+}
+
+impl<T, const D: usize, O> DaryIndexedPriorityQueue<T, D, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    const ARITY: usize = {
+        assert!(D >= 2, "A d-ary heap needs at least two children per node");
+        D
+    };
+
+    pub fn new() -> Self {
+        let _ = Self::ARITY;
+
+        Self {
+            values: Vec::new(),
+            key_of_node: Vec::new(),
+            node_of_key: PagedPositionMap::default(),
+            order: PhantomData,
+        }
+    }
+
+    /// One past the greatest key index inserted so far, which [`push`](Self::push) hands out
+    /// next.
+    pub fn next_key_index(&self) -> usize {
+        self.node_of_key.key_bound()
+    }
+
+    pub fn try_insert(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<(), IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        if key_index >= DEFAULT_MAX_KEY_BOUND {
+            return Err(IpqError::KeyTooLarge {
+                key_index,
+                max_key_bound: DEFAULT_MAX_KEY_BOUND,
+            });
+        }
+        if self.contains(key_index) {
+            return Err(IpqError::KeyInUse(key_index));
+        }
+        check_comparable(key_index, &value)?;
+
+        let i = self.size();
+        self.node_of_key.set(KeyIdx(key_index), NodeIdx(i));
+        self.key_of_node.push(key_index);
+        self.values.push(value);
+        self.swim(NodeIdx(i));
+
+        Ok(())
+    }
+
+    pub fn value_ref(&self, key_index: impl Into<KeyIdx>) -> Option<&T> {
+        self.values.get(self.node_of_key.get(key_index.into())?.0)
+    }
+
+    fn node_index_or_panic(&self, key_index: usize) -> NodeIdx {
+        match self.node_of_key.get(KeyIdx(key_index)) {
+            Some(i) => i,
+            _ => panic!("Index does not exist; received: {}", key_index),
+        }
+    }
+
+    /// Replaces the value of node `i` and sifts it, returning the previous value.
+    fn replace_value(&mut self, i: NodeIdx, value: T) -> T {
+        let old_value = std::mem::replace(&mut self.values[i.0], value);
+
+        self.sink(i);
+        self.swim(i);
+
+        old_value
+    }
+
+    fn check_used_key(&self, key_index: usize) -> Result<(), IpqError> {
+        match self.contains(key_index) {
+            true => Ok(()),
+            false => Err(IpqError::KeyNotFound(key_index)),
+        }
+    }
+
+    fn heapify(&mut self) {
+        let last_parent = self.size().saturating_sub(2) / Self::ARITY;
+        if self.size() > 1 {
+            (0..=last_parent).rev().for_each(|i| self.sink(NodeIdx(i)));
+        }
+    }
+}
+
+/// The reads and removals of [`IndexedPriorityQueue`], callable without the trait, which is
+/// private to the crate when `deny-panics` is enabled without `panic-on-misuse`, and the
+/// `try_*` updates that never panic.
+impl<T, const D: usize, O> DaryIndexedPriorityQueue<T, D, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    pub fn size(&self) -> usize {
+        IndexedBinaryHeap::size(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        IndexedBinaryHeap::is_empty(self)
+    }
+
+    pub fn contains(&self, key_index: impl Into<KeyIdx>) -> bool {
+        IndexedPriorityQueue::contains(self, key_index)
+    }
+
+    pub fn value_of(&self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        IndexedPriorityQueue::value_of(self, key_index)
+    }
+
+    pub fn peek_min_key_index(&self) -> Option<usize> {
+        IndexedPriorityQueue::peek_min_key_index(self)
+    }
+
+    pub fn peek_min_value(&self) -> Option<T> {
+        IndexedPriorityQueue::peek_min_value(self)
+    }
+
+    pub fn poll_min_key_index(&mut self) -> Option<usize> {
+        IndexedPriorityQueue::poll_min_key_index(self)
+    }
+
+    pub fn poll_min_value(&mut self) -> Option<T> {
+        IndexedPriorityQueue::poll_min_value(self)
+    }
+
+    pub fn delete(&mut self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        IndexedPriorityQueue::delete(self, key_index)
+    }
+
+    /// Same as [`update`](IndexedPriorityQueue::update), returning an error instead of
+    /// panicking when `key_index` is not in the queue or `value` is not comparable with itself.
+    pub fn try_update(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<T, IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        let i = match self.node_of_key.get(KeyIdx(key_index)) {
+            Some(i) => i,
+            None => return Err(IpqError::KeyNotFound(key_index)),
+        };
+        check_comparable(key_index, &value)?;
+
+        Ok(self.replace_value(i, value))
+    }
+
+    /// Same as [`decrease`](IndexedPriorityQueue::decrease), returning an error instead of
+    /// panicking when `key_index` is not in the queue.
+    pub fn try_decrease(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<(), IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        self.check_used_key(key_index)?;
+        IndexedPriorityQueue::decrease(self, key_index, value);
+
+        Ok(())
+    }
+
+    /// Same as [`increase`](IndexedPriorityQueue::increase), returning an error instead of
+    /// panicking when `key_index` is not in the queue.
+    pub fn try_increase(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<(), IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        self.check_used_key(key_index)?;
+        IndexedPriorityQueue::increase(self, key_index, value);
+
+        Ok(())
+    }
+}
+
+impl<T, const D: usize, O> IndexedBinaryHeap for DaryIndexedPriorityQueue<T, D, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    #[inline]
+    fn less(&self, i: NodeIdx, j: NodeIdx) -> bool {
+        O::precedes(&self.values[i.0], &self.values[j.0])
+    }
+
+    fn size(&self) -> usize {
+        self.values.len()
+    }
+
+    fn swap(&mut self, i: NodeIdx, j: NodeIdx) {
+        self.key_of_node.swap(i.0, j.0);
+        self.node_of_key.set(KeyIdx(self.key_of_node[i.0]), i);
+        self.node_of_key.set(KeyIdx(self.key_of_node[j.0]), j);
+        self.values.swap(i.0, j.0);
+    }
+
+    fn arity(&self) -> usize {
+        Self::ARITY
+    }
+}
+
+impl<T, const D: usize, O> IndexedPriorityQueue<T> for DaryIndexedPriorityQueue<T, D, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn append(&mut self, extra_values: &mut Vec<T>) {
+        let (size, first_key_index) = (self.size(), self.next_key_index());
+        match first_key_index.checked_add(extra_values.len()) {
+            Some(key_bound) if key_bound <= DEFAULT_MAX_KEY_BOUND => {}
+            _ => panic!(
+                "{}",
+                IpqError::KeyTooLarge {
+                    key_index: first_key_index.max(DEFAULT_MAX_KEY_BOUND),
+                    max_key_bound: DEFAULT_MAX_KEY_BOUND,
+                }
+            ),
+        }
+
+        self.values.append(extra_values);
+        self.key_of_node.extend(first_key_index..first_key_index + self.size() - size);
+        (size..self.size()).for_each(|i| {
+            self.node_of_key.set(KeyIdx(first_key_index + i - size), NodeIdx(i));
+        });
+        self.heapify();
+    }
+
+    fn contains(&self, key_index: impl Into<KeyIdx>) -> bool {
+        self.node_of_key.get(key_index.into()).is_some()
+    }
+
+    fn decrease(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        let KeyIdx(key_index) = key_index.into();
+        let i = self.node_index_or_panic(key_index);
+        if value < self.values[i.0] {
+            self.replace_value(i, value);
+        }
+    }
+
+    fn delete(&mut self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        let KeyIdx(key_index) = key_index.into();
+        let i = self.node_of_key.get(KeyIdx(key_index))?;
+        let last = NodeIdx(self.size() - 1);
+        self.swap(i, last);
+        self.key_of_node.pop();
+        self.node_of_key.remove(KeyIdx(key_index));
+        let value = self.values.pop();
+
+        if i < last {
+            self.sink(i);
+            self.swim(i);
+        }
+
+        value
+    }
+
+    /// Removes the keys within `key_indexes`, returning their values by ascending key index.
+    ///
+    /// # Panics
+    ///
+    /// If the range is reversed or reaches past [`next_key_index`](Self::next_key_index).
+    fn drain<R: RangeBounds<usize>>(&mut self, key_indexes: R) -> IntoIter<T> {
+        let key_bound = self.next_key_index();
+        let start = match key_indexes.start_bound() {
+            Bound::Included(&start) => Some(start),
+            Bound::Excluded(&start) => start.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match key_indexes.end_bound() {
+            Bound::Included(&end) => end.checked_add(1),
+            Bound::Excluded(&end) => Some(end),
+            Bound::Unbounded => Some(key_bound),
+        };
+        let range = match (start, end) {
+            (Some(start), Some(end)) if start <= end && end <= key_bound => start..end,
+            _ => panic!(
+                "Key index range out of bounds; received: {:?}..{:?} with key bound {}",
+                key_indexes.start_bound(),
+                key_indexes.end_bound(),
+                key_bound
+            ),
+        };
+
+        range
+            .filter_map(|key_index| self.delete(key_index))
+            .collect::<Vec<T>>()
+            .into_iter()
+    }
+
+    fn insert(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        let KeyIdx(key_index) = key_index.into();
+        if let Err(e) = self.try_insert(key_index, value) {
+            panic!("{}", e);
+        }
+    }
+
+    fn increase(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        let KeyIdx(key_index) = key_index.into();
+        let i = self.node_index_or_panic(key_index);
+        if self.values[i.0] < value {
+            self.replace_value(i, value);
+        }
+    }
+
+    fn peek_min_key_index(&self) -> Option<usize> {
+        self.key_of_node.first().copied()
+    }
+
+    fn peek_min_value(&self) -> Option<T> {
+        self.values.first().cloned()
+    }
+
+    fn poll_min_key_index(&mut self) -> Option<usize> {
+        let min_key_index = self.peek_min_key_index()?;
+        self.delete(min_key_index);
+
+        Some(min_key_index)
+    }
+
+    fn poll_min_value(&mut self) -> Option<T> {
+        let min_key_index = self.peek_min_key_index()?;
+
+        self.delete(min_key_index)
+    }
+
+    fn push(&mut self, value: T) {
+        self.insert(self.next_key_index(), value);
+    }
+
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue or `value` is not comparable with itself, see
+    /// [`try_update`](DaryIndexedPriorityQueue::try_update).
+    fn update(&mut self, key_index: impl Into<KeyIdx>, value: T) -> T {
+        self.try_update(key_index, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn value_of(&self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        self.value_ref(key_index).cloned()
+    }
+}
+
+/// Number of children per node [`AutoDaryIndexedPriorityQueue::auto_tuned_from`] settles on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arity {
+    Binary,
+    Quaternary,
+    Octonary,
+}
+
+impl Arity {
+    /// Entries below which every arity runs about as fast, the heap fitting in cache.
+    pub const SMALL_QUEUE: usize = 1 << 12;
+    /// Value size up to which four siblings share a 64 byte cache line.
+    pub const SMALL_VALUE: usize = 16;
+
+    /// Picks the arity for `len` values of type `T`, following the `d-ary heaps` benchmarks:
+    ///
+    /// - below [`SMALL_QUEUE`](Self::SMALL_QUEUE) entries, a binary heap, being the simplest;
+    /// - up to [`SMALL_VALUE`](Self::SMALL_VALUE) bytes per value, a 4-ary heap, which polls
+    ///   about 10% faster than a binary one on 100K `u64`s, its siblings being read together;
+    /// - past it, an 8-ary heap, the shallowest, since moving a large value along a path
+    ///   costs more than comparing the siblings it sits among.
+    pub fn for_values<T>(len: usize) -> Self {
+        if len < Self::SMALL_QUEUE {
+            Arity::Binary
+        } else if size_of::<T>() <= Self::SMALL_VALUE {
+            Arity::Quaternary
+        } else {
+            Arity::Octonary
+        }
+    }
+
+    pub fn children(self) -> usize {
+        match self {
+            Arity::Binary => 2,
+            Arity::Quaternary => 4,
+            Arity::Octonary => 8,
+        }
+    }
+}
+
+/// A d-ary queue whose arity is picked at run time by
+/// [`auto_tuned_from`](Self::auto_tuned_from), for callers who would rather not pick `D`.
+///
+/// Every operation dispatches on the arity with a `match`, which costs next to nothing next to
+/// a sift.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::dary::{Arity, MinAutoDaryIndexedPriorityQueue};
+///
+/// let mut small = MinAutoDaryIndexedPriorityQueue::auto_tuned_from(vec![3u64, 1, 2]);
+/// assert_eq!(small.arity(), Arity::Binary);
+/// assert_eq!(small.poll_min_key_index(), Some(1));
+///
+/// let large = MinAutoDaryIndexedPriorityQueue::auto_tuned_from((0..100_000u64).rev().collect());
+/// assert_eq!(large.arity(), Arity::Quaternary);
+/// assert_eq!(large.peek_min_value(), Some(0));
+/// ```
+#[derive(Clone, Debug)]
+pub enum AutoDaryIndexedPriorityQueue<T, O> {
+    Binary(DaryIndexedPriorityQueue<T, 2, O>),
+    Quaternary(DaryIndexedPriorityQueue<T, 4, O>),
+    Octonary(DaryIndexedPriorityQueue<T, 8, O>),
+}
+
+pub type MinAutoDaryIndexedPriorityQueue<T> = AutoDaryIndexedPriorityQueue<T, Min>;
+
+pub type MaxAutoDaryIndexedPriorityQueue<T> = AutoDaryIndexedPriorityQueue<T, Max>;
+
+macro_rules! dispatch {
+    ($queue:expr, $ipq:ident => $body:expr) => {
+        match $queue {
+            AutoDaryIndexedPriorityQueue::Binary($ipq) => $body,
+            AutoDaryIndexedPriorityQueue::Quaternary($ipq) => $body,
+            AutoDaryIndexedPriorityQueue::Octonary($ipq) => $body,
+        }
+    };
+}
+
+impl<T, O> AutoDaryIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Keys `values` by position, as [`FromIterator`] does, in a heap of the arity
+    /// [`Arity::for_values`] picks for them.
+    pub fn auto_tuned_from(values: Vec<T>) -> Self {
+        Self::with_arity(Arity::for_values::<T>(values.len()), values)
+    }
+
+    /// Same as [`auto_tuned_from`](Self::auto_tuned_from), with the arity chosen by the caller.
+    pub fn with_arity(arity: Arity, mut values: Vec<T>) -> Self {
+        let mut ipq = match arity {
+            Arity::Binary => Self::Binary(DaryIndexedPriorityQueue::new()),
+            Arity::Quaternary => Self::Quaternary(DaryIndexedPriorityQueue::new()),
+            Arity::Octonary => Self::Octonary(DaryIndexedPriorityQueue::new()),
+        };
+        ipq.append(&mut values);
+
+        ipq
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self {
+            Self::Binary(_) => Arity::Binary,
+            Self::Quaternary(_) => Arity::Quaternary,
+            Self::Octonary(_) => Arity::Octonary,
+        }
+    }
+
+    pub fn next_key_index(&self) -> usize {
+        dispatch!(self, ipq => ipq.next_key_index())
+    }
+
+    pub fn try_insert(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<(), IpqError> {
+        dispatch!(self, ipq => ipq.try_insert(key_index, value))
+    }
+
+    pub fn value_ref(&self, key_index: impl Into<KeyIdx>) -> Option<&T> {
+        dispatch!(self, ipq => ipq.value_ref(key_index))
+    }
+
+    pub fn size(&self) -> usize {
+        dispatch!(self, ipq => ipq.size())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        dispatch!(self, ipq => ipq.is_empty())
+    }
+
+    pub fn contains(&self, key_index: impl Into<KeyIdx>) -> bool {
+        dispatch!(self, ipq => ipq.contains(key_index))
+    }
+
+    pub fn value_of(&self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        dispatch!(self, ipq => ipq.value_of(key_index))
+    }
+
+    pub fn peek_min_key_index(&self) -> Option<usize> {
+        dispatch!(self, ipq => ipq.peek_min_key_index())
+    }
+
+    pub fn peek_min_value(&self) -> Option<T> {
+        dispatch!(self, ipq => ipq.peek_min_value())
+    }
+
+    pub fn poll_min_key_index(&mut self) -> Option<usize> {
+        dispatch!(self, ipq => ipq.poll_min_key_index())
+    }
+
+    pub fn poll_min_value(&mut self) -> Option<T> {
+        dispatch!(self, ipq => ipq.poll_min_value())
+    }
+
+    pub fn delete(&mut self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        dispatch!(self, ipq => ipq.delete(key_index))
+    }
+
+    pub fn try_update(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<T, IpqError> {
+        dispatch!(self, ipq => ipq.try_update(key_index, value))
+    }
+
+    pub fn try_decrease(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<(), IpqError> {
+        dispatch!(self, ipq => ipq.try_decrease(key_index, value))
+    }
+
+    pub fn try_increase(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<(), IpqError> {
+        dispatch!(self, ipq => ipq.try_increase(key_index, value))
+    }
+}
+
+impl<T, O> IndexedBinaryHeap for AutoDaryIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn is_empty(&self) -> bool {
+        dispatch!(self, ipq => ipq.is_empty())
+    }
+
+    fn less(&self, i: NodeIdx, j: NodeIdx) -> bool {
+        dispatch!(self, ipq => ipq.less(i, j))
+    }
+
+    fn size(&self) -> usize {
+        dispatch!(self, ipq => ipq.size())
+    }
+
+    fn swap(&mut self, i: NodeIdx, j: NodeIdx) {
+        dispatch!(self, ipq => ipq.swap(i, j))
+    }
+
+    fn arity(&self) -> usize {
+        dispatch!(self, ipq => ipq.arity())
+    }
+}
+
+impl<T, O> IndexedPriorityQueue<T> for AutoDaryIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn append(&mut self, extra_values: &mut Vec<T>) {
+        dispatch!(self, ipq => ipq.append(extra_values))
+    }
+
+    fn contains(&self, key_index: impl Into<KeyIdx>) -> bool {
+        dispatch!(self, ipq => ipq.contains(key_index))
+    }
+
+    fn decrease(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        dispatch!(self, ipq => ipq.decrease(key_index, value))
+    }
+
+    fn delete(&mut self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        dispatch!(self, ipq => ipq.delete(key_index))
+    }
+
+    fn drain<R: RangeBounds<usize>>(&mut self, key_indexes: R) -> IntoIter<T> {
+        dispatch!(self, ipq => ipq.drain(key_indexes))
+    }
+
+    fn insert(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        dispatch!(self, ipq => ipq.insert(key_index, value))
+    }
+
+    fn increase(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        dispatch!(self, ipq => ipq.increase(key_index, value))
+    }
+
+    fn peek_min_key_index(&self) -> Option<usize> {
+        dispatch!(self, ipq => ipq.peek_min_key_index())
+    }
+
+    fn peek_min_value(&self) -> Option<T> {
+        dispatch!(self, ipq => ipq.peek_min_value())
+    }
+
+    fn poll_min_key_index(&mut self) -> Option<usize> {
+        dispatch!(self, ipq => ipq.poll_min_key_index())
+    }
+
+    fn poll_min_value(&mut self) -> Option<T> {
+        dispatch!(self, ipq => ipq.poll_min_value())
+    }
+
+    fn push(&mut self, value: T) {
+        dispatch!(self, ipq => ipq.push(value))
+    }
+
+    fn update(&mut self, key_index: impl Into<KeyIdx>, value: T) -> T {
+        dispatch!(self, ipq => ipq.update(key_index, value))
+    }
+
+    fn value_of(&self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        dispatch!(self, ipq => ipq.value_of(key_index))
+    }
+}
+
+#[cfg(test)]
+mod dary_indexed_pq_tests {
+    use crate::dary::{
+        Arity, AutoDaryIndexedPriorityQueue, MaxDaryIndexedPriorityQueue,
+        MinDaryIndexedPriorityQueue,
+    };
+    use crate::ipq::IndexedPriorityQueue;
+    use crate::limit::{IpqError, DEFAULT_MAX_KEY_BOUND};
+    use crate::order::Min;
+    use crate::testing::{check_ops, Op};
+
+    fn check_arity<const D: usize>(ops: &[Op<u8>]) {
+        let mut ipq = MinDaryIndexedPriorityQueue::<u8, D>::new();
+        assert_eq!(check_ops::<_, _, Min>(&mut ipq, ops), Ok(()));
+    }
+
+    #[test]
+    fn every_arity_should_match_the_reference_model() {
+        let bytes = (0..3_000u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect::<Vec<u8>>();
+        let ops = Op::<u8>::decode(&bytes);
+
+        check_arity::<2>(&ops);
+        check_arity::<3>(&ops);
+        check_arity::<4>(&ops);
+        check_arity::<8>(&ops);
+    }
+
+    #[test]
+    fn append_and_drain_should_key_values_in_order() {
+        let mut ipq = (0..6).collect::<MaxDaryIndexedPriorityQueue<i32, 4>>();
+        ipq.append(&mut vec![10, -1]);
+
+        assert_eq!(ipq.next_key_index(), 8);
+        assert_eq!(ipq.drain(2..=6).collect::<Vec<i32>>(), vec![2, 3, 4, 5, 10]);
+        assert_eq!(ipq.poll_min_key_index(), Some(1));
+        assert_eq!(ipq.size(), 2);
+        ipq.push(7);
+        assert_eq!(ipq.value_of(8), Some(7));
+    }
+
+    #[test]
+    fn auto_tuned_queues_should_pick_an_arity_and_match_the_reference_model() {
+        assert_eq!(Arity::for_values::<u64>(10), Arity::Binary);
+        assert_eq!(Arity::for_values::<u64>(1 << 20), Arity::Quaternary);
+        assert_eq!(Arity::for_values::<[u64; 4]>(1 << 20), Arity::Octonary);
+        assert_eq!(Arity::Octonary.children(), 8);
+
+        let bytes = (0..3_000u32)
+            .map(|i| (i.wrapping_mul(2246822519) >> 11) as u8)
+            .collect::<Vec<u8>>();
+        let ops = Op::<u8>::decode(&bytes);
+        for arity in [Arity::Binary, Arity::Quaternary, Arity::Octonary] {
+            let mut ipq = AutoDaryIndexedPriorityQueue::<u8, Min>::with_arity(arity, Vec::new());
+            assert_eq!(ipq.arity(), arity);
+            assert_eq!(check_ops::<_, _, Min>(&mut ipq, &ops), Ok(()));
+        }
+    }
+
+    #[test]
+    fn far_keys_should_allocate_a_single_page_and_nans_should_be_refused() {
+        let mut ipq = MinDaryIndexedPriorityQueue::<f64, 4>::new();
+        assert_eq!(ipq.try_insert(DEFAULT_MAX_KEY_BOUND - 1, 1.0), Ok(()));
+        assert_eq!(ipq.node_of_key.allocated_pages(), 1);
+        assert_eq!(ipq.next_key_index(), DEFAULT_MAX_KEY_BOUND);
+
+        let refused = ipq.try_update(DEFAULT_MAX_KEY_BOUND - 1, f64::NAN);
+        assert_eq!(refused, Err(IpqError::Incomparable(DEFAULT_MAX_KEY_BOUND - 1)));
+        assert_eq!(ipq.delete(DEFAULT_MAX_KEY_BOUND - 1), Some(1.0));
+        assert_eq!(ipq.node_of_key.allocated_pages(), 0);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::index::NodeIdx;
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+/// A priority filed under the deadline bucket it is due in, earlier buckets being ranked first
+/// whatever the order `O`, which only ranks priorities within a bucket.
+#[derive(Debug)]
+struct Slot<T, O> {
+    bucket: u64,
+    deadline: u64,
+    priority: T,
+    order: PhantomData<O>,
+}
+
+impl<T, O> Clone for Slot<T, O>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            bucket: self.bucket,
+            deadline: self.deadline,
+            priority: self.priority.clone(),
+            order: PhantomData,
+        }
+    }
+}
+
+impl<T, O> PartialEq for Slot<T, O>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.bucket == other.bucket
+            && self.deadline == other.deadline
+            && self.priority == other.priority
+    }
+}
+
+impl<T, O> PartialOrd for Slot<T, O>
+where
+    T: PartialOrd,
+    O: Order,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let earlier_first = |ordering: Ordering| {
+            if O::precedes(&0, &1) {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        };
+
+        match self.bucket.cmp(&other.bucket) {
+            Ordering::Equal => match self.priority.partial_cmp(&other.priority)? {
+                Ordering::Equal => Some(earlier_first(self.deadline.cmp(&other.deadline))),
+                ordering => Some(ordering),
+            },
+            ordering => Some(earlier_first(ordering)),
+        }
+    }
+}
+
+/// A scheduling queue ranking entries by deadline bucket first and by priority within a
+/// bucket, so that an urgent entry due soon beats an important one due later.
+///
+/// Deadlines and the current time are plain `u64` ticks, in whatever unit the caller keeps
+/// time in. A deadline falls in bucket `deadline / bucket_width`, and every entry whose bucket
+/// has passed is filed under the current one, where overdue and currently due entries compete
+/// by priority alone. Within a bucket, entries of equal priority are ranked by deadline.
+///
+/// Time only moves through [`advance_time`](Self::advance_time), which rotates the buckets,
+/// refiling the `k` entries that just fell due in `O(k log n)`.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::deadline::MaxDeadlineIpq;
+///
+/// let mut jobs = MaxDeadlineIpq::new(10);
+/// jobs.try_insert(0, 25, 9).unwrap();
+/// jobs.try_insert(1, 5, 1).unwrap();
+/// jobs.try_insert(2, 8, 3).unwrap();
+///
+/// assert_eq!(jobs.poll_min(), Some((2, 8, 3)));
+/// assert_eq!(jobs.advance_time(30), 2);
+/// assert_eq!(jobs.poll_min(), Some((0, 25, 9)));
+/// assert_eq!(jobs.poll_min(), Some((1, 5, 1)));
+/// ```
+pub struct DeadlineIpq<T, O>
+where
+    T: Clone + 'static,
+    O: 'static,
+{
+    heap: OrderedIndexedPriorityQueue<'static, Slot<T, O>, O>,
+    bucket_width: u64,
+    now: u64,
+}
+
+pub type MinDeadlineIpq<T> = DeadlineIpq<T, Min>;
+
+pub type MaxDeadlineIpq<T> = DeadlineIpq<T, Max>;
+
+impl<T, O> DeadlineIpq<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order + 'static,
+{
+    /// Starts at time `0` with buckets `bucket_width` ticks wide, a width of `0` counting as
+    /// `1`, i.e. a bucket per tick.
+    pub fn new(bucket_width: u64) -> Self {
+        Self {
+            heap: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+            bucket_width: bucket_width.max(1),
+            now: 0,
+        }
+    }
+
+    pub fn bucket_width(&self) -> u64 {
+        self.bucket_width
+    }
+
+    /// The latest time given to [`advance_time`](Self::advance_time).
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn size(&self) -> usize {
+        self.heap.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.heap.contains(key_index)
+    }
+
+    pub fn deadline_of(&self, key_index: usize) -> Option<u64> {
+        self.heap.value_ref(key_index).map(|slot| slot.deadline)
+    }
+
+    pub fn priority_of(&self, key_index: usize) -> Option<&T> {
+        self.heap.value_ref(key_index).map(|slot| &slot.priority)
+    }
+
+    /// How many buckets ahead of the current one `key_index` is due, `0` meaning it is due
+    /// in the current bucket or overdue.
+    pub fn buckets_ahead(&self, key_index: usize) -> Option<u64> {
+        let slot = self.heap.value_ref(key_index)?;
+
+        Some(slot.bucket - self.current_bucket())
+    }
+
+    /// Adds `priority` under `key_index`, due at `deadline`.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is already in the queue or past its
+    /// [`max_key_bound`](OrderedIndexedPriorityQueue::max_key_bound).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&mut self, key_index: usize, deadline: u64, priority: T) {
+        if let Err(e) = self.try_insert(key_index, deadline, priority) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Same as [`insert`](Self::insert), returning an error instead of panicking.
+    pub fn try_insert(
+        &mut self,
+        key_index: usize,
+        deadline: u64,
+        priority: T,
+    ) -> Result<(), IpqError> {
+        let slot = self.slot(deadline, priority);
+        self.heap.try_insert(key_index, slot)
+    }
+
+    /// Moves `key_index` to `deadline` with `priority`, returning its previous deadline and
+    /// priority, or `None` if the key is not in the queue.
+    pub fn update(&mut self, key_index: usize, deadline: u64, priority: T) -> Option<(u64, T)> {
+        if !self.heap.contains(key_index) {
+            return None;
+        }
+        let slot = self.heap.update(key_index, self.slot(deadline, priority));
+
+        Some((slot.deadline, slot.priority))
+    }
+
+    pub fn delete(&mut self, key_index: usize) -> Option<(u64, T)> {
+        self.heap
+            .delete(key_index)
+            .map(|slot| (slot.deadline, slot.priority))
+    }
+
+    /// Moves time forward to `now`, filing every entry whose bucket has passed under the
+    /// current one, and returns how many were refiled. Time never goes back: an earlier `now`
+    /// is ignored.
+    pub fn advance_time(&mut self, now: u64) -> usize {
+        if now <= self.now {
+            return 0;
+        }
+        self.now = now;
+
+        let current_bucket = self.current_bucket();
+        let mut refiled = 0;
+        while let Some(slot) = self.heap.values.first().filter(|s| s.bucket < current_bucket) {
+            let slot = Slot {
+                bucket: current_bucket,
+                ..slot.clone()
+            };
+            let key_index = self.heap.maps.key_at(NodeIdx::ROOT).unwrap().0;
+            self.heap.update(key_index, slot);
+            refiled += 1;
+        }
+
+        refiled
+    }
+
+    /// The entry ranked first as `(key_index, deadline, &priority)`.
+    pub fn peek_min(&self) -> Option<(usize, u64, &T)> {
+        let key_index = self.heap.peek_min_key_index()?;
+        let slot = self.heap.value_ref(key_index)?;
+
+        Some((key_index, slot.deadline, &slot.priority))
+    }
+
+    /// Removes the entry ranked first, returning it as `(key_index, deadline, priority)`.
+    pub fn poll_min(&mut self) -> Option<(usize, u64, T)> {
+        let (key_index, slot) = self.heap.swap_remove_min()?;
+
+        Some((key_index, slot.deadline, slot.priority))
+    }
+
+    fn current_bucket(&self) -> u64 {
+        self.now / self.bucket_width
+    }
+
+    fn slot(&self, deadline: u64, priority: T) -> Slot<T, O> {
+        Slot {
+            bucket: (deadline / self.bucket_width).max(self.current_bucket()),
+            deadline,
+            priority,
+            order: PhantomData,
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "panic-on-misuse", not(feature = "deny-panics"))))]
+mod deadline_ipq_tests {
+    use crate::deadline::{MaxDeadlineIpq, MinDeadlineIpq};
+
+    #[test]
+    fn urgent_entries_should_beat_important_ones_until_their_buckets_merge() {
+        let mut ipq = MaxDeadlineIpq::new(10);
+        ipq.insert(0, 5, 1);
+        ipq.insert(1, 14, 7);
+        ipq.insert(2, 25, 9);
+        ipq.insert(3, 9, 1);
+        assert_eq!(ipq.buckets_ahead(2), Some(2));
+
+        assert_eq!(ipq.poll_min(), Some((0, 5, 1)));
+        assert_eq!(ipq.advance_time(19), 1);
+        assert_eq!(ipq.advance_time(12), 0);
+        assert_eq!(ipq.now(), 19);
+        assert_eq!(ipq.buckets_ahead(3), Some(0));
+        assert_eq!(ipq.peek_min(), Some((1, 14, &7)));
+
+        ipq.insert(4, 3, 8);
+        assert_eq!(ipq.update(2, 11, 9), Some((25, 9)));
+        let polled = std::iter::from_fn(|| ipq.poll_min()).map(|(key_index, ..)| key_index);
+        assert_eq!(polled.collect::<Vec<usize>>(), vec![2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn ties_within_a_bucket_should_be_ranked_by_deadline() {
+        let mut ipq = MinDeadlineIpq::new(0);
+        assert_eq!(ipq.bucket_width(), 1);
+        (0..6).for_each(|key_index| ipq.insert(key_index, 10 - key_index as u64, 'a'));
+        assert_eq!(ipq.delete(5), Some((5, 'a')));
+        assert_eq!(ipq.update(9, 0, 'a'), None);
+
+        ipq.advance_time(100);
+        assert_eq!(ipq.buckets_ahead(0), Some(0));
+        let polled = std::iter::from_fn(|| ipq.poll_min()).map(|(_, deadline, _)| deadline);
+        assert_eq!(polled.collect::<Vec<u64>>(), vec![6, 7, 8, 9, 10]);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order, Total};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::cmp::Ordering;
+
+/// Heap entry ranked by its effective priority alone.
+///
+/// The effective priority is a [`Total`], so a NaN that the decay function yields at some age,
+/// e.g. `0.0 · inf`, ranks above every other priority rather than corrupting the heap.
+#[derive(Clone, Debug)]
+struct DecayingEntry {
+    effective: Total<f64>,
+    stored: f64,
+    inserted_at: f64,
+}
+
+impl PartialEq for DecayingEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.effective == other.effective
+    }
+}
+
+impl PartialOrd for DecayingEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.effective.partial_cmp(&other.effective)
+    }
+}
+
+/// An indexed priority queue whose priorities fade with age.
+///
+/// Each entry's effective priority is `stored_priority · decay(now - inserted_at)`, `decay`
+/// being any function of the age, e.g. `|age| (-age / half_life * LN_2).exp()`. Time is a
+/// plain `f64` on whatever clock the caller uses, and only moves through
+/// [`refresh`](Self::refresh): the queue merely records the new time, and recomputes the
+/// effective priorities and re-heapifies in `O(n)` on the next query that depends on the
+/// ranking, however many refreshes happened in between.
+///
+/// Use [`MaxDecayingIndexedPriorityQueue`] to serve the most relevant entries of a feed first,
+/// [`MinDecayingIndexedPriorityQueue`] to evict the least relevant entries of a cache first.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::decay::MaxDecayingIndexedPriorityQueue;
+///
+/// let mut feed = MaxDecayingIndexedPriorityQueue::new(|age: f64| 0.5f64.powf(age));
+/// feed.try_insert(0, 10.0, 0.0).unwrap();
+/// feed.try_insert(1, 4.0, 5.0).unwrap();
+///
+/// feed.refresh(5.0);
+/// assert_eq!(feed.peek_min_key_index(), Some(1));
+/// assert_eq!(feed.effective_priority_of(0), Some(10.0 / 32.0));
+/// ```
+pub struct DecayingIndexedPriorityQueue<D, O> {
+    heap: OrderedIndexedPriorityQueue<'static, DecayingEntry, O>,
+    decay: D,
+    now: f64,
+    stale: bool,
+}
+
+pub type MinDecayingIndexedPriorityQueue<D> = DecayingIndexedPriorityQueue<D, Min>;
+
+pub type MaxDecayingIndexedPriorityQueue<D> = DecayingIndexedPriorityQueue<D, Max>;
+
+impl<D, O> DecayingIndexedPriorityQueue<D, O>
+where
+    D: Fn(f64) -> f64,
+    O: Order,
+{
+    /// Creates an empty queue whose clock starts at `0.0`.
+    pub fn new(decay: D) -> Self {
+        Self {
+            heap: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+            decay,
+            now: 0.0,
+            stale: false,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.heap.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.heap.contains(key_index)
+    }
+
+    /// Time of the last [`refresh`](Self::refresh).
+    pub fn now(&self) -> f64 {
+        self.now
+    }
+
+    /// Adds `priority` under `key_index`, aged from `inserted_at` on.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is already in the queue or `priority` is not finite, see
+    /// [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&mut self, key_index: usize, priority: f64, inserted_at: f64) {
+        if let Err(e) = self.try_insert(key_index, priority, inserted_at) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Same as [`insert`](Self::insert), returning an error instead of panicking.
+    pub fn try_insert(
+        &mut self,
+        key_index: usize,
+        priority: f64,
+        inserted_at: f64,
+    ) -> Result<(), IpqError> {
+        if !priority.is_finite() {
+            return Err(IpqError::Incomparable(key_index));
+        }
+
+        self.settle();
+        let entry = DecayingEntry {
+            effective: Total::new(self.effective(priority, inserted_at)),
+            stored: priority,
+            inserted_at,
+        };
+        self.heap.try_insert(key_index, entry)
+    }
+
+    /// Removes `key_index`, returning its stored, undecayed, priority.
+    pub fn remove(&mut self, key_index: usize) -> Option<f64> {
+        self.heap.delete(key_index).map(|entry| entry.stored)
+    }
+
+    /// Moves the clock to `now`; the effective priorities follow lazily.
+    pub fn refresh(&mut self, now: f64) {
+        if now != self.now {
+            self.now = now;
+            self.stale = true;
+        }
+    }
+
+    pub fn stored_priority_of(&self, key_index: usize) -> Option<f64> {
+        self.heap.value_ref(key_index).map(|entry| entry.stored)
+    }
+
+    /// Returns the effective priority of `key_index` at the time of the last refresh.
+    pub fn effective_priority_of(&self, key_index: usize) -> Option<f64> {
+        self.heap
+            .value_ref(key_index)
+            .map(|entry| self.effective(entry.stored, entry.inserted_at))
+    }
+
+    pub fn peek_min_key_index(&mut self) -> Option<usize> {
+        self.settle();
+        if self.is_empty() {
+            return None;
+        }
+        self.heap.peek_min_key_index()
+    }
+
+    /// Removes the entry ranked first, returning its key and effective priority.
+    pub fn poll_min(&mut self) -> Option<(usize, f64)> {
+        let key_index = self.peek_min_key_index()?;
+        let entry = self.heap.delete(key_index)?;
+
+        Some((key_index, entry.effective.into_inner()))
+    }
+
+    fn effective(&self, stored: f64, inserted_at: f64) -> f64 {
+        stored * (self.decay)((self.now - inserted_at).max(0.0))
+    }
+
+    /// Brings every effective priority up to the current time, if it moved.
+    fn settle(&mut self) {
+        if !self.stale {
+            return;
+        }
+
+        let (now, decay) = (self.now, &self.decay);
+        self.heap.values.iter_mut().for_each(|entry| {
+            let effective = entry.stored * decay((now - entry.inserted_at).max(0.0));
+            entry.effective = Total::new(effective);
+        });
+        self.heap.fix_heap_invariant();
+        self.heap.publish_min();
+        self.stale = false;
+    }
+}
+
+#[cfg(test)]
+mod decaying_indexed_pq_tests {
+    use crate::limit::IpqError;
+    use crate::decay::{MaxDecayingIndexedPriorityQueue, MinDecayingIndexedPriorityQueue};
+    use std::cell::Cell;
+
+    #[test]
+    fn older_entries_should_fall_behind_as_time_passes() {
+        let half_life = 10.0;
+        let mut feed = MaxDecayingIndexedPriorityQueue::new(move |age: f64| {
+            0.5f64.powf(age / half_life)
+        });
+        feed.try_insert(0, 8.0, 0.0).unwrap();
+        feed.try_insert(1, 5.0, 0.0).unwrap();
+        feed.try_insert(2, 5.0, 10.0).unwrap();
+        assert_eq!(feed.peek_min_key_index(), Some(0));
+
+        feed.refresh(20.0);
+        assert_eq!(feed.effective_priority_of(0), Some(2.0));
+        assert_eq!(feed.poll_min(), Some((2, 2.5)));
+        assert_eq!(feed.poll_min(), Some((0, 2.0)));
+        assert_eq!(feed.stored_priority_of(1), Some(5.0));
+        assert!(feed.heap.satisfies_heap_invariant());
+    }
+
+    #[test]
+    fn refreshes_should_only_be_applied_on_the_next_query() {
+        let decays = Cell::new(0);
+        let mut cache = MinDecayingIndexedPriorityQueue::new(|age: f64| {
+            decays.set(decays.get() + 1);
+            1.0 / (1.0 + age)
+        });
+        (0..4).for_each(|k| cache.try_insert(k, 1.0, k as f64).unwrap());
+        assert_eq!(decays.get(), 4);
+
+        (1..=100).for_each(|t| cache.refresh(t as f64));
+        assert_eq!(decays.get(), 4);
+        assert_eq!(cache.poll_min(), Some((0, 1.0 / 101.0)));
+        assert_eq!(decays.get(), 8);
+
+        cache.refresh(100.0);
+        assert_eq!(cache.peek_min_key_index(), Some(1));
+        assert_eq!(decays.get(), 8);
+    }
+
+    #[test]
+    fn future_insertions_should_not_be_amplified() {
+        let mut ipq = MaxDecayingIndexedPriorityQueue::new(|age: f64| (-age).exp());
+        ipq.try_insert(0, 1.0, 5.0).unwrap();
+
+        assert_eq!(ipq.effective_priority_of(0), Some(1.0));
+        assert_eq!(ipq.try_insert(1, f64::NAN, 0.0), Err(IpqError::Incomparable(1)));
+        assert_eq!(ipq.remove(0), Some(1.0));
+        assert!(ipq.is_empty());
+    }
+
+    #[test]
+    fn non_finite_priorities_should_be_refused_and_nan_decays_ranked_last() {
+        let mut ipq = MinDecayingIndexedPriorityQueue::new(|age: f64| 1.0 / age);
+        assert_eq!(
+            ipq.try_insert(0, f64::INFINITY, 0.0),
+            Err(IpqError::Incomparable(0))
+        );
+        assert_eq!(
+            ipq.try_insert(0, f64::NEG_INFINITY, 0.0),
+            Err(IpqError::Incomparable(0))
+        );
+
+        ipq.try_insert(0, 0.0, 1.0).unwrap();
+        ipq.try_insert(1, 3.0, 1.0).unwrap();
+        ipq.try_insert(2, 2.0, 0.0).unwrap();
+
+        ipq.refresh(1.0);
+        assert!(ipq.effective_priority_of(0).unwrap().is_nan());
+        assert_eq!(ipq.poll_min(), Some((2, 2.0)));
+        assert!(ipq.heap.satisfies_heap_invariant());
+        assert_eq!(ipq.poll_min().map(|(k, _)| k), Some(1));
+        assert_eq!(ipq.poll_min().map(|(k, _)| k), Some(0));
+    }
+}
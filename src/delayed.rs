@@ -0,0 +1,316 @@
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::{check_comparable, OrderedIndexedPriorityQueue};
+use std::collections::HashMap;
+
+/// A job scheduler's pair of queues: entries wait in a time-ordered queue until they are due,
+/// then compete by priority in a ready queue.
+///
+/// `R` is whatever the caller measures time in, e.g. milliseconds as `u64` or an `Instant`.
+/// Time only moves through [`promote`](Self::promote) and [`poll_ready`](Self::poll_ready),
+/// which move every entry due at `now` over to the ready queue, in `O(d log n)` for `d` due
+/// entries.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::delayed::MaxDelayedIndexedPriorityQueue;
+///
+/// let mut jobs = MaxDelayedIndexedPriorityQueue::new();
+/// jobs.try_schedule(0, 10u64, 1).unwrap();
+/// jobs.try_schedule(1, 20u64, 9).unwrap();
+/// jobs.try_schedule(2, 5u64, 5).unwrap();
+///
+/// assert_eq!(jobs.poll_ready(15), Some((2, 5)));
+/// assert_eq!(jobs.poll_ready(15), Some((0, 1)));
+/// assert_eq!(jobs.poll_ready(15), None);
+/// assert_eq!(jobs.next_ready_at(), Some(&20));
+/// ```
+pub struct DelayedIndexedPriorityQueue<R, T, O>
+where
+    R: Clone + 'static,
+    T: Clone + 'static,
+{
+    delayed: OrderedIndexedPriorityQueue<'static, R, Min>,
+    waiting: HashMap<usize, T>,
+    ready: OrderedIndexedPriorityQueue<'static, T, O>,
+    generations: HashMap<usize, u64>,
+    next_generation: u64,
+}
+
+pub type MinDelayedIndexedPriorityQueue<R, T> = DelayedIndexedPriorityQueue<R, T, Min>;
+
+pub type MaxDelayedIndexedPriorityQueue<R, T> = DelayedIndexedPriorityQueue<R, T, Max>;
+
+/// Ticket returned by [`schedule`](DelayedIndexedPriorityQueue::schedule), naming one
+/// scheduling of a key index rather than the key index itself.
+///
+/// Once its entry is polled or cancelled, the handle goes stale and never matches the key
+/// index again, even after it is scheduled anew, so a timer cancelled late cannot remove
+/// whichever entry reused its key index.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::delayed::MinDelayedIndexedPriorityQueue;
+///
+/// let mut timers = MinDelayedIndexedPriorityQueue::new();
+/// let retry = timers.try_schedule(7, 100u64, 0).unwrap();
+/// assert_eq!(timers.poll_ready(100), Some((7, 0)));
+///
+/// let rescheduled = timers.try_schedule(7, 200u64, 1).unwrap();
+/// assert_eq!(retry.cancel(&mut timers), None);
+/// assert!(timers.contains(7));
+/// assert_eq!(rescheduled.cancel(&mut timers), Some(1));
+/// assert_eq!(rescheduled.cancel(&mut timers), None);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CancellationHandle {
+    key_index: usize,
+    generation: u64,
+}
+
+impl CancellationHandle {
+    pub fn key_index(&self) -> usize {
+        self.key_index
+    }
+
+    /// Returns `true` while the scheduling this handle names is waiting or ready.
+    pub fn is_pending<R, T, O>(&self, queue: &DelayedIndexedPriorityQueue<R, T, O>) -> bool
+    where
+        R: Clone + PartialOrd + 'static,
+        T: Clone + PartialOrd + 'static,
+        O: Order,
+    {
+        queue.generations.get(&self.key_index) == Some(&self.generation)
+    }
+
+    /// Removes the entry this handle names in `O(log n)`, returning its priority, or returns
+    /// `None` if it already fired or was cancelled, so calling it again is harmless.
+    pub fn cancel<R, T, O>(&self, queue: &mut DelayedIndexedPriorityQueue<R, T, O>) -> Option<T>
+    where
+        R: Clone + PartialOrd + 'static,
+        T: Clone + PartialOrd + 'static,
+        O: Order,
+    {
+        if !self.is_pending(queue) {
+            return None;
+        }
+
+        queue.cancel(self.key_index)
+    }
+}
+
+impl<R, T, O> Default for DelayedIndexedPriorityQueue<R, T, O>
+where
+    R: Clone + PartialOrd + 'static,
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R, T, O> DelayedIndexedPriorityQueue<R, T, O>
+where
+    R: Clone + PartialOrd + 'static,
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    pub fn new() -> Self {
+        Self {
+            delayed: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+            waiting: HashMap::new(),
+            ready: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+            generations: HashMap::new(),
+            next_generation: 0,
+        }
+    }
+
+    /// Number of entries, due or not.
+    pub fn size(&self) -> usize {
+        self.delayed.size() + self.ready.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.delayed.is_empty() && self.ready.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.delayed.contains(key_index) || self.ready.contains(key_index)
+    }
+
+    /// Returns `true` once `key_index` has been promoted to the ready queue.
+    pub fn is_ready(&self, key_index: usize) -> bool {
+        self.ready.contains(key_index)
+    }
+
+    pub fn ready_size(&self) -> usize {
+        self.ready.size()
+    }
+
+    /// Schedules `priority` under `key_index`, to compete for polls from `ready_at` on, and
+    /// returns a [`CancellationHandle`] for this scheduling.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is already scheduled or ready, or out of the key space, or if `priority`
+    /// is not comparable with itself, e.g. a NaN, see [`try_schedule`](Self::try_schedule).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn schedule(&mut self, key_index: usize, ready_at: R, priority: T) -> CancellationHandle {
+        self.try_schedule(key_index, ready_at, priority).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_schedule(
+        &mut self,
+        key_index: usize,
+        ready_at: R,
+        priority: T,
+    ) -> Result<CancellationHandle, IpqError> {
+        if self.contains(key_index) {
+            return Err(IpqError::KeyInUse(key_index));
+        }
+        check_comparable(key_index, &priority)?;
+        self.delayed.try_insert(key_index, ready_at)?;
+        self.waiting.insert(key_index, priority);
+
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        self.generations.insert(key_index, generation);
+
+        Ok(CancellationHandle {
+            key_index,
+            generation,
+        })
+    }
+
+    /// Removes `key_index`, due or not, returning its priority.
+    pub fn cancel(&mut self, key_index: usize) -> Option<T> {
+        self.generations.remove(&key_index);
+        if self.delayed.delete(key_index).is_some() {
+            return self.waiting.remove(&key_index);
+        }
+
+        self.ready.delete(key_index)
+    }
+
+    /// Time at which the next waiting entry becomes due, e.g. to know how long to sleep.
+    pub fn next_ready_at(&self) -> Option<&R> {
+        self.delayed.values.first()
+    }
+
+    /// Moves every entry due at `now` to the ready queue, returning how many were moved.
+    pub fn promote(&mut self, now: &R) -> usize {
+        let mut promoted = 0;
+        while self.next_ready_at().map_or(false, |ready_at| ready_at <= now) {
+            let key_index = self.delayed.poll_min_key_index().unwrap();
+            let priority = self.waiting.remove(&key_index).unwrap();
+            self.ready.insert(key_index, priority);
+            promoted += 1;
+        }
+
+        promoted
+    }
+
+    /// Promotes the entries due at `now`, then removes and returns the ready entry ranked first.
+    pub fn poll_ready(&mut self, now: R) -> Option<(usize, T)> {
+        self.promote(&now);
+        let key_index = self.ready.peek_min_key_index()?;
+        self.generations.remove(&key_index);
+
+        Some((key_index, self.ready.delete(key_index)?))
+    }
+}
+
+#[cfg(test)]
+mod delayed_indexed_pq_tests {
+    use crate::delayed::{MaxDelayedIndexedPriorityQueue, MinDelayedIndexedPriorityQueue};
+    use crate::limit::IpqError;
+
+    #[test]
+    fn entries_should_only_compete_once_due() {
+        let mut ipq = MinDelayedIndexedPriorityQueue::new();
+        ipq.try_schedule(0, 3.0, 'c').unwrap();
+        ipq.try_schedule(1, 1.0, 'z').unwrap();
+        ipq.try_schedule(2, 2.0, 'a').unwrap();
+
+        assert_eq!(ipq.poll_ready(0.5), None);
+        assert_eq!(ipq.promote(&1.0), 1);
+        assert_eq!(ipq.poll_ready(2.5), Some((2, 'a')));
+        assert_eq!(ipq.poll_ready(3.0), Some((0, 'c')));
+        assert_eq!(ipq.poll_ready(3.0), Some((1, 'z')));
+        assert!(ipq.is_empty());
+    }
+
+    #[test]
+    fn cancel_should_find_waiting_and_ready_entries() {
+        let mut ipq = MaxDelayedIndexedPriorityQueue::new();
+        ipq.try_schedule(4, 10u32, 40).unwrap();
+        ipq.try_schedule(5, 0u32, 50).unwrap();
+        ipq.promote(&0);
+
+        assert!(ipq.is_ready(5));
+        assert_eq!(ipq.cancel(4), Some(40));
+        assert_eq!(ipq.cancel(5), Some(50));
+        assert_eq!(ipq.cancel(5), None);
+        assert_eq!(ipq.next_ready_at(), None);
+    }
+
+    #[test]
+    fn stale_handles_should_not_cancel_rescheduled_keys() {
+        let mut ipq = MinDelayedIndexedPriorityQueue::new();
+        let first = ipq.try_schedule(3, 1u8, 'a').unwrap();
+        assert!(first.is_pending(&ipq));
+        assert_eq!(first.cancel(&mut ipq), Some('a'));
+        assert_eq!(first.cancel(&mut ipq), None);
+
+        let second = ipq.try_schedule(3, 1u8, 'b').unwrap();
+        assert_ne!(first, second);
+        assert!(!first.is_pending(&ipq));
+        assert_eq!(first.cancel(&mut ipq), None);
+        assert_eq!(ipq.poll_ready(1), Some((3, 'b')));
+
+        let third = ipq.try_schedule(3, 2u8, 'c').unwrap();
+        assert_eq!(second.cancel(&mut ipq), None);
+        assert_eq!(ipq.cancel(3), Some('c'));
+        assert!(!third.is_pending(&ipq));
+    }
+
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    #[test]
+    #[should_panic]
+    fn schedule_should_fail_for_keys_in_use() {
+        let mut ipq = MinDelayedIndexedPriorityQueue::new();
+        ipq.schedule(0, 1u8, 1);
+        ipq.promote(&1);
+
+        ipq.schedule(0, 2u8, 2);
+    }
+
+    #[test]
+    fn try_schedule_should_reject_keys_in_use_or_out_of_range() {
+        let mut ipq = MinDelayedIndexedPriorityQueue::new();
+        assert!(ipq.try_schedule(0, 1u8, 1).is_ok());
+        ipq.promote(&1);
+
+        assert_eq!(ipq.try_schedule(0, 2u8, 2), Err(IpqError::KeyInUse(0)));
+        assert!(matches!(
+            ipq.try_schedule(usize::MAX, 2u8, 2),
+            Err(IpqError::KeyTooLarge { .. })
+        ));
+        assert!(!ipq.contains(usize::MAX));
+        assert_eq!(ipq.poll_ready(2), Some((0, 1)));
+    }
+
+    #[test]
+    fn a_nan_priority_should_be_refused_before_it_is_due() {
+        let mut ipq = MinDelayedIndexedPriorityQueue::new();
+        assert_eq!(ipq.try_schedule(0, 1u8, f64::NAN), Err(IpqError::Incomparable(0)));
+        assert!(!ipq.contains(0));
+
+        assert!(ipq.try_schedule(1, 1u8, 0.5).is_ok());
+        assert_eq!(ipq.poll_ready(1), Some((1, 0.5)));
+    }
+}
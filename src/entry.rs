@@ -0,0 +1,382 @@
+use crate::index::{KeyIdx, NodeIdx};
+use crate::ipq::IndexedBinaryHeap;
+use crate::limit::IpqError;
+use crate::order::Order;
+use crate::{debug_assert_comparable, OrderedIndexedPriorityQueue};
+
+/// A key index of a queue, in the queue or not, created by
+/// [`entry`](OrderedIndexedPriorityQueue::entry).
+pub enum Entry<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// The key index is in the queue.
+    Occupied(OccupiedEntry<'q, 'a, T, O>),
+    /// The key index is free.
+    Vacant(VacantEntry<'q, 'a, T, O>),
+}
+
+/// A key index in the queue.
+pub struct OccupiedEntry<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    queue: &'q mut OrderedIndexedPriorityQueue<'a, T, O>,
+    key_index: usize,
+    node_index: NodeIdx,
+}
+
+/// A key index not in the queue.
+pub struct VacantEntry<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    queue: &'q mut OrderedIndexedPriorityQueue<'a, T, O>,
+    key_index: usize,
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Looks `key_index` up once, for an upsert that would otherwise check
+    /// [`contains`](IndexedPriorityQueue::contains) before choosing between
+    /// [`decrease`](IndexedPriorityQueue::decrease) and
+    /// [`insert`](IndexedPriorityQueue::insert).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::entry::Entry;
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let mut frontier = OwnedMinIpq::new();
+    /// for (node, distance) in [(4, 30u64), (2, 12), (4, 18), (4, 25)] {
+    ///     if let Entry::Vacant(entry) = frontier.entry(node).and_decrease(distance) {
+    ///         entry.try_insert(distance).unwrap();
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(frontier.poll_min_key_index(), Some(2));
+    /// assert_eq!(frontier.value_of(4), Some(18));
+    /// ```
+    pub fn entry(&mut self, key_index: impl Into<KeyIdx>) -> Entry<'_, 'a, T, O> {
+        let KeyIdx(key_index) = key_index.into();
+        match self.maps.node_of(KeyIdx(key_index)) {
+            Some(node_index) => Entry::Occupied(OccupiedEntry {
+                queue: self,
+                key_index,
+                node_index,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                queue: self,
+                key_index,
+            }),
+        }
+    }
+
+    /// Inserts `value` under `key_index` if the key is free, or lowers the key's value to
+    /// `value` if it is smaller, returning whether the queue changed: the relaxation step of
+    /// Dijkstra's and Prim's algorithms, with a single key lookup.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is free and out of the key space, see
+    /// [`try_decrease_or_insert`](Self::try_decrease_or_insert).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let mut frontier = OwnedMinIpq::new();
+    /// assert!(frontier.decrease_or_insert(3, 9u64));
+    /// assert!(frontier.decrease_or_insert(3, 4));
+    /// assert!(!frontier.decrease_or_insert(3, 6));
+    ///
+    /// assert_eq!(frontier.value_of(3), Some(4));
+    /// ```
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn decrease_or_insert(&mut self, key_index: impl Into<KeyIdx>, value: T) -> bool {
+        let KeyIdx(key_index) = key_index.into();
+        match self.try_decrease_or_insert(key_index, value) {
+            Ok(changed) => changed,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Same as [`decrease_or_insert`](Self::decrease_or_insert), returning an error instead
+    /// of panicking when `key_index` is free and out of the key space.
+    pub fn try_decrease_or_insert(
+        &mut self,
+        key_index: impl Into<KeyIdx>,
+        value: T,
+    ) -> Result<bool, IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        match self.entry(key_index) {
+            Entry::Occupied(mut entry) => Ok(entry.decrease(value)),
+            Entry::Vacant(entry) => entry.try_insert(value).map(|_| true),
+        }
+    }
+}
+
+impl<'q, 'a, T, O> Entry<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    pub fn key_index(&self) -> usize {
+        match self {
+            Entry::Occupied(entry) => entry.key_index,
+            Entry::Vacant(entry) => entry.key_index,
+        }
+    }
+
+    /// Lowers an occupied entry's value to `value` if it is smaller.
+    pub fn and_decrease(self, value: T) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                entry.decrease(value);
+                Entry::Occupied(entry)
+            }
+            vacant => vacant,
+        }
+    }
+
+    /// Rewrites an occupied entry's value in place with `f`, then moves it to its new rank.
+    pub fn update_with<F: FnOnce(&mut T)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                entry.update_with(f);
+                Entry::Occupied(entry)
+            }
+            vacant => vacant,
+        }
+    }
+
+    /// Inserts `value` into a vacant entry, then returns the entry's value.
+    ///
+    /// # Panics
+    ///
+    /// If the entry is vacant and its key index is out of the key space, see
+    /// [`VacantEntry::try_insert`].
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn or_insert(self, value: T) -> &'q T {
+        self.or_insert_with(|| value)
+    }
+
+    /// Same as [`or_insert`](Self::or_insert), only computing the value when vacant.
+    ///
+    /// # Panics
+    ///
+    /// If the entry is vacant and its key index is out of the key space, see
+    /// [`VacantEntry::try_insert`].
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn or_insert_with<F: FnOnce() -> T>(self, value: F) -> &'q T {
+        match self {
+            Entry::Occupied(entry) => entry.into_ref(),
+            Entry::Vacant(entry) => entry.insert(value()),
+        }
+    }
+}
+
+impl<'q, 'a, T, O> OccupiedEntry<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    pub fn key_index(&self) -> usize {
+        self.key_index
+    }
+
+    pub fn get(&self) -> &T {
+        &self.queue.values[self.node_index.0]
+    }
+
+    pub fn into_ref(self) -> &'q T {
+        &self.queue.values[self.node_index.0]
+    }
+
+    /// Lowers the value to `value` if it is smaller, returning `true` if it did.
+    pub fn decrease(&mut self, value: T) -> bool {
+        if value < *self.get() {
+            let rises = O::precedes(&value, self.get());
+            self.queue.values[self.node_index.0] = value;
+            self.queue.sift(self.node_index, rises);
+            self.queue.publish_min();
+            self.relocate();
+
+            return true;
+        }
+
+        false
+    }
+
+    /// Replaces the value, returning the previous one.
+    ///
+    /// # Panics
+    ///
+    /// If `value` is not comparable with itself, see [`try_update`](Self::try_update).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn update(&mut self, value: T) -> T {
+        self.try_update(value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`update`](Self::update), returning [`IpqError::Incomparable`] instead of
+    /// panicking.
+    pub fn try_update(&mut self, value: T) -> Result<T, IpqError> {
+        let old_value = self.queue.try_update(self.key_index, value)?;
+        self.relocate();
+
+        Ok(old_value)
+    }
+
+    /// Rewrites the value in place with `f`, then moves it to its new rank.
+    ///
+    /// Unlike [`try_update`](Self::try_update), a value left not comparable with itself is only
+    /// caught by a debug assertion.
+    pub fn update_with<F: FnOnce(&mut T)>(&mut self, f: F) {
+        f(&mut self.queue.values[self.node_index.0]);
+        debug_assert_comparable(&self.queue.values[self.node_index.0]);
+        self.queue.bump_version();
+        self.queue.sink(self.node_index);
+        self.queue.swim(self.node_index);
+        self.queue.publish_min();
+        self.relocate();
+    }
+
+    /// Removes the entry, returning its value.
+    pub fn remove(self) -> T {
+        self.queue.swap_remove(self.node_index).unwrap().1
+    }
+
+    fn relocate(&mut self) {
+        self.node_index = self.queue.maps.node_of(KeyIdx(self.key_index)).unwrap();
+    }
+}
+
+impl<'q, 'a, T, O> VacantEntry<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    pub fn key_index(&self) -> usize {
+        self.key_index
+    }
+
+    /// Inserts `value` under the entry's key index and returns it.
+    ///
+    /// # Panics
+    ///
+    /// If the key index is out of the key space, see [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(self, value: T) -> &'q T {
+        match self.try_insert(value) {
+            Ok(value) => value,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_insert(self, value: T) -> Result<&'q T, IpqError> {
+        self.queue.try_insert(self.key_index, value)?;
+
+        self.queue
+            .value_ref(self.key_index)
+            .ok_or(IpqError::KeyNotFound(self.key_index))
+    }
+}
+
+#[cfg(all(test, any(feature = "panic-on-misuse", not(feature = "deny-panics"))))]
+mod entry_tests {
+    use crate::entry::Entry;
+    use crate::limit::IpqError;
+    use crate::{OwnedMaxIpq, OwnedMinIpq};
+
+    #[test]
+    fn upserts_should_match_contains_then_decrease_or_insert() {
+        let mut by_entry = OwnedMinIpq::new();
+        let mut by_hand = OwnedMinIpq::new();
+        for step in 0..2_000usize {
+            let (key_index, value) = (step * 31 % 97, (step * 7919 % 1009) as u32);
+            by_entry
+                .entry(key_index)
+                .and_decrease(value)
+                .or_insert(value);
+            if by_hand.contains(key_index) {
+                by_hand.decrease(key_index, value);
+            } else {
+                by_hand.insert(key_index, value);
+            }
+        }
+
+        assert!(by_entry.satisfies_heap_invariant());
+        assert_eq!(by_entry.fingerprint(), by_hand.fingerprint());
+
+        let mut fused = OwnedMinIpq::new();
+        for step in 0..2_000usize {
+            let (key_index, value) = (step * 31 % 97, (step * 7919 % 1009) as u32);
+            let changed = fused.value_of(key_index).map_or(true, |old| value < old);
+            assert_eq!(fused.decrease_or_insert(key_index, value), changed);
+        }
+        assert_eq!(fused.fingerprint(), by_hand.fingerprint());
+        assert_eq!(
+            fused.try_decrease_or_insert(1 << 40, 0),
+            Err(IpqError::KeyTooLarge {
+                key_index: 1 << 40,
+                max_key_bound: fused.max_key_bound(),
+            })
+        );
+    }
+
+    #[test]
+    fn occupied_and_vacant_entries_should_keep_the_heap_in_order() {
+        let mut ipq = OwnedMaxIpq::from(vec![5, 8, 2]);
+        ipq.entry(2).update_with(|value| *value += 10);
+        assert_eq!(ipq.peek_min_key_index(), Some(2));
+
+        match ipq.entry(1) {
+            Entry::Occupied(mut entry) => {
+                assert!(!entry.decrease(9));
+                assert!(entry.decrease(1));
+                assert_eq!(entry.update(3), 1);
+                assert_eq!(entry.remove(), 3);
+            }
+            Entry::Vacant(_) => unreachable!(),
+        }
+        assert_eq!(
+            *ipq.entry(6).update_with(|value| *value = 0).or_insert(7),
+            7
+        );
+        assert_eq!(ipq.entry(6).key_index(), 6);
+        assert!(ipq.has_consistent_mappings() && ipq.satisfies_heap_invariant());
+
+        let mut bounded = OwnedMinIpq::new().with_max_key_bound(4);
+        match bounded.entry(4) {
+            Entry::Vacant(entry) => assert_eq!(
+                entry.try_insert('a'),
+                Err(IpqError::KeyTooLarge {
+                    key_index: 4,
+                    max_key_bound: 4
+                })
+            ),
+            Entry::Occupied(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn occupied_entries_should_refuse_values_not_comparable_with_themselves() {
+        let mut ipq = OwnedMinIpq::from(vec![1.0, 2.0]);
+        match ipq.entry(1) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.try_update(f64::NAN), Err(IpqError::Incomparable(1)));
+                assert_eq!(entry.try_update(0.5), Ok(2.0));
+            }
+            Entry::Vacant(_) => unreachable!(),
+        }
+        assert_eq!(ipq.peek_min_key_index(), Some(1));
+    }
+}
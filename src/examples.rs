@@ -0,0 +1,93 @@
+use crate::{graph, OwnedMinIpq};
+
+/// Returns the length of the shortest path from `source` to each node of `graph`, given as
+/// the `(node, weight)` edges leaving each node, or `None` for nodes it cannot reach.
+///
+/// The distances alone of [`graph::dijkstra`], which also returns the shortest paths.
+pub fn dijkstra(graph: &[Vec<(usize, u64)>], source: usize) -> Vec<Option<u64>> {
+    graph::dijkstra(graph, source).distances
+}
+
+/// Runs [`dijkstra`] from node `0` of a six node graph whose node `5` is unreachable.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::examples::dijkstra_small;
+///
+/// assert_eq!(dijkstra_small(), vec![Some(0), Some(3), Some(1), Some(4), Some(7), None]);
+/// ```
+pub fn dijkstra_small() -> Vec<Option<u64>> {
+    let graph = vec![
+        vec![(1, 4), (2, 1)],
+        vec![(3, 1)],
+        vec![(1, 2), (3, 5)],
+        vec![(4, 3)],
+        vec![],
+        vec![(0, 1)],
+    ];
+
+    dijkstra(&graph, 0)
+}
+
+/// Runs a timer driven event loop and returns the `(time, timer)` trace of what fired.
+///
+/// Timers are keyed by id and valued by the time they are due, so the loop polls whichever
+/// is due first, a periodic `tick` reschedules itself, `flush` brings the `timeout` forward
+/// with [`decrease`](crate::ipq::IndexedPriorityQueue::decrease), and `timeout` cancels the
+/// pending `tick` with [`delete`](crate::ipq::IndexedPriorityQueue::delete), which ends the
+/// loop.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::examples::event_loop_demo;
+///
+/// let trace = event_loop_demo();
+///
+/// assert_eq!(trace, vec![(10, "tick"), (15, "flush"), (20, "tick"), (22, "timeout")]);
+/// ```
+pub fn event_loop_demo() -> Vec<(u64, &'static str)> {
+    const TICK: usize = 0;
+    const TIMEOUT: usize = 1;
+    const FLUSH: usize = 2;
+    const NAMES: [&str; 3] = ["tick", "timeout", "flush"];
+
+    let mut timers = OwnedMinIpq::new();
+    timers.insert(TICK, 10);
+    timers.insert(TIMEOUT, 35);
+    timers.insert(FLUSH, 15);
+
+    let mut trace = Vec::new();
+    while let Some((timer, now)) = timers.swap_remove_min() {
+        trace.push((now, NAMES[timer]));
+        match timer {
+            TICK => timers.insert(TICK, now + 10),
+            FLUSH => timers.decrease(TIMEOUT, now + 7),
+            _ => {
+                timers.delete(TICK);
+            }
+        }
+    }
+
+    trace
+}
+
+#[cfg(test)]
+mod examples_tests {
+    use crate::examples::dijkstra;
+
+    #[test]
+    fn dijkstra_should_prefer_longer_paths_of_smaller_weight() {
+        let graph = vec![
+            vec![(1, 10), (2, 1), (0, 0)],
+            vec![(3, 1)],
+            vec![(2, 1), (4, 1)],
+            vec![],
+            vec![(1, 1), (3, 20)],
+        ];
+
+        assert_eq!(dijkstra(&graph, 0), vec![Some(0), Some(3), Some(1), Some(4), Some(2)]);
+        assert_eq!(dijkstra(&graph, 3), vec![None, None, None, Some(0), None]);
+    }
+}
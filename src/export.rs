@@ -0,0 +1,229 @@
+use crate::cursor::PriorityCursor;
+use crate::ipq::IndexedBinaryHeap;
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Turns queue entries into bytes for [`OrderedIndexedPriorityQueue::export_entries`].
+pub trait EntryEncoder<T> {
+    /// Written once, before the first entry.
+    fn begin(&mut self, _writer: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn encode(&mut self, writer: &mut dyn Write, key_index: usize, value: &T) -> io::Result<()>;
+}
+
+/// Comma separated values with a `key_index,value` header line.
+///
+/// Values containing a comma, a double quote or a line break are quoted as in RFC 4180.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Csv;
+
+impl<T> EntryEncoder<T> for Csv
+where
+    T: Display,
+{
+    fn begin(&mut self, writer: &mut dyn Write) -> io::Result<()> {
+        writeln!(writer, "key_index,value")
+    }
+
+    fn encode(&mut self, writer: &mut dyn Write, key_index: usize, value: &T) -> io::Result<()> {
+        let value = value.to_string();
+        if value.contains([',', '"', '\n', '\r']) {
+            writeln!(writer, "{},\"{}\"", key_index, value.replace('"', "\"\""))
+        } else {
+            writeln!(writer, "{},{}", key_index, value)
+        }
+    }
+}
+
+/// One `{"key_index":…,"value":…}` JSON object per line.
+///
+/// Values whose [`Display`] output is a JSON number are written as numbers, anything else
+/// becomes an escaped JSON string.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonLines;
+
+impl<T> EntryEncoder<T> for JsonLines
+where
+    T: Display,
+{
+    fn encode(&mut self, writer: &mut dyn Write, key_index: usize, value: &T) -> io::Result<()> {
+        let value = value.to_string();
+        write!(writer, "{{\"key_index\":{},\"value\":", key_index)?;
+        if is_json_number(&value) {
+            write!(writer, "{}", value)?;
+        } else {
+            write_json_string(writer, &value)?;
+        }
+        writeln!(writer, "}}")
+    }
+}
+
+fn is_json_number(s: &str) -> bool {
+    let mut chars = s.chars().peekable();
+    fn take_digits(chars: &mut Peekable<Chars>) -> bool {
+        let mut taken = false;
+        while chars.next_if(char::is_ascii_digit).is_some() {
+            taken = true;
+        }
+        taken
+    }
+
+    chars.next_if_eq(&'-');
+    match chars.next() {
+        Some('0') => {}
+        Some('1'..='9') => {
+            take_digits(&mut chars);
+        }
+        _ => return false,
+    }
+    if chars.next_if_eq(&'.').is_some() && !take_digits(&mut chars) {
+        return false;
+    }
+    if chars.next_if(|&c| c == 'e' || c == 'E').is_some() {
+        chars.next_if(|&c| c == '+' || c == '-');
+        if !take_digits(&mut chars) {
+            return false;
+        }
+    }
+
+    chars.next().is_none()
+}
+
+fn write_json_string(writer: &mut dyn Write, s: &str) -> io::Result<()> {
+    write!(writer, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            '\n' => write!(writer, "\\n")?,
+            '\r' => write!(writer, "\\r")?,
+            '\t' => write!(writer, "\\t")?,
+            c if (c as u32) < 0x20 => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{}", c)?,
+        }
+    }
+    write!(writer, "\"")
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Streams every `(key_index, value)` pair to `writer`, in priority order,
+    /// through the given [`EntryEncoder`], and returns how many entries were written.
+    ///
+    /// The queue is left untouched and nothing is cloned: entries are visited with a side
+    /// frontier of node indexes, so memory stays bounded by the heap width rather than by a
+    /// fully sorted copy of the queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::export::Csv;
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![30, 10, 20];
+    /// let ipq = MinIndexedPriorityQueue::from(&mut values);
+    ///
+    /// let mut out = Vec::new();
+    /// assert_eq!(ipq.export_entries(&mut out, Csv).unwrap(), 3);
+    /// assert_eq!(String::from_utf8(out).unwrap(), "key_index,value\n1,10\n2,20\n0,30\n");
+    /// ```
+    pub fn export_entries<W, E>(&self, mut writer: W, mut encoder: E) -> io::Result<usize>
+    where
+        W: Write,
+        E: EntryEncoder<T>,
+    {
+        encoder.begin(&mut writer)?;
+
+        let size = self.size();
+        let mut cursor = PriorityCursor::new(size);
+        let mut written = 0;
+        while let Some(node_index) = cursor.next(size, |i, j| self.less(i, j)) {
+            let key_index = self.maps.key_at(node_index).unwrap().0;
+            encoder.encode(&mut writer, key_index, &self.values[node_index.0])?;
+            written += 1;
+        }
+        writer.flush()?;
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod export_tests {
+    use crate::export::{is_json_number, Csv, JsonLines};
+    use crate::MinIndexedPriorityQueue;
+
+    #[test]
+    fn export_entries_should_follow_priority_order_without_consuming_the_queue() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let mut out = Vec::new();
+        assert_eq!(ipq.export_entries(&mut out, Csv).unwrap(), 12);
+
+        let exported_values = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|r| r.split(',').nth(1).unwrap().parse::<i32>().unwrap())
+            .collect::<Vec<i32>>();
+        let mut polled = Vec::new();
+        while let Some(v) = ipq.poll_min_value() {
+            polled.push(v);
+        }
+        assert_eq!(exported_values, polled);
+    }
+
+    #[test]
+    fn json_lines_should_write_numbers_raw_and_escape_strings() {
+        let mut values = vec![String::from("b \"quoted\""), String::from("a,1")];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+        let mut out = Vec::new();
+        ipq.export_entries(&mut out, JsonLines).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"key_index\":1,\"value\":\"a,1\"}\n{\"key_index\":0,\"value\":\"b \\\"quoted\\\"\"}\n"
+        );
+
+        let mut values = vec![2.5, -1.0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+        let mut out = Vec::new();
+        ipq.export_entries(&mut out, JsonLines).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"key_index\":1,\"value\":-1}\n{\"key_index\":0,\"value\":2.5}\n"
+        );
+    }
+
+    #[test]
+    fn csv_should_quote_values_with_separators() {
+        let mut values = vec![String::from("x,\"y\"")];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+        let mut out = Vec::new();
+        ipq.export_entries(&mut out, Csv).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "key_index,value\n0,\"x,\"\"y\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn json_number_detection_should_follow_the_json_grammar() {
+        ["0", "-1", "2.5", "1e10", "-0.25E-3"]
+            .iter()
+            .for_each(|s| assert!(is_json_number(s), "{}", s));
+        ["", "-", "01", "NaN", "inf", "+1", "1.", ".5", "0x10"]
+            .iter()
+            .for_each(|s| assert!(!is_json_number(s), "{}", s));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::order::Order;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+/// Waits of the entries polled with one priority, as reported by
+/// [`fairness_report`](crate::stable::StableIndexedPriorityQueue::fairness_report).
+///
+/// A wait is counted in insertions: the number of entries that arrived in the queue between
+/// an entry's own arrival and its poll, itself included.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassWaits<T> {
+    pub class: T,
+    pub polls: usize,
+    pub mean_wait: f64,
+    pub max_wait: u64,
+}
+
+/// Per-priority wait statistics over the latest polls of a queue, best priority first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FairnessReport<T> {
+    /// Number of polls the report covers, at most the window asked for.
+    pub polls: usize,
+    pub classes: Vec<ClassWaits<T>>,
+}
+
+impl<T> FairnessReport<T> {
+    /// Returns the longest wait of any class, `None` when no poll was recorded.
+    pub fn max_wait(&self) -> Option<u64> {
+        self.classes.iter().map(|class| class.max_wait).max()
+    }
+}
+
+/// The latest polls of a queue with the wait of each, oldest first, bounded to a capacity.
+pub(crate) struct PollLog<T> {
+    capacity: usize,
+    polls: VecDeque<(T, u64)>,
+}
+
+impl<T> PollLog<T>
+where
+    T: Clone + PartialOrd,
+{
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            polls: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Records a poll in `O(1)`, forgetting the oldest one once full.
+    pub(crate) fn record(&mut self, value: &T, wait: u64) {
+        if self.polls.len() == self.capacity {
+            self.polls.pop_front();
+        }
+        if self.capacity > 0 {
+            self.polls.push_back((value.clone(), wait));
+        }
+    }
+
+    /// Groups the latest `window` polls by equal value, in `O(w log w)`.
+    pub(crate) fn report<O: Order>(&self, window: usize) -> FairnessReport<T> {
+        let skipped = self.polls.len().saturating_sub(window);
+        let mut latest = self.polls.iter().skip(skipped).collect::<Vec<&(T, u64)>>();
+        latest.sort_by(|(a, _), (b, _)| {
+            if O::precedes(a, b) {
+                Ordering::Less
+            } else if O::precedes(b, a) {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        });
+
+        let mut classes: Vec<ClassWaits<T>> = Vec::new();
+        let mut total_waits: Vec<u64> = Vec::new();
+        for (value, wait) in &latest {
+            match classes.last_mut() {
+                Some(class) if class.class == *value => {
+                    class.polls += 1;
+                    class.max_wait = class.max_wait.max(*wait);
+                    *total_waits.last_mut().unwrap() += wait;
+                }
+                _ => {
+                    classes.push(ClassWaits {
+                        class: value.clone(),
+                        polls: 1,
+                        mean_wait: 0.0,
+                        max_wait: *wait,
+                    });
+                    total_waits.push(*wait);
+                }
+            }
+        }
+        classes
+            .iter_mut()
+            .zip(total_waits)
+            .for_each(|(class, total)| class.mean_wait = total as f64 / class.polls as f64);
+
+        FairnessReport {
+            polls: latest.len(),
+            classes,
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+use std::hash::{Hash, Hasher};
+
+/// 64 bit FNV-1a, with integers written little-endian and `usize`s widened to `u64`, so that
+/// a value hashes the same on every platform and Rust release.
+struct Fnv64(u64);
+
+impl Fnv64 {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64);
+    }
+}
+
+/// SplitMix64's finalizer, spreading every input bit over the whole output.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    x ^ (x >> 31)
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Hashes the `key_index → value` pairs of the queue, whatever their place in the heap, so
+    /// that replicas can compare their pending entries by exchanging a single `u64`.
+    ///
+    /// Each pair is hashed on its own and the hashes are summed, in `O(n)` and without
+    /// allocating, so queues holding the same pairs agree however they were built. The hash
+    /// is fixed, integers being hashed little-endian, and thus stable across processes,
+    /// platforms and Rust releases for any `T` whose `Hash` implementation is. Holds, metadata
+    /// and settings are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let mut primary = OwnedMinIpq::new();
+    /// primary.try_insert_all([(3, "reindex"), (8, "backup")]);
+    ///
+    /// let mut replica = OwnedMinIpq::new();
+    /// replica.try_insert_all([(8, "backup"), (3, "reindex")]);
+    /// assert_eq!(primary.fingerprint(), replica.fingerprint());
+    ///
+    /// replica.try_update(8, "vacuum").unwrap();
+    /// assert_ne!(primary.fingerprint(), replica.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64
+    where
+        T: Hash,
+    {
+        let sum = self.iter().fold(0u64, |sum, (key_index, value)| {
+            let mut hasher = Fnv64::new();
+            key_index.hash(&mut hasher);
+            value.hash(&mut hasher);
+            sum.wrapping_add(mix(hasher.finish()))
+        });
+
+        mix(sum ^ self.size() as u64)
+    }
+}
+
+#[cfg(all(test, any(feature = "panic-on-misuse", not(feature = "deny-panics"))))]
+mod fingerprint_tests {
+    use crate::{OwnedMaxIpq, OwnedMinIpq};
+
+    #[test]
+    fn fingerprint_should_only_depend_on_the_key_value_pairs() {
+        let entries = (0..200).map(|i| (i * 7 % 211, (i * 31 % 17) as u32));
+        let min_ipq = entries.clone().collect::<OwnedMinIpq<u32>>();
+        let mut max_ipq = OwnedMaxIpq::new();
+        entries.rev().for_each(|(key_index, value)| max_ipq.insert(key_index, value));
+        assert_eq!(min_ipq.fingerprint(), max_ipq.fingerprint());
+        assert_eq!(min_ipq.fingerprint(), 0xffe8_cd74_6dcb_eb31);
+
+        let value = max_ipq.delete(7).unwrap();
+        assert_ne!(min_ipq.fingerprint(), max_ipq.fingerprint());
+        max_ipq.insert(134, value);
+        assert_ne!(min_ipq.fingerprint(), max_ipq.fingerprint());
+        max_ipq.delete(134);
+        max_ipq.insert(7, value);
+        assert_eq!(min_ipq.fingerprint(), max_ipq.fingerprint());
+
+        let empty = OwnedMinIpq::<u32>::new();
+        assert_ne!(empty.fingerprint(), OwnedMinIpq::from(vec![0]).fingerprint());
+    }
+}
@@ -0,0 +1,355 @@
+use crate::check_comparable;
+use crate::index::NodeIdx;
+use crate::ipq::IndexedBinaryHeap;
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use std::marker::PhantomData;
+
+const NO_NODE: usize = usize::MAX;
+
+/// An indexed priority queue of at most `N` entries, keyed by `0..N`, held in fixed arrays.
+///
+/// [`new`](Self::new) is a `const fn`, so an empty queue can be a `static` or live on the
+/// stack, and no operation ever allocates. Inserting into a full queue reports
+/// [`IpqError::CapacityExceeded`] through [`try_insert`](Self::try_insert) and
+/// [`try_push`](Self::try_push); since keys are unique and below `N`, a full queue has no
+/// key index left.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::fixed::MinConstIpq;
+/// use indexed_priority_queue::limit::IpqError;
+///
+/// static NO_TIMERS: MinConstIpq<u32, 2> = MinConstIpq::new();
+///
+/// let mut timers = MinConstIpq::<u32, 2>::new();
+/// assert_eq!(timers.try_push(30), Ok(0));
+/// assert_eq!(timers.try_push(10), Ok(1));
+/// assert_eq!(timers.try_push(20), Err(IpqError::CapacityExceeded { capacity: 2 }));
+/// assert_eq!(timers.poll_min(), Some((1, 10)));
+/// assert!(NO_TIMERS.is_empty());
+/// ```
+pub struct ConstIpq<T, O, const N: usize> {
+    values: [Option<T>; N],
+    inverse_map: [usize; N],
+    position_map: [usize; N],
+    size: usize,
+    order: PhantomData<O>,
+}
+
+pub type MinConstIpq<T, const N: usize> = ConstIpq<T, Min, N>;
+
+pub type MaxConstIpq<T, const N: usize> = ConstIpq<T, Max, N>;
+
+/// [`MinConstIpq`] under the name embedded users look for: never allocating, it fits interrupt
+/// handlers and RTOS tasks. Once [`is_full`](ConstIpq::is_full), no key index is left, so
+/// [`try_insert`](ConstIpq::try_insert) and [`try_push`](ConstIpq::try_push) report
+/// `IpqError::CapacityExceeded { capacity: N }`.
+pub type ArrayIndexedPriorityQueue<T, const N: usize> = MinConstIpq<T, N>;
+
+impl<T, O, const N: usize> ConstIpq<T, O, N> {
+    const VACANT: Option<T> = None;
+
+    pub const fn new() -> Self {
+        Self {
+            values: [Self::VACANT; N],
+            inverse_map: [NO_NODE; N],
+            position_map: [NO_NODE; N],
+            size: 0,
+            order: PhantomData,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Returns `true` once every key index of `0..N` is in use.
+    pub const fn is_full(&self) -> bool {
+        self.size == N
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        key_index < N && self.position_map[key_index] != NO_NODE
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Option<&T> {
+        if !self.contains(key_index) {
+            return None;
+        }
+
+        self.values[self.position_map[key_index]].as_ref()
+    }
+}
+
+impl<T, O, const N: usize> Default for ConstIpq<T, O, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, O, const N: usize> ConstIpq<T, O, N>
+where
+    T: PartialOrd,
+    O: Order,
+{
+    /// # Panics
+    ///
+    /// If the queue is full, or `key_index` is in use or not below `N`, see
+    /// [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&mut self, key_index: usize, value: T) {
+        if let Err(e) = self.try_insert(key_index, value) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Like [`insert`](Self::insert), reporting a full queue as
+    /// [`IpqError::CapacityExceeded`] before looking at `key_index`.
+    pub fn try_insert(&mut self, key_index: usize, value: T) -> Result<(), IpqError> {
+        if self.is_full() {
+            return Err(IpqError::CapacityExceeded { capacity: N });
+        }
+        if key_index >= N {
+            return Err(IpqError::KeyTooLarge {
+                key_index,
+                max_key_bound: N,
+            });
+        }
+        if self.contains(key_index) {
+            return Err(IpqError::KeyInUse(key_index));
+        }
+        check_comparable(key_index, &value)?;
+
+        let i = NodeIdx(self.size);
+        self.size += 1;
+        self.values[i.0] = Some(value);
+        self.inverse_map[i.0] = key_index;
+        self.position_map[key_index] = i.0;
+        self.swim(i);
+
+        Ok(())
+    }
+
+    /// Inserts `value` under the smallest free key index, returning it.
+    ///
+    /// # Panics
+    ///
+    /// If the queue is full, see [`try_push`](Self::try_push).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn push(&mut self, value: T) -> usize {
+        match self.try_push(value) {
+            Ok(key_index) => key_index,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Like [`push`](Self::push), reporting a full queue as
+    /// [`IpqError::CapacityExceeded`].
+    pub fn try_push(&mut self, value: T) -> Result<usize, IpqError> {
+        let key_index = match (0..N).find(|&key_index| !self.contains(key_index)) {
+            Some(key_index) => key_index,
+            None => return Err(IpqError::CapacityExceeded { capacity: N }),
+        };
+        self.try_insert(key_index, value).map(|_| key_index)
+    }
+
+    pub fn peek_min_key_index(&self) -> Option<usize> {
+        (!self.is_empty()).then(|| self.inverse_map[0])
+    }
+
+    pub fn peek_min_value(&self) -> Option<&T> {
+        self.values[..self.size].first()?.as_ref()
+    }
+
+    pub fn poll_min(&mut self) -> Option<(usize, T)> {
+        let key_index = self.peek_min_key_index()?;
+
+        Some((key_index, self.delete(key_index)?))
+    }
+
+    /// Replaces `key_index`'s value, returning the previous one.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue, see [`try_update`](Self::try_update).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn update(&mut self, key_index: usize, value: T) -> T {
+        self.try_update(key_index, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_update(&mut self, key_index: usize, value: T) -> Result<T, IpqError> {
+        let i = self.node_index(key_index)?;
+        check_comparable(key_index, &value)?;
+        let old_value = self.values[i.0].replace(value).unwrap();
+
+        self.sink(i);
+        self.swim(i);
+
+        Ok(old_value)
+    }
+
+    /// Lowers `key_index`'s value to `value` if it is smaller.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue, see [`try_decrease`](Self::try_decrease).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn decrease(&mut self, key_index: usize, value: T) {
+        self.try_decrease(key_index, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_decrease(&mut self, key_index: usize, value: T) -> Result<(), IpqError> {
+        let i = self.node_index(key_index)?;
+        if value < *self.value_at(i) {
+            self.try_update(key_index, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Raises `key_index`'s value to `value` if it is greater.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue, see [`try_increase`](Self::try_increase).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn increase(&mut self, key_index: usize, value: T) {
+        self.try_increase(key_index, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_increase(&mut self, key_index: usize, value: T) -> Result<(), IpqError> {
+        let i = self.node_index(key_index)?;
+        if *self.value_at(i) < value {
+            self.try_update(key_index, value)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self, key_index: usize) -> Option<T> {
+        if !self.contains(key_index) {
+            return None;
+        }
+
+        let i = NodeIdx(self.position_map[key_index]);
+        let last = NodeIdx(self.size - 1);
+        self.swap(i, last);
+        self.size -= 1;
+        self.position_map[key_index] = NO_NODE;
+        self.inverse_map[last.0] = NO_NODE;
+        let value = self.values[last.0].take();
+
+        if i < last {
+            self.sink(i);
+            self.swim(i);
+        }
+
+        value
+    }
+
+    fn node_index(&self, key_index: usize) -> Result<NodeIdx, IpqError> {
+        if !self.contains(key_index) {
+            return Err(IpqError::KeyNotFound(key_index));
+        }
+
+        Ok(NodeIdx(self.position_map[key_index]))
+    }
+
+    fn value_at(&self, i: NodeIdx) -> &T {
+        self.values[i.0].as_ref().unwrap()
+    }
+}
+
+impl<T, O, const N: usize> IndexedBinaryHeap for ConstIpq<T, O, N>
+where
+    T: PartialOrd,
+    O: Order,
+{
+    fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    fn less(&self, i: NodeIdx, j: NodeIdx) -> bool {
+        O::precedes(self.value_at(i), self.value_at(j))
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn swap(&mut self, i: NodeIdx, j: NodeIdx) {
+        self.values.swap(i.0, j.0);
+        self.inverse_map.swap(i.0, j.0);
+        self.position_map[self.inverse_map[i.0]] = i.0;
+        self.position_map[self.inverse_map[j.0]] = j.0;
+    }
+}
+
+#[cfg(test)]
+mod const_ipq_tests {
+    use crate::fixed::{ArrayIndexedPriorityQueue, MinConstIpq};
+    use crate::limit::IpqError;
+
+    static EMPTY: ArrayIndexedPriorityQueue<u8, 4> = ArrayIndexedPriorityQueue::new();
+
+    #[test]
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    fn const_ipq_should_poll_like_a_heap_within_its_capacity() {
+        let mut ipq = crate::fixed::MaxConstIpq::<i32, 8>::new();
+        [5, -1, 7, 3, 3, 0].iter().enumerate().for_each(|(k, &v)| ipq.insert(k, v));
+
+        ipq.decrease(2, 1);
+        ipq.increase(1, 6);
+        assert_eq!(ipq.update(4, 4), 3);
+        assert_eq!(ipq.delete(0), Some(5));
+        assert_eq!(ipq.delete(0), None);
+
+        let polled = std::iter::from_fn(|| ipq.poll_min()).collect::<Vec<(usize, i32)>>();
+        assert_eq!(polled, vec![(1, 6), (4, 4), (3, 3), (2, 1), (5, 0)]);
+    }
+
+    #[test]
+    fn const_ipq_should_refuse_keys_outside_its_key_space() {
+        let mut ipq = MinConstIpq::<char, 2>::new();
+
+        assert_eq!(ipq.try_insert(1, 'b'), Ok(()));
+        assert_eq!(ipq.try_insert(1, 'c'), Err(IpqError::KeyInUse(1)));
+        assert_eq!(
+            ipq.try_insert(2, 'c'),
+            Err(IpqError::KeyTooLarge { key_index: 2, max_key_bound: 2 })
+        );
+        assert_eq!(ipq.try_push('a'), Ok(0));
+        assert!(ipq.is_full());
+        assert_eq!(ipq.try_push('z'), Err(IpqError::CapacityExceeded { capacity: 2 }));
+        assert_eq!(ipq.peek_min_value(), Some(&'a'));
+        assert_eq!(ipq.value_of(1), Some(&'b'));
+        assert_eq!(ipq.value_of(7), None);
+        ipq.poll_min();
+        assert_eq!(ipq.value_of(0), None);
+    }
+
+    #[test]
+    fn const_ipq_should_report_a_full_queue_before_checking_the_key() {
+        let mut ipq = ArrayIndexedPriorityQueue::<u8, 2>::new();
+        assert_eq!(ipq.try_insert(0, 4), Ok(()));
+        assert_eq!(ipq.try_insert(1, 2), Ok(()));
+
+        let full = Err(IpqError::CapacityExceeded { capacity: 2 });
+        assert_eq!(ipq.try_insert(0, 1), full);
+        assert_eq!(ipq.try_insert(5, 1), full);
+        assert_eq!(ipq.poll_min(), Some((1, 2)));
+        assert_eq!(ipq.try_insert(1, 1), Ok(()));
+        assert!(EMPTY.is_empty());
+        assert_eq!(EMPTY.capacity(), 4);
+    }
+}
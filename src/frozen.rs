@@ -0,0 +1,148 @@
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+/// A read-only queue whose entries are laid out in the order they would be polled, built by
+/// [`freeze`](OrderedIndexedPriorityQueue::freeze).
+///
+/// Only two boxed slices remain, key indexes and values, so it takes no more memory than the
+/// entries themselves and answers rank queries in `O(1)` or `O(log n)`.
+pub struct FrozenIpq<T, O> {
+    key_indexes: Box<[usize]>,
+    values: Box<[T]>,
+    order: PhantomData<O>,
+}
+
+pub type MinFrozenIpq<T> = FrozenIpq<T, Min>;
+
+pub type MaxFrozenIpq<T> = FrozenIpq<T, Max>;
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Consumes the queue into a [`FrozenIpq`], sorting its entries once in `O(n log n)`.
+    ///
+    /// Entries ranked equal are laid out by ascending key index. Values borrowed from the
+    /// caller are cloned, so the caller's vector keeps them in the heap order the queue left
+    /// them in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![40, 10, 30, 20];
+    /// let frozen = MinIndexedPriorityQueue::from(&mut values).freeze();
+    ///
+    /// assert_eq!(frozen.peek_min(), Some((1, &10)));
+    /// assert_eq!(frozen.kth_smallest(2), Some((2, &30)));
+    /// assert_eq!(frozen.count_le(&25), 2);
+    /// assert_eq!(frozen.iter().map(|(_, v)| *v).collect::<Vec<i32>>(), vec![10, 20, 30, 40]);
+    /// ```
+    pub fn freeze(self) -> FrozenIpq<T, O> {
+        let size = self.values.len();
+        let values = match self.values {
+            Storage::Borrowed(values) => values.clone(),
+            Storage::Owned(values) => values,
+        };
+        let mut entries = self
+            .maps
+            .keys_by_node(size)
+            .map(|k| k.0)
+            .zip(values)
+            .collect::<Vec<(usize, T)>>();
+        entries.sort_by(|(a_key, a), (b_key, b)| {
+            if O::precedes(a, b) {
+                Ordering::Less
+            } else if O::precedes(b, a) {
+                Ordering::Greater
+            } else {
+                a_key.cmp(b_key)
+            }
+        });
+
+        let (key_indexes, values): (Vec<usize>, Vec<T>) = entries.into_iter().unzip();
+        FrozenIpq {
+            key_indexes: key_indexes.into_boxed_slice(),
+            values: values.into_boxed_slice(),
+            order: PhantomData,
+        }
+    }
+}
+
+impl<T, O> FrozenIpq<T, O>
+where
+    T: PartialOrd,
+    O: Order,
+{
+    pub fn size(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the key index and value ranked first.
+    pub fn peek_min(&self) -> Option<(usize, &T)> {
+        self.kth_smallest(0)
+    }
+
+    /// Returns the entry that would be polled after `k` others, `k` counting from `0`.
+    pub fn kth_smallest(&self, k: usize) -> Option<(usize, &T)> {
+        Some((*self.key_indexes.get(k)?, self.values.get(k)?))
+    }
+
+    /// Counts the entries ranked before or equal to `value`, i.e. the values `<= value` in a
+    /// minimum queue and `>= value` in a maximum one.
+    pub fn count_le(&self, value: &T) -> usize {
+        self.values.partition_point(|v| !O::precedes(value, v))
+    }
+
+    /// Iterates over the entries in the order they would be polled.
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (usize, &T)> + ExactSizeIterator + '_ {
+        self.key_indexes.iter().copied().zip(self.values.iter())
+    }
+}
+
+#[cfg(test)]
+mod frozen_ipq_tests {
+    use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue};
+
+    #[test]
+    fn freeze_should_lay_entries_out_in_polling_order() {
+        let mut ipq = MaxIndexedPriorityQueue::from_entries(Vec::new());
+        [3, 9, 1, 9, 5, 7].iter().enumerate().for_each(|(k, &v)| ipq.insert(k, v));
+        ipq.delete(4);
+
+        let frozen = ipq.freeze();
+        assert_eq!(frozen.size(), 5);
+        assert_eq!(frozen.peek_min(), Some((1, &9)));
+        assert_eq!(frozen.kth_smallest(1), Some((3, &9)));
+        assert_eq!(frozen.kth_smallest(5), None);
+        assert_eq!(frozen.count_le(&7), 3);
+        assert_eq!(frozen.count_le(&10), 0);
+        assert_eq!(frozen.iter().next_back(), Some((2, &1)));
+        assert_eq!(
+            frozen.iter().collect::<Vec<(usize, &i32)>>(),
+            vec![(1, &9), (3, &9), (5, &7), (0, &3), (2, &1)]
+        );
+    }
+
+    #[test]
+    fn freeze_should_leave_borrowed_values_in_heap_order() {
+        let mut values = vec![2.5, 0.5, 1.5];
+        let frozen = MinIndexedPriorityQueue::from(&mut values).freeze();
+
+        assert_eq!(frozen.count_le(&1.0), 1);
+        assert_eq!(frozen.peek_min(), Some((1, &0.5)));
+        assert_eq!(values, [0.5, 2.5, 1.5]);
+        assert!(MinIndexedPriorityQueue::<u8>::from_entries(Vec::new()).freeze().is_empty());
+    }
+}
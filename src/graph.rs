@@ -0,0 +1,175 @@
+use crate::storage::Storage;
+use crate::{MinIndexedPriorityQueue, OwnedMinIpq};
+
+/// Shortest paths from one source node, as found by [`dijkstra`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShortestPaths {
+    /// Length of the shortest path to each node, `None` for nodes the source cannot reach.
+    pub distances: Vec<Option<u64>>,
+    /// Node preceding each node on its shortest path, `None` for the source and for
+    /// unreachable nodes.
+    pub predecessors: Vec<Option<usize>>,
+}
+
+impl ShortestPaths {
+    /// Returns the nodes of a shortest path from the source to `target`, both included, or
+    /// `None` if `target` cannot be reached.
+    pub fn path_to(&self, target: usize) -> Option<Vec<usize>> {
+        self.distances.get(target).copied().flatten()?;
+        let mut path = vec![target];
+        while let Some(predecessor) = self.predecessors[path[path.len() - 1]] {
+            path.push(predecessor);
+        }
+        path.reverse();
+
+        Some(path)
+    }
+}
+
+/// Finds the shortest paths from `source` to every node of `graph`, given as the
+/// `(node, weight)` edges leaving each node.
+///
+/// Each node is keyed by its own index in the frontier, so relaxing an edge is a single
+/// [`decrease_or_insert`](crate::OrderedIndexedPriorityQueue::decrease_or_insert) and the
+/// frontier never holds more than one entry per node, unlike a plain binary heap. Among paths
+/// of equal length, the one whose last edge was relaxed first is kept. Path lengths saturate
+/// at `u64::MAX`, and a `source` outside `graph` reaches no node.
+///
+/// # Panics
+///
+/// If the target of an edge is not a node of `graph`.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::graph::dijkstra;
+///
+/// let roads = vec![vec![(1, 7), (2, 2)], vec![(3, 1)], vec![(1, 3), (3, 8)], vec![]];
+/// let paths = dijkstra(&roads, 0);
+///
+/// assert_eq!(paths.distances, [Some(0), Some(5), Some(2), Some(6)]);
+/// assert_eq!(paths.path_to(3), Some(vec![0, 2, 1, 3]));
+/// ```
+pub fn dijkstra(graph: &[Vec<(usize, u64)>], source: usize) -> ShortestPaths {
+    let mut distances = vec![None; graph.len()];
+    let mut predecessors = vec![None; graph.len()];
+    let mut frontier: OwnedMinIpq<u64> =
+        OwnedMinIpq::with_capacity(graph.len()).with_max_key_bound(graph.len());
+    // Fails only for a source outside the graph, which then leaves every node unreached.
+    let _ = frontier.try_decrease_or_insert(source, 0);
+
+    while let Some((node, distance)) = frontier.swap_remove_min() {
+        distances[node] = Some(distance);
+        for &(next, weight) in &graph[node] {
+            if distances[next].is_some() {
+                continue;
+            }
+            let candidate = distance.saturating_add(weight);
+            if frontier.try_decrease_or_insert(next, candidate) == Ok(true) {
+                predecessors[next] = Some(node);
+            }
+        }
+    }
+
+    ShortestPaths {
+        distances,
+        predecessors,
+    }
+}
+
+/// Returns the `(parent, node, weight)` edges of a minimum spanning forest of the undirected
+/// `graph`, given as the `(node, weight)` edges of each node, listed from both ends.
+///
+/// Each tree grows from its lowest node, taking the lightest edge out of the tree at every
+/// step; the frontier keeps one entry per node, the lightest edge reaching it so far, lowered
+/// with [`decrease_or_insert`](crate::OrderedIndexedPriorityQueue::decrease_or_insert). Edges
+/// come in the order they join the forest, so each `parent` was reached before its `node`.
+///
+/// # Panics
+///
+/// If the target of an edge is not a node of `graph`.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::graph::prim_mst;
+///
+/// let cables = vec![
+///     vec![(1, 4.0), (2, 1.5)],
+///     vec![(0, 4.0), (2, 2.0), (3, 6.5)],
+///     vec![(0, 1.5), (1, 2.0), (3, 3.0)],
+///     vec![(1, 6.5), (2, 3.0)],
+/// ];
+///
+/// assert_eq!(prim_mst(&cables), [(0, 2, 1.5), (2, 1, 2.0), (2, 3, 3.0)]);
+/// ```
+pub fn prim_mst<W>(graph: &[Vec<(usize, W)>]) -> Vec<(usize, usize, W)>
+where
+    W: Clone + PartialOrd,
+{
+    let mut in_forest = vec![false; graph.len()];
+    let mut parents = vec![None; graph.len()];
+    let mut forest = Vec::with_capacity(graph.len().saturating_sub(1));
+    let mut cut: MinIndexedPriorityQueue<W> =
+        MinIndexedPriorityQueue::with_storage(Storage::Owned(Vec::with_capacity(graph.len())))
+            .with_max_key_bound(graph.len());
+
+    for root in 0..graph.len() {
+        let mut reached = (!in_forest[root]).then(|| (root, None));
+        while let Some((node, weight)) = reached {
+            in_forest[node] = true;
+            if let (Some(parent), Some(weight)) = (parents[node], weight) {
+                forest.push((parent, node, weight));
+            }
+            for (next, weight) in &graph[node] {
+                if !in_forest[*next]
+                    && cut.try_decrease_or_insert(*next, weight.clone()) == Ok(true)
+                {
+                    parents[*next] = Some(node);
+                }
+            }
+            reached = cut.poll_min().map(|(node, weight)| (node, Some(weight)));
+        }
+    }
+
+    forest
+}
+
+#[cfg(test)]
+mod graph_tests {
+    use crate::graph::{dijkstra, prim_mst};
+
+    #[test]
+    fn predecessors_should_only_move_to_strictly_shorter_paths() {
+        let graph = vec![
+            vec![(1, 2), (2, 2)],
+            vec![(3, 3)],
+            vec![(3, 4), (1, 0)],
+            vec![(0, 1)],
+            vec![(3, 0)],
+        ];
+        let paths = dijkstra(&graph, 0);
+
+        assert_eq!(paths.distances, [Some(0), Some(2), Some(2), Some(5), None]);
+        assert_eq!(paths.predecessors, [None, Some(0), Some(0), Some(1), None]);
+        assert_eq!(paths.path_to(0), Some(vec![0]));
+        assert_eq!(paths.path_to(4), None);
+        assert_eq!(paths.path_to(9), None);
+        assert_eq!(dijkstra(&graph, 9).distances, [None; 5]);
+    }
+
+    #[test]
+    fn prim_mst_should_span_every_component_with_the_lightest_edges() {
+        let graph = vec![
+            vec![(1, 3), (2, 1), (0, 0)],
+            vec![(0, 3), (2, 1)],
+            vec![(0, 1), (1, 1)],
+            vec![(4, 9)],
+            vec![(3, 9)],
+            vec![],
+        ];
+
+        assert_eq!(prim_mst(&graph), [(0, 2, 1), (2, 1, 1), (3, 4, 9)]);
+        assert_eq!(prim_mst::<u8>(&[]), []);
+    }
+}
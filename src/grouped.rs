@@ -0,0 +1,340 @@
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Reason an entry was refused by a [`GroupedIndexedPriorityQueue`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GroupError {
+    Key(IpqError),
+    QuotaExceeded { group: usize, quota: usize },
+    ZeroWeight { group: usize },
+}
+
+impl Display for GroupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupError::Key(e) => e.fmt(f),
+            GroupError::QuotaExceeded { group, quota } => write!(
+                f,
+                "Group {} already holds its quota of {} entries",
+                group, quota
+            ),
+            GroupError::ZeroWeight { group } => write!(
+                f,
+                "Group weight must be positive; received: 0 for group {}",
+                group
+            ),
+        }
+    }
+}
+
+impl Error for GroupError {}
+
+impl From<IpqError> for GroupError {
+    fn from(e: IpqError) -> Self {
+        GroupError::Key(e)
+    }
+}
+
+struct Group<T, O>
+where
+    T: Clone + 'static,
+{
+    heap: OrderedIndexedPriorityQueue<'static, T, O>,
+    weight: u32,
+    quota: Option<usize>,
+    paused: bool,
+    credit: i64,
+}
+
+impl<T, O> Group<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn new() -> Self {
+        Self {
+            heap: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+            weight: 1,
+            quota: None,
+            paused: false,
+            credit: 0,
+        }
+    }
+
+    fn is_eligible(&self) -> bool {
+        !self.paused && !self.heap.is_empty()
+    }
+}
+
+/// An indexed priority queue whose keys each belong to a group, e.g. a tenant, served fairly
+/// against one another.
+///
+/// Within a group, entries are polled by priority. Across groups, polls are shared out by
+/// smooth weighted round robin: a group of weight 3 is served three times as often as a group
+/// of weight 1, in an interleaved rather than bursty sequence, as long as both have entries.
+/// Paused groups keep accepting entries but are skipped until resumed, and a group's quota
+/// caps how many entries it may hold at once.
+///
+/// Key indexes are global: a key belongs to at most one group at a time.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::grouped::MinGroupedIndexedPriorityQueue;
+///
+/// let (free, paid) = (0, 1);
+/// let mut jobs = MinGroupedIndexedPriorityQueue::new();
+/// jobs.try_set_weight(paid, 2).unwrap();
+/// (0..6).for_each(|key| jobs.insert(key % 2, key, key).unwrap());
+///
+/// let groups = (0..6).map(|_| jobs.poll_min().unwrap().0).collect::<Vec<usize>>();
+/// assert_eq!(groups, vec![paid, free, paid, paid, free, free]);
+/// ```
+pub struct GroupedIndexedPriorityQueue<T, O>
+where
+    T: Clone + 'static,
+{
+    groups: BTreeMap<usize, Group<T, O>>,
+    group_of: HashMap<usize, usize>,
+}
+
+pub type MinGroupedIndexedPriorityQueue<T> = GroupedIndexedPriorityQueue<T, Min>;
+
+pub type MaxGroupedIndexedPriorityQueue<T> = GroupedIndexedPriorityQueue<T, Max>;
+
+impl<T, O> Default for GroupedIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, O> GroupedIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    pub fn new() -> Self {
+        Self {
+            groups: BTreeMap::new(),
+            group_of: HashMap::new(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.group_of.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.group_of.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.group_of.contains_key(&key_index)
+    }
+
+    pub fn group_of(&self, key_index: usize) -> Option<usize> {
+        self.group_of.get(&key_index).copied()
+    }
+
+    pub fn group_size(&self, group: usize) -> usize {
+        self.groups.get(&group).map_or(0, |g| g.heap.size())
+    }
+
+    /// Sets the share of polls `group` gets relative to the other groups, `1` by default.
+    ///
+    /// # Panics
+    ///
+    /// If `weight` is `0`; pause the group instead. See [`try_set_weight`](Self::try_set_weight).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn set_weight(&mut self, group: usize, weight: u32) {
+        if let Err(e) = self.try_set_weight(group, weight) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Same as [`set_weight`](Self::set_weight), returning [`GroupError::ZeroWeight`] instead
+    /// of panicking.
+    pub fn try_set_weight(&mut self, group: usize, weight: u32) -> Result<(), GroupError> {
+        if weight == 0 {
+            return Err(GroupError::ZeroWeight { group });
+        }
+        self.group_mut(group).weight = weight;
+
+        Ok(())
+    }
+
+    /// Caps the number of entries `group` may hold, `None` lifting the cap. Entries already
+    /// past a lowered quota stay.
+    pub fn set_quota(&mut self, group: usize, quota: Option<usize>) {
+        self.group_mut(group).quota = quota;
+    }
+
+    /// Stops serving `group` until [`resume_group`](Self::resume_group) is called.
+    pub fn pause_group(&mut self, group: usize) {
+        self.group_mut(group).paused = true;
+    }
+
+    pub fn resume_group(&mut self, group: usize) {
+        self.group_mut(group).paused = false;
+    }
+
+    pub fn is_paused(&self, group: usize) -> bool {
+        self.groups.get(&group).map_or(false, |g| g.paused)
+    }
+
+    /// Adds `value` under `key_index` to `group`, unless the key is taken, out of the key space
+    /// or the group is at its quota.
+    pub fn insert(&mut self, group: usize, key_index: usize, value: T) -> Result<(), GroupError> {
+        if self.contains(key_index) {
+            return Err(IpqError::KeyInUse(key_index).into());
+        }
+
+        let g = self.group_mut(group);
+        if let Some(quota) = g.quota.filter(|&quota| g.heap.size() >= quota) {
+            return Err(GroupError::QuotaExceeded { group, quota });
+        }
+        g.heap.try_insert(key_index, value)?;
+        self.group_of.insert(key_index, group);
+
+        Ok(())
+    }
+
+    pub fn delete(&mut self, key_index: usize) -> Option<T> {
+        let group = self.group_of.remove(&key_index)?;
+
+        self.groups.get_mut(&group)?.heap.delete(key_index)
+    }
+
+    /// Replaces the value of `key_index`, which stays in its group, and returns the previous one.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue, see [`try_update`](Self::try_update).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn update(&mut self, key_index: usize, value: T) -> T {
+        self.try_update(key_index, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_update(&mut self, key_index: usize, value: T) -> Result<T, IpqError> {
+        let group = self.group_of(key_index).ok_or(IpqError::KeyNotFound(key_index))?;
+
+        self.group_mut(group).heap.try_update(key_index, value)
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Option<&T> {
+        self.groups.get(&self.group_of(key_index)?)?.heap.value_ref(key_index)
+    }
+
+    /// Returns the key the next [`poll_min`](Self::poll_min) removes: the one ranked first in
+    /// the group whose turn it is, paused and empty groups being skipped.
+    pub fn peek_min_key_index(&self) -> Option<usize> {
+        self.groups.get(&self.next_group()?)?.heap.peek_min_key_index()
+    }
+
+    /// Removes the entry ranked first in the group whose turn it is, returning its group, key
+    /// index and value.
+    pub fn poll_min(&mut self) -> Option<(usize, usize, T)> {
+        let group = self.next_group()?;
+        let total_weight = self
+            .groups
+            .values_mut()
+            .filter(|g| g.is_eligible())
+            .map(|g| {
+                g.credit += g.weight as i64;
+                g.weight as i64
+            })
+            .sum::<i64>();
+
+        let g = self.groups.get_mut(&group)?;
+        g.credit -= total_weight;
+        let key_index = g.heap.peek_min_key_index()?;
+        let value = g.heap.delete(key_index)?;
+        if g.heap.is_empty() {
+            g.credit = 0;
+        }
+        self.group_of.remove(&key_index);
+
+        Some((group, key_index, value))
+    }
+
+    /// Returns the eligible group with the most credit once this round's weights are added,
+    /// the lowest group id winning ties.
+    fn next_group(&self) -> Option<usize> {
+        self.groups
+            .iter()
+            .filter(|(_, g)| g.is_eligible())
+            .max_by(|(a, ga), (b, gb)| {
+                (ga.credit + ga.weight as i64)
+                    .cmp(&(gb.credit + gb.weight as i64))
+                    .then(b.cmp(a))
+            })
+            .map(|(&group, _)| group)
+    }
+
+    fn group_mut(&mut self, group: usize) -> &mut Group<T, O> {
+        self.groups.entry(group).or_insert_with(Group::new)
+    }
+}
+
+#[cfg(test)]
+mod grouped_indexed_pq_tests {
+    use crate::grouped::{GroupError, MaxGroupedIndexedPriorityQueue, MinGroupedIndexedPriorityQueue};
+    use crate::limit::IpqError;
+
+    #[test]
+    fn polls_should_be_shared_by_weight_and_ranked_within_groups() {
+        let mut ipq = MaxGroupedIndexedPriorityQueue::new();
+        assert_eq!(ipq.try_set_weight(7, 0), Err(GroupError::ZeroWeight { group: 7 }));
+        assert_eq!(ipq.try_set_weight(7, 3), Ok(()));
+        (0..8).for_each(|key| ipq.insert(7, key, key).unwrap());
+        (8..16).for_each(|key| ipq.insert(2, key, key).unwrap());
+
+        let polled = (0..8).map(|_| ipq.poll_min().unwrap()).collect::<Vec<(usize, usize, usize)>>();
+        let from_7 = polled.iter().filter(|(group, _, _)| *group == 7).count();
+        assert_eq!(from_7, 6);
+        assert_eq!(polled[0], (7, 7, 7));
+        assert!(polled.contains(&(2, 15, 15)));
+        assert_eq!(ipq.size(), 8);
+    }
+
+    #[test]
+    fn paused_groups_should_be_skipped_until_resumed() {
+        let mut ipq = MinGroupedIndexedPriorityQueue::new();
+        ipq.insert(0, 0, 'a').unwrap();
+        ipq.insert(1, 1, 'b').unwrap();
+        ipq.pause_group(0);
+        ipq.insert(0, 2, 'c').unwrap();
+
+        assert_eq!(ipq.peek_min_key_index(), Some(1));
+        assert_eq!(ipq.poll_min(), Some((1, 1, 'b')));
+        assert_eq!(ipq.poll_min(), None);
+
+        ipq.resume_group(0);
+        assert_eq!(ipq.poll_min(), Some((0, 0, 'a')));
+        assert_eq!(ipq.group_size(0), 1);
+    }
+
+    #[test]
+    fn insert_should_enforce_quotas_and_global_keys() {
+        let mut ipq = MinGroupedIndexedPriorityQueue::new();
+        ipq.set_quota(3, Some(1));
+        ipq.insert(3, 10, 1).unwrap();
+
+        assert_eq!(ipq.insert(3, 11, 2), Err(GroupError::QuotaExceeded { group: 3, quota: 1 }));
+        assert_eq!(ipq.insert(4, 10, 2), Err(GroupError::Key(IpqError::KeyInUse(10))));
+        assert_eq!(ipq.delete(10), Some(1));
+        assert_eq!(ipq.insert(3, 11, 2), Ok(()));
+        assert_eq!(ipq.group_of(11), Some(3));
+        assert_eq!(ipq.try_update(11, 5), Ok(2));
+        assert_eq!(ipq.try_update(10, 5), Err(IpqError::KeyNotFound(10)));
+    }
+}
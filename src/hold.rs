@@ -0,0 +1,137 @@
+use crate::cursor::PriorityCursor;
+use crate::index::{KeyIdx, NodeIdx};
+use crate::ipq::IndexedBinaryHeap;
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Keeps `key_index` from being peeked or polled until [`release`](Self::release) is called,
+    /// returning `false` for keys not in the queue.
+    ///
+    /// A held entry stays in the heap at its rightful place: it can still be looked up, updated
+    /// or deleted. Peeking and polling walk past held entries from the root in priority order,
+    /// so they cost `O(h log h)` extra comparisons when the `h` entries ranked first are held,
+    /// and nothing at all while no entry is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut deadlines = vec![10, 20, 30];
+    /// let mut jobs = MinIndexedPriorityQueue::from(&mut deadlines);
+    /// jobs.hold(0);
+    ///
+    /// assert_eq!(jobs.poll_min_key_index(), Some(1));
+    /// jobs.try_update(0, 40).unwrap();
+    /// assert_eq!(jobs.peek_min_key_index(), Some(2));
+    ///
+    /// jobs.release(0);
+    /// jobs.try_decrease(0, 5).unwrap();
+    /// assert_eq!(jobs.poll_min_key_index(), Some(0));
+    /// ```
+    pub fn hold(&mut self, key_index: impl Into<KeyIdx>) -> bool {
+        let KeyIdx(key_index) = key_index.into();
+        if !self.contains(key_index) {
+            return false;
+        }
+        self.held.insert(key_index);
+        self.publish_min();
+
+        true
+    }
+
+    /// Makes a held `key_index` pollable again, returning `false` if it was not held.
+    pub fn release(&mut self, key_index: impl Into<KeyIdx>) -> bool {
+        let KeyIdx(key_index) = key_index.into();
+        let released = self.held.remove(&key_index);
+        if released {
+            self.publish_min();
+        }
+
+        released
+    }
+
+    pub fn is_held(&self, key_index: impl Into<KeyIdx>) -> bool {
+        let KeyIdx(key_index) = key_index.into();
+        self.held.contains(&key_index)
+    }
+
+    pub fn held_count(&self) -> usize {
+        self.held.len()
+    }
+
+    /// Returns the node of the entry ranked first among those not held.
+    pub(crate) fn first_unheld_node(&self) -> Option<NodeIdx> {
+        if self.held.is_empty() {
+            return if self.is_empty() { None } else { Some(NodeIdx::ROOT) };
+        }
+
+        let size = self.size();
+        let mut cursor = PriorityCursor::new(size);
+        while let Some(i) = cursor.next(size, |i, j| self.less(i, j)) {
+            if !self.held.contains(&self.maps.key_at(i).unwrap().0) {
+                return Some(i);
+            }
+        }
+
+        None
+    }
+
+    /// Drops the hold of a key leaving the queue.
+    pub(crate) fn forget_hold(&mut self, key_index: usize) {
+        if !self.held.is_empty() {
+            self.held.remove(&key_index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod hold_tests {
+    use crate::MinIndexedPriorityQueue;
+
+    #[test]
+    fn held_entries_should_be_skipped_until_released() {
+        let mut values = (0..32).collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        (0..5).for_each(|k| {
+            ipq.hold(k);
+        });
+
+        assert_eq!(ipq.peek_min_value(), Some(5));
+        assert_eq!(ipq.poll_min_key_index(), Some(5));
+        assert_eq!(ipq.held_count(), 5);
+        assert!(ipq.release(2));
+        assert!(!ipq.release(2));
+        assert_eq!(ipq.poll_min_value(), Some(2));
+        assert!(ipq.satisfies_heap_invariant());
+    }
+
+    #[test]
+    fn a_fully_held_queue_should_poll_nothing() {
+        let mut values = vec![1, 2];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        ipq.hold(0);
+        ipq.hold(1);
+
+        assert_eq!(ipq.poll_min_value(), None);
+        assert_eq!(ipq.size(), 2);
+        assert!(!ipq.hold(7));
+    }
+
+    #[test]
+    fn removed_keys_should_lose_their_hold() {
+        let mut values = vec![3, 1, 2];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        ipq.hold(1);
+        ipq.delete(1);
+        ipq.insert(1, 0);
+
+        assert!(!ipq.is_held(1));
+        assert_eq!(ipq.poll_min_key_index(), Some(1));
+    }
+}
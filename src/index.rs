@@ -0,0 +1,120 @@
+use std::fmt::{Display, Formatter};
+
+/// Position of an entry in the heap, i.e. in the queue's values, which changes whenever the
+/// entry is sifted.
+///
+/// Key indexes and node indexes are both `usize`s underneath; keeping them apart at the type
+/// level turns passing one where the other is expected into a compile error.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeIdx(pub usize);
+
+impl NodeIdx {
+    pub const ROOT: NodeIdx = NodeIdx(0);
+
+    /// Returns the parent node, the root being its own parent.
+    #[inline]
+    pub fn parent(self) -> NodeIdx {
+        NodeIdx(self.0.saturating_sub(1) / 2)
+    }
+
+    /// Returns the `nth` (`0` or `1`) child of the node, `None` once past `usize::MAX`.
+    #[inline]
+    pub fn child(self, nth: usize) -> Option<NodeIdx> {
+        Some(NodeIdx(self.0.checked_mul(2)?.checked_add(nth + 1)?))
+    }
+
+    /// Returns the number of edges between the node and the root, `⌊log2(i + 1)⌋`, which
+    /// reaches `usize::BITS` for the very last node index.
+    pub fn depth(self) -> usize {
+        match self.0.checked_add(1) {
+            Some(position) => (usize::BITS - 1 - position.leading_zeros()) as usize,
+            None => usize::BITS as usize,
+        }
+    }
+}
+
+impl From<NodeIdx> for usize {
+    fn from(node_index: NodeIdx) -> Self {
+        node_index.0
+    }
+}
+
+impl Display for NodeIdx {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "node {}", self.0)
+    }
+}
+
+/// Key index of an entry as stored in the queue's maps, which stays the same however the entry
+/// is sifted.
+///
+/// The keyed methods of the queues take an `impl Into<KeyIdx>`, so they accept a `KeyIdx` or a
+/// plain `usize` key index but refuse a [`NodeIdx`] at compile time, and the maps can never be
+/// fed a node index. [`KeyedIndexedPriorityQueue`](crate::key::KeyedIndexedPriorityQueue)
+/// hands out [`Key`](crate::key::Key)s instead.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::index::KeyIdx;
+/// use indexed_priority_queue::MinIndexedPriorityQueue;
+///
+/// let mut values = vec![3, 1];
+/// let ipq = MinIndexedPriorityQueue::from(&mut values);
+/// assert!(ipq.contains(KeyIdx(1)));
+/// assert!(ipq.contains(1));
+/// ```
+///
+/// ```compile_fail
+/// use indexed_priority_queue::index::NodeIdx;
+/// use indexed_priority_queue::MinIndexedPriorityQueue;
+///
+/// let mut values = vec![3, 1];
+/// let ipq = MinIndexedPriorityQueue::from(&mut values);
+/// ipq.contains(NodeIdx::ROOT);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyIdx(pub usize);
+
+impl From<usize> for KeyIdx {
+    fn from(key_index: usize) -> Self {
+        KeyIdx(key_index)
+    }
+}
+
+impl From<KeyIdx> for usize {
+    fn from(key_index: KeyIdx) -> Self {
+        key_index.0
+    }
+}
+
+impl Display for KeyIdx {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "key {}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod index_tests {
+    use crate::index::{KeyIdx, NodeIdx};
+
+    #[test]
+    fn node_navigation_should_follow_the_binary_heap_layout() {
+        assert_eq!(NodeIdx::ROOT.parent(), NodeIdx::ROOT);
+        assert_eq!(NodeIdx(5).parent(), NodeIdx(2));
+        assert_eq!(NodeIdx(2).child(0), Some(NodeIdx(5)));
+        assert_eq!(NodeIdx(2).child(1), Some(NodeIdx(6)));
+        assert_eq!(NodeIdx(usize::MAX / 2).child(1), None);
+        assert_eq!(NodeIdx(6).depth(), 2);
+        assert_eq!(NodeIdx(7).depth(), 3);
+        assert_eq!(NodeIdx::ROOT.depth(), 0);
+        assert_eq!(NodeIdx(usize::MAX).depth(), usize::BITS as usize);
+    }
+
+    #[test]
+    fn key_and_node_indexes_should_unwrap_and_display_apart() {
+        assert_eq!(usize::from(KeyIdx(3)), usize::from(NodeIdx(3)));
+        assert_eq!(KeyIdx(3).to_string(), "key 3");
+        assert_eq!(NodeIdx(3).to_string(), "node 3");
+    }
+}
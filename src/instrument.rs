@@ -0,0 +1,298 @@
+use crate::index::KeyIdx;
+use crate::ipq::IndexedPriorityQueue;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::ops::{AddAssign, RangeBounds};
+use std::vec::IntoIter;
+
+thread_local! {
+    static COUNTERS: Cell<OpStats> = Cell::new(OpStats::default());
+}
+
+/// Work done by the heap during one or more operations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpStats {
+    pub comparisons: u64,
+    pub swaps: u64,
+    /// Number of sink and swim passes started.
+    pub sifts: u64,
+    /// Levels travelled by the longest sift.
+    pub max_sift_depth: u64,
+}
+
+impl AddAssign for OpStats {
+    fn add_assign(&mut self, other: Self) {
+        self.comparisons += other.comparisons;
+        self.swaps += other.swaps;
+        self.sifts += other.sifts;
+        self.max_sift_depth = self.max_sift_depth.max(other.max_sift_depth);
+    }
+}
+
+fn count(f: impl FnOnce(&mut OpStats)) {
+    COUNTERS.with(|counters| {
+        let mut stats = counters.get();
+        f(&mut stats);
+        counters.set(stats);
+    });
+}
+
+pub(crate) fn record_comparison() {
+    count(|stats| stats.comparisons += 1);
+}
+
+pub(crate) fn record_swap() {
+    count(|stats| stats.swaps += 1);
+}
+
+pub(crate) fn record_sift(depth: u64) {
+    count(|stats| {
+        stats.sifts += 1;
+        stats.max_sift_depth = stats.max_sift_depth.max(depth);
+    });
+}
+
+/// An indexed priority queue that records the work each operation costs, for comparing heap
+/// variants empirically.
+///
+/// Counting relies on hooks compiled in by the `instrument` feature, which cost nothing when
+/// it is disabled. Every call through [`IndexedPriorityQueue`] appends one record, which can be
+/// read back with [`records`](Self::records) or summed per operation into a CSV with
+/// [`write_csv`](Self::write_csv).
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::instrument::MinInstrumentedIndexedPriorityQueue;
+/// use indexed_priority_queue::ipq::IndexedPriorityQueue;
+///
+/// let mut ipq = MinInstrumentedIndexedPriorityQueue::from((0..15).rev().collect::<Vec<i32>>());
+/// ipq.poll_min_value();
+///
+/// let (operation, stats) = ipq.records()[1];
+/// assert_eq!(operation, "poll_min_value");
+/// assert_eq!(stats.max_sift_depth, 3);
+///
+/// let mut csv = Vec::new();
+/// ipq.write_csv(&mut csv).unwrap();
+/// assert!(String::from_utf8(csv).unwrap().starts_with("operation,calls,comparisons"));
+/// ```
+pub struct InstrumentedIndexedPriorityQueue<T, O>
+where
+    T: Clone + 'static,
+{
+    heap: OrderedIndexedPriorityQueue<'static, T, O>,
+    records: Vec<(&'static str, OpStats)>,
+}
+
+pub type MinInstrumentedIndexedPriorityQueue<T> = InstrumentedIndexedPriorityQueue<T, Min>;
+
+pub type MaxInstrumentedIndexedPriorityQueue<T> = InstrumentedIndexedPriorityQueue<T, Max>;
+
+impl<T, O> Default for InstrumentedIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, O> From<Vec<T>> for InstrumentedIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    /// Heapifies `values` under the key indexes `0..n`, recorded as a `heapify` operation.
+    ///
+    /// # Panics
+    ///
+    /// If a value is not comparable with itself.
+    fn from(values: Vec<T>) -> Self {
+        let mut ipq = Self::new();
+        ipq.heap = ipq.measure("heapify", |_| {
+            OrderedIndexedPriorityQueue::with_storage(Storage::Owned(values))
+        });
+
+        ipq
+    }
+}
+
+impl<T, O> InstrumentedIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    pub fn new() -> Self {
+        Self {
+            heap: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+            records: Vec::new(),
+        }
+    }
+
+    /// The instrumented queue, for reads that are not worth recording.
+    pub fn queue(&self) -> &OrderedIndexedPriorityQueue<'static, T, O> {
+        &self.heap
+    }
+
+    /// The `(operation, stats)` record of every operation so far, oldest first.
+    pub fn records(&self) -> &[(&'static str, OpStats)] {
+        &self.records
+    }
+
+    pub fn clear_records(&mut self) {
+        self.records.clear();
+    }
+
+    /// Sums the records of each operation, returning `(operation, calls, stats)` rows sorted by
+    /// operation name.
+    pub fn summary(&self) -> Vec<(&'static str, u64, OpStats)> {
+        let mut summary = BTreeMap::new();
+        for &(operation, stats) in &self.records {
+            let (calls, total) = summary.entry(operation).or_insert((0, OpStats::default()));
+            *calls += 1;
+            *total += stats;
+        }
+
+        summary
+            .into_iter()
+            .map(|(operation, (calls, total))| (operation, calls, total))
+            .collect()
+    }
+
+    /// Writes the [`summary`](Self::summary) as CSV, one row per operation after a header.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "operation,calls,comparisons,swaps,sifts,max_sift_depth")?;
+        for (operation, calls, stats) in self.summary() {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{}",
+                operation, calls, stats.comparisons, stats.swaps, stats.sifts, stats.max_sift_depth
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn measure<F, R>(&mut self, operation: &'static str, f: F) -> R
+    where
+        F: FnOnce(&mut OrderedIndexedPriorityQueue<'static, T, O>) -> R,
+    {
+        let outer = COUNTERS.with(|counters| counters.replace(OpStats::default()));
+        let result = f(&mut self.heap);
+        let stats = COUNTERS.with(|counters| counters.replace(outer));
+        self.records.push((operation, stats));
+
+        result
+    }
+}
+
+impl<T, O> IndexedPriorityQueue<T> for InstrumentedIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn append(&mut self, extra_values: &mut Vec<T>) {
+        self.measure("append", |heap| heap.append(extra_values))
+    }
+
+    fn contains(&self, key_index: impl Into<KeyIdx>) -> bool {
+        self.heap.contains(key_index)
+    }
+
+    fn decrease(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        self.measure("decrease", |heap| heap.decrease(key_index, value))
+    }
+
+    fn delete(&mut self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        self.measure("delete", |heap| heap.delete(key_index))
+    }
+
+    fn drain<R: RangeBounds<usize>>(&mut self, key_indexes: R) -> IntoIter<T> {
+        self.measure("drain", |heap| heap.drain(key_indexes))
+    }
+
+    fn insert(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        self.measure("insert", |heap| heap.insert(key_index, value))
+    }
+
+    fn increase(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        self.measure("increase", |heap| heap.increase(key_index, value))
+    }
+
+    fn peek_min_key_index(&self) -> Option<usize> {
+        self.heap.peek_min_key_index()
+    }
+
+    fn peek_min_value(&self) -> Option<T> {
+        self.heap.peek_min_value()
+    }
+
+    fn poll_min_key_index(&mut self) -> Option<usize> {
+        self.measure("poll_min_key_index", |heap| heap.poll_min_key_index())
+    }
+
+    fn poll_min_value(&mut self) -> Option<T> {
+        self.measure("poll_min_value", |heap| heap.poll_min_value())
+    }
+
+    fn push(&mut self, value: T) {
+        self.measure("push", |heap| heap.push(value))
+    }
+
+    fn update(&mut self, key_index: impl Into<KeyIdx>, value: T) -> T {
+        self.measure("update", |heap| heap.update(key_index, value))
+    }
+
+    fn value_of(&self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        self.heap.value_of(key_index)
+    }
+}
+
+#[cfg(test)]
+mod instrumented_indexed_pq_tests {
+    use crate::instrument::{
+        MaxInstrumentedIndexedPriorityQueue, MinInstrumentedIndexedPriorityQueue, OpStats,
+    };
+    use crate::ipq::IndexedPriorityQueue;
+
+    #[test]
+    fn records_should_count_the_work_of_each_operation() {
+        let mut ipq = MinInstrumentedIndexedPriorityQueue::new();
+        ipq.push(5);
+        ipq.push(4);
+        ipq.push(3);
+        ipq.push(1);
+
+        let expected = OpStats { comparisons: 2, swaps: 2, sifts: 1, max_sift_depth: 2 };
+        assert_eq!(ipq.records()[3], ("push", expected));
+        assert_eq!(ipq.records()[0].1.comparisons, 0);
+        assert_eq!(ipq.records().len(), 4);
+    }
+
+    #[test]
+    fn summary_should_sum_records_per_operation() {
+        let mut ipq = MaxInstrumentedIndexedPriorityQueue::from(vec![1, 2, 3, 4]);
+        ipq.poll_min_value();
+        ipq.poll_min_value();
+        ipq.clear_records();
+        ipq.update(0, 10);
+        ipq.update(1, 0);
+
+        let summary = ipq.summary();
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].0, "update");
+        assert_eq!(summary[0].1, 2);
+
+        let mut csv = Vec::new();
+        ipq.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.lines().nth(1).unwrap().starts_with("update,2,"));
+    }
+}
@@ -1,26 +1,190 @@
+use crate::index::{KeyIdx, NodeIdx};
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+use std::ops::RangeBounds;
+use std::vec::IntoIter;
+
+/// The keyed operations every queue of the crate implements, so that code can be written once
+/// against any of them.
+///
+/// Reads and keyed removals return `None` for a missing key or an empty queue and never panic.
+/// The other operations panic on misuse: [`insert`](Self::insert) on a key in use or past
+/// the key space, [`update`](Self::update), [`decrease`](Self::decrease) and
+/// [`increase`](Self::increase) on a missing key, [`drain`](Self::drain) on a reversed or
+/// out of range key index range, and [`insert`](Self::insert), [`update`](Self::update) and
+/// [`append`](Self::append) on a value that is not comparable with itself, such as a NaN, in
+/// release builds too. The core queue's
+/// [`try_drain`](crate::OrderedIndexedPriorityQueue::try_drain) reports a bad range as an
+/// error instead.
 pub trait IndexedPriorityQueue<T> {
+    /// Pushes every value of `extra_values`, leaving it empty.
     fn append(&mut self, extra_values: &mut Vec<T>);
-    fn contains(&self, key_index: usize) -> bool;
-    fn decrease(&mut self, key_index: usize, value: T);
-    fn delete(&mut self, key_index: usize) -> Option<T>;
-    fn drain(&mut self, start: usize, end: usize) -> Vec<T>;
-    fn insert(&mut self, key_index: usize, value: T);
-    fn increase(&mut self, key_index: usize, value: T);
+    fn contains(&self, key_index: impl Into<KeyIdx>) -> bool;
+    /// Lowers `key_index`'s value to `value` if it is smaller.
+    fn decrease(&mut self, key_index: impl Into<KeyIdx>, value: T);
+    /// Removes `key_index`, returning its value, or `None` if it was not in the queue.
+    fn delete(&mut self, key_index: impl Into<KeyIdx>) -> Option<T>;
+    /// Removes the keys in `key_indexes`, returning their values by ascending key index.
+    ///
+    /// # Panics
+    ///
+    /// If `key_indexes` is reversed or reaches past the key space.
+    fn drain<R: RangeBounds<usize>>(&mut self, key_indexes: R) -> IntoIter<T>;
+    fn insert(&mut self, key_index: impl Into<KeyIdx>, value: T);
+    /// Raises `key_index`'s value to `value` if it is greater.
+    fn increase(&mut self, key_index: impl Into<KeyIdx>, value: T);
     fn peek_min_key_index(&self) -> Option<usize>;
     fn peek_min_value(&self) -> Option<T>;
     fn poll_min_key_index(&mut self) -> Option<usize>;
     fn poll_min_value(&mut self) -> Option<T>;
+    /// Inserts `value` under the queue's next free key index.
     fn push(&mut self, value: T);
-    fn update(&mut self, key_index: usize, value: T) -> T;
-    fn value_of(&self, key_index: usize) -> Option<T>;
+    /// Replaces `key_index`'s value, returning the previous one.
+    fn update(&mut self, key_index: impl Into<KeyIdx>, value: T) -> T;
+    fn value_of(&self, key_index: impl Into<KeyIdx>) -> Option<T>;
+
+    /// Like [`peek_min_key_index`](Self::peek_min_key_index), for callers who know the queue
+    /// has an entry to peek.
+    ///
+    /// # Panics
+    ///
+    /// If there is no such entry.
+    fn peek_min_key_index_unchecked(&self) -> usize {
+        self.peek_min_key_index().expect("Priority queue is empty")
+    }
+
+    /// # Panics
+    ///
+    /// If the queue has no entry to peek.
+    fn peek_min_value_unchecked(&self) -> T {
+        self.peek_min_value().expect("Priority queue is empty")
+    }
+
+    /// # Panics
+    ///
+    /// If the queue has no entry to poll.
+    fn poll_min_key_index_unchecked(&mut self) -> usize {
+        self.poll_min_key_index().expect("Priority queue is empty")
+    }
+
+    /// # Panics
+    ///
+    /// If the queue has no entry to poll.
+    fn poll_min_value_unchecked(&mut self) -> T {
+        self.poll_min_value().expect("Priority queue is empty")
+    }
 }
 
+/// The node level operations of a heap laid out as an implicit tree, every node `i` having its
+/// children at `i · arity + 1` onwards.
+///
+/// Implementors only say how two nodes compare and how they are swapped, keeping their index
+/// maps in step; the sifts are shared by every heap of the crate.
 pub trait IndexedBinaryHeap {
     fn is_empty(&self) -> bool;
-    fn less(&self, i: usize, j: usize) -> bool;
-    fn min_child(&self, i: usize) -> Option<usize>;
+    fn less(&self, i: NodeIdx, j: NodeIdx) -> bool;
     fn size(&self) -> usize;
-    fn swap(&mut self, i: usize, j: usize);
-    fn swim(&mut self, i: usize);
-    fn sink(&mut self, i: usize);
+    fn swap(&mut self, i: NodeIdx, j: NodeIdx);
+
+    /// Number of children per node, `2` unless overridden.
+    fn arity(&self) -> usize {
+        2
+    }
+
+    /// Returns the child ranked first among the children of `i`, if it ranks before `i`
+    /// itself.
+    fn min_child(&self, i: NodeIdx) -> Option<NodeIdx> {
+        let first = i.0.checked_mul(self.arity())?.checked_add(1)?;
+        if first >= self.size() {
+            return None;
+        }
+
+        let last = first.saturating_add(self.arity()).min(self.size());
+        let child = (first + 1..last).map(NodeIdx).fold(NodeIdx(first), |child, j| {
+            if self.less(j, child) {
+                j
+            } else {
+                child
+            }
+        });
+
+        self.less(child, i).then(|| child)
+    }
+
+    /// Moves the entry at `i` up while it ranks before its parent.
+    fn swim(&mut self, mut i: NodeIdx) {
+        let start = i;
+        while i != NodeIdx::ROOT {
+            let parent = NodeIdx((i.0 - 1) / self.arity());
+            if !self.less(i, parent) {
+                break;
+            }
+            self.swap(i, parent);
+            i = parent;
+        }
+        self.on_sift(start, i);
+    }
+
+    /// Moves the entry at `i` down while a child ranks before it.
+    fn sink(&mut self, mut i: NodeIdx) {
+        let start = i;
+        while let Some(j) = self.min_child(i) {
+            self.swap(i, j);
+            i = j;
+        }
+        self.on_sift(start, i);
+    }
+
+    /// Called once a sift moved an entry from `from` to `to`; does nothing unless overridden.
+    fn on_sift(&mut self, _from: NodeIdx, _to: NodeIdx) {}
+}
+
+/// Defines the panicking methods of [`IndexedPriorityQueue`] as inherent methods of the queue
+/// too, with the visibility `$vis`.
+macro_rules! inherent_panicking_methods {
+    ($vis:vis) => {
+        // Some of them are not called by the crate itself when they are private to it.
+        #[allow(dead_code)]
+        impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+        where
+            T: Clone + PartialOrd,
+            O: Order,
+        {
+            $vis fn append(&mut self, extra_values: &mut Vec<T>) {
+                IndexedPriorityQueue::append(self, extra_values)
+            }
+
+            $vis fn decrease(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+                IndexedPriorityQueue::decrease(self, key_index, value)
+            }
+
+            $vis fn drain<R: RangeBounds<usize>>(&mut self, key_indexes: R) -> IntoIter<T> {
+                IndexedPriorityQueue::drain(self, key_indexes)
+            }
+
+            $vis fn insert(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+                IndexedPriorityQueue::insert(self, key_index, value)
+            }
+
+            $vis fn increase(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+                IndexedPriorityQueue::increase(self, key_index, value)
+            }
+
+            $vis fn push(&mut self, value: T) {
+                IndexedPriorityQueue::push(self, value)
+            }
+
+            $vis fn update(&mut self, key_index: impl Into<KeyIdx>, value: T) -> T {
+                IndexedPriorityQueue::update(self, key_index, value)
+            }
+        }
+    };
 }
+
+// The queue's reads, removals and `try_*` methods are inherent as well, so the traits are only
+// needed by code generic over several queue types. Under `deny-panics` the twins stay private
+// to the crate, like the traits.
+#[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+inherent_panicking_methods!(pub);
+#[cfg(not(any(feature = "panic-on-misuse", not(feature = "deny-panics"))))]
+inherent_panicking_methods!(pub(crate));
@@ -0,0 +1,254 @@
+use crate::index::NodeIdx;
+#[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+use crate::limit::{IpqError, DEFAULT_MAX_KEY_BOUND};
+use crate::maps::IndexMaps;
+use crate::order::Order;
+use crate::{OrderedIndexedPriorityQueue, OwnedIpq};
+use std::iter::{FromIterator, FusedIterator};
+use std::ops::Range;
+use std::slice;
+
+/// Iterator over the `(key_index, &value)` pairs of a queue in heap order, i.e. in no
+/// particular one, created by [`OrderedIndexedPriorityQueue::iter`].
+#[derive(Clone, Debug)]
+pub struct Iter<'q, T> {
+    maps: &'q IndexMaps,
+    nodes: Range<usize>,
+    values: slice::Iter<'q, T>,
+}
+
+impl<'q, T> Iter<'q, T> {
+    pub(crate) fn new(maps: &'q IndexMaps, values: &'q [T]) -> Self {
+        Self {
+            maps,
+            nodes: 0..values.len(),
+            values: values.iter(),
+        }
+    }
+}
+
+impl<'q, T> Iterator for Iter<'q, T> {
+    type Item = (usize, &'q T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = NodeIdx(self.nodes.next()?);
+
+        Some((self.maps.key_at(i).unwrap().0, self.values.next()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<'q, T> DoubleEndedIterator for Iter<'q, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let i = NodeIdx(self.nodes.next_back()?);
+
+        Some((self.maps.key_at(i).unwrap().0, self.values.next_back()?))
+    }
+}
+
+impl<'q, T> ExactSizeIterator for Iter<'q, T> {}
+
+impl<'q, T> FusedIterator for Iter<'q, T> {}
+
+/// Consuming iterator over the `(key_index, value)` pairs of a queue in priority order,
+/// created by its [`IntoIterator`] implementation.
+///
+/// Every `next` polls the entry ranked first in `O(log n)`, held entries included.
+pub struct IntoIter<'a, T, O>
+where
+    T: Clone,
+{
+    queue: OrderedIndexedPriorityQueue<'a, T, O>,
+}
+
+impl<'a, T, O> Iterator for IntoIter<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.queue.swap_remove(NodeIdx::ROOT)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.size(), Some(self.queue.size()))
+    }
+}
+
+impl<'a, T, O> ExactSizeIterator for IntoIter<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+}
+
+impl<'a, T, O> FusedIterator for IntoIter<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+}
+
+/// Polls every entry, from the one ranked first to the one ranked last.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::OwnedMaxIpq;
+///
+/// let mut bids = OwnedMaxIpq::new();
+/// bids.try_insert_all([(4, 120), (9, 180), (2, 95)]);
+///
+/// assert_eq!(bids.into_iter().collect::<Vec<_>>(), vec![(9, 180), (4, 120), (2, 95)]);
+/// ```
+impl<'a, T, O> IntoIterator for OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    type Item = (usize, T);
+    type IntoIter = IntoIter<'a, T, O>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { queue: self }
+    }
+}
+
+impl<'q, 'a, T, O> IntoIterator for &'q OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    type Item = (usize, &'q T);
+    type IntoIter = Iter<'q, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Stores the values under the key indexes `0..n`, in iteration order, and heapifies them in
+/// `O(n)`, as `From<Vec<T>>` does.
+///
+/// # Panics
+///
+/// If a value is not comparable with itself, see [`OwnedIpq::try_from_vec`].
+impl<T, O> FromIterator<T> for OwnedIpq<T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        Self::from(values.into_iter().collect::<Vec<T>>())
+    }
+}
+
+/// Stores each value under its paired key index, the last value winning when a key index is
+/// given twice, and heapifies them in `O(n)`.
+///
+/// # Panics
+///
+/// If a key index is past the default
+/// [`max_key_bound`](OrderedIndexedPriorityQueue::max_key_bound), or its value is not comparable
+/// with itself.
+#[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+impl<T, O> FromIterator<(usize, T)> for OwnedIpq<T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn from_iter<I: IntoIterator<Item = (usize, T)>>(entries: I) -> Self {
+        let mut entries = entries.into_iter().collect::<Vec<(usize, T)>>();
+        if let Some(&(key_index, _)) = entries.iter().find(|(k, _)| *k >= DEFAULT_MAX_KEY_BOUND) {
+            panic!(
+                "{}",
+                IpqError::KeyTooLarge {
+                    key_index,
+                    max_key_bound: DEFAULT_MAX_KEY_BOUND,
+                }
+            );
+        }
+        entries.reverse();
+        entries.sort_by_key(|(key_index, _)| *key_index);
+        entries.dedup_by_key(|(key_index, _)| *key_index);
+
+        Self::from_entries(entries)
+    }
+}
+
+/// Pushes the values under the next key indexes, as
+/// [`append`](crate::ipq::IndexedPriorityQueue::append) does.
+///
+/// # Panics
+///
+/// If the key space runs out.
+#[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+impl<'a, T, O> Extend<T> for OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        self.append(&mut values.into_iter().collect());
+    }
+}
+
+/// Inserts each value under its paired key index, as
+/// [`insert`](crate::ipq::IndexedPriorityQueue::insert) does.
+///
+/// # Panics
+///
+/// If a key index is in use or past the
+/// [`max_key_bound`](OrderedIndexedPriorityQueue::max_key_bound).
+#[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+impl<'a, T, O> Extend<(usize, T)> for OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn extend<I: IntoIterator<Item = (usize, T)>>(&mut self, entries: I) {
+        entries
+            .into_iter()
+            .for_each(|(key_index, value)| self.insert(key_index, value));
+    }
+}
+
+#[cfg(all(test, any(feature = "panic-on-misuse", not(feature = "deny-panics"))))]
+mod iter_tests {
+    use crate::{OwnedMaxIpq, OwnedMinIpq};
+
+    #[test]
+    fn collecting_and_consuming_should_round_trip_keys_and_values() {
+        let ipq = vec![(3, 'c'), (0, 'x'), (7, 'a'), (0, 'b')]
+            .into_iter()
+            .collect::<OwnedMinIpq<char>>();
+        assert_eq!(ipq.size(), 3);
+        assert_eq!(ipq.value_of(0), Some('b'));
+
+        let mut pairs = ipq.iter().map(|(k, &v)| (k, v)).collect::<Vec<(usize, char)>>();
+        pairs.sort_unstable();
+        assert_eq!(pairs, vec![(0, 'b'), (3, 'c'), (7, 'a')]);
+        assert_eq!((&ipq).into_iter().len(), 3);
+
+        let polled = ipq.into_iter();
+        assert_eq!(polled.len(), 3);
+        assert_eq!(polled.collect::<Vec<_>>(), vec![(7, 'a'), (0, 'b'), (3, 'c')]);
+    }
+
+    #[test]
+    fn extend_should_push_values_and_insert_entries() {
+        let mut ipq = "bca".chars().collect::<OwnedMaxIpq<char>>();
+        ipq.extend("zy".chars());
+        ipq.extend(vec![(9, 'd'), (6, 'x')]);
+
+        assert_eq!(ipq.next_key_index(), 10);
+        assert_eq!(ipq.value_of(3), Some('z'));
+        let polled = ipq.into_iter().map(|(_, v)| v).collect::<String>();
+        assert_eq!(polled, "zyxdcba");
+    }
+}
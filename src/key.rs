@@ -0,0 +1,200 @@
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::fmt::{Display, Formatter};
+
+/// Handle to an entry of a [`KeyedIndexedPriorityQueue`], so that key indexes cannot be mixed
+/// up with node indexes, positions or any other `usize`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key(usize);
+
+impl Key {
+    /// Wraps a raw key index, e.g. one read back from storage.
+    pub fn from_index(key_index: usize) -> Self {
+        Self(key_index)
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl From<Key> for usize {
+    fn from(key: Key) -> Self {
+        key.0
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// An indexed priority queue that hands out a [`Key`] for every inserted value and only
+/// accepts those keys back.
+///
+/// Keys are never handed out twice, so a key kept past its entry's removal simply finds
+/// nothing rather than addressing a newer entry.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::key::MinKeyedIndexedPriorityQueue;
+///
+/// let mut jobs = MinKeyedIndexedPriorityQueue::new();
+/// let slow = jobs.try_insert(30).unwrap();
+/// let fast = jobs.try_insert(10).unwrap();
+///
+/// jobs.decrease(slow, 5);
+/// assert_eq!(jobs.poll_min(), Some((slow, 5)));
+/// assert_eq!(jobs.value_of(fast), Some(&10));
+/// ```
+pub struct KeyedIndexedPriorityQueue<T, O>
+where
+    T: Clone + 'static,
+{
+    heap: OrderedIndexedPriorityQueue<'static, T, O>,
+}
+
+pub type MinKeyedIndexedPriorityQueue<T> = KeyedIndexedPriorityQueue<T, Min>;
+
+pub type MaxKeyedIndexedPriorityQueue<T> = KeyedIndexedPriorityQueue<T, Max>;
+
+impl<T, O> Default for KeyedIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, O> KeyedIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    pub fn new() -> Self {
+        Self {
+            heap: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+        }
+    }
+
+    /// The underlying queue, addressed by raw key indexes.
+    pub fn queue(&self) -> &OrderedIndexedPriorityQueue<'static, T, O> {
+        &self.heap
+    }
+
+    pub fn size(&self) -> usize {
+        self.heap.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains(&self, key: Key) -> bool {
+        self.heap.contains(key.0)
+    }
+
+    pub fn value_of(&self, key: Key) -> Option<&T> {
+        self.heap.value_ref(key.0)
+    }
+
+    /// Adds `value`, returning the key it is stored under.
+    ///
+    /// # Panics
+    ///
+    /// Once the key space is exhausted, see [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&mut self, value: T) -> Key {
+        match self.try_insert(value) {
+            Ok(key) => key,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_insert(&mut self, value: T) -> Result<Key, IpqError> {
+        self.heap.try_push(value).map(Key)
+    }
+
+    /// Replaces `key`'s value, returning the previous one, or `None` if the key is not in the
+    /// queue.
+    pub fn update(&mut self, key: Key, value: T) -> Option<T> {
+        self.heap.contains(key.0).then(|| self.heap.update(key.0, value))
+    }
+
+    /// Lowers `key`'s value to `value` if it is smaller, returning `false` if the key is not
+    /// in the queue.
+    pub fn decrease(&mut self, key: Key, value: T) -> bool {
+        let contained = self.heap.contains(key.0);
+        if contained {
+            self.heap.decrease(key.0, value);
+        }
+
+        contained
+    }
+
+    /// Raises `key`'s value to `value` if it is greater, returning `false` if the key is not
+    /// in the queue.
+    pub fn increase(&mut self, key: Key, value: T) -> bool {
+        let contained = self.heap.contains(key.0);
+        if contained {
+            self.heap.increase(key.0, value);
+        }
+
+        contained
+    }
+
+    pub fn delete(&mut self, key: Key) -> Option<T> {
+        self.heap.delete(key.0)
+    }
+
+    pub fn peek_min(&self) -> Option<(Key, &T)> {
+        let key_index = self.heap.peek_min_key_index()?;
+
+        Some((Key(key_index), self.heap.value_ref(key_index)?))
+    }
+
+    pub fn poll_min(&mut self) -> Option<(Key, T)> {
+        let key_index = self.heap.peek_min_key_index()?;
+
+        Some((Key(key_index), self.heap.delete(key_index)?))
+    }
+}
+
+#[cfg(all(test, any(feature = "panic-on-misuse", not(feature = "deny-panics"))))]
+mod keyed_indexed_pq_tests {
+    use crate::key::{Key, MaxKeyedIndexedPriorityQueue, MinKeyedIndexedPriorityQueue};
+
+    #[test]
+    fn keys_should_address_their_entries_until_removed() {
+        let mut ipq = MaxKeyedIndexedPriorityQueue::new();
+        let keys = (0..5).map(|value| ipq.insert(value)).collect::<Vec<Key>>();
+
+        assert_eq!(ipq.update(keys[1], 9), Some(1));
+        assert!(ipq.increase(keys[0], 7));
+        assert_eq!(ipq.poll_min(), Some((keys[1], 9)));
+        assert_eq!(ipq.peek_min(), Some((keys[0], &7)));
+        assert_eq!(ipq.delete(keys[4]), Some(4));
+        assert_eq!(ipq.size(), 3);
+    }
+
+    #[test]
+    fn stale_keys_should_not_address_newer_entries() {
+        let mut ipq = MinKeyedIndexedPriorityQueue::new();
+        let first = ipq.insert('a');
+        ipq.poll_min();
+        let second = ipq.insert('b');
+
+        assert_ne!(first, second);
+        assert!(!ipq.contains(first));
+        assert!(!ipq.decrease(first, 'a'));
+        assert_eq!(ipq.update(first, 'c'), None);
+        assert_eq!(ipq.value_of(second), Some(&'b'));
+        assert_eq!(usize::from(second), second.index());
+    }
+}
@@ -0,0 +1,282 @@
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// An indexed priority queue addressed by keys of any hashable type, such as `String`s, ids or
+/// enums, rather than by dense key indexes.
+///
+/// Each key is mapped to a key index of an underlying queue through a `HashMap`, and the key
+/// indexes of removed entries are reused, so the key space stays as dense as the queue is
+/// large however sparse or numerous the keys are. Where [`key::KeyedIndexedPriorityQueue`]
+/// hands out its own keys, this queue takes the caller's.
+///
+/// [`key::KeyedIndexedPriorityQueue`]: crate::key::KeyedIndexedPriorityQueue
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::keyed::KeyedMinIpq;
+///
+/// let mut distances = KeyedMinIpq::new();
+/// distances.try_insert("lisbon".to_string(), 30).unwrap();
+/// distances.try_insert("porto".to_string(), 12).unwrap();
+///
+/// assert!(distances.decrease_key("lisbon", 4));
+/// assert!(distances.contains_key("porto"));
+/// assert_eq!(distances.poll_min(), Some(("lisbon".to_string(), 4)));
+/// assert_eq!(distances.value_of("porto"), Some(&12));
+/// ```
+pub struct KeyedIpq<K, T, O>
+where
+    T: Clone + 'static,
+{
+    heap: OrderedIndexedPriorityQueue<'static, T, O>,
+    key_indexes: HashMap<K, usize>,
+    keys: Vec<Option<K>>,
+    free_key_indexes: Vec<usize>,
+}
+
+pub type KeyedMinIpq<K, T> = KeyedIpq<K, T, Min>;
+
+pub type KeyedMaxIpq<K, T> = KeyedIpq<K, T, Max>;
+
+impl<K, T, O> Default for KeyedIpq<K, T, O>
+where
+    K: Hash + Eq + Clone,
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, T, O> KeyedIpq<K, T, O>
+where
+    K: Hash + Eq + Clone,
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Makes room for `capacity` entries before either the key map or the queue reallocates.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            heap: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::with_capacity(
+                capacity,
+            ))),
+            key_indexes: HashMap::with_capacity(capacity),
+            keys: Vec::with_capacity(capacity),
+            free_key_indexes: Vec::new(),
+        }
+    }
+
+    /// The underlying queue, addressed by the key indexes the keys are mapped to.
+    pub fn queue(&self) -> &OrderedIndexedPriorityQueue<'static, T, O> {
+        &self.heap
+    }
+
+    pub fn size(&self) -> usize {
+        self.heap.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.key_indexes.contains_key(key)
+    }
+
+    pub fn value_of<Q>(&self, key: &Q) -> Option<&T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.heap.value_ref(*self.key_indexes.get(key)?)
+    }
+
+    /// Adds `value` under `key`, or replaces the value `key` already holds and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Once the key space is exhausted, see [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&mut self, key: K, value: T) -> Option<T> {
+        match self.try_insert(key, value) {
+            Ok(old_value) => old_value,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Same as [`insert`](Self::insert), returning an error when a new key would need a key
+    /// index past the [`max_key_bound`](OrderedIndexedPriorityQueue::max_key_bound) of the
+    /// underlying queue.
+    pub fn try_insert(&mut self, key: K, value: T) -> Result<Option<T>, IpqError> {
+        if let Some(&key_index) = self.key_indexes.get(&key) {
+            return self.heap.try_update(key_index, value).map(Some);
+        }
+
+        let key_index = self.free_key_indexes.pop().unwrap_or(self.keys.len());
+        if let Err(e) = self.heap.try_insert(key_index, value) {
+            if key_index < self.keys.len() {
+                self.free_key_indexes.push(key_index);
+            }
+            return Err(e);
+        }
+
+        if key_index == self.keys.len() {
+            self.keys.push(None);
+        }
+        self.keys[key_index] = Some(key.clone());
+        self.key_indexes.insert(key, key_index);
+
+        Ok(None)
+    }
+
+    /// Replaces `key`'s value, returning the previous one, or `None` if the key is not in the
+    /// queue.
+    ///
+    /// # Panics
+    ///
+    /// If `value` is not comparable with itself, see [`try_update`](Self::try_update).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn update<Q>(&mut self, key: &Q, value: T) -> Option<T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.try_update(key, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`update`](Self::update), returning an error instead of panicking.
+    pub fn try_update<Q>(&mut self, key: &Q, value: T) -> Result<Option<T>, IpqError>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.key_indexes.get(key) {
+            Some(&key_index) => self.heap.try_update(key_index, value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Lowers `key`'s value to `value` if it is smaller, returning `false` if the key is not
+    /// in the queue.
+    pub fn decrease_key<Q>(&mut self, key: &Q, value: T) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.key_indexes.get(key) {
+            Some(&key_index) => self.heap.try_decrease(key_index, value).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Raises `key`'s value to `value` if it is greater, returning `false` if the key is not
+    /// in the queue.
+    pub fn increase_key<Q>(&mut self, key: &Q, value: T) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.key_indexes.get(key) {
+            Some(&key_index) => self.heap.try_increase(key_index, value).is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let key_index = self.key_indexes.remove(key)?;
+        self.keys[key_index] = None;
+        self.free_key_indexes.push(key_index);
+
+        self.heap.delete(key_index)
+    }
+
+    pub fn peek_min(&self) -> Option<(&K, &T)> {
+        let key_index = self.heap.peek_min_key_index()?;
+
+        Some((self.keys[key_index].as_ref()?, self.heap.value_ref(key_index)?))
+    }
+
+    pub fn poll_min(&mut self) -> Option<(K, T)> {
+        let (key_index, value) = self.heap.swap_remove_min()?;
+        let key = self.keys[key_index].take()?;
+        self.key_indexes.remove(&key);
+        self.free_key_indexes.push(key_index);
+
+        Some((key, value))
+    }
+}
+
+#[cfg(all(test, any(feature = "panic-on-misuse", not(feature = "deny-panics"))))]
+mod keyed_ipq_tests {
+    use crate::keyed::{KeyedMaxIpq, KeyedMinIpq};
+    use crate::limit::IpqError;
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    enum Job {
+        Build,
+        Test,
+        Deploy,
+    }
+
+    #[test]
+    fn enum_keys_should_address_their_entries_until_removed() {
+        let mut ipq = KeyedMaxIpq::new();
+        assert_eq!(ipq.insert(Job::Build, 3), None);
+        assert_eq!(ipq.insert(Job::Test, 1), None);
+        assert_eq!(ipq.insert(Job::Deploy, 2), None);
+
+        assert_eq!(ipq.insert(Job::Build, 0), Some(3));
+        assert!(ipq.increase_key(&Job::Test, 5));
+        assert_eq!(ipq.peek_min(), Some((&Job::Test, &5)));
+        assert_eq!(ipq.remove(&Job::Deploy), Some(2));
+        assert_eq!(ipq.remove(&Job::Deploy), None);
+        assert!(!ipq.decrease_key(&Job::Deploy, 0));
+        assert_eq!(ipq.poll_min(), Some((Job::Test, 5)));
+        assert_eq!(ipq.update(&Job::Build, 4), Some(0));
+        assert_eq!(ipq.size(), 1);
+    }
+
+    #[test]
+    fn reinserting_a_key_with_a_nan_should_be_refused() {
+        let mut ipq = KeyedMinIpq::new();
+        assert_eq!(ipq.try_insert(Job::Build, 1.0), Ok(None));
+
+        assert_eq!(ipq.try_insert(Job::Build, f64::NAN), Err(IpqError::Incomparable(0)));
+        assert_eq!(ipq.try_insert(Job::Build, 0.5), Ok(Some(1.0)));
+        assert_eq!(ipq.poll_min(), Some((Job::Build, 0.5)));
+    }
+
+    #[test]
+    fn key_indexes_should_be_reused_once_their_keys_are_gone() {
+        let mut ipq = KeyedMinIpq::with_capacity(2);
+        for round in 0..100 {
+            ipq.insert(format!("a{}", round), round);
+            ipq.insert(format!("b{}", round), round + 1);
+            assert_eq!(ipq.poll_min(), Some((format!("a{}", round), round)));
+            assert_eq!(ipq.remove(format!("b{}", round).as_str()), Some(round + 1));
+        }
+
+        assert!(ipq.is_empty());
+        assert!(ipq.queue().next_key_index() <= 2);
+        assert!(!ipq.contains_key("a0"));
+    }
+}
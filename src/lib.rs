@@ -1,86 +1,197 @@
+use crate::index::{KeyIdx, NodeIdx};
+#[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+pub use crate::ipq::{IndexedBinaryHeap, IndexedPriorityQueue};
+#[cfg(not(any(feature = "panic-on-misuse", not(feature = "deny-panics"))))]
 use crate::ipq::{IndexedBinaryHeap, IndexedPriorityQueue};
+use crate::limit::{IpqError, DEFAULT_MAX_KEY_BOUND};
+use crate::maps::IndexMaps;
+use crate::meta::MetaMap;
+use crate::observe::IpqObserver;
+use crate::order::{Max, Min, Order};
+use crate::shrink::ShrinkPolicy;
+use crate::storage::Storage;
+use crate::tuning::{BulkTuning, MapLayout};
+use crate::watch::MinPublisher;
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
-use std::ops::Range;
-use std::slice::{Iter, IterMut};
-
+use std::marker::PhantomData;
+use std::ops::{Bound, Range, RangeBounds};
+use std::slice::IterMut;
+use std::vec::IntoIter;
+
+#[cfg(feature = "abi")]
+pub mod abi;
+pub mod algorithms;
+#[cfg(feature = "rkyv")]
+pub mod archive;
+pub mod backoff;
+pub mod builder;
+mod bulk;
+pub mod cache;
+pub mod channel;
+mod checked;
+#[cfg(feature = "serde")]
+mod checkpoint;
+pub mod coalesce;
+#[cfg(feature = "bincode")]
+pub mod codec;
+pub mod concurrent;
+mod cursor;
+pub mod dary;
+pub mod deadline;
+pub mod decay;
+pub mod delayed;
+pub mod entry;
+pub mod examples;
+pub mod export;
+pub mod fairness;
+pub mod fingerprint;
+pub mod fixed;
+pub mod frozen;
+pub mod graph;
+pub mod grouped;
+mod hold;
+pub mod index;
+#[cfg(feature = "instrument")]
+pub mod instrument;
+#[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
 pub mod ipq;
-
-fn parent_node_index(node_index: usize) -> usize {
-    return match node_index {
-        0 => 0,
-        n if n % 2 == 0 => (n / 2) - 1,
-        _ => (node_index - 1) / 2,
-    };
-}
-
-fn max_value_index<T: Copy + Ord>(array: &Vec<T>) -> usize {
-    array
-        .iter()
-        .enumerate()
-        .max_by_key(|(_, &v)| v)
-        .map(|(i, _)| i)
-        .unwrap()
-}
-
-pub struct MinIndexedPriorityQueue<'a, T>
+#[cfg(not(any(feature = "panic-on-misuse", not(feature = "deny-panics"))))]
+mod ipq;
+pub mod iter;
+pub mod key;
+pub mod keyed;
+pub mod limit;
+pub mod maintain;
+mod maps;
+pub mod merge;
+pub mod meta;
+pub mod multi;
+pub mod multiset;
+pub mod node;
+pub mod observe;
+pub mod order;
+mod paged;
+pub mod prelude;
+pub mod reorder;
+pub mod repair;
+pub mod reverse;
+pub mod scheduler;
+pub mod scored;
+#[cfg(all(feature = "shm", unix))]
+pub mod shm;
+pub mod shrink;
+pub mod slice;
+pub mod soak;
+pub mod sorted;
+pub mod split;
+pub mod stable;
+#[cfg(feature = "async")]
+pub mod stream;
+mod storage;
+pub mod testing;
+pub mod tuning;
+pub mod view;
+pub mod watch;
+
+/// An indexed binary heap whose ranking direction is chosen by the [`Order`] marker `O`.
+///
+/// Most code should use the [`MinIndexedPriorityQueue`] and [`MaxIndexedPriorityQueue`]
+/// aliases rather than naming this type directly.
+pub struct OrderedIndexedPriorityQueue<'a, T, O>
 where
     T: Clone,
 {
-    values: &'a mut Vec<T>,
-    position_map: Vec<Option<usize>>,
-    inverse_map: Vec<Option<usize>>,
+    values: Storage<'a, T>,
+    maps: IndexMaps,
+    shrink_policy: ShrinkPolicy,
+    bulk_tuning: BulkTuning,
+    min_publisher: Option<MinPublisher<T>>,
+    observer: Option<Box<dyn IpqObserver<T> + Send + Sync>>,
+    metadata: MetaMap,
+    held: HashSet<usize>,
+    max_key_bound: usize,
+    dedup: bool,
+    /// Bumped by every mutation, so detached cursors can tell they went stale.
+    version: u64,
+    order: PhantomData<O>,
 }
 
-impl<'a, T> Display for MinIndexedPriorityQueue<'a, T>
+pub type MinIndexedPriorityQueue<'a, T> = OrderedIndexedPriorityQueue<'a, T, Min>;
+
+pub type MaxIndexedPriorityQueue<'a, T> = OrderedIndexedPriorityQueue<'a, T, Max>;
+
+/// A queue owning its values, which borrows nothing and can be stored anywhere.
+pub type OwnedIpq<T, O> = OrderedIndexedPriorityQueue<'static, T, O>;
+
+pub type OwnedMinIpq<T> = OwnedIpq<T, Min>;
+
+pub type OwnedMaxIpq<T> = OwnedIpq<T, Max>;
+
+impl<'a, T, O> Display for OrderedIndexedPriorityQueue<'a, T, O>
     where
         T: Clone + PartialOrd,
+        O: Order,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Minimum Priority Queue of {} elements and {} branches",
+            "{} Priority Queue of {} elements and {} branches",
+            O::NAME,
             self.size(),
             self.branches_count()
         )
     }
 }
 
-impl<'a, T> PartialEq<Self> for MinIndexedPriorityQueue<'a, T>
+impl<'a, T, O> PartialEq<Self> for OrderedIndexedPriorityQueue<'a, T, O>
     where
         T: Clone + PartialOrd,
+        O: Order,
 {
     fn eq(&self, other: &Self) -> bool {
         self.peek_min_value().eq(&other.peek_min_value())
     }
 }
 
-impl<'a, T> PartialOrd for MinIndexedPriorityQueue<'a, T>
+impl<'a, T, O> PartialOrd for OrderedIndexedPriorityQueue<'a, T, O>
     where
         T: Clone + PartialOrd,
+        O: Order,
 {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         self.peek_min_value().partial_cmp(&other.peek_min_value())
     }
 }
 
-impl<'a, T> From<&'a mut Vec<T>> for MinIndexedPriorityQueue<'a, T>
+impl<'a, T, O> From<&'a mut Vec<T>> for OrderedIndexedPriorityQueue<'a, T, O>
     where
         T: Clone + PartialOrd,
+        O: Order,
 {
-    /// Initializes a minimum indexed priority queue from a mutably borrowed `values` vector.
+    /// Initializes an indexed priority queue from a mutably borrowed `values` vector.
+    ///
+    /// Key index `i` names the `i`-th value as passed in, but the queue keeps `values` in heap
+    /// order: building the heap, and every later insertion, removal or update, moves values
+    /// around the caller's vector, which is left holding the entries in node order once the
+    /// borrow ends. Build an owned queue from a `Vec<T>` to keep the caller's vector as it is.
+    ///
+    /// # Panics
+    ///
+    /// If a value is not comparable with itself, e.g. a NaN, see
+    /// [`try_from_borrowed`](OrderedIndexedPriorityQueue::try_from_borrowed).
     ///
     /// # Arguments
     ///
     /// * `values`: `&mut Vec<T>` where `T` implements `Clone` and `PartialOrd`
     ///
-    /// returns: `MinIndexedPriorityQueue<T>`
+    /// returns: `OrderedIndexedPriorityQueue<T, O>`, e.g. a `MinIndexedPriorityQueue<T>`
     ///
     /// # Examples
     ///
     /// ```
     /// use std::any::type_name;
-    /// use indexed_priority_queue::ipq::IndexedBinaryHeap;
     /// use indexed_priority_queue::MinIndexedPriorityQueue;
     ///
     /// let mut values: Vec<u8> = Vec::new();
@@ -91,105 +202,359 @@ impl<'a, T> From<&'a mut Vec<T>> for MinIndexedPriorityQueue<'a, T>
     /// }
     ///
     /// assert_eq!(min_ipq.is_empty(), true);
-    /// assert_eq!(type_of(min_ipq), "indexed_priority_queue::MinIndexedPriorityQueue<u8>");
+    /// let name = type_of(min_ipq);
+    /// assert!(name.starts_with("indexed_priority_queue::OrderedIndexedPriorityQueue<"));
+    /// assert!(name.ends_with("u8, indexed_priority_queue::order::Min>"));
     /// ```
     fn from(values: &'a mut Vec<T>) -> Self {
-        let npt = values.len().next_power_of_two();
-        let mut values_map = vec![None; npt];
-        Range {
-            start: 0,
-            end: values.len(),
-        }
-            .for_each(|i| values_map[i] = Some(i));
-
-        let position_map = values_map.clone();
-        let inverse_map = values_map;
+        Self::with_storage(Storage::Borrowed(values))
+    }
+}
 
-        let mut min_ipq = Self {
-            values,
-            position_map,
-            inverse_map,
-        };
-        min_ipq.fix_heap_invariant();
+impl<T, O> From<Vec<T>> for OwnedIpq<T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Takes ownership of `values`, stored under the key indexes `0..values.len()` and
+    /// heapified in place without reallocating.
+    ///
+    /// # Panics
+    ///
+    /// If a value is not comparable with itself, e.g. a NaN, see
+    /// [`try_from_vec`](OwnedIpq::try_from_vec).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// struct Scheduler {
+    ///     jobs: OwnedMinIpq<u32>,
+    /// }
+    ///
+    /// let mut scheduler = Scheduler { jobs: OwnedMinIpq::from(vec![30, 10, 20]) };
+    /// assert_eq!(scheduler.jobs.poll_min_key_index(), Some(1));
+    ///
+    /// let mut rest = scheduler.jobs.into_inner();
+    /// rest.sort_unstable();
+    /// assert_eq!(rest, vec![20, 30]);
+    /// ```
+    fn from(values: Vec<T>) -> Self {
+        Self::with_storage(Storage::Owned(values))
+    }
+}
 
-        min_ipq
+impl<T, O> Default for OwnedIpq<T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl<'a, T> IndexedBinaryHeap for MinIndexedPriorityQueue<'a, T>
-    where
-        T: Clone + PartialOrd,
+impl<T, O> OwnedIpq<T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
 {
-    fn is_empty(&self) -> bool {
-        self.values.is_empty()
+    pub fn new() -> Self {
+        Self::with_capacity(0)
     }
 
-    #[inline]
-    fn less(&self, i: usize, j: usize) -> bool {
-        self.priority_sequenced_value(i) < self.priority_sequenced_value(j)
+    /// Same as `From<Vec<T>>`, returning an error instead of panicking on a value that is not
+    /// comparable with itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::limit::IpqError;
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let latencies = OwnedMinIpq::try_from_vec(vec![2.5, f64::NAN, 0.5]);
+    /// assert_eq!(latencies.err(), Some(IpqError::Incomparable(1)));
+    /// ```
+    pub fn try_from_vec(values: Vec<T>) -> Result<Self, IpqError> {
+        Self::try_with_storage(Storage::Owned(values))
     }
 
-    fn min_child(&self, mut i: usize) -> Option<usize> {
-        let number_of_direct_childs_per_node = 2;
-        let mut from = number_of_direct_childs_per_node * i + 1;
-        let to = if let true = (from + number_of_direct_childs_per_node) > self.size() {
-            self.size()
-        } else {
-            from + number_of_direct_childs_per_node
-        };
+    /// Builds an empty queue able to hold `capacity` entries before reallocating its values
+    /// or its index maps.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut ipq = Self::with_storage(Storage::Owned(Vec::with_capacity(capacity)));
+        while ipq.maps.node_capacity() < capacity {
+            ipq.expand_mapping();
+        }
 
-        let mut index: Option<usize> = None;
+        ipq
+    }
 
-        while from < to {
-            if self.less(from, i) {
-                i = from;
-                index = Some(i);
-            }
-            from += 1;
+    /// Consumes the queue and returns its values in heap order, i.e. in no particular one,
+    /// as [`BinaryHeap::into_vec`](std::collections::BinaryHeap::into_vec) does. Values a
+    /// queue borrowed are cloned, the caller's vector keeping them in that same order.
+    pub fn into_inner(self) -> Vec<T> {
+        match self.values {
+            Storage::Borrowed(values) => values.clone(),
+            Storage::Owned(values) => values,
         }
+    }
+}
 
-        index
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Same as `From<&mut Vec<T>>`, returning an error instead of panicking on a value that is
+    /// not comparable with itself; the caller's vector is then left untouched.
+    pub fn try_from_borrowed(values: &'a mut Vec<T>) -> Result<Self, IpqError> {
+        Self::try_with_storage(Storage::Borrowed(values))
     }
 
-    fn size(&self) -> usize {
-        self.values.len()
+    /// Heapifies `values` under the key indexes `0..n`.
+    ///
+    /// # Panics
+    ///
+    /// If a value is not comparable with itself, see
+    /// [`try_with_storage`](Self::try_with_storage).
+    pub(crate) fn with_storage(values: Storage<'a, T>) -> Self {
+        Self::try_with_storage(values).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`with_storage`](Self::with_storage), returning an error instead of panicking.
+    pub(crate) fn try_with_storage(values: Storage<'a, T>) -> Result<Self, IpqError> {
+        check_all_comparable(&values)?;
+        let mut ipq = Self::with_heap_ordered_storage(values);
+        ipq.fix_heap_invariant();
+
+        Ok(ipq)
     }
 
-    fn swap(&mut self, i: usize, j: usize) {
-        self.position_map[self.inverse_map[j].unwrap()] = Some(i);
-        self.position_map[self.inverse_map[i].unwrap()] = Some(j);
-        self.inverse_map.swap(i, j);
+    /// Copies the entries, heap layout and settings into a queue owning its values, leaving
+    /// behind the min publisher, the observer and the metadata, which cannot be cloned.
+    pub(crate) fn detached_copy(&self) -> OwnedIpq<T, O>
+    where
+        T: 'static,
+    {
+        OrderedIndexedPriorityQueue {
+            values: Storage::Owned(self.values.to_vec()),
+            maps: self.maps.clone(),
+            shrink_policy: self.shrink_policy,
+            bulk_tuning: self.bulk_tuning,
+            min_publisher: None,
+            observer: None,
+            metadata: MetaMap::new(),
+            held: self.held.clone(),
+            max_key_bound: self.max_key_bound,
+            dedup: self.dedup,
+            version: self.version,
+            order: self.order,
+        }
     }
 
-    fn swim(&mut self, mut i: usize) {
-        let mut pi = parent_node_index(i);
-        while i != pi && self.less(i, pi) {
-            self.swap(i, pi);
-            i = pi;
-            pi = parent_node_index(i);
+    /// Maps each node to the key index of the same number, trusting `values` to already be in
+    /// heap order.
+    fn with_heap_ordered_storage(values: Storage<'a, T>) -> Self {
+        let maps = IndexMaps::identity(values.len());
+
+        Self {
+            values,
+            maps,
+            shrink_policy: ShrinkPolicy::default(),
+            bulk_tuning: BulkTuning::default(),
+            min_publisher: None,
+            observer: None,
+            metadata: MetaMap::new(),
+            held: HashSet::new(),
+            max_key_bound: DEFAULT_MAX_KEY_BOUND,
+            dedup: false,
+            version: 0,
+            order: PhantomData,
         }
     }
 
-    fn sink(&mut self, mut i: usize) {
-        let mut j = self.min_child(i);
+    /// Builds an owned queue holding the values of `iter` under the key indexes `0..n`, in
+    /// iteration order.
+    ///
+    /// Values already sorted in the queue's order, i.e. ascending for a minimum queue and
+    /// descending for a maximum one, are a valid heap as they are: checking so takes `n - 1`
+    /// comparisons and then nothing is moved. Unsorted input is heapified as usual.
+    ///
+    /// # Panics
+    ///
+    /// If a value is not comparable with itself, see
+    /// [`try_from_sorted_iter`](Self::try_from_sorted_iter).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue};
+    ///
+    /// let mut ascending = MinIndexedPriorityQueue::from_sorted_iter(10..20);
+    /// assert_eq!(ascending.poll_min_key_index(), Some(0));
+    ///
+    /// let mut shuffled = MaxIndexedPriorityQueue::from_sorted_iter(vec![3, 9, 1]);
+    /// assert_eq!(shuffled.poll_min_key_index(), Some(1));
+    /// ```
+    pub fn from_sorted_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        Self::try_from_sorted_iter(iter).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-        while j.is_some() && j != Some(self.values.len()) {
-            self.swap(i, j.unwrap());
-            i = j.unwrap();
-            j = self.min_child(i);
+    /// Same as [`from_sorted_iter`](Self::from_sorted_iter), returning an error instead of
+    /// panicking.
+    pub fn try_from_sorted_iter<I>(iter: I) -> Result<Self, IpqError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let values = iter.into_iter().collect::<Vec<T>>();
+        check_all_comparable(&values)?;
+        let sorted = values
+            .windows(2)
+            .all(|pair| !O::precedes(&pair[1], &pair[0]));
+
+        let mut ipq = Self::with_heap_ordered_storage(Storage::Owned(values));
+        if sorted {
+            debug_assert!(ipq.satisfies_heap_invariant());
+        } else {
+            ipq.fix_heap_invariant();
         }
+
+        Ok(ipq)
+    }
+
+    /// Builds an owned queue holding `entries` under their given, distinct, key indexes.
+    ///
+    /// # Panics
+    ///
+    /// If a value is not comparable with itself, see
+    /// [`try_from_entries`](Self::try_from_entries).
+    pub(crate) fn from_entries(entries: Vec<(usize, T)>) -> Self {
+        Self::try_from_entries(entries).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`from_entries`](Self::from_entries), returning an error instead of panicking.
+    pub(crate) fn try_from_entries(entries: Vec<(usize, T)>) -> Result<Self, IpqError> {
+        Self::try_from_entries_in(entries, MapLayout::default())
+    }
+
+    /// Same as [`from_entries`](Self::from_entries), laying the maps out as `map_layout`.
+    pub(crate) fn from_entries_in(entries: Vec<(usize, T)>, map_layout: MapLayout) -> Self {
+        Self::try_from_entries_in(entries, map_layout).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`from_entries_in`](Self::from_entries_in), returning an error instead of
+    /// panicking.
+    pub(crate) fn try_from_entries_in(
+        entries: Vec<(usize, T)>,
+        map_layout: MapLayout,
+    ) -> Result<Self, IpqError> {
+        entries
+            .iter()
+            .try_for_each(|(key_index, value)| check_comparable(*key_index, value))?;
+        let node_capacity = entries.len().next_power_of_two();
+        let mut maps = IndexMaps::with_node_capacity(map_layout, node_capacity);
+        let values = entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, (key_index, value))| {
+                maps.map(KeyIdx(key_index), NodeIdx(i));
+                value
+            })
+            .collect();
+
+        let mut ipq = Self {
+            values: Storage::Owned(values),
+            maps,
+            shrink_policy: ShrinkPolicy::default(),
+            bulk_tuning: BulkTuning::default(),
+            min_publisher: None,
+            observer: None,
+            metadata: MetaMap::new(),
+            held: HashSet::new(),
+            max_key_bound: DEFAULT_MAX_KEY_BOUND,
+            dedup: false,
+            version: 0,
+            order: PhantomData,
+        };
+        ipq.fix_heap_invariant();
+
+        Ok(ipq)
     }
 }
 
-impl<'a, T> IndexedPriorityQueue<T> for MinIndexedPriorityQueue<'a, T>
+impl<'a, T, O> IndexedBinaryHeap for OrderedIndexedPriorityQueue<'a, T, O>
+    where
+        T: Clone + PartialOrd,
+        O: Order,
+{
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    #[inline]
+    fn less(&self, i: NodeIdx, j: NodeIdx) -> bool {
+        #[cfg(feature = "instrument")]
+        instrument::record_comparison();
+        O::precedes(self.priority_sequenced_value(i), self.priority_sequenced_value(j))
+    }
+
+    fn size(&self) -> usize {
+        self.values.len()
+    }
+
+    fn swap(&mut self, i: NodeIdx, j: NodeIdx) {
+        #[cfg(feature = "instrument")]
+        instrument::record_swap();
+        self.maps.swap_nodes(i, j);
+        self.values.swap(i.0, j.0);
+        self.observe(|observer| observer.on_swap(i, j));
+    }
+
+    #[cfg(feature = "instrument")]
+    fn on_sift(&mut self, from: NodeIdx, to: NodeIdx) {
+        let (from, to) = (from.depth(), to.depth());
+        instrument::record_sift((from.max(to) - from.min(to)) as u64);
+    }
+}
+
+impl<'a, T, O> IndexedPriorityQueue<T> for OrderedIndexedPriorityQueue<'a, T, O>
 where
     T: Clone + PartialOrd,
+    O: Order,
 {
     fn append(&mut self, extra_values: &mut Vec<T>) {
+        if self.dedup {
+            extra_values.drain(..).for_each(|value| self.push(value));
+            return;
+        }
+
         let size = self.size();
         let next_size = size + extra_values.len();
+        let first_key_index = self.next_key_index();
+        match first_key_index.checked_add(extra_values.len()) {
+            Some(key_bound) if key_bound <= self.max_key_bound => {}
+            _ => panic!(
+                "{}",
+                IpqError::KeyTooLarge {
+                    key_index: first_key_index.max(self.max_key_bound),
+                    max_key_bound: self.max_key_bound,
+                }
+            ),
+        }
 
-        if next_size > self.position_map.len() {
+        let first_incomparable = extra_values
+            .iter()
+            .enumerate()
+            .find_map(|(nth, value)| check_comparable(first_key_index + nth, value).err());
+        if let Some(e) = first_incomparable {
+            panic!("{}", e);
+        }
+        while next_size > self.maps.node_capacity() {
             self.expand_mapping();
         }
 
@@ -200,248 +565,526 @@ where
             end: self.size(),
         }
             .for_each(|i| {
-                self.inverse_map[i] = Some(i);
-                self.position_map[i] = Some(i);
+                let key_index = KeyIdx(first_key_index + i - size);
+                self.maps.map(key_index, NodeIdx(i));
+                if let Some(observer) = self.observer.as_deref_mut() {
+                    observer.on_insert(key_index.0, NodeIdx(i), &self.values[i]);
+                }
             });
 
-        self.fix_heap_invariant();
+        if self.bulk_tuning.append.should_rebuild(next_size - size, next_size) {
+            self.fix_heap_invariant();
+        } else {
+            (size..next_size).for_each(|i| self.swim(NodeIdx(i)));
+        }
+        self.publish_min();
     }
 
-    fn contains(&self, key_index: usize) -> bool {
-        return if key_index > (self.size() - 1) {
-            false
-        } else {
-            true
-        };
+    fn contains(&self, key_index: impl Into<KeyIdx>) -> bool {
+        self.maps.node_of(key_index.into()).is_some()
     }
 
-    fn decrease(&mut self, key_index: usize, value: T) {
-        self.key_exists_or_panic(key_index);
-        if value < self.values[key_index] {
-            self.values[key_index] = value;
+    fn decrease(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        let KeyIdx(key_index) = key_index.into();
+        let i = self.node_index_or_panic(key_index);
+        if value < self.values[i.0] {
+            let rises = O::precedes(&value, &self.values[i.0]);
+            self.values[i.0] = value;
 
-            self.swim(self.node_index(key_index))
+            self.sift(i, rises);
+            self.publish_min();
         }
     }
 
-    fn delete(&mut self, key_index: usize) -> Option<T> {
-        let size = self.size() - 1;
-        if size < key_index {
-            return None;
-        }
-
-        let im_index_max = max_value_index(&self.inverse_map);
-        let pm_index_max = max_value_index(&self.position_map);
-
-        let i = self.node_index(key_index);
-
-        self.inverse_map.swap(size, im_index_max);
-        self.position_map.swap(size, pm_index_max);
-        self.inverse_map[size] = None;
-        self.position_map[size] = None;
-
-        let value = Some(self.values[key_index].clone());
-        self.values.remove(key_index);
+    fn delete(&mut self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        let i = self.maps.node_of(key_index.into())?;
 
-        self.sink(i);
-        self.swim(i);
-
-        value
+        self.swap_remove(i).map(|(_, value)| value)
     }
 
-    fn drain(&mut self, start: usize, end: usize) -> Vec<T> {
-        let mapping_len = self.position_map.len();
-        let remaining = self.size().wrapping_sub(1 + end - start);
-        let drain = self.values.drain(start..=end).collect::<Vec<T>>();
-
-        self.inverse_map.truncate(0);
-        self.position_map.truncate(0);
-
-        self.inverse_map.resize_with(mapping_len, || None);
-        self.position_map.resize_with(mapping_len, || None);
+    fn drain<R: RangeBounds<usize>>(&mut self, key_indexes: R) -> IntoIter<T> {
+        let range = self.key_range(key_indexes);
+        let keys = if range.len() < self.size() {
+            range.filter(|&key_index| self.contains(key_index)).collect()
+        } else {
+            self.keys().filter(|key_index| range.contains(key_index)).collect::<Vec<usize>>()
+        };
 
-        if remaining > 0 {
-            Range {
-                start: 0,
-                end: remaining,
-            }
-                .for_each(|i| {
-                    self.inverse_map[i] = Some(i);
-                    self.position_map[i] = Some(i);
-                });
+        self.remove_all(keys)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<T>>()
+            .into_iter()
+    }
 
-            self.fix_heap_invariant();
+    fn insert(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        let KeyIdx(key_index) = key_index.into();
+        if let Err(e) = self
+            .check_free_key(key_index)
+            .and_then(|()| check_comparable(key_index, &value))
+        {
+            panic!("{}", e);
         }
 
-        drain
-    }
-
-    fn insert(&mut self, key_index: usize, value: T) {
         let size = self.size();
-        if key_index == size {
-            self.push(value);
-        } else {
-            self.position_map[size] = Some(size);
-            self.inverse_map[size] = Some(size);
-            self.values.insert(key_index, value);
-            self.swim(size);
+        if size >= self.maps.node_capacity() {
+            self.expand_mapping();
         }
+
+        self.maps.map(KeyIdx(key_index), NodeIdx(size));
+        self.values.push(value);
+        if let Some(observer) = self.observer.as_deref_mut() {
+            observer.on_insert(key_index, NodeIdx(size), &self.values[size]);
+        }
+        self.swim(NodeIdx(size));
+        self.publish_min();
     }
 
-    fn increase(&mut self, key_index: usize, value: T) {
-        self.key_exists_or_panic(key_index);
-        if self.values[key_index] < value {
-            self.values[key_index] = value;
-            self.sink(self.node_index(key_index));
+    fn increase(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        let KeyIdx(key_index) = key_index.into();
+        let i = self.node_index_or_panic(key_index);
+        if self.values[i.0] < value {
+            let rises = O::precedes(&value, &self.values[i.0]);
+            self.values[i.0] = value;
+
+            self.sift(i, rises);
+            self.publish_min();
         }
     }
 
     #[inline]
     fn peek_min_key_index(&self) -> Option<usize> {
-        self.inverse_map[0]
+        Some(self.maps.key_at(self.first_unheld_node()?)?.0)
     }
 
     fn peek_min_value(&self) -> Option<T> {
-        if self.is_empty() {
-            return None;
-        }
-        Some(self.values[self.peek_min_key_index().unwrap()].clone())
+        self.values.get(self.first_unheld_node()?.0).cloned()
     }
 
     fn poll_min_key_index(&mut self) -> Option<usize> {
-        if self.is_empty() {
-            return None;
-        }
-        let min_key_index = self.peek_min_key_index();
-        self.delete(min_key_index.unwrap());
-
-        min_key_index
+        self.swap_remove_min().map(|(key_index, _)| key_index)
     }
 
     fn poll_min_value(&mut self) -> Option<T> {
-        if self.is_empty() {
-            return None;
-        }
-        let min_value = self.peek_min_value();
-        let min_key_index = self.peek_min_key_index();
-        self.delete(min_key_index.unwrap());
-
-        min_value
+        self.swap_remove_min().map(|(_, value)| value)
     }
 
     fn push(&mut self, value: T) {
-        let size = self.size();
-
-        if size >= self.position_map.len() {
-            self.expand_mapping();
-        };
-
-        self.position_map[size] = Some(size);
-        self.inverse_map[size] = Some(size);
-        self.values.push(value);
-        self.swim(size);
+        if let Err(e) = self.try_push(value) {
+            panic!("{}", e);
+        }
     }
 
-    fn update(&mut self, key_index: usize, value: T) -> T {
-        self.key_exists_or_panic(key_index);
-
-        let i = self.node_index(key_index);
-        let old_value = self.values[key_index].clone();
+    fn update(&mut self, key_index: impl Into<KeyIdx>, value: T) -> T {
+        let KeyIdx(key_index) = key_index.into();
+        let i = self.node_index_or_panic(key_index);
+        if let Err(e) = check_comparable(key_index, &value) {
+            panic!("{}", e);
+        }
+        let old_value = std::mem::replace(&mut self.values[i.0], value);
 
-        self.values[key_index] = value;
         self.sink(i);
         self.swim(i);
+        self.publish_min();
 
         old_value
     }
 
-    fn value_of(&self, key_index: usize) -> Option<T> {
-        if (self.size() - 1) < key_index {
-            return None;
-        }
-        Some(self.values[key_index].clone())
+    fn value_of(&self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        let KeyIdx(key_index) = key_index.into();
+        self.value_ref(key_index).cloned()
     }
 }
 
-impl<'a, T> MinIndexedPriorityQueue<'a, T>
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
 where
     T: Clone + PartialOrd,
+    O: Order,
 {
     #[inline]
-    fn node_index(&self, i: usize) -> usize {
-        self.position_map[i].unwrap()
+    fn node_index(&self, key_index: usize) -> NodeIdx {
+        self.maps.node_of(KeyIdx(key_index)).unwrap()
     }
 
     #[inline]
-    fn priority_sequenced_value(&self, i: usize) -> &T {
-        &self.values[self.inverse_map[i].unwrap()]
+    pub(crate) fn value_ref(&self, key_index: usize) -> Option<&T> {
+        self.maps
+            .node_of(KeyIdx(key_index))
+            .map(|i| &self.values[i.0])
+    }
+
+    /// Key index under which [`push`](IndexedPriorityQueue::push) stores its next value: one
+    /// past the greatest key index used since the queue was built or cleared.
+    ///
+    /// Keys are stable, so deleting an entry never renumbers the others and its key index is
+    /// not handed out again by `push`.
+    pub fn next_key_index(&self) -> usize {
+        self.maps.key_bound()
+    }
+
+    /// Iterates over the key indexes in use, by ascending key index.
+    pub fn keys(&self) -> impl Iterator<Item = usize> + '_ {
+        self.maps.iter().map(|(key_index, _)| key_index.0)
     }
 
-    pub fn left_child(&self, node_index: usize) -> Option<&T> {
-        let i = 2 * node_index + 1;
-        return if i < self.values.len() {
-            Some(&self.values[self.inverse_map[i].unwrap()])
+    /// Exchanges the values of `key_a` and `key_b`, e.g. to move a job right above another.
+    ///
+    /// Only the two values move: keys, metadata and holds stay where they were. The key whose
+    /// new value ranks later is sunk first and the other one then swum up, so at most two sifts
+    /// restore the heap invariant.
+    ///
+    /// # Panics
+    ///
+    /// If either key is not in the queue, see [`try_swap_keys`](Self::try_swap_keys).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut ranks = vec![1, 2, 3];
+    /// let mut jobs = MinIndexedPriorityQueue::from(&mut ranks);
+    ///
+    /// jobs.swap_keys(2, 0);
+    /// assert_eq!(jobs.poll_min_key_index(), Some(2));
+    /// assert_eq!(jobs.value_of(0), Some(3));
+    /// ```
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn swap_keys(&mut self, key_a: usize, key_b: usize) {
+        self.try_swap_keys(key_a, key_b).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`swap_keys`](Self::swap_keys), returning an error instead of panicking when
+    /// either key is not in the queue.
+    pub fn try_swap_keys(&mut self, key_a: usize, key_b: usize) -> Result<(), IpqError> {
+        let node_of = |key_index| {
+            self.maps.node_of(KeyIdx(key_index)).ok_or(IpqError::KeyNotFound(key_index))
+        };
+        let (i, j) = (node_of(key_a)?, node_of(key_b)?);
+        if i == j {
+            return Ok(());
+        }
+
+        self.values.swap(i.0, j.0);
+        let (sinking, rising) = if self.less(i, j) {
+            (key_b, key_a)
         } else {
-            None
+            (key_a, key_b)
         };
+        self.sift(self.node_index(sinking), false);
+        self.sift(self.node_index(rising), true);
+        self.publish_min();
+
+        Ok(())
+    }
+
+    /// Takes out the entry at `node_index`, returning its key index and value, or `None` when
+    /// there is no such node.
+    ///
+    /// The last node is moved into the hole and sifted from there, so only `O(log n)` entries
+    /// move; [`delete`](IndexedPriorityQueue::delete) and the polls are built on it. It suits
+    /// callers that walk the heap by node, e.g. to evict a leaf without looking its key up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::index::NodeIdx;
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![4, 1, 3, 2];
+    /// let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+    ///
+    /// assert_eq!(ipq.swap_remove(NodeIdx::ROOT), Some((1, 1)));
+    /// assert_eq!(ipq.swap_remove(NodeIdx(3)), None);
+    /// assert_eq!(ipq.peek_min_value(), Some(2));
+    /// ```
+    pub fn swap_remove(&mut self, node_index: NodeIdx) -> Option<(usize, T)> {
+        let key_index = self.maps.key_at(node_index)?.0;
+        let last = NodeIdx(self.size() - 1);
+        self.swap(node_index, last);
+
+        self.maps.unmap(last);
+        self.forget_meta(key_index);
+        self.forget_hold(key_index);
+        let value = self.values.pop()?;
+        self.observe(|observer| observer.on_remove(key_index, last));
+
+        if node_index < last {
+            self.sink(node_index);
+            self.swim(node_index);
+        }
+        self.apply_shrink_policy();
+        self.publish_min();
+
+        Some((key_index, value))
     }
 
-    pub fn right_child(&self, node_index: usize) -> Option<&T> {
-        let i = 2 * node_index + 2;
-        return if i < self.values.len() {
-            Some(&self.values[self.inverse_map[i].unwrap()])
+    /// Polls the entry ranked first, skipping held ones, returning its key index and value.
+    pub fn swap_remove_min(&mut self) -> Option<(usize, T)> {
+        let i = self.first_unheld_node()?;
+
+        self.swap_remove(i)
+    }
+
+    #[inline]
+    fn sift(&mut self, i: NodeIdx, rises: bool) {
+        if rises {
+            self.swim(i);
         } else {
-            None
-        };
+            self.sink(i);
+        }
     }
 
-    pub fn iter(&self) -> Iter<'_, T> {
-        self.values.iter()
+    #[inline]
+    fn priority_sequenced_value(&self, i: NodeIdx) -> &T {
+        &self.values[i.0]
+    }
+
+    pub fn left_child(&self, node_index: NodeIdx) -> Option<&T> {
+        self.values.get(node_index.child(0)?.0)
+    }
+
+    pub fn right_child(&self, node_index: NodeIdx) -> Option<&T> {
+        self.values.get(node_index.child(1)?.0)
+    }
+
+    /// Iterates over the `(key_index, &value)` pairs in heap order, i.e. in no particular
+    /// one, starting with the entry ranked first.
+    pub fn iter(&self) -> iter::Iter<'_, T> {
+        iter::Iter::new(&self.maps, &self.values)
     }
 
     pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.bump_version();
         self.values.iter_mut()
     }
 
-    fn fix_heap_invariant(&mut self) {
-        let mut edge_layer_range = Range {
-            start: (self.inverse_map.len() / 2).wrapping_sub(1),
-            end: self.size(),
-        };
+    /// Returns `true` when both queues map the same keys to equal values, whatever their
+    /// order or the shape their heaps ended up in.
+    pub fn logically_eq<P: Order>(&self, other: &OrderedIndexedPriorityQueue<'_, T, P>) -> bool {
+        self.size() == other.size()
+            && self
+                .maps
+                .keys_by_node(self.size())
+                .zip(self.values.iter())
+                .all(|(k, value)| other.value_ref(k.0) == Some(value))
+    }
+
+    /// Returns `true` when both queues hold equal values under identical keys at every node,
+    /// spare mapping capacity aside; i.e. when they are the very same heap.
+    pub fn structurally_eq(&self, other: &OrderedIndexedPriorityQueue<'_, T, O>) -> bool {
+        let size = self.size();
 
-        if edge_layer_range.start > edge_layer_range.end {
-            edge_layer_range.start = (self.size().next_power_of_two() / 2).wrapping_sub(1);
+        *self.values == *other.values
+            && self.maps.keys_by_node(size).eq(other.maps.keys_by_node(size))
+    }
+
+    /// Takes out every entry for which `remove(key_index, &value)` holds, in no particular
+    /// order, then heapifies what is left in a single pass.
+    pub(crate) fn remove_and_rebuild<F>(&mut self, mut remove: F) -> Vec<(usize, T)>
+    where
+        F: FnMut(usize, &T) -> bool,
+    {
+        if self.observer.is_some() {
+            return self.remove_observed_and_rebuild(remove);
+        }
+
+        let size = self.size();
+        let entries = self
+            .maps
+            .keys_by_node(size)
+            .map(|k| k.0)
+            .zip(self.values.drain(..))
+            .collect::<Vec<(usize, T)>>();
+        self.maps.clear_nodes(0..size);
+
+        let mut removed = Vec::new();
+        entries.into_iter().for_each(|(key_index, value)| {
+            if remove(key_index, &value) {
+                self.maps.forget_key(KeyIdx(key_index));
+                self.forget_meta(key_index);
+                self.forget_hold(key_index);
+                removed.push((key_index, value));
+            } else {
+                let i = self.size();
+                self.maps.map(KeyIdx(key_index), NodeIdx(i));
+                self.values.push(value);
+            }
+        });
+
+        self.fix_heap_invariant();
+        self.apply_shrink_policy();
+        self.publish_min();
+
+        removed
+    }
+
+    /// Same as [`remove_and_rebuild`](Self::remove_and_rebuild), in events an observer can
+    /// replay: each removed entry is swapped with the last node and popped, without sifting,
+    /// then the heap is rebuilt once and its sinks are reported swap by swap.
+    fn remove_observed_and_rebuild<F>(&mut self, mut remove: F) -> Vec<(usize, T)>
+    where
+        F: FnMut(usize, &T) -> bool,
+    {
+        let mut removed = Vec::new();
+        let mut i = NodeIdx(0);
+        while i.0 < self.size() {
+            let key_index = self.maps.key_at(i).unwrap().0;
+            if !remove(key_index, &self.values[i.0]) {
+                i = NodeIdx(i.0 + 1);
+                continue;
+            }
+
+            // The last node has not been visited yet, so it is checked next from node `i`.
+            let last = NodeIdx(self.size() - 1);
+            if i < last {
+                self.swap(i, last);
+            }
+            self.maps.unmap(last);
+            self.forget_meta(key_index);
+            self.forget_hold(key_index);
+            removed.push((key_index, self.values.pop().unwrap()));
+            self.observe(|observer| observer.on_remove(key_index, last));
+        }
+
+        self.fix_heap_invariant();
+        self.apply_shrink_policy();
+        self.publish_min();
+
+        removed
+    }
+
+    fn fix_heap_invariant(&mut self) {
+        Range {
+            start: 0,
+            end: self.size() / 2,
         }
-        edge_layer_range.for_each(|i| self.swim(i));
+            .rev()
+            .for_each(|i| self.sink(NodeIdx(i)));
+
+        debug_assert!(self.has_consistent_mappings());
+        debug_assert!(self.satisfies_heap_invariant());
     }
 
     fn expand_mapping(&mut self) {
-        let pm_len = self.position_map.len();
-        let extra_len = (pm_len + 1).next_power_of_two() - pm_len;
+        let mapping_capacity = self.maps.expand();
+        self.observe(|observer| observer.on_expand(mapping_capacity));
+    }
 
-        let mut mapping_expansion = vec![None; extra_len];
-        self.position_map.append(&mut mapping_expansion.clone());
-        self.inverse_map.append(&mut mapping_expansion);
+    /// Returns the number of parent to child links of the heap, i.e. one less than its size,
+    /// and `0` for an empty queue as for a lone root.
+    pub fn branches_count(&self) -> usize {
+        self.size().saturating_sub(1)
     }
 
-    fn branches_count(&self) -> usize {
-        self.size() - 1
+    /// Resolves `key_indexes` against the key space in use, panicking when the range is
+    /// reversed or reaches past [`next_key_index`](Self::next_key_index).
+    pub(crate) fn key_range<R: RangeBounds<usize>>(&self, key_indexes: R) -> Range<usize> {
+        match self.try_key_range(key_indexes) {
+            Ok(range) => range,
+            Err(e) => panic!("{}", e),
+        }
     }
 
-    fn key_exists_or_panic(&self, key_index: usize) {
-        if !self.contains(key_index) {
-            panic!("Index does not exist; received: {}", key_index);
+    /// Same as [`key_range`](Self::key_range), reporting a bad range as
+    /// [`IpqError::InvalidRange`] with saturated bounds.
+    pub(crate) fn try_key_range<R: RangeBounds<usize>>(
+        &self,
+        key_indexes: R,
+    ) -> Result<Range<usize>, IpqError> {
+        let key_bound = self.maps.key_bound();
+        let start = match key_indexes.start_bound() {
+            Bound::Included(&start) => Some(start),
+            Bound::Excluded(&start) => start.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match key_indexes.end_bound() {
+            Bound::Included(&end) => end.checked_add(1),
+            Bound::Excluded(&end) => Some(end),
+            Bound::Unbounded => Some(key_bound),
+        };
+
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end && end <= key_bound => Ok(start..end),
+            _ => Err(IpqError::InvalidRange {
+                start: start.unwrap_or(usize::MAX),
+                end: end.unwrap_or(usize::MAX),
+                len: key_bound,
+            }),
         }
     }
+
+    /// Looks `key_index` up once, panicking when it is not in the queue.
+    #[inline]
+    fn node_index_or_panic(&self, key_index: usize) -> NodeIdx {
+        match self.maps.node_of(KeyIdx(key_index)) {
+            Some(i) => i,
+            None => panic!("Index does not exist; received: {}", key_index),
+        }
+    }
+
+    pub(crate) fn has_consistent_mappings(&self) -> bool {
+        self.maps.is_consistent(self.size())
+    }
+
+    pub(crate) fn satisfies_heap_invariant(&self) -> bool {
+        Range {
+            start: 1,
+            end: self.size(),
+        }
+            .map(NodeIdx)
+            .all(|i| !self.less(i, i.parent()))
+    }
+}
+
+/// Rejects values a heap cannot order, such as NaNs, which would otherwise silently break the
+/// heap invariant; see [`order::Total`].
+#[inline]
+pub(crate) fn check_comparable<T: PartialOrd>(key_index: usize, value: &T) -> Result<(), IpqError> {
+    if value.partial_cmp(value).is_none() {
+        return Err(IpqError::Incomparable(key_index));
+    }
+
+    Ok(())
+}
+
+/// Runs [`check_comparable`] over `values`, held under the key indexes `0..n`.
+pub(crate) fn check_all_comparable<T: PartialOrd>(values: &[T]) -> Result<(), IpqError> {
+    values
+        .iter()
+        .enumerate()
+        .try_for_each(|(key_index, value)| check_comparable(key_index, value))
+}
+
+/// Same as [`check_comparable`], for values rewritten in place where no error can be returned.
+#[inline]
+pub(crate) fn debug_assert_comparable<T: PartialOrd>(value: &T) {
+    debug_assert!(
+        value.partial_cmp(value).is_some(),
+        "Value is not comparable with itself, e.g. a NaN; wrap it in order::Total"
+    );
 }
 
 #[cfg(test)]
 mod min_indexed_pq_tests {
-    use crate::{IndexedBinaryHeap, IndexedPriorityQueue, MinIndexedPriorityQueue};
+    use crate::index::{KeyIdx, NodeIdx};
+    use crate::{IndexedPriorityQueue, MinIndexedPriorityQueue, OwnedMaxIpq, OwnedMinIpq};
+
+    fn position_map_of(ipq: &MinIndexedPriorityQueue<i32>) -> Vec<Option<usize>> {
+        (0..16)
+            .map(|k| ipq.maps.node_of(KeyIdx(k)).map(usize::from))
+            .collect()
+    }
+
+    fn inverse_map_of(ipq: &MinIndexedPriorityQueue<i32>) -> Vec<Option<usize>> {
+        (0..ipq.maps.node_capacity())
+            .map(|i| ipq.maps.key_at(NodeIdx(i)).map(|k| k.0))
+            .collect()
+    }
 
     #[test]
     fn test_iter_max_and_min_with_integers() {
-        let values = vec![
+        let values = [
             Some(1),
             Some(0),
             Some(2),
@@ -492,6 +1135,17 @@ mod min_indexed_pq_tests {
         let mut values = vec![3, 4, 5, -1];
         let ipq = MinIndexedPriorityQueue::from(&mut values);
         assert_eq!(ipq.branches_count(), 3);
+        drop(ipq);
+        drop(values);
+
+        let mut values = vec![7];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        assert_eq!(ipq.branches_count(), 0);
+        ipq.poll_min_value();
+        assert_eq!(ipq.branches_count(), 0);
+        assert_eq!(ipq.height(), 0);
+        ipq.fix_heap_invariant();
+        assert_eq!(format!("{}", ipq), "Minimum Priority Queue of 0 elements and 0 branches");
     }
 
     #[test]
@@ -512,17 +1166,17 @@ mod min_indexed_pq_tests {
         let mut min_ipq = MinIndexedPriorityQueue::from(&mut values);
 
         assert_eq!(
-            min_ipq.position_map,
+            position_map_of(&min_ipq),
             [
-                Some(3),
+                Some(11),
                 Some(8),
                 Some(5),
                 Some(7),
                 Some(9),
-                Some(11),
-                Some(6),
                 Some(2),
+                Some(6),
                 Some(1),
+                Some(3),
                 Some(4),
                 Some(10),
                 Some(0),
@@ -533,12 +1187,12 @@ mod min_indexed_pq_tests {
             ]
         );
         assert_eq!(
-            min_ipq.inverse_map,
+            inverse_map_of(&min_ipq),
             [
                 Some(11),
-                Some(8),
                 Some(7),
-                Some(0),
+                Some(5),
+                Some(8),
                 Some(9),
                 Some(2),
                 Some(6),
@@ -546,7 +1200,7 @@ mod min_indexed_pq_tests {
                 Some(1),
                 Some(4),
                 Some(10),
-                Some(5),
+                Some(0),
                 None,
                 None,
                 None,
@@ -557,17 +1211,17 @@ mod min_indexed_pq_tests {
         min_ipq.insert(v_len, -100);
 
         assert_eq!(
-            min_ipq.position_map,
+            position_map_of(&min_ipq),
             [
-                Some(3),
+                Some(11),
                 Some(8),
                 Some(12),
                 Some(7),
                 Some(9),
-                Some(11),
-                Some(6),
                 Some(5),
+                Some(6),
                 Some(1),
+                Some(3),
                 Some(4),
                 Some(10),
                 Some(2),
@@ -578,20 +1232,20 @@ mod min_indexed_pq_tests {
             ]
         );
         assert_eq!(
-            min_ipq.inverse_map,
+            inverse_map_of(&min_ipq),
             [
                 Some(12),
-                Some(8),
+                Some(7),
                 Some(11),
-                Some(0),
+                Some(8),
                 Some(9),
-                Some(7),
+                Some(5),
                 Some(6),
                 Some(3),
                 Some(1),
                 Some(4),
                 Some(10),
-                Some(5),
+                Some(0),
                 Some(2),
                 None,
                 None,
@@ -606,17 +1260,17 @@ mod min_indexed_pq_tests {
 
         let ipq = MinIndexedPriorityQueue::from(&mut values);
 
-        assert_eq!(ipq.left_child(4), Some(&3));
-        assert_eq!(ipq.right_child(4), Some(&4));
+        assert_eq!(ipq.left_child(NodeIdx(4)), Some(&8));
+        assert_eq!(ipq.right_child(NodeIdx(4)), Some(&4));
 
-        assert_eq!(ipq.left_child(5), Some(&7));
-        assert_eq!(ipq.right_child(5), None);
+        assert_eq!(ipq.left_child(NodeIdx(5)), Some(&8));
+        assert_eq!(ipq.right_child(NodeIdx(5)), None);
 
-        assert_eq!(ipq.left_child(7), None);
-        assert_eq!(ipq.right_child(7), None);
+        assert_eq!(ipq.left_child(NodeIdx(7)), None);
+        assert_eq!(ipq.right_child(NodeIdx(7)), None);
 
-        assert_eq!(ipq.left_child(12), None);
-        assert_eq!(ipq.right_child(12), None);
+        assert_eq!(ipq.left_child(NodeIdx(12)), None);
+        assert_eq!(ipq.right_child(NodeIdx(12)), None);
     }
 
     #[test]
@@ -627,7 +1281,7 @@ mod min_indexed_pq_tests {
         assert_eq!(ipq.poll_min_value(), Some(1));
         assert_eq!(ipq.poll_min_value(), Some(2));
 
-        ipq.insert(ipq.size(), -100);
+        ipq.insert(ipq.next_key_index(), -100);
         assert_eq!(ipq.peek_min_value(), Some(-100));
     }
 
@@ -642,7 +1296,7 @@ mod min_indexed_pq_tests {
         ipq.insert(ipq.size(), 6);
 
         let values_len = ipq.size();
-        let nones_len = ipq.inverse_map.len() - values_len;
+        let nones_len = ipq.maps.node_capacity() - values_len;
         let nones = vec![None; nones_len];
 
         let mut pm = vec![
@@ -671,12 +1325,35 @@ mod min_indexed_pq_tests {
         im.append(&mut nones.clone());
 
         assert_eq!(values_len, 9);
-        assert_eq!(ipq.inverse_map.len(), 16);
-        assert_eq!(ipq.position_map.len(), 16);
+        assert_eq!(ipq.maps.node_capacity(), 16);
+        assert_eq!(ipq.next_key_index(), 9);
 
-        assert_eq!(ipq.values, &mut [1, 2, 2, 2, 0, 3, 4, 5, 6]);
-        assert_eq!(ipq.inverse_map, im);
-        assert_eq!(ipq.position_map, pm);
+        assert_eq!(*ipq.values, [0, 1, 2, 2, 2, 3, 4, 5, 6]);
+        assert_eq!(inverse_map_of(&ipq), im);
+        assert_eq!(
+            position_map_of(&ipq),
+            pm
+        );
+    }
+
+    #[test]
+    fn insert_should_accept_sparse_key_indexes_without_a_dense_mapping() {
+        let mut values = vec![7, 3];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        ipq.insert(10_000_000, 5);
+        ipq.insert(4_096, 1);
+        assert_eq!(ipq.maps.allocated_pages(), 3);
+        assert_eq!(ipq.maps.node_capacity(), 4);
+        assert_eq!(ipq.next_key_index(), 10_000_001);
+
+        assert_eq!(ipq.delete(0), Some(7));
+        assert_eq!(ipq.keys().collect::<Vec<usize>>(), vec![1, 4_096, 10_000_000]);
+        assert_eq!(ipq.value_of(10_000_000), Some(5));
+        assert_eq!(ipq.poll_min_key_index(), Some(4_096));
+        assert_eq!(ipq.poll_min_key_index(), Some(1));
+        assert_eq!(ipq.poll_min_key_index(), Some(10_000_000));
+        assert_eq!(ipq.maps.allocated_pages(), 0);
     }
 
     #[test]
@@ -688,8 +1365,8 @@ mod min_indexed_pq_tests {
         ipq.append(&mut extra_values);
 
         assert_eq!(ipq.size(), 8);
-        assert_eq!(ipq.inverse_map.len(), 8);
-        assert_eq!(ipq.position_map.len(), 8);
+        assert_eq!(ipq.maps.node_capacity(), 8);
+        assert_eq!(ipq.next_key_index(), 8);
     }
 
     #[test]
@@ -701,8 +1378,8 @@ mod min_indexed_pq_tests {
         ipq.append(&mut extra_values);
 
         assert_eq!(ipq.size(), 9);
-        assert_eq!(ipq.inverse_map.len(), 16);
-        assert_eq!(ipq.position_map.len(), 16);
+        assert_eq!(ipq.maps.node_capacity(), 16);
+        assert_eq!(ipq.next_key_index(), 9);
     }
 
     #[test]
@@ -718,12 +1395,16 @@ mod min_indexed_pq_tests {
         let mut values: Vec<i32> = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
         let mut ipq = MinIndexedPriorityQueue::from(&mut values);
 
-        ipq.drain(5, 11);
+        let drained = ipq.drain(5..);
 
+        assert_eq!(drained.len(), 7);
+        assert_eq!(drained.collect::<Vec<i32>>(), vec![1, 2, 2, 2, 3, 4, 0]);
         assert_eq!(ipq.size(), 5);
         assert_eq!(ipq.peek_min_value(), Some(5));
-        assert_eq!(ipq.inverse_map.len(), 16);
-        assert_eq!(ipq.position_map.len(), 16);
+        assert_eq!(ipq.maps.node_capacity(), 16);
+        assert_eq!(ipq.next_key_index(), 12);
+        assert_eq!(ipq.value_of(4), Some(5));
+        assert!(!ipq.contains(5));
     }
 
     #[test]
@@ -731,8 +1412,21 @@ mod min_indexed_pq_tests {
         let mut values = vec![1, 2, 2, 2, 0];
         let mut ipq = MinIndexedPriorityQueue::from(&mut values);
 
-        ipq.drain(0, 4);
+        ipq.drain(..);
+
+        assert!(ipq.is_empty());
+    }
+
+    #[test]
+    fn drain_should_follow_every_kind_of_range_bound() {
+        let mut values = (0..10).collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
 
+        assert_eq!(ipq.drain(..2).collect::<Vec<i32>>(), vec![0, 1]);
+        assert_eq!(ipq.drain(8..=9).rev().collect::<Vec<i32>>(), vec![9, 8]);
+        assert_eq!(ipq.drain(4..4).count(), 0);
+        assert_eq!(ipq.drain(..=3).collect::<Vec<i32>>(), vec![2, 3]);
+        assert_eq!(ipq.drain(..).collect::<Vec<i32>>(), vec![4, 5, 6, 7]);
         assert!(ipq.is_empty());
     }
 
@@ -747,7 +1441,7 @@ mod min_indexed_pq_tests {
         assert_eq!(ipq.size(), 3);
         assert_eq!(ipq.peek_min_key_index(), Some(0));
         assert_eq!(ipq.poll_min_value(), Some(-100));
-        assert_eq!(ipq.peek_min_key_index(), Some(0));
+        assert_eq!(ipq.peek_min_key_index(), Some(1));
         assert_eq!(ipq.poll_min_value(), Some(-2));
         assert_eq!(ipq.poll_min_value(), Some(0));
     }
@@ -777,6 +1471,42 @@ mod min_indexed_pq_tests {
         assert_eq!(ipq.poll_min_value(), Some(8));
         assert_eq!(ipq.poll_min_value(), Some(9));
         assert!(ipq.is_empty());
+        assert_eq!(ipq.peek_min_key_index(), None);
+        assert_eq!(ipq.poll_min_value(), None);
+        assert_eq!(ipq.to_string(), "Minimum Priority Queue of 0 elements and 0 branches");
+    }
+
+    #[test]
+    fn from_sorted_iter_should_keep_sorted_input_in_place() {
+        let ipq = MinIndexedPriorityQueue::from_sorted_iter(vec![1, 1, 2, 5, 8]);
+        assert_eq!(ipq.iter().map(|(_, &v)| v).collect::<Vec<i32>>(), vec![1, 1, 2, 5, 8]);
+        assert!(ipq.has_consistent_mappings());
+
+        let mut ipq = crate::MaxIndexedPriorityQueue::from_sorted_iter(vec![1, 1, 2, 5, 8]);
+        assert!(ipq.satisfies_heap_invariant());
+        assert_eq!(ipq.poll_min_key_index(), Some(4));
+        assert_eq!(ipq.value_of(0), Some(1));
+    }
+
+    #[test]
+    fn unchecked_variants_should_return_the_entry_ranked_first() {
+        let mut values: Vec<i32> = vec![9, 8, 0];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.peek_min_key_index_unchecked(), 2);
+        assert_eq!(ipq.peek_min_value_unchecked(), 0);
+        assert_eq!(ipq.poll_min_key_index_unchecked(), 2);
+        assert_eq!(ipq.poll_min_value_unchecked(), 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unchecked_poll_should_fail_when_empty() {
+        let mut values: Vec<i32> = vec![1];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        ipq.poll_min_value_unchecked();
+
+        ipq.poll_min_key_index_unchecked();
     }
 
     #[test]
@@ -819,7 +1549,7 @@ mod min_indexed_pq_tests {
         let mut values = vec![1, 2, 2, 2, 0];
         let mut ipq = MinIndexedPriorityQueue::from(&mut values);
 
-        ipq.drain(11, 4);
+        ipq.drain(11..);
     }
 
     #[test]
@@ -828,7 +1558,7 @@ mod min_indexed_pq_tests {
         let mut values = vec![1, 2, 2, 2, 0];
         let mut ipq = MinIndexedPriorityQueue::from(&mut values);
 
-        ipq.drain(0, 20);
+        ipq.drain(0..=5);
     }
 
     #[test]
@@ -862,7 +1592,26 @@ mod min_indexed_pq_tests {
         let mut values = vec![1, 2, 2, 2, 0];
         let mut ipq = MinIndexedPriorityQueue::from(&mut values);
 
-        ipq.insert(ipq.size() + 1, -1);
+        ipq.insert(ipq.size() - 1, -1);
+    }
+
+    #[test]
+    fn swap_keys_should_keep_the_heap_invariant_along_any_path() {
+        let mut values = (0..63).collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        (0..63).for_each(|a| {
+            let b = (a * 17 + 5) % 63;
+            assert_eq!(ipq.try_swap_keys(a, b), Ok(()));
+            assert!(ipq.satisfies_heap_invariant());
+            assert!(ipq.has_consistent_mappings());
+        });
+        assert_eq!(ipq.try_swap_keys(0, 63), Err(crate::limit::IpqError::KeyNotFound(63)));
+        let mut polled = Vec::new();
+        while let Some(v) = ipq.poll_min_value() {
+            polled.push(v);
+        }
+        assert_eq!(polled, (0..63).collect::<Vec<i32>>());
     }
 
     #[test]
@@ -871,6 +1620,125 @@ mod min_indexed_pq_tests {
         let mut values = vec![1, 2, 2, 2, 0];
         let ipq = MinIndexedPriorityQueue::from(&mut values);
 
-        ipq.priority_sequenced_value(5);
+        ipq.priority_sequenced_value(NodeIdx(5));
+    }
+
+    #[test]
+    fn owned_ipq_should_keep_its_values_without_a_borrow() {
+        let mut ipq = OwnedMinIpq::with_capacity(100);
+        assert_eq!(ipq.mapping_capacity(), 128);
+        (0..100).rev().for_each(|v| ipq.push(v));
+        assert_eq!(ipq.peek_min_key_index(), Some(99));
+
+        let mut reused = OwnedMinIpq::from(ipq.into_inner());
+        assert_eq!(reused.size(), 100);
+        assert_eq!(reused.poll_min_value(), Some(0));
+        assert!(OwnedMaxIpq::<u8>::default().is_empty());
+    }
+
+    #[test]
+    fn swap_remove_should_fill_the_hole_with_the_last_node() {
+        let mut values = vec![5, 1, 9, 3, 7, 2, 8];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let inner = ipq.maps.key_at(NodeIdx(1)).unwrap().0;
+        assert_eq!(ipq.swap_remove(NodeIdx(1)).map(|(k, _)| k), Some(inner));
+        assert_eq!(ipq.check_invariants(), Ok(()));
+        assert_eq!(ipq.swap_remove(NodeIdx(6)), None);
+        assert_eq!(ipq.swap_remove_min(), Some((1, 1)));
+        assert!(!ipq.contains(inner));
+        assert_eq!(ipq.size(), 5);
+        assert_eq!(ipq.check_invariants(), Ok(()));
+    }
+}
+
+#[cfg(test)]
+mod equality_tests {
+    use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue};
+
+    #[test]
+    fn logically_eq_should_ignore_heap_shape_and_order() {
+        let mut a_values = vec![1, 2, 2, 2, 0];
+        let mut b_values = vec![1, 2, 2, 2, 0];
+        let a = MinIndexedPriorityQueue::from(&mut a_values);
+        let b = MaxIndexedPriorityQueue::from(&mut b_values);
+
+        assert!(a.logically_eq(&b));
+        assert!(b.logically_eq(&a));
+    }
+
+    #[test]
+    fn structurally_eq_should_tell_apart_heaps_built_differently() {
+        let mut a_values = vec![3, 2, 1];
+        let mut b_values = vec![3];
+        let a = MinIndexedPriorityQueue::from(&mut a_values);
+        let mut b = MinIndexedPriorityQueue::from(&mut b_values);
+        b.push(2);
+        b.push(1);
+
+        assert!(a.logically_eq(&b));
+        assert!(!a.structurally_eq(&b));
+
+        let mut c_values = vec![3, 2, 1];
+        let c = MinIndexedPriorityQueue::from(&mut c_values);
+        assert!(a.structurally_eq(&c));
+    }
+
+    #[test]
+    fn logically_eq_should_compare_every_key() {
+        let mut a_values = vec![1, 2, 3];
+        let mut b_values = vec![1, 3, 2];
+        let a = MinIndexedPriorityQueue::from(&mut a_values);
+        let mut b = MinIndexedPriorityQueue::from(&mut b_values);
+
+        assert!(!a.logically_eq(&b));
+        b.update(1, 2);
+        b.update(2, 3);
+        assert!(a.logically_eq(&b));
+    }
+}
+
+#[cfg(test)]
+mod max_indexed_pq_tests {
+    use crate::MaxIndexedPriorityQueue;
+
+    #[test]
+    fn max_ipq_should_poll_values_in_descending_order() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.peek_min_key_index(), Some(0));
+        let mut polled = Vec::new();
+        while let Some(v) = ipq.poll_min_value() {
+            polled.push(v);
+        }
+        assert_eq!(polled, vec![9, 8, 7, 6, 5, 4, 3, 2, 2, 2, 1, 0]);
+        assert!(ipq.is_empty());
+    }
+
+    #[test]
+    fn decrease_and_increase_should_move_entries_the_other_way_in_a_max_ipq() {
+        let mut values: Vec<i32> = vec![9, 8, 0];
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        ipq.decrease(0, -100);
+        assert_eq!(ipq.peek_min_key_index(), Some(1));
+        ipq.increase(2, 100);
+        assert_eq!(ipq.peek_min_value(), Some(100));
+
+        assert_eq!(ipq.poll_min_value(), Some(100));
+        assert_eq!(ipq.poll_min_value(), Some(8));
+        assert_eq!(ipq.poll_min_value(), Some(-100));
+    }
+
+    #[test]
+    fn display_should_name_the_order() {
+        let mut values = vec![3, 4, 5, -1];
+        let ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(
+            format!("{}", ipq),
+            "Maximum Priority Queue of 4 elements and 3 branches"
+        )
     }
 }
@@ -0,0 +1,218 @@
+use crate::index::KeyIdx;
+use crate::order::Order;
+use crate::{check_comparable, OrderedIndexedPriorityQueue};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Key indexes a queue accepts unless told otherwise: `0..2³⁰`.
+///
+/// Both map layouts page their key slots, keeping one directory slot per 1024 keys up to the
+/// greatest one, so this bounds the memory a single hostile key index can make the queue
+/// allocate to a 2²⁰ slot directory, 24 MiB on 64-bit targets, plus one page.
+pub const DEFAULT_MAX_KEY_BOUND: usize = 1 << 30;
+
+/// Reason a `try_*` operation of a queue failed: a refused key index, an empty queue, a bad
+/// key index range or a value the heap cannot rank, so that callers handle every misuse
+/// through one type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IpqError {
+    KeyInUse(usize),
+    KeyTooLarge { key_index: usize, max_key_bound: usize },
+    KeyNotFound(usize),
+    Empty,
+    /// A key index range is reversed or reaches past the key space of length `len`, its
+    /// bounds resolved to the half-open `start..end`.
+    InvalidRange { start: usize, end: usize, len: usize },
+    /// The value given for a key index is not comparable with itself, e.g. a NaN, so the heap
+    /// could not rank it; wrap such values in [`order::Total`](crate::order::Total).
+    Incomparable(usize),
+    /// A fixed capacity queue holds `capacity` entries already, so no key index is left.
+    CapacityExceeded { capacity: usize },
+    /// A sharded queue was asked for no shard at all, leaving no shard to hold a key.
+    NoShards,
+}
+
+/// Former name of [`IpqError`], from when it only covered refused key indexes.
+pub type KeyError = IpqError;
+
+impl Display for IpqError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpqError::KeyInUse(key_index) => {
+                write!(f, "Index already in use; received: {}", key_index)
+            }
+            IpqError::KeyTooLarge {
+                key_index,
+                max_key_bound,
+            } => write!(
+                f,
+                "Index exceeds the key space; received: {}, bound: {}",
+                key_index, max_key_bound
+            ),
+            IpqError::KeyNotFound(key_index) => {
+                write!(f, "Index does not exist; received: {}", key_index)
+            }
+            IpqError::Empty => write!(f, "Queue is empty"),
+            IpqError::InvalidRange { start, end, len } => write!(
+                f,
+                "Key index range out of bounds; received: {}..{} with key bound {}",
+                start, end, len
+            ),
+            IpqError::Incomparable(key_index) => write!(
+                f,
+                "Value is not comparable with itself, e.g. a NaN; received for index: {}",
+                key_index
+            ),
+            IpqError::CapacityExceeded { capacity } => {
+                write!(f, "Queue is full; capacity: {}", capacity)
+            }
+            IpqError::NoShards => write!(f, "Shard count must be positive; received: 0"),
+        }
+    }
+}
+
+impl Error for IpqError {}
+
+/// Per-key outcome of a bulk `try_*` operation that applies what it can and skips the rest,
+/// so a caller can retry or roll back exactly the keys concerned.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BulkOutcome {
+    /// Keys the operation was applied to, in the order they were given.
+    pub succeeded: Vec<usize>,
+    /// Why each remaining key was skipped, in the order they were given.
+    pub failed: Vec<IpqError>,
+}
+
+impl BulkOutcome {
+    /// Returns `true` if the operation was applied to every key it was given.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Caps the key indexes accepted from now on to `0..max_key_bound`, consuming and returning
+    /// the queue.
+    ///
+    /// Keys already in the queue are left alone, even past the new bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::limit::IpqError;
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![3, 1];
+    /// let mut ipq = MinIndexedPriorityQueue::from(&mut values).with_max_key_bound(100);
+    ///
+    /// assert_eq!(ipq.try_insert(99, 2), Ok(()));
+    /// assert_eq!(
+    ///     ipq.try_insert(100, 0),
+    ///     Err(IpqError::KeyTooLarge { key_index: 100, max_key_bound: 100 })
+    /// );
+    /// assert_eq!(ipq.try_push(4), Err(IpqError::KeyTooLarge { key_index: 100, max_key_bound: 100 }));
+    /// ```
+    pub fn with_max_key_bound(mut self, max_key_bound: usize) -> Self {
+        self.set_max_key_bound(max_key_bound);
+        self
+    }
+
+    pub fn set_max_key_bound(&mut self, max_key_bound: usize) {
+        self.max_key_bound = max_key_bound;
+    }
+
+    /// Exclusive upper bound of the key indexes the queue accepts, [`DEFAULT_MAX_KEY_BOUND`]
+    /// unless set otherwise.
+    pub fn max_key_bound(&self) -> usize {
+        self.max_key_bound
+    }
+
+    /// Same as [`insert`](IndexedPriorityQueue::insert), returning an error instead of
+    /// panicking when `key_index` is taken or out of the key space, or when `value` is not
+    /// comparable with itself.
+    pub fn try_insert(&mut self, key_index: impl Into<KeyIdx>, value: T) -> Result<(), IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        self.check_free_key(key_index)?;
+        check_comparable(key_index, &value)?;
+        self.insert(key_index, value);
+
+        Ok(())
+    }
+
+    /// Same as [`push`](IndexedPriorityQueue::push), returning the key index given to `value`,
+    /// or an error once the key space is exhausted.
+    ///
+    /// With [deduplication](Self::with_dedup) on, a value equal to one already queued updates
+    /// the key holding it instead, and that key is returned.
+    pub fn try_push(&mut self, value: T) -> Result<usize, IpqError> {
+        if let Some(existing) = self.dedup.then(|| self.key_of_value(&value)).flatten() {
+            self.update(existing, value);
+            return Ok(existing);
+        }
+
+        let key_index = self.next_key_index();
+        self.try_insert(key_index, value)?;
+
+        Ok(key_index)
+    }
+
+    /// Checks that `key_index` is free and within the key space.
+    pub(crate) fn check_free_key(&self, key_index: usize) -> Result<(), IpqError> {
+        if key_index >= self.max_key_bound {
+            return Err(IpqError::KeyTooLarge {
+                key_index,
+                max_key_bound: self.max_key_bound,
+            });
+        }
+        if self.contains(key_index) {
+            return Err(IpqError::KeyInUse(key_index));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod limit_tests {
+    use crate::limit::{IpqError, DEFAULT_MAX_KEY_BOUND};
+    use crate::MinIndexedPriorityQueue;
+
+    #[test]
+    fn try_insert_should_refuse_hostile_key_indexes_without_allocating() {
+        let mut values = vec![1];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(
+            ipq.try_insert(usize::MAX, 0),
+            Err(IpqError::KeyTooLarge {
+                key_index: usize::MAX,
+                max_key_bound: DEFAULT_MAX_KEY_BOUND
+            })
+        );
+        assert_eq!(ipq.try_insert(0, 0), Err(IpqError::KeyInUse(0)));
+        assert_eq!(ipq.size(), 1);
+        assert_eq!(ipq.next_key_index(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index exceeds the key space")]
+    fn insert_should_panic_past_the_max_key_bound() {
+        let mut values = vec![1];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values).with_max_key_bound(8);
+
+        ipq.insert(8, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index exceeds the key space")]
+    fn append_should_panic_before_running_out_of_key_indexes() {
+        let mut values = vec![1, 2];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values).with_max_key_bound(3);
+
+        ipq.append(&mut vec![3, 4]);
+    }
+}
@@ -0,0 +1,56 @@
+use std::time::{Duration, Instant};
+
+/// How much deferred work a `maintain` call may do before returning, so that a real-time loop
+/// spreads heavy maintenance over its frames instead of pausing on it at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Budget {
+    /// At most this many units of work, each one a sift of a single entry or a shrink of the
+    /// index maps.
+    Ops(usize),
+    /// Work until this much time has passed, checked after each unit of work.
+    Time(Duration),
+}
+
+/// Tracks the work done against a [`Budget`].
+pub(crate) struct Meter {
+    budget: Budget,
+    started: Instant,
+    spent: usize,
+}
+
+impl Meter {
+    pub(crate) fn start(budget: Budget) -> Self {
+        Self {
+            budget,
+            started: Instant::now(),
+            spent: 0,
+        }
+    }
+
+    /// Returns `true` and counts one unit of work if the budget allows another.
+    pub(crate) fn spend(&mut self) -> bool {
+        let allowed = match self.budget {
+            Budget::Ops(ops) => self.spent < ops,
+            Budget::Time(time) => self.started.elapsed() < time,
+        };
+        self.spent += usize::from(allowed);
+
+        allowed
+    }
+}
+
+#[cfg(test)]
+mod maintain_tests {
+    use crate::maintain::{Budget, Meter};
+    use std::time::Duration;
+
+    #[test]
+    fn meters_should_stop_once_the_budget_is_spent() {
+        let mut ops = Meter::start(Budget::Ops(3));
+        let spent = (0..5).filter(|_| ops.spend()).count();
+        assert_eq!(spent, 3);
+
+        let mut time = Meter::start(Budget::Time(Duration::ZERO));
+        assert!(!time.spend());
+    }
+}
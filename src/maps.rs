@@ -0,0 +1,511 @@
+use crate::index::{KeyIdx, NodeIdx};
+use crate::paged::{PagedPositionMap, PAGE_BITS, PAGE_LEN, PAGE_MASK};
+use crate::tuning::MapLayout;
+
+const VACANT: usize = usize::MAX;
+
+/// Both maps at one index: the key held by node `i`, and the node holding key `i`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Slot {
+    key_index: usize,
+    node_index: usize,
+}
+
+impl Slot {
+    const EMPTY: Slot = Slot {
+        key_index: VACANT,
+        node_index: VACANT,
+    };
+}
+
+#[derive(Clone, Debug)]
+struct SlotPage {
+    slots: Box<[Slot]>,
+    mapped: usize,
+}
+
+/// Slots split into the pages of [`PagedPositionMap`], allocated once an index falling into
+/// them gets used, so that a far key costs its own page rather than every slot before it.
+///
+/// A page past the node capacity is released as soon as its last key is unmapped, its slots
+/// then holding no node either.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PagedSlots {
+    pages: Vec<Option<SlotPage>>,
+}
+
+impl PagedSlots {
+    #[inline]
+    fn get(&self, index: usize) -> Option<&Slot> {
+        match self.pages.get(index >> PAGE_BITS) {
+            Some(Some(page)) => Some(&page.slots[index & PAGE_MASK]),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn get_mut(&mut self, index: usize) -> Option<&mut Slot> {
+        match self.pages.get_mut(index >> PAGE_BITS) {
+            Some(Some(page)) => Some(&mut page.slots[index & PAGE_MASK]),
+            _ => None,
+        }
+    }
+
+    fn get_or_insert(&mut self, index: usize) -> &mut SlotPage {
+        let page_index = index >> PAGE_BITS;
+        if page_index >= self.pages.len() {
+            self.pages.resize_with(page_index + 1, || None);
+        }
+
+        self.pages[page_index].get_or_insert_with(|| SlotPage {
+            slots: vec![Slot::EMPTY; PAGE_LEN].into_boxed_slice(),
+            mapped: 0,
+        })
+    }
+
+    /// Points the slot of `key_index` to `node_index`, returning `true` if it pointed nowhere.
+    fn set_node(&mut self, key_index: usize, node_index: usize) -> bool {
+        let page = self.get_or_insert(key_index);
+        let slot = &mut page.slots[key_index & PAGE_MASK];
+        let was_vacant = slot.node_index == VACANT;
+        slot.node_index = node_index;
+        if was_vacant {
+            page.mapped += 1;
+        }
+
+        was_vacant
+    }
+
+    /// Points the slot of `key_index` nowhere, returning `true` if it pointed to a node.
+    fn clear_node(&mut self, key_index: usize, node_capacity: usize) -> bool {
+        let page_index = key_index >> PAGE_BITS;
+        let page = match self.pages.get_mut(page_index) {
+            Some(Some(page)) => page,
+            _ => return false,
+        };
+        let slot = &mut page.slots[key_index & PAGE_MASK];
+        if slot.node_index == VACANT {
+            return false;
+        }
+
+        slot.node_index = VACANT;
+        page.mapped -= 1;
+        if page.mapped == 0 && page_index << PAGE_BITS >= node_capacity {
+            self.pages[page_index] = None;
+        }
+
+        true
+    }
+
+    /// Releases the pages past `node_capacity` holding no key, and the directory slots left
+    /// without a page at its end.
+    fn shrink_to_fit(&mut self, node_capacity: usize) {
+        self.pages.iter_mut().enumerate().for_each(|(page_index, page)| {
+            if matches!(page, Some(p) if p.mapped == 0 && page_index << PAGE_BITS >= node_capacity)
+            {
+                *page = None;
+            }
+        });
+        let len = self
+            .pages
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |page_index| page_index + 1);
+        self.pages.truncate(len);
+        self.pages.shrink_to_fit();
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (usize, &Slot)> + '_ {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter_map(|(page_index, page)| page.as_ref().map(|page| (page_index, page)))
+            .flat_map(|(page_index, page)| {
+                page.slots
+                    .iter()
+                    .enumerate()
+                    .map(move |(offset, slot)| ((page_index << PAGE_BITS) | offset, slot))
+            })
+    }
+}
+
+/// The key to node and node to key maps of a queue, laid out as its [`MapLayout`] says.
+///
+/// Nodes are mapped up to [`node_capacity`](Self::node_capacity), which grows by powers of two
+/// whatever the layout, so the shrink policy and the observers see the same capacity either
+/// way.
+#[derive(Clone, Debug)]
+pub(crate) enum IndexMaps {
+    Paged {
+        position_map: PagedPositionMap,
+        inverse_map: Vec<Option<KeyIdx>>,
+    },
+    Interleaved {
+        slots: PagedSlots,
+        node_capacity: usize,
+        key_bound: usize,
+        mapped: usize,
+    },
+}
+
+impl IndexMaps {
+    /// Builds empty maps with room for `node_capacity` nodes.
+    pub(crate) fn with_node_capacity(layout: MapLayout, node_capacity: usize) -> Self {
+        match layout {
+            MapLayout::Paged => IndexMaps::Paged {
+                position_map: PagedPositionMap::default(),
+                inverse_map: vec![None; node_capacity],
+            },
+            MapLayout::Interleaved => IndexMaps::Interleaved {
+                slots: PagedSlots::default(),
+                node_capacity,
+                key_bound: 0,
+                mapped: 0,
+            },
+        }
+    }
+
+    /// Maps the keys `0..len` to the node with the same index, room being made for the next
+    /// power of two of nodes.
+    pub(crate) fn identity(len: usize) -> Self {
+        let mut inverse_map = vec![None; len.next_power_of_two()];
+        (0..len).for_each(|i| inverse_map[i] = Some(KeyIdx(i)));
+
+        IndexMaps::Paged {
+            position_map: PagedPositionMap::identity(len),
+            inverse_map,
+        }
+    }
+
+    /// Copies the mappings of the nodes `0..size` into the given layout, keeping the node
+    /// capacity and the key bound.
+    pub(crate) fn relaid(&self, layout: MapLayout, size: usize) -> Self {
+        let mut maps = IndexMaps::with_node_capacity(layout, self.node_capacity());
+        (0..size).map(NodeIdx).for_each(|i| maps.map(self.key_at(i).unwrap(), i));
+        maps.raise_key_bound(self.key_bound());
+
+        maps
+    }
+
+    pub(crate) fn layout(&self) -> MapLayout {
+        match self {
+            IndexMaps::Paged { .. } => MapLayout::Paged,
+            IndexMaps::Interleaved { .. } => MapLayout::Interleaved,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn node_of(&self, key_index: KeyIdx) -> Option<NodeIdx> {
+        match self {
+            IndexMaps::Paged { position_map, .. } => position_map.get(key_index),
+            IndexMaps::Interleaved { slots, .. } => match slots.get(key_index.0) {
+                Some(slot) if slot.node_index != VACANT => Some(NodeIdx(slot.node_index)),
+                _ => None,
+            },
+        }
+    }
+
+    #[inline]
+    pub(crate) fn key_at(&self, node_index: NodeIdx) -> Option<KeyIdx> {
+        match self {
+            IndexMaps::Paged { inverse_map, .. } => inverse_map.get(node_index.0).copied()?,
+            IndexMaps::Interleaved {
+                slots,
+                node_capacity,
+                ..
+            } => match slots.get(node_index.0) {
+                Some(slot) if node_index.0 < *node_capacity && slot.key_index != VACANT => {
+                    Some(KeyIdx(slot.key_index))
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// Key indexes of the nodes `0..len`, which must all be mapped.
+    pub(crate) fn keys_by_node(&self, len: usize) -> impl Iterator<Item = KeyIdx> + '_ {
+        (0..len).map(move |i| self.key_at(NodeIdx(i)).unwrap())
+    }
+
+    /// Maps `key_index` to `node_index` and back, `node_index` being below the node capacity.
+    pub(crate) fn map(&mut self, key_index: KeyIdx, node_index: NodeIdx) {
+        match self {
+            IndexMaps::Paged {
+                position_map,
+                inverse_map,
+            } => {
+                position_map.set(key_index, node_index);
+                inverse_map[node_index.0] = Some(key_index);
+            }
+            IndexMaps::Interleaved {
+                slots,
+                key_bound,
+                mapped,
+                ..
+            } => {
+                if slots.set_node(key_index.0, node_index.0) {
+                    *mapped += 1;
+                }
+                slots.get_or_insert(node_index.0).slots[node_index.0 & PAGE_MASK].key_index =
+                    key_index.0;
+                *key_bound = (*key_bound).max(key_index.0 + 1);
+            }
+        }
+    }
+
+    /// Unmaps `node_index` and the key it held, returning that key.
+    pub(crate) fn unmap(&mut self, node_index: NodeIdx) -> Option<KeyIdx> {
+        let key_index = self.key_at(node_index)?;
+        self.clear_nodes(node_index.0..node_index.0 + 1);
+        self.forget_key(key_index);
+
+        Some(key_index)
+    }
+
+    /// Unmaps `key_index` from its node, leaving that node's own mapping as it is.
+    pub(crate) fn forget_key(&mut self, key_index: KeyIdx) {
+        match self {
+            IndexMaps::Paged { position_map, .. } => {
+                position_map.remove(key_index);
+            }
+            IndexMaps::Interleaved {
+                slots,
+                node_capacity,
+                mapped,
+                ..
+            } => {
+                if slots.clear_node(key_index.0, *node_capacity) {
+                    *mapped -= 1;
+                }
+            }
+        }
+    }
+
+    /// Unmaps the nodes within `nodes` from their keys, leaving the keys' own mappings as they
+    /// are.
+    pub(crate) fn clear_nodes(&mut self, nodes: std::ops::Range<usize>) {
+        match self {
+            IndexMaps::Paged { inverse_map, .. } => {
+                inverse_map[nodes].iter_mut().for_each(|k| *k = None);
+            }
+            IndexMaps::Interleaved { slots, .. } => nodes.for_each(|i| {
+                if let Some(slot) = slots.get_mut(i) {
+                    slot.key_index = VACANT;
+                }
+            }),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn swap_nodes(&mut self, i: NodeIdx, j: NodeIdx) {
+        match self {
+            IndexMaps::Paged {
+                position_map,
+                inverse_map,
+            } => {
+                position_map.set(inverse_map[j.0].unwrap(), i);
+                position_map.set(inverse_map[i.0].unwrap(), j);
+                inverse_map.swap(i.0, j.0);
+            }
+            IndexMaps::Interleaved { slots, .. } => {
+                let key_i = slots.get(i.0).unwrap().key_index;
+                let key_j = std::mem::replace(&mut slots.get_mut(j.0).unwrap().key_index, key_i);
+                slots.get_mut(i.0).unwrap().key_index = key_j;
+                slots.get_mut(key_i).unwrap().node_index = j.0;
+                slots.get_mut(key_j).unwrap().node_index = i.0;
+            }
+        }
+    }
+
+    /// Number of nodes that can be mapped before the maps have to grow.
+    pub(crate) fn node_capacity(&self) -> usize {
+        match self {
+            IndexMaps::Paged { inverse_map, .. } => inverse_map.len(),
+            IndexMaps::Interleaved { node_capacity, .. } => *node_capacity,
+        }
+    }
+
+    /// Grows the node capacity to the next power of two, returning the new capacity.
+    pub(crate) fn expand(&mut self) -> usize {
+        let node_capacity = (self.node_capacity() + 1).next_power_of_two();
+        match self {
+            IndexMaps::Paged { inverse_map, .. } => inverse_map.resize(node_capacity, None),
+            IndexMaps::Interleaved {
+                node_capacity: capacity,
+                ..
+            } => *capacity = node_capacity,
+        }
+
+        node_capacity
+    }
+
+    /// Trims the node capacity down to `node_capacity` and drops whatever the remaining
+    /// mappings do not need.
+    pub(crate) fn shrink_to_fit(&mut self, node_capacity: usize) {
+        match self {
+            IndexMaps::Paged {
+                position_map,
+                inverse_map,
+            } => {
+                inverse_map.truncate(node_capacity);
+                inverse_map.shrink_to_fit();
+                position_map.shrink_to_fit();
+            }
+            IndexMaps::Interleaved {
+                slots,
+                node_capacity: capacity,
+                ..
+            } => {
+                *capacity = node_capacity.min(*capacity);
+                slots.shrink_to_fit(*capacity);
+            }
+        }
+    }
+
+    /// Unmaps everything, keeping the node capacity, and starts the key bound over from `0`.
+    pub(crate) fn clear(&mut self) {
+        match self {
+            IndexMaps::Paged {
+                position_map,
+                inverse_map,
+            } => {
+                position_map.clear();
+                inverse_map.iter_mut().for_each(|k| *k = None);
+            }
+            IndexMaps::Interleaved {
+                slots,
+                key_bound,
+                mapped,
+                ..
+            } => {
+                *slots = PagedSlots::default();
+                *key_bound = 0;
+                *mapped = 0;
+            }
+        }
+    }
+
+    /// One past the greatest key index mapped since the maps were created or cleared.
+    pub(crate) fn key_bound(&self) -> usize {
+        match self {
+            IndexMaps::Paged { position_map, .. } => position_map.key_bound(),
+            IndexMaps::Interleaved { key_bound, .. } => *key_bound,
+        }
+    }
+
+    /// Makes [`key_bound`](Self::key_bound) at least `key_bound`.
+    pub(crate) fn raise_key_bound(&mut self, key_bound: usize) {
+        match self {
+            IndexMaps::Paged { position_map, .. } => position_map.raise_key_bound(key_bound),
+            IndexMaps::Interleaved {
+                key_bound: bound, ..
+            } => *bound = (*bound).max(key_bound),
+        }
+    }
+
+    /// Number of keys currently mapped.
+    pub(crate) fn mapped(&self) -> usize {
+        match self {
+            IndexMaps::Paged { position_map, .. } => position_map.mapped(),
+            IndexMaps::Interleaved { mapped, .. } => *mapped,
+        }
+    }
+
+    /// Mapped `(key_index, node_index)` pairs, by ascending key index.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (KeyIdx, NodeIdx)> + '_ {
+        let (paged, slots) = match self {
+            IndexMaps::Paged { position_map, .. } => (Some(position_map.iter()), None),
+            IndexMaps::Interleaved { slots, .. } => (None, Some(slots.iter())),
+        };
+        let slots = slots
+            .into_iter()
+            .flatten()
+            .filter(|(_, slot)| slot.node_index != VACANT)
+            .map(|(key_index, slot)| (KeyIdx(key_index), NodeIdx(slot.node_index)));
+
+        paged.into_iter().flatten().chain(slots)
+    }
+
+    /// Number of position map pages, or of slot pages, currently allocated.
+    pub(crate) fn allocated_pages(&self) -> usize {
+        match self {
+            IndexMaps::Paged { position_map, .. } => position_map.allocated_pages(),
+            IndexMaps::Interleaved { slots, .. } => slots.pages.iter().flatten().count(),
+        }
+    }
+
+    /// Number of position map directory slots, or of slot page directory slots.
+    pub(crate) fn directory_len(&self) -> usize {
+        match self {
+            IndexMaps::Paged { position_map, .. } => position_map.directory_len(),
+            IndexMaps::Interleaved { slots, .. } => slots.pages.len(),
+        }
+    }
+
+    /// Returns `true` when the nodes `0..size` and their keys map to each other, and neither
+    /// the nodes past them nor any other key are mapped.
+    pub(crate) fn is_consistent(&self, size: usize) -> bool {
+        if self.node_capacity() < size || self.mapped() != size {
+            return false;
+        }
+
+        let mapped_nodes_are_consistent = (0..size).map(NodeIdx).all(|i| {
+            matches!(self.key_at(i), Some(k) if self.node_of(k) == Some(i))
+        });
+        let padding_is_unmapped = (size..self.node_capacity())
+            .all(|i| self.key_at(NodeIdx(i)).is_none());
+
+        mapped_nodes_are_consistent && padding_is_unmapped
+    }
+}
+
+#[cfg(test)]
+mod index_maps_tests {
+    use crate::index::{KeyIdx, NodeIdx};
+    use crate::maps::IndexMaps;
+    use crate::paged::PAGE_LEN;
+    use crate::tuning::MapLayout;
+
+    #[test]
+    fn both_layouts_should_map_swap_and_unmap_alike() {
+        for layout in [MapLayout::Paged, MapLayout::Interleaved] {
+            let mut maps = IndexMaps::with_node_capacity(layout, 0);
+            assert_eq!(maps.expand(), 1);
+            assert_eq!(maps.expand(), 2);
+            maps.map(KeyIdx(9), NodeIdx(0));
+            maps.map(KeyIdx(2), NodeIdx(1));
+            maps.swap_nodes(NodeIdx(0), NodeIdx(1));
+
+            assert_eq!(maps.node_of(KeyIdx(9)), Some(NodeIdx(1)));
+            assert_eq!(maps.key_at(NodeIdx(0)), Some(KeyIdx(2)));
+            assert!(maps.is_consistent(2));
+            assert_eq!(maps.unmap(NodeIdx(1)), Some(KeyIdx(9)));
+            assert_eq!(maps.node_of(KeyIdx(9)), None);
+            assert_eq!(maps.key_bound(), 10);
+            assert!(maps.is_consistent(1));
+
+            let relaid = maps.relaid(MapLayout::Interleaved, 1);
+            assert_eq!(relaid.iter().collect::<Vec<_>>(), vec![(KeyIdx(2), NodeIdx(0))]);
+            assert_eq!((relaid.node_capacity(), relaid.key_bound()), (2, 10));
+        }
+    }
+
+    #[test]
+    fn a_far_interleaved_key_should_only_allocate_its_own_page() {
+        let mut maps = IndexMaps::with_node_capacity(MapLayout::Interleaved, 2);
+        let far = KeyIdx((1 << 30) - 1);
+        maps.map(far, NodeIdx(0));
+        maps.map(KeyIdx(PAGE_LEN), NodeIdx(1));
+
+        assert_eq!(maps.node_of(far), Some(NodeIdx(0)));
+        assert_eq!(maps.node_of(KeyIdx(far.0 - 1)), None);
+        assert_eq!(maps.allocated_pages(), 3);
+        assert!(maps.is_consistent(2));
+
+        maps.unmap(NodeIdx(1));
+        maps.unmap(NodeIdx(0));
+        assert_eq!(maps.allocated_pages(), 1);
+        maps.shrink_to_fit(0);
+        assert_eq!((maps.allocated_pages(), maps.directory_len()), (0, 0));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::index::{KeyIdx, NodeIdx};
+use crate::limit::IpqError;
+use crate::order::Order;
+use crate::{check_comparable, OrderedIndexedPriorityQueue};
+
+/// What [`try_merge`](OrderedIndexedPriorityQueue::try_merge) does with a key index that both
+/// queues use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyCollision {
+    /// Fail with [`IpqError::KeyInUse`], leaving both queues untouched.
+    Reject,
+    /// Move the other queue's entry under a fresh key index, past those of both queues.
+    Remap,
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Moves every entry of `other` into this queue, emptying it, and returns the
+    /// `(old, new)` key indexes of the entries remapped by [`KeyCollision::Remap`], by
+    /// ascending old key index.
+    ///
+    /// The entries are appended as they are and the heap is rebuilt once, in `O(n + m)`,
+    /// rather than sifting each of the `m` entries in. Fails, leaving both queues untouched,
+    /// on a collision under [`KeyCollision::Reject`], when a key index would pass this
+    /// queue's key space or when a value of `other` is not comparable with itself. The
+    /// metadata and holds of `other`'s entries are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::limit::IpqError;
+    /// use indexed_priority_queue::merge::KeyCollision;
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let mut east = OwnedMinIpq::from(vec![40, 10]);
+    /// let mut west = OwnedMinIpq::new();
+    /// west.try_insert_all([(1, 5), (7, 20)]);
+    ///
+    /// assert_eq!(east.try_merge(&mut west, KeyCollision::Reject), Err(IpqError::KeyInUse(1)));
+    /// assert_eq!(east.try_merge(&mut west, KeyCollision::Remap), Ok(vec![(1, 8)]));
+    /// assert!(west.is_empty());
+    /// assert_eq!(east.poll_min_key_index(), Some(8));
+    /// assert_eq!(east.poll_min_key_index(), Some(1));
+    /// ```
+    pub fn try_merge(
+        &mut self,
+        other: &mut OrderedIndexedPriorityQueue<'_, T, O>,
+        on_collision: KeyCollision,
+    ) -> Result<Vec<(usize, usize)>, IpqError> {
+        let mut colliding = other
+            .iter()
+            .map(|(key_index, _)| key_index)
+            .filter(|&key_index| self.contains(key_index))
+            .collect::<Vec<usize>>();
+        colliding.sort_unstable();
+        if let (KeyCollision::Reject, Some(&key_index)) = (on_collision, colliding.first()) {
+            return Err(IpqError::KeyInUse(key_index));
+        }
+
+        let first_fresh = self.next_key_index().max(other.next_key_index());
+        let remapped = colliding
+            .into_iter()
+            .enumerate()
+            .map(|(nth, key_index)| (key_index, first_fresh.saturating_add(nth)))
+            .collect::<Vec<(usize, usize)>>();
+        let key_bound = other
+            .iter()
+            .map(|(key_index, _)| key_index)
+            .chain(remapped.iter().map(|&(_, fresh)| fresh))
+            .max();
+        if let Some(key_index) = key_bound.filter(|&key_index| key_index >= self.max_key_bound) {
+            return Err(IpqError::KeyTooLarge {
+                key_index,
+                max_key_bound: self.max_key_bound,
+            });
+        }
+        if let Some(e) = other
+            .iter()
+            .find_map(|(key_index, value)| check_comparable(key_index, value).err())
+        {
+            return Err(e);
+        }
+
+        let size = self.size();
+        let entries = other.remove_and_rebuild(|_, _| true);
+        let next_size = size + entries.len();
+        while next_size > self.maps.node_capacity() {
+            self.expand_mapping();
+        }
+        for (i, (key_index, value)) in (size..).zip(entries) {
+            let key_index = match remapped.binary_search_by_key(&key_index, |&(old, _)| old) {
+                Ok(nth) => remapped[nth].1,
+                Err(_) => key_index,
+            };
+            self.values.push(value);
+            self.maps.map(KeyIdx(key_index), NodeIdx(i));
+            if let Some(observer) = self.observer.as_deref_mut() {
+                observer.on_insert(key_index, NodeIdx(i), &self.values[i]);
+            }
+        }
+
+        self.fix_heap_invariant();
+        self.publish_min();
+
+        Ok(remapped)
+    }
+
+    /// Same as [`try_merge`](Self::try_merge) with [`KeyCollision::Remap`], taking `other` by
+    /// value.
+    ///
+    /// # Panics
+    ///
+    /// If a key index would pass this queue's key space.
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn merge(&mut self, mut other: Self) -> Vec<(usize, usize)> {
+        match self.try_merge(&mut other, KeyCollision::Remap) {
+            Ok(remapped) => remapped,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Combines two queues into one, `b`'s colliding keys being remapped as
+    /// [`merge`](Self::merge) does; the result keeps `a`'s settings.
+    ///
+    /// # Panics
+    ///
+    /// If a key index would pass `a`'s key space.
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn meld(mut a: Self, b: Self) -> Self {
+        a.merge(b);
+
+        a
+    }
+}
+
+#[cfg(test)]
+mod merge_tests {
+    use crate::limit::IpqError;
+    use crate::merge::KeyCollision;
+    use crate::OwnedMinIpq;
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    use crate::OwnedMaxIpq;
+
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    #[test]
+    fn merged_queues_should_poll_every_entry_in_order() {
+        let a = (0..50).map(|i| i * 7 % 23).collect::<OwnedMaxIpq<i32>>();
+        let mut b = OwnedMaxIpq::new();
+        (30..90).for_each(|key_index| b.insert(key_index, key_index as i32 % 31));
+
+        let mut expected = a.iter().chain(b.iter()).map(|(_, &v)| v).collect::<Vec<i32>>();
+        expected.sort_unstable_by(|x, y| y.cmp(x));
+        let mut melded = OwnedMaxIpq::meld(a, b);
+        assert_eq!(melded.size(), 110);
+        assert!(melded.has_consistent_mappings());
+        assert_eq!(melded.keys().filter(|&key_index| key_index >= 90).count(), 20);
+        assert!(std::iter::from_fn(|| melded.poll_min_value()).eq(expected));
+    }
+
+    #[test]
+    fn failed_merges_should_leave_both_queues_untouched() {
+        let mut a = OwnedMinIpq::<u8>::new().with_max_key_bound(10);
+        a.insert(9, 1);
+        let mut b = OwnedMinIpq::new();
+        b.insert(9, 0);
+
+        assert_eq!(
+            a.try_merge(&mut b, KeyCollision::Remap),
+            Err(IpqError::KeyTooLarge { key_index: 10, max_key_bound: 10 })
+        );
+        assert_eq!(a.try_merge(&mut b, KeyCollision::Reject), Err(IpqError::KeyInUse(9)));
+        assert_eq!((a.size(), b.size()), (1, 1));
+
+        b.update(9, 4);
+        b.insert(2, 3);
+        b.delete(9);
+        assert_eq!(a.try_merge(&mut b, KeyCollision::Reject), Ok(Vec::new()));
+        assert_eq!(a.poll_min_key_index(), Some(9));
+        assert_eq!(a.poll_min_key_index(), Some(2));
+    }
+}
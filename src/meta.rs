@@ -0,0 +1,181 @@
+use crate::limit::IpqError;
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+use std::any::Any;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Metadata slots by key index, each holding a value of the type it was set with.
+pub(crate) type MetaMap = HashMap<usize, Box<dyn Any + Send + Sync>>;
+
+/// Reason a typed metadata read of a key index failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MetaError {
+    /// The key index is not in the queue or has no metadata.
+    Missing(usize),
+    /// The key index has metadata, of another type than the one asked for.
+    WrongType(usize),
+}
+
+impl Display for MetaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetaError::Missing(key_index) => {
+                write!(f, "Index has no metadata; received: {}", key_index)
+            }
+            MetaError::WrongType(key_index) => {
+                write!(f, "Index has metadata of another type; received: {}", key_index)
+            }
+        }
+    }
+}
+
+impl Error for MetaError {}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Stashes `meta` alongside `key_index`, returning the metadata it replaces when it had
+    /// the same type `M`; metadata of another type is dropped.
+    ///
+    /// Metadata is never compared nor moved around by the heap: it sticks to its key through
+    /// updates and is dropped when the key leaves the queue. Queues that never set any pay a
+    /// single empty-map check per removal.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue, see [`try_set_meta`](Self::try_set_meta).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::meta::MetaError;
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut deadlines = vec![30, 10];
+    /// let mut retries = MinIndexedPriorityQueue::from(&mut deadlines);
+    /// retries.set_meta(1, 0u32);
+    ///
+    /// *retries.meta_mut::<u32>(1).unwrap() += 1;
+    /// retries.update(1, 40);
+    /// assert_eq!(retries.meta::<u32>(1), Ok(&1));
+    /// assert_eq!(retries.meta::<u64>(1), Err(MetaError::WrongType(1)));
+    /// assert_eq!(retries.meta::<u32>(0), Err(MetaError::Missing(0)));
+    ///
+    /// retries.delete(1);
+    /// assert_eq!(retries.meta::<u32>(1), Err(MetaError::Missing(1)));
+    /// ```
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn set_meta<M>(&mut self, key_index: usize, meta: M) -> Option<M>
+    where
+        M: Any + Send + Sync,
+    {
+        self.try_set_meta(key_index, meta).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`set_meta`](Self::set_meta), returning an error instead of panicking when
+    /// `key_index` is not in the queue.
+    pub fn try_set_meta<M>(&mut self, key_index: usize, meta: M) -> Result<Option<M>, IpqError>
+    where
+        M: Any + Send + Sync,
+    {
+        if !self.contains(key_index) {
+            return Err(IpqError::KeyNotFound(key_index));
+        }
+
+        Ok(self
+            .metadata
+            .insert(key_index, Box::new(meta))
+            .and_then(|previous| previous.downcast().ok())
+            .map(|previous| *previous))
+    }
+
+    /// Returns the metadata of `key_index`, or why it has none of type `M`.
+    pub fn meta<M: Any>(&self, key_index: usize) -> Result<&M, MetaError> {
+        self.metadata
+            .get(&key_index)
+            .ok_or(MetaError::Missing(key_index))?
+            .downcast_ref()
+            .ok_or(MetaError::WrongType(key_index))
+    }
+
+    pub fn meta_mut<M: Any>(&mut self, key_index: usize) -> Result<&mut M, MetaError> {
+        self.metadata
+            .get_mut(&key_index)
+            .ok_or(MetaError::Missing(key_index))?
+            .downcast_mut()
+            .ok_or(MetaError::WrongType(key_index))
+    }
+
+    /// Removes and returns the metadata of `key_index`, or why it has none of type `M`;
+    /// metadata of another type is left in place.
+    pub fn take_meta<M: Any>(&mut self, key_index: usize) -> Result<M, MetaError> {
+        let meta = self.metadata.remove(&key_index).ok_or(MetaError::Missing(key_index))?;
+
+        match meta.downcast() {
+            Ok(meta) => Ok(*meta),
+            Err(meta) => {
+                self.metadata.insert(key_index, meta);
+                Err(MetaError::WrongType(key_index))
+            }
+        }
+    }
+
+    /// Drops the metadata of a key leaving the queue.
+    pub(crate) fn forget_meta(&mut self, key_index: usize) {
+        if !self.metadata.is_empty() {
+            self.metadata.remove(&key_index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod meta_tests {
+    use crate::limit::IpqError;
+    use crate::meta::MetaError;
+    use crate::MinIndexedPriorityQueue;
+
+    #[test]
+    fn meta_should_follow_its_key_through_heap_moves() {
+        let mut values = (0..16).rev().collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        (0..16).for_each(|k| {
+            ipq.try_set_meta(k, format!("job-{}", k)).unwrap();
+        });
+
+        ipq.decrease(0, -1);
+        ipq.poll_min_value();
+        assert_eq!(ipq.meta::<String>(0), Err(MetaError::Missing(0)));
+        assert_eq!(ipq.meta::<String>(15).map(String::as_str), Ok("job-15"));
+
+        ipq.drain(3..=5);
+        ipq.clear();
+        assert!(ipq.metadata.is_empty());
+    }
+
+    #[test]
+    fn meta_of_another_type_should_neither_be_read_nor_taken() {
+        let mut values = vec![1, 2];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.try_set_meta(1, 7u8), Ok(None));
+        assert_eq!(ipq.meta::<u16>(1), Err(MetaError::WrongType(1)));
+        assert_eq!(ipq.meta_mut::<u16>(1), Err(MetaError::WrongType(1)));
+        assert_eq!(ipq.take_meta::<u16>(1), Err(MetaError::WrongType(1)));
+        assert_eq!(ipq.try_set_meta(1, 8u8), Ok(Some(7)));
+        assert_eq!(ipq.take_meta::<u8>(1), Ok(8));
+        assert_eq!(ipq.meta::<u8>(1), Err(MetaError::Missing(1)));
+        assert_eq!(ipq.take_meta::<u8>(0), Err(MetaError::Missing(0)));
+    }
+
+    #[test]
+    fn set_meta_should_fail_for_keys_not_in_the_queue() {
+        let mut values = vec![1];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.try_set_meta(3, ()), Err(IpqError::KeyNotFound(3)));
+    }
+}
@@ -0,0 +1,386 @@
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+
+/// Decides which queue of a [`MultiQueue`] is polled next, given the value ranked first in
+/// each of them, `None` standing for an empty queue.
+pub trait Arbiter<T> {
+    /// Returns the index of the queue to poll next, which must have a head.
+    fn pick<O: Order>(&self, heads: &[Option<&T>]) -> Option<usize>;
+
+    /// Called once `queue`, picked among `heads`, is about to be polled.
+    fn served(&mut self, _queue: usize, _heads: &[Option<&T>]) {}
+}
+
+/// Polls the head ranked first across all queues, as if they were one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GlobalMin;
+
+impl<T: PartialOrd> Arbiter<T> for GlobalMin {
+    fn pick<O: Order>(&self, heads: &[Option<&T>]) -> Option<usize> {
+        heads
+            .iter()
+            .enumerate()
+            .filter_map(|(queue, head)| head.map(|head| (queue, head)))
+            .reduce(|a, b| if O::precedes(b.1, a.1) { b } else { a })
+            .map(|(queue, _)| queue)
+    }
+}
+
+/// Polls the non-empty queues in turn, whatever their heads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoundRobin {
+    next: usize,
+}
+
+impl<T> Arbiter<T> for RoundRobin {
+    fn pick<O: Order>(&self, heads: &[Option<&T>]) -> Option<usize> {
+        let count = heads.len();
+        (0..count)
+            .map(|offset| (self.next + offset) % count)
+            .find(|&queue| heads[queue].is_some())
+    }
+
+    fn served(&mut self, queue: usize, _heads: &[Option<&T>]) {
+        self.next = queue + 1;
+    }
+}
+
+/// Shares polls out by smooth weighted round robin: a queue of weight 3 is polled three times
+/// as often as one of weight 1, interleaved, as long as both have entries.
+///
+/// Queues without a weight have weight 1.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Weighted {
+    weights: Vec<u32>,
+    credits: Vec<i64>,
+}
+
+impl Weighted {
+    /// # Panics
+    ///
+    /// If any weight is `0`.
+    pub fn new(weights: Vec<u32>) -> Self {
+        if let Some(queue) = weights.iter().position(|&weight| weight == 0) {
+            panic!("Queue weight must be positive; received: 0 for queue {}", queue);
+        }
+
+        Self {
+            credits: vec![0; weights.len()],
+            weights,
+        }
+    }
+
+    fn weight(&self, queue: usize) -> i64 {
+        self.weights.get(queue).map_or(1, |&weight| weight as i64)
+    }
+
+    fn credit(&self, queue: usize) -> i64 {
+        self.credits.get(queue).copied().unwrap_or(0)
+    }
+}
+
+impl<T> Arbiter<T> for Weighted {
+    /// Returns the non-empty queue with the most credit once this round's weights are added,
+    /// the lowest index winning ties.
+    fn pick<O: Order>(&self, heads: &[Option<&T>]) -> Option<usize> {
+        (0..heads.len())
+            .filter(|&queue| heads[queue].is_some())
+            .map(|queue| (queue, self.credit(queue) + self.weight(queue)))
+            .reduce(|a, b| if b.1 > a.1 { b } else { a })
+            .map(|(queue, _)| queue)
+    }
+
+    fn served(&mut self, queue: usize, heads: &[Option<&T>]) {
+        if self.credits.len() < heads.len() {
+            self.credits.resize(heads.len(), 0);
+        }
+
+        let mut total_weight = 0;
+        for (i, head) in heads.iter().enumerate() {
+            if head.is_some() {
+                self.credits[i] += self.weight(i);
+                total_weight += self.weight(i);
+            } else {
+                self.credits[i] = 0;
+            }
+        }
+        self.credits[queue] -= total_weight;
+    }
+}
+
+/// Several independent indexed priority queues, e.g. one per shard or priority class, polled
+/// as one through a pluggable [`Arbiter`].
+///
+/// Each queue keeps its own key space, so entries are addressed by `(queue, key_index)` and
+/// edited through [`queue_mut`](Self::queue_mut).
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::multi::{MinMultiQueue, RoundRobin};
+///
+/// let mut shards = MinMultiQueue::new(RoundRobin::default());
+/// let (a, b) = (shards.add_queue(), shards.add_queue());
+/// shards.queue_mut(a).unwrap().try_insert_all([(0, 1), (1, 2)]);
+/// shards.queue_mut(b).unwrap().try_insert(0, 9).unwrap();
+///
+/// assert_eq!(shards.poll_min(), Some((a, 0, 1)));
+/// assert_eq!(shards.poll_min(), Some((b, 0, 9)));
+/// assert_eq!(shards.poll_min(), Some((a, 1, 2)));
+/// ```
+pub struct MultiQueue<T, O, A>
+where
+    T: Clone + 'static,
+{
+    queues: Vec<OrderedIndexedPriorityQueue<'static, T, O>>,
+    arbiter: A,
+}
+
+pub type MinMultiQueue<T, A> = MultiQueue<T, Min, A>;
+
+pub type MaxMultiQueue<T, A> = MultiQueue<T, Max, A>;
+
+impl<T, O, A> MultiQueue<T, O, A>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+    A: Arbiter<T>,
+{
+    pub fn new(arbiter: A) -> Self {
+        Self {
+            queues: Vec::new(),
+            arbiter,
+        }
+    }
+
+    /// Adds an empty queue, returning its index.
+    pub fn add_queue(&mut self) -> usize {
+        self.push_queue(OrderedIndexedPriorityQueue::from_entries(Vec::new()))
+    }
+
+    /// Takes over an existing queue, returning its index.
+    pub fn push_queue(&mut self, queue: OrderedIndexedPriorityQueue<'static, T, O>) -> usize {
+        self.queues.push(queue);
+
+        self.queues.len() - 1
+    }
+
+    pub fn queue(&self, queue: usize) -> Option<&OrderedIndexedPriorityQueue<'static, T, O>> {
+        self.queues.get(queue)
+    }
+
+    pub fn queue_mut(
+        &mut self,
+        queue: usize,
+    ) -> Option<&mut OrderedIndexedPriorityQueue<'static, T, O>> {
+        self.queues.get_mut(queue)
+    }
+
+    pub fn queue_count(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Number of entries across all queues.
+    pub fn size(&self) -> usize {
+        self.queues.iter().map(|queue| queue.size()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.iter().all(|queue| queue.is_empty())
+    }
+
+    pub fn arbiter(&self) -> &A {
+        &self.arbiter
+    }
+
+    /// Returns the queue and key index the next [`poll_min`](Self::poll_min) removes.
+    pub fn peek_min(&self) -> Option<(usize, usize)> {
+        let queue = self.arbiter.pick::<O>(&Self::heads(&self.queues))?;
+
+        Some((queue, self.queues[queue].peek_min_key_index()?))
+    }
+
+    /// Removes the head of the queue picked by the arbiter, returning that queue's index and
+    /// the entry's key index and value.
+    pub fn poll_min(&mut self) -> Option<(usize, usize, T)> {
+        let heads = Self::heads(&self.queues);
+        let queue = self.arbiter.pick::<O>(&heads)?;
+        self.arbiter.served(queue, &heads);
+
+        let ipq = &mut self.queues[queue];
+        let key_index = ipq.peek_min_key_index()?;
+        Some((queue, key_index, ipq.delete(key_index)?))
+    }
+
+    fn heads<'q>(queues: &'q [OrderedIndexedPriorityQueue<'static, T, O>]) -> Vec<Option<&'q T>> {
+        queues
+            .iter()
+            .map(|queue| queue.value_ref(queue.peek_min_key_index()?))
+            .collect()
+    }
+}
+
+/// Index of a queue among those handed to [`merge_sorted`], as in a [`MultiQueue`].
+pub type QueueId = usize;
+
+/// Iterator returned by [`merge_sorted`].
+pub struct MergeSorted<T, O>
+where
+    T: Clone + 'static,
+{
+    queues: Vec<OrderedIndexedPriorityQueue<'static, T, O>>,
+    heads: OrderedIndexedPriorityQueue<'static, T, O>,
+}
+
+/// Lazily polls `queues` as one, yielding each entry's queue, key index and value, the value
+/// ranked first across all queues coming first.
+///
+/// The value at the front of each queue is kept in a queue of its own, keyed by queue id, so
+/// every step costs `O(log k + log n)` for `k` queues, where [`MultiQueue`] with [`GlobalMin`]
+/// scans all `k` heads. Entries ranked equal in different queues come in no particular order.
+/// The queues not drained yet can be taken back with [`into_queues`](MergeSorted::into_queues).
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::multi::merge_sorted;
+/// use indexed_priority_queue::MinIndexedPriorityQueue;
+///
+/// let shards = vec![
+///     MinIndexedPriorityQueue::from_sorted_iter(vec![1, 4, 7]),
+///     MinIndexedPriorityQueue::from_sorted_iter(vec![2, 3]),
+/// ];
+/// let merged = merge_sorted(shards).collect::<Vec<(usize, usize, i32)>>();
+///
+/// assert_eq!(merged, vec![(0, 0, 1), (1, 0, 2), (1, 1, 3), (0, 1, 4), (0, 2, 7)]);
+/// ```
+pub fn merge_sorted<T, O>(
+    queues: Vec<OrderedIndexedPriorityQueue<'static, T, O>>,
+) -> MergeSorted<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    let mut heads = OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new()));
+    queues
+        .iter()
+        .enumerate()
+        .filter_map(|(queue, ipq)| Some((queue, ipq.peek_min_value()?)))
+        .for_each(|(queue, head)| heads.insert(queue, head));
+
+    MergeSorted { queues, heads }
+}
+
+impl<T, O> MergeSorted<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    /// Returns the queues with whatever entries were not yielded yet.
+    pub fn into_queues(self) -> Vec<OrderedIndexedPriorityQueue<'static, T, O>> {
+        self.queues
+    }
+}
+
+impl<T, O> Iterator for MergeSorted<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    type Item = (QueueId, usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let queue = self.heads.peek_min_key_index()?;
+        let ipq = &mut self.queues[queue];
+        let key_index = ipq.peek_min_key_index()?;
+        let value = ipq.delete(key_index)?;
+        match ipq.peek_min_value() {
+            Some(head) => {
+                self.heads.update(queue, head);
+            }
+            None => {
+                self.heads.delete(queue);
+            }
+        }
+
+        Some((queue, key_index, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self
+            .queues
+            .iter()
+            .map(|queue| queue.size() - queue.held_count())
+            .sum();
+
+        (remaining, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod multi_queue_tests {
+    use crate::multi::{merge_sorted, GlobalMin, MaxMultiQueue, MinMultiQueue, Weighted};
+    use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue};
+
+    #[test]
+    fn global_min_should_poll_as_one_merged_queue() {
+        let mut ipq = MaxMultiQueue::new(GlobalMin);
+        for shard in 0..3 {
+            let queue = ipq.add_queue();
+            (0..4).for_each(|key| ipq.queue_mut(queue).unwrap().insert(key, key * 3 + shard));
+        }
+
+        let polled = (0..12).map(|_| ipq.poll_min().unwrap().2).collect::<Vec<usize>>();
+        assert_eq!(polled, (0..12).rev().collect::<Vec<usize>>());
+        assert!(ipq.is_empty());
+        assert_eq!(ipq.poll_min(), None);
+    }
+
+    #[test]
+    fn merge_sorted_should_yield_every_entry_in_global_order() {
+        let shards = (0..4)
+            .map(|shard| {
+                let mut queue = MaxIndexedPriorityQueue::from_entries(Vec::new());
+                (0..5).for_each(|key| queue.insert(key, (key * 7 + shard * 3) % 11));
+                queue
+            })
+            .collect::<Vec<MaxIndexedPriorityQueue<'static, usize>>>();
+
+        let mut merged = merge_sorted(shards);
+        assert_eq!(merged.size_hint(), (20, Some(20)));
+        let polled = merged.by_ref().take(15).collect::<Vec<(usize, usize, usize)>>();
+        assert!(polled.windows(2).all(|pair| pair[0].2 >= pair[1].2));
+        assert!(polled.iter().all(|&(shard, key, value)| value == (key * 7 + shard * 3) % 11));
+
+        let rest = merged.into_queues();
+        assert_eq!(rest.iter().map(|queue| queue.size()).sum::<usize>(), 5);
+        assert!(rest.iter().all(|queue| queue
+            .peek_min_value()
+            .map_or(true, |head| head <= polled[14].2)));
+
+        let mut held = MaxIndexedPriorityQueue::from_entries(vec![(0, 4), (1, 9)]);
+        held.hold(1);
+        let merged = merge_sorted(vec![held]);
+        assert_eq!(merged.size_hint(), (1, Some(1)));
+        assert_eq!(merged.count(), 1);
+    }
+
+    #[test]
+    fn weighted_should_share_polls_by_weight() {
+        let mut ipq = MinMultiQueue::new(Weighted::new(vec![1, 3]));
+        ipq.push_queue(MinIndexedPriorityQueue::from_sorted_iter(0..8));
+        let light = ipq.add_queue();
+        (0..8).for_each(|key| ipq.queue_mut(light).unwrap().push(key));
+
+        let queues = (0..8).map(|_| ipq.poll_min().unwrap().0).collect::<Vec<usize>>();
+        assert_eq!(queues, vec![1, 0, 1, 1, 1, 0, 1, 1]);
+        assert_eq!(ipq.queue(0).unwrap().size(), 6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn weighted_should_reject_zero_weights() {
+        Weighted::new(vec![2, 0]);
+    }
+}
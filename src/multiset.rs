@@ -0,0 +1,214 @@
+use crate::order::Order;
+use crate::index::NodeIdx;
+use crate::maps::IndexMaps;
+use crate::OrderedIndexedPriorityQueue;
+use std::marker::PhantomData;
+
+/// Iterator over the `(key_index, &value)` pairs whose value equals a given priority, created
+/// by [`OrderedIndexedPriorityQueue::iter_eq`].
+///
+/// The heap is walked depth-first and every subtree whose root already ranks after the
+/// priority is skipped, so only the nodes ranked no later than the priority are ever compared.
+pub struct IterEq<'q, T, O> {
+    values: &'q [T],
+    maps: &'q IndexMaps,
+    priority: &'q T,
+    pending: Vec<NodeIdx>,
+    order: PhantomData<O>,
+}
+
+impl<'q, T, O> Iterator for IterEq<'q, T, O>
+where
+    T: PartialOrd,
+    O: Order,
+{
+    type Item = (usize, &'q T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(i) = self.pending.pop() {
+            if O::precedes(self.priority, &self.values[i.0]) {
+                continue;
+            }
+
+            [1, 0]
+                .into_iter()
+                .filter_map(|nth| i.child(nth))
+                .filter(|&child| child.0 < self.values.len())
+                .for_each(|child| self.pending.push(child));
+
+            if self.values[i.0] == *self.priority {
+                return Some((self.maps.key_at(i).unwrap().0, &self.values[i.0]));
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Iterates over every entry whose value equals `priority`, in heap pre-order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![9, 2, 7, 2, 5, 2];
+    /// let ipq = MinIndexedPriorityQueue::from(&mut values);
+    ///
+    /// let mut keys = ipq.iter_eq(&2).map(|(k, _)| k).collect::<Vec<usize>>();
+    /// keys.sort();
+    /// assert_eq!(keys, vec![1, 3, 5]);
+    /// ```
+    pub fn iter_eq<'q>(&'q self, priority: &'q T) -> IterEq<'q, T, O> {
+        let mut pending = Vec::new();
+        if !self.is_empty() {
+            pending.push(NodeIdx::ROOT);
+        }
+
+        IterEq {
+            values: &self.values,
+            maps: &self.maps,
+            priority,
+            pending,
+            order: PhantomData,
+        }
+    }
+
+    /// Counts the entries whose value equals `priority`, see [`iter_eq`](Self::iter_eq).
+    pub fn count_eq(&self, priority: &T) -> usize {
+        self.iter_eq(priority).count()
+    }
+
+    /// Returns `true` when some entry's value equals `value`, pruning as
+    /// [`iter_eq`](Self::iter_eq) does.
+    pub fn contains_value(&self, value: &T) -> bool {
+        self.key_of_value(value).is_some()
+    }
+
+    /// Returns the key index of an entry whose value equals `value`, if any.
+    pub fn key_of_value(&self, value: &T) -> Option<usize> {
+        self.iter_eq(value).next().map(|(key_index, _)| key_index)
+    }
+
+    /// Turns deduplication on or off, consuming and returning the queue.
+    ///
+    /// While on, `push`, [`try_push`](Self::try_push) and `append` update the key holding a
+    /// value equal to the one given instead of adding a duplicate, consuming no key index for
+    /// it. `insert` names its key index, so it always adds the entry under that key. The lookup
+    /// costs a pruned [`iter_eq`](Self::iter_eq) search per push. Duplicates already queued
+    /// when deduplication is turned on are left as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut work = vec!["build", "test"];
+    /// let mut ipq = MinIndexedPriorityQueue::from(&mut work).with_dedup(true);
+    ///
+    /// for job in ["test", "deploy", "build"] {
+    ///     ipq.try_push(job).unwrap();
+    /// }
+    /// assert_eq!(ipq.size(), 3);
+    /// assert_eq!(ipq.key_of_value(&"deploy"), Some(2));
+    /// ```
+    pub fn with_dedup(mut self, dedup: bool) -> Self {
+        self.set_dedup(dedup);
+        self
+    }
+
+    pub fn set_dedup(&mut self, dedup: bool) {
+        self.dedup = dedup;
+    }
+
+    pub fn dedups(&self) -> bool {
+        self.dedup
+    }
+}
+
+#[cfg(test)]
+mod multiset_tests {
+    use crate::order::Total;
+    use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue, OwnedMinIpq};
+
+    #[test]
+    fn count_eq_should_find_every_tie() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.count_eq(&2), 3);
+        assert_eq!(ipq.count_eq(&0), 1);
+        assert_eq!(ipq.count_eq(&9), 1);
+        assert_eq!(ipq.count_eq(&-1), 0);
+        assert_eq!(ipq.count_eq(&10), 0);
+    }
+
+    #[test]
+    fn iter_eq_should_follow_the_queue_order_when_pruning() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        let mut keys = ipq.iter_eq(&2).map(|(k, v)| (k, *v)).collect::<Vec<(usize, i32)>>();
+        keys.sort();
+        assert_eq!(keys, vec![(6, 2), (7, 2), (8, 2)]);
+        assert_eq!(ipq.count_eq(&0), 1);
+    }
+
+    #[test]
+    fn contains_value_should_prune_subtrees_ranked_after_the_value() {
+        let mut values: Vec<Total<f64>> = vec![0.5, 0.25, f64::NAN, 1.0]
+            .into_iter()
+            .map(Total::new)
+            .collect();
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert!(ipq.contains_value(&Total::new(1.0)));
+        assert!(!ipq.contains_value(&Total::new(0.75)));
+        assert!(ipq.contains_value(&Total::new(f64::NAN)));
+    }
+
+    #[test]
+    fn dedup_should_update_the_existing_key_instead_of_inserting() {
+        let mut values = vec![3, 1];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values).with_dedup(true);
+
+        ipq.push(2);
+        assert_eq!(ipq.try_push(3), Ok(0));
+        assert_eq!(ipq.size(), 3);
+        assert_eq!(ipq.next_key_index(), 3);
+
+        ipq.insert(7, 3);
+        assert_eq!(ipq.value_of(7), Some(3));
+        assert_eq!(ipq.count_eq(&3), 2);
+
+        ipq.set_dedup(false);
+        ipq.push(2);
+        assert_eq!(ipq.count_eq(&2), 2);
+
+        let mut upserted = OwnedMinIpq::new().with_dedup(true);
+        assert_eq!(upserted.try_insert(0, 5), Ok(()));
+        assert_eq!(upserted.try_decrease_or_insert(2, 5), Ok(true));
+        assert!(upserted.has_consistent_mappings());
+    }
+
+    #[test]
+    fn iter_eq_should_allow_deleting_a_whole_priority_level() {
+        let mut values = vec![3, 1, 3, 2, 3];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let keys = ipq.iter_eq(&3).map(|(k, _)| k).collect::<Vec<usize>>();
+        keys.into_iter().for_each(|k| {
+            ipq.delete(k);
+        });
+
+        assert_eq!(ipq.count_eq(&3), 0);
+        assert_eq!(ipq.poll_min_value(), Some(1));
+        assert_eq!(ipq.poll_min_value(), Some(2));
+        assert_eq!(ipq.poll_min_value(), None);
+    }
+}
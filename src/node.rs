@@ -0,0 +1,461 @@
+use crate::index::{KeyIdx, NodeIdx};
+use crate::maps::IndexMaps;
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// A read-only handle to one node of the heap, borrowed from its queue.
+///
+/// Navigation never exposes the underlying node indexes: a `NodeRef` is reached through
+/// [`OrderedIndexedPriorityQueue::root`] or [`OrderedIndexedPriorityQueue::node`] and moves around
+/// the tree through [`parent`](NodeRef::parent), [`left`](NodeRef::left),
+/// [`right`](NodeRef::right) and [`descendants`](NodeRef::descendants).
+pub struct NodeRef<'q, 'a, T, O>
+where
+    T: Clone,
+{
+    queue: &'q OrderedIndexedPriorityQueue<'a, T, O>,
+    node_index: NodeIdx,
+}
+
+impl<'q, 'a, T, O> Clone for NodeRef<'q, 'a, T, O>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'q, 'a, T, O> Copy for NodeRef<'q, 'a, T, O> where T: Clone {}
+
+impl<'q, 'a, T, O> NodeRef<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn at(&self, node_index: NodeIdx) -> Option<Self> {
+        if node_index.0 < self.queue.size() {
+            Some(NodeRef {
+                queue: self.queue,
+                node_index,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the parent node, or `None` when called on the root.
+    pub fn parent(&self) -> Option<Self> {
+        if self.node_index == NodeIdx::ROOT {
+            return None;
+        }
+        self.at(self.node_index.parent())
+    }
+
+    pub fn left(&self) -> Option<Self> {
+        self.at(self.node_index.child(0)?)
+    }
+
+    pub fn right(&self) -> Option<Self> {
+        self.at(self.node_index.child(1)?)
+    }
+
+    /// Returns the key index whose value is stored at this node.
+    pub fn key(&self) -> usize {
+        self.queue.maps.key_at(self.node_index).unwrap().0
+    }
+
+    pub fn value(&self) -> &'q T {
+        &self.queue.values[self.node_index.0]
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.left().is_none()
+    }
+
+    /// Iterates over every node below this one, level by level, excluding the node itself.
+    pub fn descendants(&self) -> Descendants<'q, 'a, T, O> {
+        let mut pending = VecDeque::new();
+        pending.extend(self.left());
+        pending.extend(self.right());
+
+        Descendants { pending }
+    }
+}
+
+/// Breadth-first iterator over the nodes of a subtree, created by [`NodeRef::descendants`].
+pub struct Descendants<'q, 'a, T, O>
+where
+    T: Clone,
+{
+    pending: VecDeque<NodeRef<'q, 'a, T, O>>,
+}
+
+impl<'q, 'a, T, O> Iterator for Descendants<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    type Item = NodeRef<'q, 'a, T, O>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.pending.pop_front()?;
+        self.pending.extend(node.left());
+        self.pending.extend(node.right());
+
+        Some(node)
+    }
+}
+
+/// Depth-first iterator over the `(key_index, &value)` pairs of a subtree, created by
+/// [`OrderedIndexedPriorityQueue::iter_subtree`].
+pub struct SubtreeIter<'q, T> {
+    values: &'q [T],
+    maps: &'q IndexMaps,
+    size: usize,
+    pending: Vec<NodeIdx>,
+}
+
+impl<'q, T> Iterator for SubtreeIter<'q, T> {
+    type Item = (usize, &'q T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.pending.pop()?;
+        let first_child = i.child(0).map_or(self.size, |child| child.0);
+        Range {
+            start: first_child,
+            end: first_child.saturating_add(2).min(self.size),
+        }
+            .rev()
+            .for_each(|child| self.pending.push(NodeIdx(child)));
+
+        Some((self.maps.key_at(i).unwrap().0, &self.values[i.0]))
+    }
+}
+
+/// Iterator over the heap levels, from the root down, created by
+/// [`OrderedIndexedPriorityQueue::levels`].
+pub struct Levels<'q, T> {
+    values: &'q [T],
+    maps: &'q IndexMaps,
+    size: usize,
+    level_start: usize,
+}
+
+impl<'q, T> Iterator for Levels<'q, T> {
+    type Item = Level<'q, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.level_start >= self.size {
+            return None;
+        }
+        let next_level_start = NodeIdx(self.level_start).child(0).map_or(usize::MAX, usize::from);
+        let nodes = Range {
+            start: self.level_start,
+            end: next_level_start.min(self.size),
+        };
+        self.level_start = next_level_start;
+
+        Some(Level {
+            values: self.values,
+            maps: self.maps,
+            nodes,
+        })
+    }
+}
+
+/// The `(key_index, &value)` pairs of a single heap level, left to right.
+pub struct Level<'q, T> {
+    values: &'q [T],
+    maps: &'q IndexMaps,
+    nodes: Range<usize>,
+}
+
+impl<'q, T> Iterator for Level<'q, T> {
+    type Item = (usize, &'q T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.nodes.next()?;
+        Some((self.maps.key_at(NodeIdx(i)).unwrap().0, &self.values[i]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
+    }
+}
+
+impl<'q, T> ExactSizeIterator for Level<'q, T> {}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Returns the root of the heap, which holds the value ranked first by the queue's order.
+    pub fn root(&self) -> Option<NodeRef<'_, 'a, T, O>> {
+        self.node(self.maps.key_at(NodeIdx::ROOT)?.0)
+    }
+
+    /// Returns the node currently holding `key_index`, if the key is mapped.
+    pub fn node(&self, key_index: impl Into<KeyIdx>) -> Option<NodeRef<'_, 'a, T, O>> {
+        Some(NodeRef {
+            queue: self,
+            node_index: self.maps.node_of(key_index.into())?,
+        })
+    }
+
+    /// Returns how many levels the heap has: `0` for an empty queue, `1` for a lone root.
+    ///
+    /// Being a complete binary tree, a healthy heap of `n` elements always has
+    /// `⌊log2(n)⌋ + 1` levels, whatever sequence of operations led to it.
+    pub fn height(&self) -> usize {
+        (usize::BITS - self.size().leading_zeros()) as usize
+    }
+
+    /// Returns the depth of the node holding `key_index`, the root being at depth `0`.
+    pub fn depth_of(&self, key_index: impl Into<KeyIdx>) -> Option<usize> {
+        Some(self.maps.node_of(key_index.into())?.depth())
+    }
+
+    /// Iterates over the heap one level at a time, each level being itself an iterator over
+    /// its entries from left to right; there are exactly [`height`](Self::height) of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![5, 4, 3, 2, 1];
+    /// let ipq = MinIndexedPriorityQueue::from(&mut values);
+    ///
+    /// let widths = ipq.levels().map(|level| level.len()).collect::<Vec<usize>>();
+    /// assert_eq!(widths, vec![1, 2, 2]);
+    /// ```
+    pub fn levels(&self) -> Levels<'_, T> {
+        Levels {
+            values: &self.values,
+            maps: &self.maps,
+            size: self.size(),
+            level_start: 0,
+        }
+    }
+
+    /// Iterates over the subtree rooted at `node_index` in pre-order, yielding each entry's key
+    /// index along with its value. An out of range `node_index` yields an empty iterator.
+    pub fn iter_subtree(&self, node_index: NodeIdx) -> SubtreeIter<'_, T> {
+        let size = self.size();
+        let mut pending = Vec::new();
+        if node_index.0 < size {
+            pending.push(node_index);
+        }
+
+        SubtreeIter {
+            values: &self.values,
+            maps: &self.maps,
+            size,
+            pending,
+        }
+    }
+
+    /// Returns the value ranked first in the subtree rooted at `node_index` by the queue's
+    /// [`Order`]: the smallest one of a [`Min`](crate::order::Min) queue, the largest one of a
+    /// [`Max`](crate::order::Max) queue.
+    ///
+    /// Thanks to the heap invariant this is simply the value stored at that node, which lets
+    /// searches skip whole subtrees whose first value already ranks after what they look for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::index::NodeIdx;
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// fn keys_up_to(ipq: &MinIndexedPriorityQueue<i32>, node_index: NodeIdx, x: i32, found: &mut Vec<usize>) {
+    ///     match ipq.min_of_subtree(node_index) {
+    ///         Some(&v) if v <= x => {
+    ///             found.push(ipq.iter_subtree(node_index).next().unwrap().0);
+    ///             for child in (0..2).filter_map(|nth| node_index.child(nth)) {
+    ///                 keys_up_to(ipq, child, x, found);
+    ///             }
+    ///         }
+    ///         _ => {}
+    ///     }
+    /// }
+    ///
+    /// let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+    /// let ipq = MinIndexedPriorityQueue::from(&mut values);
+    ///
+    /// let mut found = Vec::new();
+    /// keys_up_to(&ipq, NodeIdx::ROOT, 2, &mut found);
+    /// found.sort();
+    /// assert_eq!(found, vec![5, 6, 7, 8, 11]);
+    /// ```
+    pub fn min_of_subtree(&self, node_index: NodeIdx) -> Option<&T> {
+        if node_index.0 < self.size() {
+            Some(self.priority_sequenced_value(node_index))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod node_ref_tests {
+    use crate::index::NodeIdx;
+    use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue};
+
+    #[test]
+    fn root_should_hold_the_minimum_and_navigate_back_from_children() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let root = ipq.root().unwrap();
+        assert_eq!(root.value(), &0);
+        assert_eq!(root.key(), 11);
+        assert!(root.parent().is_none());
+
+        let left = root.left().unwrap();
+        assert_eq!(left.parent().unwrap().key(), root.key());
+        let right = root.right().unwrap();
+        assert_eq!(right.parent().unwrap().key(), root.key());
+    }
+
+    #[test]
+    fn node_should_be_reachable_by_key_and_none_for_unmapped_keys() {
+        let mut values = vec![1, 2, 2, 2, 0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.node(0).unwrap().value(), &1);
+        assert_eq!(ipq.node(4).unwrap().value(), &0);
+        assert!(ipq.node(5).is_none());
+    }
+
+    #[test]
+    fn descendants_should_visit_every_other_node_in_level_order() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+        let root = ipq.root().unwrap();
+
+        let mut keys = root.descendants().map(|n| n.key()).collect::<Vec<usize>>();
+        assert_eq!(keys.len(), 11);
+
+        keys.push(root.key());
+        keys.sort_unstable();
+        assert_eq!(keys, (0..12).collect::<Vec<usize>>());
+        assert!(root.descendants().filter(|n| n.is_leaf()).count() > 0);
+    }
+
+    #[test]
+    fn iter_subtree_should_yield_every_entry_below_a_node() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let mut whole = ipq.iter_subtree(NodeIdx::ROOT).map(|(_, &v)| v).collect::<Vec<i32>>();
+        whole.sort_unstable();
+        assert_eq!(whole, vec![0, 1, 2, 2, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        let left = ipq.root().unwrap().left().unwrap();
+        let mut expected = left.descendants().map(|n| n.key()).collect::<Vec<usize>>();
+        expected.push(left.key());
+        expected.sort_unstable();
+        let mut keys = ipq.iter_subtree(NodeIdx(1)).map(|(k, _)| k).collect::<Vec<usize>>();
+        keys.sort_unstable();
+        assert_eq!(keys, expected);
+
+        assert_eq!(ipq.iter_subtree(NodeIdx(12)).count(), 0);
+    }
+
+    #[test]
+    fn min_of_subtree_should_not_exceed_any_value_of_that_subtree() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        ipq.poll_min_value();
+        ipq.delete(3);
+
+        (0..ipq.size()).map(NodeIdx).for_each(|i| {
+            let min = ipq.min_of_subtree(i).unwrap();
+            assert!(ipq.iter_subtree(i).all(|(_, v)| min <= v));
+        });
+        assert_eq!(ipq.min_of_subtree(NodeIdx::ROOT), Some(&1));
+        assert_eq!(ipq.min_of_subtree(NodeIdx(ipq.size())), None);
+    }
+
+    #[test]
+    fn min_of_subtree_should_rank_by_the_queue_order() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        (0..ipq.size()).map(NodeIdx).for_each(|i| {
+            let first = ipq.min_of_subtree(i).unwrap();
+            assert!(ipq.iter_subtree(i).all(|(_, v)| v <= first));
+        });
+        assert_eq!(ipq.min_of_subtree(NodeIdx::ROOT), Some(&9));
+    }
+
+    #[test]
+    fn height_should_follow_the_number_of_elements() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        assert_eq!(ipq.height(), 4);
+
+        ipq.append(&mut vec![10, 11, 12, 13]);
+        assert_eq!(ipq.height(), 5);
+
+        ipq.drain(1..=15);
+        assert_eq!(ipq.height(), 1);
+        ipq.poll_min_value();
+        assert_eq!(ipq.height(), 0);
+    }
+
+    #[test]
+    fn depth_of_should_locate_keys_within_the_tree() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.depth_of(11), Some(0));
+        assert_eq!(ipq.depth_of(ipq.root().unwrap().left().unwrap().key()), Some(1));
+        (0..ipq.size()).for_each(|k| assert!(ipq.depth_of(k).unwrap() < ipq.height()));
+        assert_eq!(ipq.depth_of(12), None);
+    }
+
+    #[test]
+    fn levels_should_split_the_heap_by_depth() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let levels = ipq
+            .levels()
+            .map(|level| level.map(|(k, _)| k).collect::<Vec<usize>>())
+            .collect::<Vec<Vec<usize>>>();
+
+        assert_eq!(levels.len(), ipq.height());
+        assert_eq!(levels[0], vec![11]);
+        assert_eq!(
+            levels.iter().map(|l| l.len()).collect::<Vec<usize>>(),
+            vec![1, 2, 4, 5]
+        );
+        levels.iter().enumerate().for_each(|(depth, keys)| {
+            keys.iter()
+                .for_each(|&k| assert_eq!(ipq.depth_of(k), Some(depth)));
+        });
+    }
+
+    #[test]
+    fn levels_of_an_empty_queue_should_be_empty() {
+        let mut values: Vec<u8> = Vec::new();
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.levels().count(), 0);
+    }
+
+    #[test]
+    fn empty_queue_should_have_no_root() {
+        let mut values: Vec<u8> = Vec::new();
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert!(ipq.root().is_none());
+        assert!(ipq.node(0).is_none());
+    }
+}
@@ -0,0 +1,186 @@
+use crate::index::NodeIdx;
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+
+/// Receives every structural change of the queue it is attached to, so that an external mirror
+/// of the heap, such as a UI model or a metrics exporter, can replay them instead of diffing
+/// snapshots.
+///
+/// Replaying the events on a vector of key indexes in node order keeps it equal to the queue's
+/// own: an insertion pushes a node at the end, a removal pops the last node, which the queue
+/// first swaps into place, and sifts are reported swap by swap. Bulk removals swap each removed
+/// entry with the last node and pop it, then rebuild the heap once, reporting those swaps too.
+/// Every method defaults to doing nothing.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::index::NodeIdx;
+/// use indexed_priority_queue::observe::IpqObserver;
+/// use indexed_priority_queue::OwnedMinIpq;
+/// use std::sync::{Arc, Mutex};
+///
+/// #[derive(Clone, Default)]
+/// struct Mirror(Arc<Mutex<Vec<usize>>>);
+///
+/// impl IpqObserver<u32> for Mirror {
+///     fn on_insert(&mut self, key_index: usize, _: NodeIdx, _: &u32) {
+///         self.0.lock().unwrap().push(key_index);
+///     }
+///
+///     fn on_remove(&mut self, _: usize, _: NodeIdx) {
+///         self.0.lock().unwrap().pop();
+///     }
+///
+///     fn on_swap(&mut self, i: NodeIdx, j: NodeIdx) {
+///         self.0.lock().unwrap().swap(i.0, j.0);
+///     }
+/// }
+///
+/// let mirror = Mirror::default();
+/// let mut ipq = OwnedMinIpq::new().with_observer(mirror.clone());
+/// ipq.try_insert_all([(0, 30), (1, 10), (2, 20)]);
+/// ipq.poll_min_key_index();
+///
+/// let mirrored_keys = mirror.0.lock().unwrap().clone();
+/// let mirrored_values = mirrored_keys.iter().map(|&k| ipq.value_of(k).unwrap());
+/// assert!(ipq.iter().map(|(_, &value)| value).eq(mirrored_values));
+/// ```
+pub trait IpqObserver<T> {
+    /// `key_index` was inserted with `value` at `node_index`, the last node, before being
+    /// sifted into place.
+    fn on_insert(&mut self, _key_index: usize, _node_index: NodeIdx, _value: &T) {}
+
+    /// `key_index` was taken out of `node_index`, the last node.
+    fn on_remove(&mut self, _key_index: usize, _node_index: NodeIdx) {}
+
+    /// The entries at `i` and `j` traded places.
+    fn on_swap(&mut self, _i: NodeIdx, _j: NodeIdx) {}
+
+    /// The mapping grew to hold up to `mapping_capacity` entries.
+    fn on_expand(&mut self, _mapping_capacity: usize) {}
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Attaches `observer` to the queue, consuming and returning it. The entries already in
+    /// the queue are not reported.
+    pub fn with_observer<B>(mut self, observer: B) -> Self
+    where
+        B: IpqObserver<T> + Send + Sync + 'static,
+    {
+        self.set_observer(observer);
+        self
+    }
+
+    /// Attaches `observer` to the queue in place of the current one, if any.
+    pub fn set_observer<B>(&mut self, observer: B)
+    where
+        B: IpqObserver<T> + Send + Sync + 'static,
+    {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Detaches the observer, returning it.
+    pub fn take_observer(&mut self) -> Option<Box<dyn IpqObserver<T> + Send + Sync>> {
+        self.observer.take()
+    }
+
+    pub(crate) fn observe<F>(&mut self, event: F)
+    where
+        F: FnOnce(&mut (dyn IpqObserver<T> + Send + Sync)),
+    {
+        if let Some(observer) = self.observer.as_deref_mut() {
+            event(observer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod observe_tests {
+    use crate::index::NodeIdx;
+    use crate::observe::IpqObserver;
+    use crate::OwnedMaxIpq;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Mirror {
+        nodes: Arc<Mutex<Vec<usize>>>,
+        expansions: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl IpqObserver<i32> for Mirror {
+        fn on_insert(&mut self, key_index: usize, node_index: NodeIdx, _: &i32) {
+            let mut nodes = self.nodes.lock().unwrap();
+            assert_eq!(node_index.0, nodes.len());
+            nodes.push(key_index);
+        }
+
+        fn on_remove(&mut self, key_index: usize, node_index: NodeIdx) {
+            let mut nodes = self.nodes.lock().unwrap();
+            assert_eq!(node_index.0 + 1, nodes.len());
+            assert_eq!(nodes.pop(), Some(key_index));
+        }
+
+        fn on_swap(&mut self, i: NodeIdx, j: NodeIdx) {
+            self.nodes.lock().unwrap().swap(i.0, j.0);
+        }
+
+        fn on_expand(&mut self, mapping_capacity: usize) {
+            self.expansions.lock().unwrap().push(mapping_capacity);
+        }
+    }
+
+    #[test]
+    fn a_mirror_replaying_the_events_should_match_the_queue_after_every_operation() {
+        let mirror = Mirror::default();
+        let mut ipq = OwnedMaxIpq::new().with_observer(mirror.clone());
+        let matches = |ipq: &OwnedMaxIpq<i32>| {
+            let keys = ipq.maps.keys_by_node(ipq.size()).map(|k| k.0);
+            *mirror.nodes.lock().unwrap() == keys.collect::<Vec<usize>>()
+        };
+
+        (0..20).for_each(|key_index| ipq.insert(key_index, (key_index as i32 * 7) % 11));
+        assert!(matches(&ipq));
+        ipq.append(&mut vec![3, 30, -3]);
+        ipq.update(4, 100);
+        ipq.delete(9);
+        ipq.poll_min_value();
+        assert!(matches(&ipq));
+        assert_eq!(ipq.remove_where(|value| value % 2 == 0).len(), 9);
+        ipq.drain(..5);
+        assert!(matches(&ipq));
+        ipq.clear();
+        assert!(mirror.nodes.lock().unwrap().is_empty());
+
+        assert!(ipq.take_observer().is_some());
+        ipq.insert(0, 1);
+        assert!(mirror.nodes.lock().unwrap().is_empty());
+        assert_eq!(*mirror.expansions.lock().unwrap(), vec![2, 4, 8, 16, 32]);
+    }
+
+    #[derive(Clone, Default)]
+    struct SwapCounter(Arc<Mutex<usize>>);
+
+    impl IpqObserver<i32> for SwapCounter {
+        fn on_swap(&mut self, _: NodeIdx, _: NodeIdx) {
+            *self.0.lock().unwrap() += 1;
+        }
+    }
+
+    #[test]
+    fn bulk_removals_should_rebuild_once_while_observed() {
+        let swaps = SwapCounter::default();
+        let mut ipq = OwnedMaxIpq::from((0..1024).collect::<Vec<i32>>());
+        ipq.set_observer(swaps.clone());
+
+        assert_eq!(ipq.remove_where(|value| *value >= 512).len(), 512);
+        assert!(ipq.satisfies_heap_invariant());
+        // At most one swap per removal and one per survivor for the rebuild, where deleting the
+        // top entries one by one would sift a leaf down from the root each time.
+        assert!(*swaps.0.lock().unwrap() <= 1024);
+    }
+}
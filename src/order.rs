@@ -0,0 +1,372 @@
+use crate::OrderedIndexedPriorityQueue;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+/// Direction in which a queue ranks its values.
+///
+/// Every algorithm of [`OrderedIndexedPriorityQueue`](crate::OrderedIndexedPriorityQueue) is
+/// written once against this trait; the `*_min_*` methods of
+/// [`IndexedPriorityQueue`](crate::ipq::IndexedPriorityQueue) always refer to the value the
+/// order ranks first, i.e. the smallest for [`Min`] and the greatest for [`Max`].
+pub trait Order {
+    /// Human readable name, used by the queue's `Display` implementation.
+    const NAME: &'static str;
+
+    /// The order ranking values the other way around.
+    type Reversed: Order;
+
+    /// Returns `true` when `a` must be polled before `b`.
+    fn precedes<T: PartialOrd>(a: &T, b: &T) -> bool;
+}
+
+/// Ranks smaller values first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Min;
+
+/// Ranks greater values first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Max;
+
+impl Order for Min {
+    const NAME: &'static str = "Minimum";
+
+    type Reversed = Max;
+
+    #[inline]
+    fn precedes<T: PartialOrd>(a: &T, b: &T) -> bool {
+        a < b
+    }
+}
+
+impl Order for Max {
+    const NAME: &'static str = "Maximum";
+
+    type Reversed = Min;
+
+    #[inline]
+    fn precedes<T: PartialOrd>(a: &T, b: &T) -> bool {
+        a > b
+    }
+}
+
+/// Where a [`Total`] ranks the values that are not comparable with themselves, such as NaNs.
+pub trait IncomparablePolicy {
+    /// Whether such values compare greater than every other value, rather than less.
+    const GREATEST: bool;
+}
+
+/// Ranks incomparable values above every other one: last in a minimum queue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IncomparableGreatest;
+
+/// Ranks incomparable values below every other one: first in a minimum queue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IncomparableLeast;
+
+impl IncomparablePolicy for IncomparableGreatest {
+    const GREATEST: bool = true;
+}
+
+impl IncomparablePolicy for IncomparableLeast {
+    const GREATEST: bool = false;
+}
+
+/// A value whose `PartialOrd` is made total by the policy `P`, e.g. `Total<f64>` for floats
+/// that may be NaN.
+///
+/// Queues only ask their values for `PartialOrd`, as every `Ord` type already is, but a heap
+/// is only ordered if every comparison it makes is meaningful: a NaN compares false both ways
+/// with everything, so it can sit anywhere and hide better values below it. The queues refuse
+/// values that are not comparable with themselves on construction, insertion and update, their
+/// `try_*` methods and `try_from_*` constructors reporting them as
+/// [`IpqError::Incomparable`](crate::limit::IpqError::Incomparable), while values rewritten in
+/// place, e.g. through [`peek_min_mut`](crate::OrderedIndexedPriorityQueue::peek_min_mut), are
+/// only checked in debug builds; wrap such values in a `Total` to queue them with an explicit
+/// rank instead. Values comparable with themselves but not with some others, as in a genuine
+/// partial order, are equal to those under a `Total`, which is only a total order when such
+/// incomparabilities never occur.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::order::Total;
+/// use indexed_priority_queue::OwnedMinIpq;
+///
+/// let mut latencies: OwnedMinIpq<Total<f64>> = OwnedMinIpq::new();
+/// for latency in [f64::NAN, 2.5, 0.5] {
+///     latencies.try_push(Total::new(latency)).unwrap();
+/// }
+///
+/// assert_eq!(latencies.poll_min_value().map(Total::into_inner), Some(0.5));
+/// assert_eq!(latencies.poll_min_value().map(Total::into_inner), Some(2.5));
+/// assert!(latencies.poll_min_value().unwrap().into_inner().is_nan());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Total<T, P = IncomparableGreatest> {
+    value: T,
+    policy: PhantomData<P>,
+}
+
+impl<T, P> Total<T, P>
+where
+    T: PartialOrd,
+    P: IncomparablePolicy,
+{
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            policy: PhantomData,
+        }
+    }
+
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    fn is_incomparable(&self) -> bool {
+        self.value.partial_cmp(&self.value).is_none()
+    }
+}
+
+impl<T: PartialOrd, P: IncomparablePolicy> Ord for Total<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if let Some(ordering) = self.value.partial_cmp(&other.value) {
+            return ordering;
+        }
+
+        let incomparable_above = if P::GREATEST {
+            Ordering::Greater
+        } else {
+            Ordering::Less
+        };
+        match (self.is_incomparable(), other.is_incomparable()) {
+            (true, false) => incomparable_above,
+            (false, true) => incomparable_above.reverse(),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+impl<T: PartialOrd, P: IncomparablePolicy> PartialOrd for Total<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: PartialOrd, P: IncomparablePolicy> PartialEq for Total<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: PartialOrd, P: IncomparablePolicy> Eq for Total<T, P> {}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Re-heapifies the queue under the order `P`, in `O(n)` and without reallocating.
+    ///
+    /// Keys keep addressing the same values; only their positions in the heap change.
+    pub fn into_order<P: Order>(self) -> OrderedIndexedPriorityQueue<'a, T, P> {
+        let mut ipq = OrderedIndexedPriorityQueue {
+            values: self.values,
+            maps: self.maps,
+            shrink_policy: self.shrink_policy,
+            bulk_tuning: self.bulk_tuning,
+            min_publisher: self.min_publisher,
+            observer: self.observer,
+            metadata: self.metadata,
+            held: self.held,
+            max_key_bound: self.max_key_bound,
+            dedup: self.dedup,
+            version: self.version,
+            order: PhantomData,
+        };
+        ipq.fix_heap_invariant();
+        ipq.publish_min();
+
+        ipq
+    }
+
+    /// Turns the queue into a maximum queue, see [`into_order`](Self::into_order).
+    pub fn into_max(self) -> OrderedIndexedPriorityQueue<'a, T, Max> {
+        self.into_order()
+    }
+
+    /// Turns the queue into a minimum queue, see [`into_order`](Self::into_order).
+    pub fn into_min(self) -> OrderedIndexedPriorityQueue<'a, T, Min> {
+        self.into_order()
+    }
+
+    /// Flips the queue's order, reusing its buffers as [`into_order`](Self::into_order) does.
+    ///
+    /// As the order is part of the queue's type, the reversal has to hand back a new value
+    /// rather than mutate through `&mut self`; no allocation happens either way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![4, 1, 3, 2];
+    /// let mut best_first = MinIndexedPriorityQueue::from(&mut values);
+    /// assert_eq!(best_first.poll_min_value(), Some(1));
+    ///
+    /// let mut worst_first = best_first.into_reversed();
+    /// assert_eq!(worst_first.poll_min_value(), Some(4));
+    /// ```
+    pub fn into_reversed(self) -> OrderedIndexedPriorityQueue<'a, T, O::Reversed> {
+        self.into_order()
+    }
+}
+
+/// The `*_min_*` reads and removals under the names a maximum queue's callers expect.
+///
+/// They only forward to their `*_min_*` counterparts, which already refer to the greatest
+/// value of a [`MaxIndexedPriorityQueue`](crate::MaxIndexedPriorityQueue).
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::MaxIndexedPriorityQueue;
+///
+/// let mut bids = vec![120, 180, 95];
+/// let mut auction = MaxIndexedPriorityQueue::from(&mut bids);
+///
+/// assert_eq!(auction.peek_max_value(), Some(180));
+/// assert_eq!(auction.poll_max_key_index(), Some(1));
+/// assert_eq!(auction.poll_max_value(), Some(120));
+/// ```
+impl<'a, T> OrderedIndexedPriorityQueue<'a, T, Max>
+where
+    T: Clone + PartialOrd,
+{
+    pub fn peek_max_key_index(&self) -> Option<usize> {
+        self.peek_min_key_index()
+    }
+
+    pub fn peek_max_value(&self) -> Option<T> {
+        self.peek_min_value()
+    }
+
+    pub fn poll_max_key_index(&mut self) -> Option<usize> {
+        self.poll_min_key_index()
+    }
+
+    pub fn poll_max_value(&mut self) -> Option<T> {
+        self.poll_min_value()
+    }
+}
+
+#[cfg(test)]
+mod order_tests {
+    use crate::limit::IpqError;
+    use crate::order::{IncomparableLeast, Total};
+    use crate::{MinIndexedPriorityQueue, OwnedMaxIpq, OwnedMinIpq};
+
+    #[test]
+    fn into_max_and_back_should_keep_keys_and_rank_the_other_way() {
+        let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+        let min_ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let mut max_ipq = min_ipq.into_max();
+        assert!(max_ipq.satisfies_heap_invariant());
+        assert_eq!(max_ipq.peek_min_key_index(), Some(0));
+        assert_eq!(max_ipq.value_of(11), Some(0));
+        assert_eq!(max_ipq.poll_min_value(), Some(9));
+
+        let mut min_ipq = max_ipq.into_min();
+        assert_eq!(min_ipq.size(), 11);
+        assert_eq!(min_ipq.poll_min_value(), Some(0));
+    }
+
+    #[test]
+    fn into_reversed_twice_should_restore_the_original_order() {
+        let mut values = vec![1, 2, 2, 2, 0];
+        let ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let ipq: MinIndexedPriorityQueue<i32> = ipq.into_reversed().into_reversed();
+        assert!(ipq.has_consistent_mappings());
+        assert_eq!(ipq.peek_min_value(), Some(0));
+    }
+
+    #[test]
+    fn into_order_should_handle_empty_queues() {
+        let mut values: Vec<u8> = Vec::new();
+        let ipq = MinIndexedPriorityQueue::from(&mut values).into_max();
+
+        assert!(ipq.is_empty());
+        assert_eq!(ipq.peek_min_value(), None);
+    }
+
+    #[test]
+    fn max_named_methods_should_follow_the_greatest_value() {
+        let mut values = vec![3, 7, 5];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values).into_max();
+
+        ipq.increase(0, 9);
+        assert_eq!(ipq.peek_max_key_index(), Some(0));
+        assert_eq!(ipq.poll_max_value(), Some(9));
+        ipq.decrease(1, 1);
+        assert_eq!(ipq.poll_max_key_index(), Some(2));
+        assert_eq!(ipq.peek_max_value(), Some(1));
+    }
+
+    #[test]
+    fn total_should_rank_nans_by_its_policy() {
+        let mut values = [3.0, f64::NAN, -1.0, f64::NAN, 0.5]
+            .iter()
+            .map(|&v| Total::<f64, IncomparableLeast>::new(v))
+            .collect::<Vec<_>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert!(ipq.poll_min_value().unwrap().get().is_nan());
+        assert!(ipq.poll_min_value().unwrap().get().is_nan());
+        assert_eq!(ipq.poll_min_value().map(Total::into_inner), Some(-1.0));
+        assert_eq!(Total::<f64>::new(f64::NAN), Total::new(f64::NAN));
+        assert!(Total::<f64>::new(f64::NAN) > Total::new(f64::INFINITY));
+    }
+
+    #[test]
+    #[should_panic(expected = "Value is not comparable with itself")]
+    fn values_incomparable_with_themselves_should_be_rejected() {
+        let mut values = vec![1.0, 2.0];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        ipq.update(0, f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "Value is not comparable with itself")]
+    fn queues_built_over_incomparable_values_should_be_rejected() {
+        let mut values = vec![1.0, f64::NAN];
+
+        let _ = MinIndexedPriorityQueue::from(&mut values);
+    }
+
+    #[test]
+    fn try_from_constructors_should_report_the_first_incomparable_value() {
+        let mut values = vec![3.0, 1.0, f64::NAN, f64::NAN];
+
+        let borrowed = MinIndexedPriorityQueue::try_from_borrowed(&mut values);
+        assert_eq!(borrowed.err(), Some(IpqError::Incomparable(2)));
+        assert_eq!(values[..2], [3.0, 1.0]);
+        assert_eq!(
+            OwnedMaxIpq::try_from_vec(values.clone()).err(),
+            Some(IpqError::Incomparable(2))
+        );
+        assert_eq!(
+            OwnedMinIpq::try_from_sorted_iter(values).err(),
+            Some(IpqError::Incomparable(2))
+        );
+
+        let ipq = OwnedMinIpq::try_from_sorted_iter(vec![1.0, 2.0]).unwrap();
+        assert_eq!(ipq.peek_min_key_index(), Some(0));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::index::{KeyIdx, NodeIdx};
+
+pub(crate) const PAGE_BITS: u32 = 10;
+pub(crate) const PAGE_LEN: usize = 1 << PAGE_BITS;
+pub(crate) const PAGE_MASK: usize = PAGE_LEN - 1;
+
+#[derive(Clone, Debug)]
+struct Page {
+    node_indexes: Box<[Option<NodeIdx>]>,
+    mapped: usize,
+}
+
+impl Page {
+    fn new() -> Self {
+        Self {
+            node_indexes: vec![None; PAGE_LEN].into_boxed_slice(),
+            mapped: 0,
+        }
+    }
+}
+
+/// Key index to node index map split into fixed size pages that are only allocated once a
+/// key falling into them gets mapped.
+///
+/// A lookup costs two array accesses whatever the key, so compact key ranges keep the speed of
+/// a dense vector, while a lone key in the ten millions costs a single page plus one directory
+/// slot per page before it. Pages are released as soon as their last key is unmapped.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PagedPositionMap {
+    pages: Vec<Option<Page>>,
+    key_bound: usize,
+    mapped: usize,
+}
+
+impl PagedPositionMap {
+    /// Maps the keys `0..len` to the node with the same index.
+    pub(crate) fn identity(len: usize) -> Self {
+        let mut position_map = Self::default();
+        (0..len).for_each(|key_index| {
+            position_map.set(KeyIdx(key_index), NodeIdx(key_index));
+        });
+
+        position_map
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, KeyIdx(key_index): KeyIdx) -> Option<NodeIdx> {
+        match self.pages.get(key_index >> PAGE_BITS) {
+            Some(Some(page)) => page.node_indexes[key_index & PAGE_MASK],
+            _ => None,
+        }
+    }
+
+    /// Maps `key_index` to `node_index`, returning the node index it was mapped to before.
+    pub(crate) fn set(
+        &mut self,
+        KeyIdx(key_index): KeyIdx,
+        node_index: NodeIdx,
+    ) -> Option<NodeIdx> {
+        let page_index = key_index >> PAGE_BITS;
+        if page_index >= self.pages.len() {
+            self.pages.resize_with(page_index + 1, || None);
+        }
+
+        let page = self.pages[page_index].get_or_insert_with(Page::new);
+        let previous = page.node_indexes[key_index & PAGE_MASK].replace(node_index);
+        if previous.is_none() {
+            page.mapped += 1;
+            self.mapped += 1;
+            self.key_bound = self.key_bound.max(key_index + 1);
+        }
+
+        previous
+    }
+
+    /// Unmaps `key_index`, returning the node index it was mapped to.
+    pub(crate) fn remove(&mut self, KeyIdx(key_index): KeyIdx) -> Option<NodeIdx> {
+        let page_index = key_index >> PAGE_BITS;
+        let page = self.pages.get_mut(page_index)?.as_mut()?;
+        let previous = page.node_indexes[key_index & PAGE_MASK].take();
+        if previous.is_some() {
+            page.mapped -= 1;
+            self.mapped -= 1;
+            if page.mapped == 0 {
+                self.pages[page_index] = None;
+            }
+        }
+
+        previous
+    }
+
+    /// One past the greatest key index mapped since the map was created or cleared.
+    pub(crate) fn key_bound(&self) -> usize {
+        self.key_bound
+    }
+
+    /// Makes [`key_bound`](Self::key_bound) at least `key_bound`, as if keys up to it had been
+    /// mapped and removed since.
+    pub(crate) fn raise_key_bound(&mut self, key_bound: usize) {
+        self.key_bound = self.key_bound.max(key_bound);
+    }
+
+    /// Number of keys currently mapped.
+    pub(crate) fn mapped(&self) -> usize {
+        self.mapped
+    }
+
+    /// Number of pages currently allocated.
+    pub(crate) fn allocated_pages(&self) -> usize {
+        self.pages.iter().flatten().count()
+    }
+
+    /// Number of directory slots, allocated pages or not.
+    pub(crate) fn directory_len(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Mapped `(key_index, node_index)` pairs, by ascending key index.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (KeyIdx, NodeIdx)> + '_ {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter_map(|(page_index, page)| page.as_ref().map(|page| (page_index, page)))
+            .flat_map(|(page_index, page)| {
+                page.node_indexes
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(offset, node_index)| {
+                        node_index.map(|n| (KeyIdx((page_index << PAGE_BITS) | offset), n))
+                    })
+            })
+    }
+
+    /// Drops the trailing directory slots left without a page.
+    pub(crate) fn shrink_to_fit(&mut self) {
+        let len = self
+            .pages
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |page_index| page_index + 1);
+        self.pages.truncate(len);
+        self.pages.shrink_to_fit();
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.pages.clear();
+        self.key_bound = 0;
+        self.mapped = 0;
+    }
+}
+
+#[cfg(test)]
+mod paged_position_map_tests {
+    use crate::index::{KeyIdx, NodeIdx};
+    use crate::paged::{PagedPositionMap, PAGE_LEN};
+
+    #[test]
+    fn a_far_key_should_only_allocate_its_own_page() {
+        let mut position_map = PagedPositionMap::default();
+        assert_eq!(position_map.set(KeyIdx(10_000_000), NodeIdx(0)), None);
+
+        assert_eq!(position_map.allocated_pages(), 1);
+        assert_eq!(position_map.get(KeyIdx(10_000_000)), Some(NodeIdx(0)));
+        assert_eq!(position_map.get(KeyIdx(9_999_999)), None);
+        assert_eq!(position_map.get(KeyIdx(usize::MAX)), None);
+        assert_eq!(position_map.key_bound(), 10_000_001);
+    }
+
+    #[test]
+    fn removing_the_last_key_of_a_page_should_release_it() {
+        let mut position_map = PagedPositionMap::identity(PAGE_LEN + 1);
+        assert_eq!(position_map.allocated_pages(), 2);
+
+        assert_eq!(position_map.remove(KeyIdx(PAGE_LEN)), Some(NodeIdx(PAGE_LEN)));
+        assert_eq!(position_map.remove(KeyIdx(PAGE_LEN)), None);
+        assert_eq!(position_map.allocated_pages(), 1);
+        assert_eq!(position_map.mapped(), PAGE_LEN);
+        assert_eq!(position_map.key_bound(), PAGE_LEN + 1);
+
+        position_map.shrink_to_fit();
+        assert_eq!(position_map.pages.len(), 1);
+    }
+
+    #[test]
+    fn iter_should_yield_mapped_keys_in_ascending_order() {
+        let mut position_map = PagedPositionMap::default();
+        position_map.set(KeyIdx(3 * PAGE_LEN), NodeIdx(2));
+        position_map.set(KeyIdx(7), NodeIdx(0));
+        position_map.set(KeyIdx(PAGE_LEN - 1), NodeIdx(1));
+        assert_eq!(position_map.set(KeyIdx(7), NodeIdx(3)), Some(NodeIdx(0)));
+
+        assert_eq!(
+            position_map.iter().map(|(k, i)| (k.0, i.0)).collect::<Vec<(usize, usize)>>(),
+            vec![(7, 3), (PAGE_LEN - 1, 1), (3 * PAGE_LEN, 2)]
+        );
+    }
+}
@@ -0,0 +1,31 @@
+//! Everything needed to build and use a queue, for a single glob import.
+//!
+//! Besides the queue types, the prelude brings the [`IndexedPriorityQueue`] and
+//! [`IndexedBinaryHeap`] traits into scope, for code generic over the crate's other queues;
+//! the queue's own methods need no import. With `deny-panics` and without `panic-on-misuse`
+//! the traits are left out, like the queue's panicking insertions and updates.
+//!
+//! # Examples
+//!
+//! ```
+//! use indexed_priority_queue::prelude::*;
+//!
+//! let mut jobs = OwnedMaxIpq::new();
+//! jobs.try_insert(2, 30)?;
+//! jobs.try_insert(5, 70)?;
+//! jobs.try_decrease(5, 10)?;
+//!
+//! assert_eq!(jobs.try_insert(2, 0), Err(IpqError::KeyInUse(2)));
+//! assert_eq!(jobs.poll_min_key_index(), Some(2));
+//! assert_eq!(jobs.size(), 1);
+//! # Ok::<(), IpqError>(())
+//! ```
+
+#[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+pub use crate::ipq::{IndexedBinaryHeap, IndexedPriorityQueue};
+pub use crate::limit::{IpqError, KeyError};
+pub use crate::order::{Max, Min, Order};
+pub use crate::{
+    MaxIndexedPriorityQueue, MinIndexedPriorityQueue, OrderedIndexedPriorityQueue, OwnedIpq,
+    OwnedMaxIpq, OwnedMinIpq,
+};
@@ -0,0 +1,221 @@
+use crate::index::{KeyIdx, NodeIdx};
+use crate::ipq::IndexedBinaryHeap;
+use crate::limit::IpqError;
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+
+/// Values with an immediate neighbour on each side, used to rank an entry right before or right
+/// after another one.
+pub trait Step: Sized {
+    /// The greatest value smaller than `self`, or `self` at the bottom of the range.
+    fn pred(&self) -> Self;
+
+    /// The smallest value greater than `self`, or `self` at the top of the range.
+    fn succ(&self) -> Self;
+}
+
+macro_rules! impl_step_for_integers {
+    ($($t:ty),*) => {
+        $(impl Step for $t {
+            fn pred(&self) -> Self {
+                self.saturating_sub(1)
+            }
+
+            fn succ(&self) -> Self {
+                self.saturating_add(1)
+            }
+        })*
+    };
+}
+
+impl_step_for_integers!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_step_for_floats {
+    ($($t:ty),*) => {
+        $(impl Step for $t {
+            fn pred(&self) -> Self {
+                -(-self).succ()
+            }
+
+            fn succ(&self) -> Self {
+                if self.is_nan() || *self == <$t>::INFINITY {
+                    return *self;
+                }
+                if *self == 0.0 {
+                    return <$t>::from_bits(1);
+                }
+                let bits = self.to_bits();
+                if *self > 0.0 {
+                    <$t>::from_bits(bits + 1)
+                } else {
+                    <$t>::from_bits(bits - 1)
+                }
+            }
+        })*
+    };
+}
+
+impl_step_for_floats!(f32, f64);
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd + Step,
+    O: Order,
+{
+    /// Gives `key_index` the value ranked right before every other entry's, so that it is
+    /// polled next, and returns its previous value.
+    ///
+    /// The value ranked first among the others sits at the root or one of its children, so
+    /// this costs a single sift. An entry already ranked strictly first keeps its value, and
+    /// one promoted past the end of `T`'s range ties with the first instead.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue; see [`try_promote_to_min`](Self::try_promote_to_min).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut ranks = vec![10, 20, 30, 40];
+    /// let mut jobs = MinIndexedPriorityQueue::from(&mut ranks);
+    ///
+    /// assert_eq!(jobs.promote_to_min(2), 30);
+    /// assert_eq!(jobs.value_of(2), Some(9));
+    /// jobs.demote_to_max(2);
+    /// assert_eq!(jobs.value_of(2), Some(41));
+    /// assert_eq!(jobs.poll_min_key_index(), Some(0));
+    /// ```
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn promote_to_min(&mut self, key_index: impl Into<KeyIdx>) -> T {
+        self.try_promote_to_min(key_index).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`promote_to_min`](Self::promote_to_min), returning
+    /// [`IpqError::KeyNotFound`] instead of panicking when `key_index` is not in the queue.
+    pub fn try_promote_to_min(&mut self, key_index: impl Into<KeyIdx>) -> Result<T, IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        let i = self.node_of_key(key_index)?;
+
+        let first_other = match i {
+            NodeIdx::ROOT => [0, 1]
+                .into_iter()
+                .filter_map(|nth| NodeIdx::ROOT.child(nth))
+                .filter(|&child| child.0 < self.size())
+                .reduce(|a, b| if self.less(b, a) { b } else { a }),
+            _ => Some(NodeIdx::ROOT),
+        };
+        match first_other {
+            Some(j) if !self.less(i, j) => {
+                let value = Self::ranked_before(&self.values[j.0]);
+                self.try_update(key_index, value)
+            }
+            _ => Ok(self.values[i.0].clone()),
+        }
+    }
+
+    /// Gives `key_index` the value ranked right after every other entry's, so that it is
+    /// polled last, and returns its previous value.
+    ///
+    /// The value ranked last hides among the leaves, which are all compared, so this costs
+    /// `O(n)`. An entry already ranked strictly last keeps its value.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue; see [`try_demote_to_max`](Self::try_demote_to_max).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn demote_to_max(&mut self, key_index: impl Into<KeyIdx>) -> T {
+        self.try_demote_to_max(key_index).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`demote_to_max`](Self::demote_to_max), returning
+    /// [`IpqError::KeyNotFound`] instead of panicking when `key_index` is not in the queue.
+    pub fn try_demote_to_max(&mut self, key_index: impl Into<KeyIdx>) -> Result<T, IpqError> {
+        let KeyIdx(key_index) = key_index.into();
+        let i = self.node_of_key(key_index)?;
+
+        let size = self.size();
+        let last_other = (size / 2..size)
+            .map(NodeIdx)
+            .chain(Some(i.parent()))
+            .filter(|&j| j != i)
+            .reduce(|a, b| if self.less(a, b) { b } else { a });
+        match last_other {
+            Some(j) if !self.less(j, i) => {
+                let value = Self::ranked_after(&self.values[j.0]);
+                self.try_update(key_index, value)
+            }
+            _ => Ok(self.values[i.0].clone()),
+        }
+    }
+
+    fn node_of_key(&self, key_index: usize) -> Result<NodeIdx, IpqError> {
+        self.maps.node_of(KeyIdx(key_index)).ok_or(IpqError::KeyNotFound(key_index))
+    }
+
+    /// Returns the neighbour of `value` ranked right before it, or `value` itself at the end
+    /// of the range.
+    fn ranked_before(value: &T) -> T {
+        [value.pred(), value.succ()]
+            .into_iter()
+            .find(|neighbour| O::precedes(neighbour, value))
+            .unwrap_or_else(|| value.clone())
+    }
+
+    fn ranked_after(value: &T) -> T {
+        [value.succ(), value.pred()]
+            .into_iter()
+            .find(|neighbour| O::precedes(value, neighbour))
+            .unwrap_or_else(|| value.clone())
+    }
+}
+
+#[cfg(test)]
+mod reorder_tests {
+    use crate::limit::IpqError;
+    use crate::reorder::Step;
+    use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue};
+
+    #[test]
+    fn promote_and_demote_should_follow_the_queue_order() {
+        let mut values = vec![5, 9, 7, 1];
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.try_promote_to_min(3), Ok(1));
+        assert_eq!(ipq.value_of(3), Some(10));
+        assert_eq!(ipq.try_demote_to_max(1).unwrap(), 9);
+        assert_eq!(ipq.value_of(1), Some(4));
+
+        let polled = (0..4).map(|_| ipq.poll_min_key_index().unwrap()).collect::<Vec<usize>>();
+        assert_eq!(polled, vec![3, 2, 0, 1]);
+    }
+
+    #[test]
+    fn entries_already_strictly_at_an_end_should_keep_their_value() {
+        let mut values = (0..31).collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        assert_eq!(ipq.try_promote_to_min(0).unwrap(), 0);
+        assert_eq!(ipq.value_of(0), Some(0));
+        assert_eq!(ipq.try_demote_to_max(30).unwrap(), 30);
+        assert_eq!(ipq.value_of(30), Some(30));
+
+        ipq.try_demote_to_max(0).unwrap();
+        assert_eq!(ipq.value_of(0), Some(31));
+        assert_eq!(ipq.poll_min_key_index(), Some(1));
+        ipq.update(2, i32::MIN);
+        ipq.try_promote_to_min(5).unwrap();
+        assert_eq!(ipq.value_of(5), Some(i32::MIN));
+        assert_eq!(ipq.try_demote_to_max(31), Err(IpqError::KeyNotFound(31)));
+    }
+
+    #[test]
+    fn float_steps_should_reach_the_adjacent_representable_values() {
+        assert!(1.0f64.pred() < 1.0 && 1.0f64.pred() > 0.9999999);
+        assert_eq!(0.0f32.succ(), f32::from_bits(1));
+        assert_eq!((-0.0f64).pred(), -f64::from_bits(1));
+        assert_eq!(f64::INFINITY.succ(), f64::INFINITY);
+        assert_eq!(i8::MIN.pred(), i8::MIN);
+    }
+}
@@ -0,0 +1,418 @@
+use crate::index::{KeyIdx, NodeIdx};
+use crate::ipq::IndexedBinaryHeap;
+use crate::limit::IpqError;
+use crate::order::Order;
+use crate::{debug_assert_comparable, OrderedIndexedPriorityQueue};
+use std::collections::BTreeSet;
+use std::ops::{Deref, DerefMut, Index, IndexMut, RangeBounds};
+
+/// Mutable view of a queue's values in node order, created by
+/// [`OrderedIndexedPriorityQueue::as_mut_slice_raw`].
+///
+/// Values can be rewritten in place, ECS-style, but the heap order is only restored for the
+/// keys passed to [`repair`](Self::repair) or [`repair_range`](Self::repair_range). Debug
+/// builds check the heap order when the view is dropped, so a forgotten repair panics there
+/// rather than surfacing later as a wrong poll.
+pub struct RawValuesMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    queue: &'q mut OrderedIndexedPriorityQueue<'a, T, O>,
+}
+
+impl<'q, 'a, T, O> RawValuesMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Key index of the value at `node_index`.
+    pub fn key_of(&self, node_index: NodeIdx) -> Option<KeyIdx> {
+        self.queue.maps.key_at(node_index)
+    }
+
+    /// Node index of `key_index`'s value, valid until the next repair.
+    pub fn node_index_of(&self, key_index: impl Into<KeyIdx>) -> Option<NodeIdx> {
+        self.queue.maps.node_of(key_index.into())
+    }
+
+    /// See [`OrderedIndexedPriorityQueue::repair`].
+    pub fn repair(&mut self, key_index: impl Into<KeyIdx>) -> bool {
+        self.queue.repair(key_index)
+    }
+
+    /// See [`OrderedIndexedPriorityQueue::repair_range`].
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn repair_range<R: RangeBounds<usize>>(&mut self, key_indexes: R) -> usize {
+        self.queue.repair_range(key_indexes)
+    }
+
+    /// See [`OrderedIndexedPriorityQueue::try_repair_range`].
+    pub fn try_repair_range<R: RangeBounds<usize>>(
+        &mut self,
+        key_indexes: R,
+    ) -> Result<usize, IpqError> {
+        self.queue.try_repair_range(key_indexes)
+    }
+}
+
+impl<'q, 'a, T, O> Deref for RawValuesMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        &self.queue.values
+    }
+}
+
+impl<'q, 'a, T, O> DerefMut for RawValuesMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.queue.values
+    }
+}
+
+impl<'q, 'a, T, O> Index<NodeIdx> for RawValuesMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    type Output = T;
+
+    fn index(&self, node_index: NodeIdx) -> &Self::Output {
+        &self.queue.values[node_index.0]
+    }
+}
+
+impl<'q, 'a, T, O> IndexMut<NodeIdx> for RawValuesMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn index_mut(&mut self, node_index: NodeIdx) -> &mut Self::Output {
+        &mut self.queue.values[node_index.0]
+    }
+}
+
+impl<'q, 'a, T, O> Drop for RawValuesMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn drop(&mut self) {
+        debug_assert!(
+            std::thread::panicking() || self.queue.satisfies_heap_invariant(),
+            "Values changed through as_mut_slice_raw were not all repaired"
+        );
+    }
+}
+
+/// Mutable access to the entry ranked first, created by
+/// [`OrderedIndexedPriorityQueue::peek_min_mut`].
+///
+/// The entry is sifted back into place when the guard is dropped, and only if it was borrowed
+/// mutably, so that rescoring the best candidate costs one sift instead of a delete and an
+/// insert.
+pub struct PeekMinMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    queue: &'q mut OrderedIndexedPriorityQueue<'a, T, O>,
+    node_index: NodeIdx,
+    touched: bool,
+}
+
+impl<'q, 'a, T, O> PeekMinMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    pub fn key_index(&self) -> usize {
+        self.queue.maps.key_at(self.node_index).unwrap().0
+    }
+
+    /// Removes the peeked entry, returning its key index and value, without sifting it first.
+    pub fn pop(mut guard: Self) -> (usize, T) {
+        guard.touched = false;
+        let node_index = guard.node_index;
+
+        guard.queue.swap_remove(node_index).unwrap()
+    }
+}
+
+impl<'q, 'a, T, O> Deref for PeekMinMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.queue.values[self.node_index.0]
+    }
+}
+
+impl<'q, 'a, T, O> DerefMut for PeekMinMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.touched = true;
+        &mut self.queue.values[self.node_index.0]
+    }
+}
+
+impl<'q, 'a, T, O> Drop for PeekMinMut<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn drop(&mut self) {
+        if !self.touched {
+            return;
+        }
+        debug_assert_comparable(&self.queue.values[self.node_index.0]);
+        self.queue.bump_version();
+        self.queue.sink(self.node_index);
+        self.queue.swim(self.node_index);
+        self.queue.publish_min();
+    }
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Lends the entry ranked first, held ones skipped, for in-place mutation, or returns
+    /// `None` if there is none. The heap order is restored when the guard is dropped; a value
+    /// left not comparable with itself is only caught by a debug assertion then.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::repair::PeekMinMut;
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let mut candidates = OwnedMinIpq::from(vec![4, 1, 3]);
+    ///
+    /// if let Some(mut best) = candidates.peek_min_mut() {
+    ///     assert_eq!(best.key_index(), 1);
+    ///     *best += 5;
+    /// }
+    /// assert_eq!(candidates.peek_min_key_index(), Some(2));
+    ///
+    /// let best = candidates.peek_min_mut().unwrap();
+    /// assert_eq!(PeekMinMut::pop(best), (2, 3));
+    /// ```
+    pub fn peek_min_mut(&mut self) -> Option<PeekMinMut<'_, 'a, T, O>> {
+        let node_index = self.first_unheld_node()?;
+
+        Some(PeekMinMut {
+            queue: self,
+            node_index,
+            touched: false,
+        })
+    }
+
+    /// Lends the values in node order for direct mutation, without the cost of a keyed
+    /// [`update`](crate::ipq::IndexedPriorityQueue::update) per change.
+    ///
+    /// Every key whose value is changed must then be repaired, which only sifts the touched
+    /// entries instead of rebuilding the whole heap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![10, 20, 30, 40];
+    /// let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+    ///
+    /// let mut raw = ipq.as_mut_slice_raw();
+    /// let node = raw.node_index_of(3).unwrap();
+    /// raw[node] = 5;
+    /// raw.repair(3);
+    /// drop(raw);
+    ///
+    /// assert_eq!(ipq.poll_min_key_index(), Some(3));
+    /// ```
+    pub fn as_mut_slice_raw(&mut self) -> RawValuesMut<'_, 'a, T, O> {
+        self.bump_version();
+        RawValuesMut { queue: self }
+    }
+
+    /// Moves `key_index`'s value back where the heap order wants it after it was changed in
+    /// place, in `O(log n)`. Returns `false` if the key is not in the queue.
+    ///
+    /// Only one value may be out of place; use [`repair_range`](Self::repair_range) when
+    /// several were changed.
+    pub fn repair(&mut self, key_index: impl Into<KeyIdx>) -> bool {
+        let i = match self.maps.node_of(key_index.into()) {
+            Some(i) => i,
+            None => return false,
+        };
+        self.sink(i);
+        self.swim(i);
+        self.publish_min();
+
+        true
+    }
+
+    /// Restores the heap order after the values of any keys in `key_indexes` were changed in
+    /// place, returning how many of those keys are in the queue.
+    ///
+    /// Only the changed nodes and their ancestors are sifted, deepest first, so `k` changed
+    /// keys cost `O(k log² n)` at worst and usually far less.
+    ///
+    /// # Panics
+    ///
+    /// If the range is reversed or reaches past
+    /// [`next_key_index`](OrderedIndexedPriorityQueue::next_key_index); see
+    /// [`try_repair_range`](Self::try_repair_range).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn repair_range<R: RangeBounds<usize>>(&mut self, key_indexes: R) -> usize {
+        self.try_repair_range(key_indexes).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`repair_range`](Self::repair_range), returning [`IpqError::InvalidRange`]
+    /// instead of panicking on a bad range.
+    pub fn try_repair_range<R: RangeBounds<usize>>(
+        &mut self,
+        key_indexes: R,
+    ) -> Result<usize, IpqError> {
+        let range = self.try_key_range(key_indexes)?;
+        let touched = if range.len() < self.size() {
+            range
+                .filter_map(|key_index| self.maps.node_of(KeyIdx(key_index)))
+                .collect::<Vec<NodeIdx>>()
+        } else {
+            (0..self.size())
+                .map(NodeIdx)
+                .filter(|&i| range.contains(&self.maps.key_at(i).unwrap().0))
+                .collect()
+        };
+
+        self.repair_nodes(&touched);
+
+        Ok(touched.len())
+    }
+
+    /// Sifts the `touched` nodes and their ancestors, deepest first, after their values were
+    /// changed in place.
+    pub(crate) fn repair_nodes(&mut self, touched: &[NodeIdx]) {
+        let mut dirty = BTreeSet::new();
+        for &i in touched {
+            let mut node = i;
+            while dirty.insert(node) && node != NodeIdx::ROOT {
+                node = node.parent();
+            }
+        }
+        dirty.into_iter().rev().for_each(|i| self.sink(i));
+        self.publish_min();
+
+        debug_assert!(self.has_consistent_mappings());
+    }
+}
+
+#[cfg(test)]
+mod repair_tests {
+    use crate::index::{KeyIdx, NodeIdx};
+    use crate::repair::PeekMinMut;
+    use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue};
+
+    #[test]
+    fn repair_range_should_restore_the_heap_after_many_changes() {
+        let mut values = (0..64).collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let mut raw = ipq.as_mut_slice_raw();
+        for key_index in 10..30 {
+            let node = raw.node_index_of(key_index).unwrap();
+            raw[node] = 100 - key_index as i32 * 7;
+        }
+        assert_eq!(raw.try_repair_range(10..30), Ok(20));
+        drop(raw);
+
+        assert!(ipq.satisfies_heap_invariant());
+        assert_eq!(ipq.poll_min_key_index(), Some(29));
+        assert_eq!(ipq.value_of(10), Some(30));
+    }
+
+    #[test]
+    fn repair_should_follow_the_queue_order() {
+        let mut values = vec![3, 1, 2];
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        for value in ipq.iter_mut() {
+            if *value == 1 {
+                *value = 9;
+            }
+        }
+        assert!(ipq.repair(1));
+        assert!(!ipq.repair(7));
+        assert_eq!(ipq.peek_min_key_index(), Some(1));
+        assert_eq!(ipq.try_repair_range(..), Ok(3));
+        assert!(ipq.try_repair_range(2..5).is_err());
+    }
+
+    #[test]
+    fn peek_min_mut_should_sift_the_best_entry_once_dropped() {
+        let mut values = (0..32).map(|v| v * 10).collect::<Vec<i32>>();
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        for round in 0..32 {
+            let mut best = ipq.peek_min_mut().unwrap();
+            assert_eq!(best.key_index(), 31 - round);
+            *best -= 1_000;
+            drop(best);
+            assert!(ipq.satisfies_heap_invariant());
+        }
+        let version = ipq.version();
+        let best = ipq.peek_min_mut().unwrap();
+        assert_eq!(*best, -690);
+        drop(best);
+        assert_eq!(ipq.version(), version);
+
+        let best = ipq.peek_min_mut().unwrap();
+        assert_eq!(PeekMinMut::pop(best), (31, -690));
+        assert!(ipq.satisfies_heap_invariant());
+        assert_eq!(ipq.size(), 31);
+    }
+
+    #[test]
+    fn raw_values_should_map_nodes_to_key_indexes_and_back() {
+        let mut values = vec![5, 3, 8, 1];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let mut raw = ipq.as_mut_slice_raw();
+        assert_eq!(raw.key_of(NodeIdx::ROOT), Some(KeyIdx(3)));
+        assert_eq!(raw.key_of(NodeIdx(4)), None);
+
+        let key_index = raw.key_of(NodeIdx(2)).unwrap();
+        let node = raw.node_index_of(key_index).unwrap();
+        assert_eq!(node, NodeIdx(2));
+        raw[node] = 0;
+        assert!(raw.repair(key_index));
+        assert_eq!(raw.node_index_of(key_index), Some(NodeIdx::ROOT));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn forgotten_repairs_should_fail_in_debug_builds() {
+        let mut values = vec![1, 2, 3];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let mut raw = ipq.as_mut_slice_raw();
+        raw[NodeIdx::ROOT] = 9;
+    }
+}
@@ -0,0 +1,116 @@
+use crate::index::KeyIdx;
+use crate::order::Order;
+use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue, OrderedIndexedPriorityQueue};
+use std::cmp::Reverse;
+
+/// A minimum queue of [`Reverse`]d values, which ranks the greatest inner value first.
+///
+/// This is the `std::collections::BinaryHeap` idiom; prefer
+/// [`MaxIndexedPriorityQueue`] in new code, which needs no wrapping.
+pub type MaxVia<'a, T> = MinIndexedPriorityQueue<'a, Reverse<T>>;
+
+/// A maximum queue of [`Reverse`]d values, which ranks the smallest inner value first.
+pub type MinVia<'a, T> = MaxIndexedPriorityQueue<'a, Reverse<T>>;
+
+/// Methods taking and returning the inner values of a queue of [`Reverse`]d values.
+///
+/// `decrease` and `increase` compare the wrapped values, so they stay on the
+/// [`IndexedPriorityQueue`] trait where their direction is explicit.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::reverse::MaxVia;
+///
+/// let mut ipq = MaxVia::from_unwrapped(vec![3, 9, 1]);
+/// ipq.push_unwrapped(7);
+///
+/// assert_eq!(ipq.poll_min_value_unwrapped(), Some(9));
+/// assert_eq!(ipq.peek_min_value_unwrapped(), Some(7));
+/// assert_eq!(ipq.value_of_unwrapped(2), Some(1));
+/// ```
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, Reverse<T>, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Builds an owned queue wrapping the values of `iter` under the key indexes `0..n`.
+    ///
+    /// # Panics
+    ///
+    /// If a value is not comparable with itself, see
+    /// [`try_from_sorted_iter`](OrderedIndexedPriorityQueue::try_from_sorted_iter).
+    pub fn from_unwrapped<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from_sorted_iter(iter.into_iter().map(Reverse))
+    }
+
+    /// See [`IndexedPriorityQueue::insert`].
+    pub fn insert_unwrapped(&mut self, key_index: impl Into<KeyIdx>, value: T) {
+        self.insert(key_index, Reverse(value));
+    }
+
+    /// See [`IndexedPriorityQueue::push`].
+    pub fn push_unwrapped(&mut self, value: T) {
+        self.push(Reverse(value));
+    }
+
+    /// See [`IndexedPriorityQueue::update`].
+    pub fn update_unwrapped(&mut self, key_index: impl Into<KeyIdx>, value: T) -> T {
+        self.update(key_index, Reverse(value)).0
+    }
+
+    /// See [`IndexedPriorityQueue::peek_min_value`].
+    pub fn peek_min_value_unwrapped(&self) -> Option<T> {
+        self.peek_min_value().map(|Reverse(value)| value)
+    }
+
+    /// See [`IndexedPriorityQueue::poll_min_value`].
+    pub fn poll_min_value_unwrapped(&mut self) -> Option<T> {
+        self.poll_min_value().map(|Reverse(value)| value)
+    }
+
+    /// See [`IndexedPriorityQueue::delete`].
+    pub fn delete_unwrapped(&mut self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        self.delete(key_index).map(|Reverse(value)| value)
+    }
+
+    /// See [`IndexedPriorityQueue::value_of`].
+    pub fn value_of_unwrapped(&self, key_index: impl Into<KeyIdx>) -> Option<T> {
+        let KeyIdx(key_index) = key_index.into();
+        self.value_ref(key_index).map(|Reverse(value)| value.clone())
+    }
+}
+
+#[cfg(test)]
+mod reverse_tests {
+    use crate::reverse::{MaxVia, MinVia};
+    use crate::MaxIndexedPriorityQueue;
+    use std::cmp::Reverse;
+
+    #[test]
+    fn max_via_should_poll_like_a_max_queue() {
+        let values = vec![5, 2, 8, 8, 1, 6];
+        let mut via = MaxVia::from_unwrapped(values.clone());
+        let mut max = MaxIndexedPriorityQueue::from_sorted_iter(values);
+
+        while let Some(key_index) = max.peek_min_key_index() {
+            assert_eq!(via.peek_min_value_unwrapped(), max.value_of(key_index));
+            assert_eq!(via.delete_unwrapped(key_index), max.delete(key_index));
+        }
+        assert_eq!(via.poll_min_value_unwrapped(), None);
+    }
+
+    #[test]
+    fn unwrapped_methods_should_mirror_the_wrapped_ones() {
+        let mut ipq = MinVia::from_unwrapped(Vec::new());
+        ipq.insert_unwrapped(4, 'c');
+        ipq.push_unwrapped('a');
+        ipq.insert(1, Reverse('b'));
+
+        assert_eq!(ipq.update_unwrapped(4, 'z'), 'c');
+        assert_eq!(ipq.peek_min_value(), Some(Reverse('a')));
+        assert_eq!(ipq.poll_min_value_unwrapped(), Some('a'));
+        assert_eq!(ipq.poll_min_value_unwrapped(), Some('b'));
+        assert_eq!(ipq.peek_min_value_unwrapped(), Some('z'));
+    }
+}
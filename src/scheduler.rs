@@ -0,0 +1,339 @@
+use crate::limit::IpqError;
+use crate::order::Max;
+use crate::split::PayloadIndexedPriorityQueue;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct State<P>
+where
+    P: Clone + 'static,
+{
+    jobs: PayloadIndexedPriorityQueue<P, Job, Max>,
+    /// Submission each pending job's key index stands for, as a job's key index is handed out
+    /// again once a worker takes it.
+    generations: HashMap<usize, u64>,
+    next_generation: u64,
+    closed: bool,
+    panic: Option<Box<dyn Any + Send + 'static>>,
+}
+
+struct Shared<P>
+where
+    P: Clone + 'static,
+{
+    state: Mutex<State<P>>,
+    submitted: Condvar,
+}
+
+impl<P> Shared<P>
+where
+    P: Clone + PartialOrd + 'static,
+{
+    fn lock(&self) -> MutexGuard<'_, State<P>> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Runs jobs, greatest priority first, until the scope is closed and no job is left. The
+    /// first job to panic has its panic kept for [`scope`] to resume.
+    fn work(&self) {
+        let mut state = self.lock();
+        loop {
+            if let Some((key_index, _, job)) = state.jobs.poll_min() {
+                state.generations.remove(&key_index);
+                drop(state);
+                let outcome = panic::catch_unwind(AssertUnwindSafe(job));
+                state = self.lock();
+                if let (Err(payload), None) = (outcome, &state.panic) {
+                    state.panic = Some(payload);
+                }
+            } else if state.closed {
+                return;
+            } else {
+                state = self
+                    .submitted
+                    .wait(state)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        }
+    }
+}
+
+/// Names one job submitted to a [`Scope`], to [`cancel`](Scope::cancel) it even after its key
+/// index went to a later job.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct JobHandle {
+    key_index: usize,
+    generation: u64,
+}
+
+impl JobHandle {
+    pub fn key_index(&self) -> usize {
+        self.key_index
+    }
+}
+
+/// Worker pool handed to the closure of [`scope`], sharing one priority queue of jobs.
+pub struct Scope<P>
+where
+    P: Clone + 'static,
+{
+    shared: Arc<Shared<P>>,
+    workers: RefCell<Vec<JoinHandle<()>>>,
+}
+
+impl<P> Scope<P>
+where
+    P: Clone + PartialOrd + Send + 'static,
+{
+    /// Spawns `n` more worker threads, each running the pending job of greatest priority.
+    pub fn spawn_workers(&self, n: usize) {
+        let mut workers = self.workers.borrow_mut();
+        workers.extend((0..n).map(|_| {
+            let shared = Arc::clone(&self.shared);
+            thread::spawn(move || shared.work())
+        }));
+    }
+
+    /// Queues `job` with `priority`, returning a handle to cancel it with.
+    ///
+    /// # Panics
+    ///
+    /// If `priority` is not comparable with itself, see [`try_submit`](Self::try_submit).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn submit<F>(&self, priority: P, job: F) -> JobHandle
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.try_submit(priority, job).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`submit`](Self::submit), returning an error instead of panicking, which
+    /// leaves the queue and its lock untouched.
+    pub fn try_submit<F>(&self, priority: P, job: F) -> Result<JobHandle, IpqError>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut state = self.shared.lock();
+        let key_index = state.jobs.try_push(priority, Box::new(job))?;
+        let generation = state.next_generation;
+        state.next_generation += 1;
+        state.generations.insert(key_index, generation);
+        drop(state);
+        self.shared.submitted.notify_one();
+
+        Ok(JobHandle {
+            key_index,
+            generation,
+        })
+    }
+
+    /// Withdraws the job `handle` names if no worker took it yet, returning `true` if it did.
+    /// A handle whose job was taken never cancels the later job reusing its key index.
+    pub fn cancel(&self, handle: JobHandle) -> bool {
+        let mut state = self.shared.lock();
+        if state.generations.get(&handle.key_index) != Some(&handle.generation) {
+            return false;
+        }
+        state.generations.remove(&handle.key_index);
+
+        state.jobs.delete(handle.key_index).is_some()
+    }
+
+    /// Number of jobs waiting for a worker.
+    pub fn pending(&self) -> usize {
+        self.shared.lock().jobs.size()
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.workers.borrow().len()
+    }
+
+    /// Lets the workers drain the queue, running it on this thread when there are none, and
+    /// joins them, returning the first panic of a job.
+    fn finish(&self) -> thread::Result<()> {
+        self.shared.lock().closed = true;
+        self.shared.submitted.notify_all();
+        if self.worker_count() == 0 {
+            self.shared.work();
+        }
+        self.workers.borrow_mut().drain(..).for_each(|worker| {
+            let _ = worker.join();
+        });
+
+        self.shared.lock().panic.take().map_or(Ok(()), Err)
+    }
+}
+
+impl<P> Drop for Scope<P>
+where
+    P: Clone + 'static,
+{
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.closed = true;
+        }
+        self.shared.submitted.notify_all();
+        self.workers.get_mut().drain(..).for_each(|worker| {
+            let _ = worker.join();
+        });
+    }
+}
+
+/// Runs `f` with a priority thread-pool and returns once every job submitted to it ran and
+/// every worker thread was joined, so no thread outlives the call.
+///
+/// Workers always pick the pending job of greatest priority; wrap priorities in
+/// [`std::cmp::Reverse`] to run the smallest first. Jobs submitted before any worker is spawned
+/// wait for one, and with no worker at all they run on the calling thread when `f` returns. A
+/// job that panics does not stop the others; its panic is resumed here once all are joined.
+///
+/// Jobs are `'static`, as `std::thread::scope` is past the crate's minimum Rust version; move
+/// `Arc`s or channels into them to share data with the caller.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::scheduler::scope;
+/// use std::sync::{Arc, Mutex};
+///
+/// let log = Arc::new(Mutex::new(Vec::new()));
+/// scope(|s| {
+///     for (priority, name) in [(1, "compact"), (9, "serve"), (5, "index")] {
+///         let log = Arc::clone(&log);
+///         s.try_submit(priority, move || log.lock().unwrap().push(name)).unwrap();
+///     }
+///     s.spawn_workers(1);
+/// });
+///
+/// assert_eq!(*log.lock().unwrap(), ["serve", "index", "compact"]);
+/// ```
+pub fn scope<P, F, R>(f: F) -> R
+where
+    P: Clone + PartialOrd + Send + 'static,
+    F: FnOnce(&Scope<P>) -> R,
+{
+    let scope = Scope {
+        shared: Arc::new(Shared {
+            state: Mutex::new(State {
+                jobs: PayloadIndexedPriorityQueue::new(),
+                generations: HashMap::new(),
+                next_generation: 0,
+                closed: false,
+                panic: None,
+            }),
+            submitted: Condvar::new(),
+        }),
+        workers: RefCell::new(Vec::new()),
+    };
+
+    let result = f(&scope);
+    if let Err(payload) = scope.finish() {
+        panic::resume_unwind(payload);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod scheduler_tests {
+    use crate::limit::IpqError;
+    use crate::scheduler::scope;
+    use std::panic;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn scope_should_run_every_job_and_join_its_workers() {
+        let ran = Arc::new(AtomicUsize::new(0));
+        let cancelled = scope(|s| {
+            s.spawn_workers(4);
+            let keys = (0..1000u32)
+                .map(|i| {
+                    let ran = Arc::clone(&ran);
+                    s.try_submit(i % 13, move || {
+                        ran.fetch_add(1, Ordering::Relaxed);
+                    })
+                    .unwrap()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(s.worker_count(), 4);
+
+            keys.into_iter().filter(|&handle| s.cancel(handle)).count()
+        });
+
+        assert_eq!(ran.load(Ordering::Relaxed) + cancelled, 1000);
+    }
+
+    #[test]
+    fn a_stale_handle_should_not_cancel_the_job_reusing_its_key() {
+        let ran = Arc::new(AtomicUsize::new(0));
+        scope(|s| {
+            let first = s.try_submit(1, || {}).unwrap();
+            assert!(s.cancel(first));
+            let ran = Arc::clone(&ran);
+            let second = s
+                .try_submit(2, move || {
+                    ran.fetch_add(1, Ordering::Relaxed);
+                })
+                .unwrap();
+            assert_eq!(second.key_index(), first.key_index());
+
+            assert!(!s.cancel(first));
+            assert_eq!(s.pending(), 1);
+        });
+
+        assert_eq!(ran.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn jobs_should_run_by_priority_and_panics_should_reach_the_caller() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        scope(|s| {
+            for priority in [3, 8, 1, 8, 5] {
+                let log = Arc::clone(&log);
+                s.try_submit(priority, move || log.lock().unwrap().push(priority)).unwrap();
+            }
+            assert_eq!(s.pending(), 5);
+        });
+        assert_eq!(*log.lock().unwrap(), [8, 8, 5, 3, 1]);
+
+        let ran = Arc::new(AtomicUsize::new(0));
+        let outcome = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            scope(|s| {
+                s.try_submit(1, || panic!("job failed")).unwrap();
+                let ran = Arc::clone(&ran);
+                s.try_submit(0, move || {
+                    ran.fetch_add(1, Ordering::Relaxed);
+                })
+                .unwrap();
+                s.spawn_workers(1);
+            })
+        }));
+        assert!(outcome.is_err());
+        assert_eq!(ran.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn a_refused_job_should_leave_the_pool_usable() {
+        let ran = Arc::new(AtomicUsize::new(0));
+        scope(|s| {
+            assert_eq!(s.try_submit(f64::NAN, || {}).err(), Some(IpqError::Incomparable(0)));
+            let ran = Arc::clone(&ran);
+            let handle = s
+                .try_submit(1.0, move || {
+                    ran.fetch_add(1, Ordering::Relaxed);
+                })
+                .unwrap();
+            assert_eq!(handle.key_index(), 0);
+            assert_eq!(s.pending(), 1);
+        });
+
+        assert_eq!(ran.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::index::KeyIdx;
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+
+/// An indexed priority queue of bare keys, ranked by scores a user function derives from them.
+///
+/// Scores are computed once when a key is inserted and cached in the heap. When the external
+/// state they come from changes, [`invalidate`](Self::invalidate) or
+/// [`invalidate_all`](Self::invalidate_all) marks them dirty; dirty scores are recomputed
+/// lazily, right before the next query that depends on the ranking.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::scored::MinScoredIndexedPriorityQueue;
+/// use std::cell::RefCell;
+///
+/// let loads = RefCell::new(vec![30, 10, 20]);
+/// let mut least_loaded = MinScoredIndexedPriorityQueue::new(|server| loads.borrow()[server]);
+/// (0..3).for_each(|server| least_loaded.try_insert(server).unwrap());
+/// assert_eq!(least_loaded.peek_min_key_index(), Some(1));
+///
+/// loads.borrow_mut()[1] = 50;
+/// least_loaded.invalidate(1);
+/// assert_eq!(least_loaded.peek_min_key_index(), Some(2));
+/// ```
+pub struct ScoredIndexedPriorityQueue<P, F, O>
+where
+    P: Clone + 'static,
+{
+    heap: OrderedIndexedPriorityQueue<'static, P, O>,
+    score: F,
+    dirty_keys: Vec<usize>,
+    all_dirty: bool,
+}
+
+pub type MinScoredIndexedPriorityQueue<P, F> = ScoredIndexedPriorityQueue<P, F, Min>;
+
+pub type MaxScoredIndexedPriorityQueue<P, F> = ScoredIndexedPriorityQueue<P, F, Max>;
+
+impl<P, F, O> ScoredIndexedPriorityQueue<P, F, O>
+where
+    P: Clone + PartialOrd + 'static,
+    F: Fn(usize) -> P,
+    O: Order,
+{
+    pub fn new(score: F) -> Self {
+        Self {
+            heap: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+            score,
+            dirty_keys: Vec::new(),
+            all_dirty: false,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.heap.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.heap.contains(key_index)
+    }
+
+    /// Scores `key_index` and adds it to the queue.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is already in the queue or its score is not comparable with itself, see
+    /// [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&mut self, key_index: usize) {
+        if let Err(e) = self.try_insert(key_index) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Same as [`insert`](Self::insert), returning an error instead of panicking.
+    pub fn try_insert(&mut self, key_index: usize) -> Result<(), IpqError> {
+        let score = (self.score)(key_index);
+        self.heap.try_insert(key_index, score)
+    }
+
+    pub fn remove(&mut self, key_index: usize) -> bool {
+        self.heap.delete(key_index).is_some()
+    }
+
+    /// Marks the score of `key_index` as stale; unknown keys are ignored.
+    pub fn invalidate(&mut self, key_index: usize) {
+        if self.contains(key_index) && !self.all_dirty {
+            self.dirty_keys.push(key_index);
+        }
+    }
+
+    /// Marks every score as stale, e.g. after a change of the state they all derive from.
+    pub fn invalidate_all(&mut self) {
+        self.dirty_keys.clear();
+        self.all_dirty = true;
+    }
+
+    /// Returns `true` while some scores wait to be recomputed.
+    pub fn is_dirty(&self) -> bool {
+        self.all_dirty || !self.dirty_keys.is_empty()
+    }
+
+    /// Recomputes the stale scores now rather than on the next query.
+    ///
+    /// Few stale keys are re-sifted one by one; once their count times the heap height
+    /// reaches the size of the queue, every score is recomputed and heapified in `O(n)`.
+    /// Either way the recomputed scores are written in place, unchecked, like the values of
+    /// [`as_mut_slice_raw`](OrderedIndexedPriorityQueue::as_mut_slice_raw).
+    pub fn refresh(&mut self) {
+        self.dirty_keys.sort_unstable();
+        self.dirty_keys.dedup();
+        self.dirty_keys.retain(|&key_index| self.heap.contains(key_index));
+
+        if self.all_dirty || self.dirty_keys.len() * self.heap.height() >= self.heap.size() {
+            let heap = &mut self.heap;
+            heap.maps
+                .keys_by_node(heap.values.len())
+                .zip(heap.values.iter_mut())
+                .for_each(|(key_index, score)| *score = (self.score)(key_index.0));
+            heap.fix_heap_invariant();
+            heap.publish_min();
+        } else {
+            let touched = self
+                .dirty_keys
+                .iter()
+                .filter_map(|&key_index| {
+                    let i = self.heap.maps.node_of(KeyIdx(key_index))?;
+                    self.heap.values[i.0] = (self.score)(key_index);
+                    Some(i)
+                })
+                .collect::<Vec<_>>();
+            self.heap.repair_nodes(&touched);
+        }
+
+        self.dirty_keys.clear();
+        self.all_dirty = false;
+    }
+
+    /// Returns the last computed score of `key_index`, which may be stale.
+    pub fn cached_score_of(&self, key_index: usize) -> Option<&P> {
+        self.heap.value_ref(key_index)
+    }
+
+    pub fn peek_min_key_index(&mut self) -> Option<usize> {
+        self.refresh_if_dirty();
+        if self.is_empty() {
+            return None;
+        }
+        self.heap.peek_min_key_index()
+    }
+
+    /// Removes the key ranked first, returning it alongside its freshly computed score.
+    pub fn poll_min(&mut self) -> Option<(usize, P)> {
+        let key_index = self.peek_min_key_index()?;
+        let score = self.heap.delete(key_index)?;
+
+        Some((key_index, score))
+    }
+
+    fn refresh_if_dirty(&mut self) {
+        if self.is_dirty() {
+            self.refresh();
+        }
+    }
+}
+
+#[cfg(test)]
+mod scored_indexed_pq_tests {
+    use crate::limit::IpqError;
+    use crate::scored::{MaxScoredIndexedPriorityQueue, MinScoredIndexedPriorityQueue};
+    use std::cell::{Cell, RefCell};
+
+    #[test]
+    fn scores_should_only_be_recomputed_for_invalidated_keys() {
+        let calls = Cell::new(0);
+        let weights = RefCell::new(vec![5, 3, 8, 1]);
+        let mut ipq = MinScoredIndexedPriorityQueue::new(|k| {
+            calls.set(calls.get() + 1);
+            weights.borrow()[k]
+        });
+        (0..4).for_each(|k| ipq.try_insert(k).unwrap());
+        assert_eq!(calls.get(), 4);
+
+        weights.borrow_mut()[3] = 9;
+        weights.borrow_mut()[0] = 0;
+        ipq.invalidate(3);
+        assert_eq!(ipq.cached_score_of(3), Some(&1));
+        assert_eq!(ipq.poll_min(), Some((1, 3)));
+        assert_eq!(calls.get(), 5);
+        assert_eq!(ipq.cached_score_of(3), Some(&9));
+    }
+
+    #[test]
+    fn invalidate_all_should_rescore_and_reheapify_everything() {
+        let offset = Cell::new(0);
+        let mut ipq = MaxScoredIndexedPriorityQueue::new(|k| (k as i64 - offset.get()).abs());
+        (0..10).for_each(|k| ipq.try_insert(k).unwrap());
+        assert_eq!(ipq.peek_min_key_index(), Some(9));
+
+        offset.set(9);
+        ipq.invalidate_all();
+        assert!(ipq.is_dirty());
+        assert_eq!(ipq.poll_min(), Some((0, 9)));
+        assert!(!ipq.is_dirty());
+        assert!(ipq.heap.satisfies_heap_invariant());
+    }
+
+    #[test]
+    fn invalidating_removed_or_unknown_keys_should_be_harmless() {
+        let mut ipq = MinScoredIndexedPriorityQueue::new(|k| k);
+        ipq.try_insert(4).unwrap();
+        ipq.try_insert(2).unwrap();
+        ipq.invalidate(2);
+        assert!(ipq.remove(2));
+        ipq.invalidate(7);
+
+        assert_eq!(ipq.poll_min(), Some((4, 4)));
+        assert_eq!(ipq.poll_min(), None);
+    }
+
+    #[test]
+    fn keys_scored_nan_should_be_refused() {
+        let mut ipq = MinScoredIndexedPriorityQueue::new(|k| if k == 1 { f64::NAN } else { 1.0 });
+        assert_eq!(ipq.try_insert(1), Err(IpqError::Incomparable(1)));
+        assert_eq!(ipq.try_insert(0), Ok(()));
+        assert!(!ipq.contains(1));
+    }
+}
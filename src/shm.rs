@@ -0,0 +1,653 @@
+use crate::check_comparable;
+use crate::index::NodeIdx;
+use crate::ipq::IndexedBinaryHeap;
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use std::ffi::CString;
+use std::io;
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of, MaybeUninit};
+use std::ptr;
+use std::sync::atomic::{fence, AtomicU32, AtomicU64, Ordering};
+use std::thread;
+
+/// Written last when a segment is created, so that a half-initialized one is never opened.
+const MAGIC: u64 = 0x4950_515f_5348_4d31;
+
+const NO_NODE: u64 = u64::MAX;
+
+/// Start of a shared segment, followed by the values, the node to key map and the key to node
+/// map, each `capacity` long.
+#[repr(C)]
+struct Header {
+    magic: AtomicU64,
+    capacity: u64,
+    value_size: u64,
+    value_align: u64,
+    min_first: u64,
+    lock: AtomicU32,
+    sequence: AtomicU64,
+    size: AtomicU64,
+}
+
+/// Offsets of the values, the node to key map and the key to node map in a segment holding
+/// `capacity` values of type `T`, followed by the segment's length, or `None` if the segment
+/// would not fit in the address space.
+fn layout<T>(capacity: usize) -> Option<[usize; 4]> {
+    let align_up = |offset: usize, align: usize| {
+        offset.checked_add(align - 1).map(|end| end / align * align)
+    };
+    let map_len = capacity.checked_mul(size_of::<u64>())?;
+    let values = align_up(size_of::<Header>(), align_of::<T>())?;
+    let values_end = values.checked_add(capacity.checked_mul(size_of::<T>())?)?;
+    let inverse_map = align_up(values_end, align_of::<u64>())?;
+    let position_map = inverse_map.checked_add(map_len)?;
+    let len = position_map.checked_add(map_len)?;
+
+    Some([values, inverse_map, position_map, len]).filter(|_| len <= isize::MAX as usize)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn c_name(name: &str) -> io::Result<CString> {
+    CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// A value type a shared segment may hold, every bit pattern of its size being a valid value.
+///
+/// Another process, or one that crashed halfway through a write, can leave any bytes in a
+/// segment, and [`ShmIpq`] hands them out as values; that is only sound for types with no
+/// invalid representation, such as integers and floats, unlike `bool`, `char`, enums or
+/// references.
+///
+/// # Safety
+///
+/// Every bit pattern of `size_of::<Self>()` bytes, padding aside, must be a valid `Self`, and
+/// `Self` must hold no pointer, since a pointer means nothing in another process.
+pub unsafe trait ShmValue: Copy {}
+
+macro_rules! shm_values {
+    ($($t:ty),*) => {
+        $(unsafe impl ShmValue for $t {})*
+    };
+}
+
+shm_values!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: ShmValue, const N: usize> ShmValue for [T; N] {}
+
+macro_rules! shm_tuples {
+    ($(($($t:ident),+)),*) => {
+        $(unsafe impl<$($t: ShmValue),+> ShmValue for ($($t,)+) {})*
+    };
+}
+
+shm_tuples!((A), (A, B), (A, B, C), (A, B, C, D));
+
+/// Holds the segment's lock, with the sequence odd, until dropped.
+struct WriteGuard<'s> {
+    header: &'s Header,
+    sequence: u64,
+}
+
+impl<'s> WriteGuard<'s> {
+    fn lock(header: &'s Header) -> Self {
+        let mut spins = 0u32;
+        while header
+            .lock
+            .compare_exchange_weak(0, 1, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spins = spins.saturating_add(1);
+            if spins < 64 {
+                std::hint::spin_loop();
+            } else {
+                thread::yield_now();
+            }
+        }
+        let sequence = header.sequence.load(Ordering::Relaxed);
+        header.sequence.store(sequence + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        Self { header, sequence }
+    }
+}
+
+impl<'s> Drop for WriteGuard<'s> {
+    fn drop(&mut self) {
+        self.header.sequence.store(self.sequence + 2, Ordering::Release);
+        self.header.lock.store(0, Ordering::Release);
+    }
+}
+
+/// A fixed-capacity indexed priority queue living in a POSIX shared memory segment, so that
+/// processes, e.g. a producer and a consumer, share one queue without serializing it.
+///
+/// The segment holds a `#[repr(C)]` header and three arrays: the values, copied in and out as
+/// is, which is why they must be [`ShmValue`]s, and the two index maps.
+/// Keys range over `0..capacity`. [`open`](Self::open) checks that both sides agree on the
+/// value size and alignment and on the order.
+///
+/// Writers take a spin lock stored in the segment. Readers, i.e. `size`, `contains`,
+/// `value_of` and `peek_min`, never block writers: they follow a sequence lock, retrying
+/// whenever a write went through while they were reading. A process dying while it holds the
+/// lock leaves it held, so every user of the segment must then move to a new one.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::shm::MinShmIpq;
+///
+/// let name = format!("/ipq-doc-{}", std::process::id());
+/// let producer = MinShmIpq::<u64>::create(&name, 64)?;
+/// // e.g. in another process
+/// let consumer = MinShmIpq::<u64>::open(&name)?;
+///
+/// producer.insert(7, 300);
+/// producer.insert(2, 100);
+/// assert_eq!(consumer.peek_min(), Some((2, 100)));
+/// assert_eq!(consumer.poll_min(), Some((2, 100)));
+/// assert_eq!(producer.size(), 1);
+///
+/// MinShmIpq::<u64>::unlink(&name)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct ShmIpq<T, O> {
+    base: *mut u8,
+    len: usize,
+    capacity: usize,
+    values: *mut T,
+    inverse_map: *mut u64,
+    position_map: *mut u64,
+    order: PhantomData<O>,
+}
+
+pub type MinShmIpq<T> = ShmIpq<T, Min>;
+
+pub type MaxShmIpq<T> = ShmIpq<T, Max>;
+
+// The segment is only ever mutated under its lock and read under its sequence lock.
+unsafe impl<T: Send, O> Send for ShmIpq<T, O> {}
+
+unsafe impl<T: Send, O> Sync for ShmIpq<T, O> {}
+
+impl<T, O> ShmIpq<T, O>
+where
+    T: ShmValue + PartialOrd,
+    O: Order,
+{
+    /// Creates the segment `name`, e.g. `"/scheduler"`, sized for `capacity` entries, failing
+    /// if it already exists.
+    pub fn create(name: &str, capacity: usize) -> io::Result<Self> {
+        let offsets = layout::<T>(capacity).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Shared memory queue is too large")
+        })?;
+        let flags = libc::O_CREAT | libc::O_EXCL | libc::O_RDWR;
+        let (base, len) = map(name, flags, Some(offsets[3]))?;
+        let mut queue = Self::from_mapping(base, len);
+        queue.locate(capacity, offsets);
+        unsafe {
+            let header = base as *mut Header;
+            ptr::addr_of_mut!((*header).capacity).write(capacity as u64);
+            ptr::addr_of_mut!((*header).value_size).write(size_of::<T>() as u64);
+            ptr::addr_of_mut!((*header).value_align).write(align_of::<T>() as u64);
+            ptr::addr_of_mut!((*header).min_first).write(O::precedes(&0, &1) as u64);
+        }
+        (0..capacity).for_each(|key_index| queue.set_node_of(key_index, NO_NODE));
+        queue.header().magic.store(MAGIC, Ordering::Release);
+
+        Ok(queue)
+    }
+
+    /// Maps the existing segment `name`, checking that it was created for the same value type
+    /// and order.
+    pub fn open(name: &str) -> io::Result<Self> {
+        let (base, len) = map(name, libc::O_RDWR, None)?;
+        let mut queue = Self::from_mapping(base, len);
+        let header = queue.header();
+        if header.magic.load(Ordering::Acquire) != MAGIC {
+            return Err(invalid_data("Shared memory segment is not an initialized queue"));
+        }
+        if header.value_size != size_of::<T>() as u64
+            || header.value_align != align_of::<T>() as u64
+            || header.min_first != O::precedes(&0, &1) as u64
+        {
+            return Err(invalid_data("Shared memory queue holds another value type or order"));
+        }
+        let (capacity, offsets) = match usize::try_from(header.capacity)
+            .ok()
+            .and_then(|capacity| Some((capacity, layout::<T>(capacity)?)))
+        {
+            Some((capacity, offsets)) if offsets[3] <= len => (capacity, offsets),
+            _ => return Err(invalid_data("Shared memory segment is shorter than its queue")),
+        };
+        queue.locate(capacity, offsets);
+
+        Ok(queue)
+    }
+
+    /// Removes the segment `name`; processes that mapped it keep their mapping until dropped.
+    pub fn unlink(name: &str) -> io::Result<()> {
+        let name = c_name(name)?;
+        if unsafe { libc::shm_unlink(name.as_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Wraps a mapping with no entries located yet, so that dropping it unmaps the segment.
+    fn from_mapping(base: *mut u8, len: usize) -> Self {
+        Self {
+            base,
+            len,
+            capacity: 0,
+            values: ptr::null_mut(),
+            inverse_map: ptr::null_mut(),
+            position_map: ptr::null_mut(),
+            order: PhantomData,
+        }
+    }
+
+    /// Points the arrays where a segment of `capacity` entries keeps them, at the `offsets`
+    /// given by [`layout`].
+    fn locate(&mut self, capacity: usize, offsets: [usize; 4]) {
+        let [values, inverse_map, position_map, _] = offsets;
+        self.capacity = capacity;
+        self.values = self.base.wrapping_add(values) as *mut T;
+        self.inverse_map = self.base.wrapping_add(inverse_map) as *mut u64;
+        self.position_map = self.base.wrapping_add(position_map) as *mut u64;
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn size(&self) -> usize {
+        self.read(|queue| queue.len_now())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.read(|queue| queue.node_of(key_index).is_some())
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Option<T> {
+        let value = self.read(|queue| Some(queue.raw_value(queue.node_of(key_index)?)))?;
+
+        // `read` only returns what no write overlapped, i.e. a value whole.
+        Some(unsafe { value.assume_init() })
+    }
+
+    pub fn peek_min(&self) -> Option<(usize, T)> {
+        let (key_index, value) = self.read(|queue| {
+            if queue.len_now() == 0 {
+                return None;
+            }
+
+            Some((queue.key_at(0)?, queue.raw_value(0)))
+        })?;
+
+        Some((key_index, unsafe { value.assume_init() }))
+    }
+
+    /// Adds `value` under `key_index`.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is already in the queue or not below its capacity.
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&self, key_index: usize, value: T) {
+        if let Err(e) = self.try_insert(key_index, value) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Same as [`insert`](Self::insert), returning an error instead of panicking.
+    pub fn try_insert(&self, key_index: usize, value: T) -> Result<(), IpqError> {
+        if key_index >= self.capacity {
+            return Err(IpqError::KeyTooLarge {
+                key_index,
+                max_key_bound: self.capacity,
+            });
+        }
+        check_comparable(key_index, &value)?;
+
+        let _guard = WriteGuard::lock(self.header());
+        if self.node_of(key_index).is_some() {
+            return Err(IpqError::KeyInUse(key_index));
+        }
+        let node = self.len_now();
+        self.set_len(node + 1);
+        self.set_value(node, value);
+        self.set_key_at(node, key_index as u64);
+        self.set_node_of(key_index, node as u64);
+        self.heap().swim(NodeIdx(node));
+
+        Ok(())
+    }
+
+    /// Replaces `key_index`'s value, returning the previous one.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue, or `value` is not comparable with itself, see
+    /// [`try_update`](Self::try_update).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn update(&self, key_index: usize, value: T) -> T {
+        self.try_update(key_index, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`update`](Self::update), returning an error instead of panicking.
+    pub fn try_update(&self, key_index: usize, value: T) -> Result<T, IpqError> {
+        check_comparable(key_index, &value)?;
+
+        let _guard = WriteGuard::lock(self.header());
+        let node = self.node_of(key_index).ok_or(IpqError::KeyNotFound(key_index))?;
+        let previous = self.value(node);
+        self.set_value(node, value);
+        self.heap().sink(NodeIdx(node));
+        self.heap().swim(NodeIdx(node));
+
+        Ok(previous)
+    }
+
+    pub fn delete(&self, key_index: usize) -> Option<T> {
+        let _guard = WriteGuard::lock(self.header());
+        let node = self.node_of(key_index)?;
+
+        Some(self.remove_node(node).1)
+    }
+
+    pub fn poll_min(&self) -> Option<(usize, T)> {
+        let _guard = WriteGuard::lock(self.header());
+        if self.len_now() == 0 {
+            return None;
+        }
+
+        Some(self.remove_node(0))
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*(self.base as *const Header) }
+    }
+
+    /// Runs `read` until no write went through meanwhile. Every index it reads is checked
+    /// against the capacity, so a torn read only costs a retry; values are read as
+    /// [`raw_value`](Self::raw_value)s, to be assumed initialized only once `read` returns.
+    fn read<R>(&self, read: impl Fn(&Self) -> R) -> R {
+        let header = self.header();
+        loop {
+            let before = header.sequence.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+            let result = read(self);
+            fence(Ordering::Acquire);
+            if header.sequence.load(Ordering::Relaxed) == before {
+                return result;
+            }
+        }
+    }
+
+    fn len_now(&self) -> usize {
+        (self.header().size.load(Ordering::Relaxed) as usize).min(self.capacity)
+    }
+
+    fn set_len(&self, len: usize) {
+        self.header().size.store(len as u64, Ordering::Relaxed);
+    }
+
+    /// Reads a value under the lock.
+    fn value(&self, node: usize) -> T {
+        unsafe { ptr::read_volatile(self.values.add(node)) }
+    }
+
+    /// Copies a value's bytes without assuming them valid, since a writer may be halfway
+    /// through them.
+    fn raw_value(&self, node: usize) -> MaybeUninit<T> {
+        unsafe { ptr::read_volatile(self.values.add(node) as *const MaybeUninit<T>) }
+    }
+
+    fn set_value(&self, node: usize, value: T) {
+        unsafe { ptr::write_volatile(self.values.add(node), value) }
+    }
+
+    fn key_at(&self, node: usize) -> Option<usize> {
+        let key_index = unsafe { ptr::read_volatile(self.inverse_map.add(node)) };
+
+        Some(key_index as usize).filter(|&key_index| key_index < self.capacity)
+    }
+
+    fn set_key_at(&self, node: usize, key_index: u64) {
+        unsafe { ptr::write_volatile(self.inverse_map.add(node), key_index) }
+    }
+
+    fn node_of(&self, key_index: usize) -> Option<usize> {
+        if key_index >= self.capacity {
+            return None;
+        }
+        let node = unsafe { ptr::read_volatile(self.position_map.add(key_index)) };
+
+        Some(node as usize).filter(|&node| node < self.len_now())
+    }
+
+    fn set_node_of(&self, key_index: usize, node: u64) {
+        unsafe { ptr::write_volatile(self.position_map.add(key_index), node) }
+    }
+
+    /// Lends the heap to the holder of the write lock.
+    fn heap(&self) -> LockedHeap<'_, T, O> {
+        LockedHeap(self)
+    }
+
+    /// Swaps `node` with the last one and pops it, sifting whatever took its place.
+    fn remove_node(&self, node: usize) -> (usize, T) {
+        let last = self.len_now() - 1;
+        self.heap().swap(NodeIdx(node), NodeIdx(last));
+
+        let key_index = self.key_at(last).unwrap();
+        let value = self.value(last);
+        self.set_node_of(key_index, NO_NODE);
+        self.set_key_at(last, NO_NODE);
+        self.set_len(last);
+        if node < last {
+            self.heap().sink(NodeIdx(node));
+            self.heap().swim(NodeIdx(node));
+        }
+
+        (key_index, value)
+    }
+}
+
+/// The heap of a queue whose write lock is held, its nodes sifted in place in the segment.
+struct LockedHeap<'s, T, O>(&'s ShmIpq<T, O>);
+
+impl<'s, T, O> IndexedBinaryHeap for LockedHeap<'s, T, O>
+where
+    T: ShmValue + PartialOrd,
+    O: Order,
+{
+    fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    fn less(&self, i: NodeIdx, j: NodeIdx) -> bool {
+        O::precedes(&self.0.value(i.0), &self.0.value(j.0))
+    }
+
+    fn size(&self) -> usize {
+        self.0.len_now()
+    }
+
+    fn swap(&mut self, i: NodeIdx, j: NodeIdx) {
+        let queue = self.0;
+        let (value_i, value_j) = (queue.value(i.0), queue.value(j.0));
+        queue.set_value(i.0, value_j);
+        queue.set_value(j.0, value_i);
+
+        let (key_i, key_j) = (queue.key_at(i.0).unwrap(), queue.key_at(j.0).unwrap());
+        queue.set_key_at(i.0, key_j as u64);
+        queue.set_key_at(j.0, key_i as u64);
+        queue.set_node_of(key_i, j.0 as u64);
+        queue.set_node_of(key_j, i.0 as u64);
+    }
+}
+
+impl<T, O> Drop for ShmIpq<T, O> {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.base as *mut libc::c_void, self.len) };
+    }
+}
+
+/// Opens or creates the segment `name` and maps it whole, returning its base and length.
+/// Creating it first sizes it to `create_len` bytes.
+fn map(name: &str, flags: i32, create_len: Option<usize>) -> io::Result<(*mut u8, usize)> {
+    let name = c_name(name)?;
+    let fd = unsafe { libc::shm_open(name.as_ptr(), flags, 0o600u32) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let len = match create_len {
+        Some(len) if unsafe { libc::ftruncate(fd, len as libc::off_t) } == 0 => Ok(len),
+        Some(_) => Err(io::Error::last_os_error()),
+        None => {
+            let mut stat = MaybeUninit::<libc::stat>::uninit();
+            if unsafe { libc::fstat(fd, stat.as_mut_ptr()) } == 0 {
+                Ok(unsafe { stat.assume_init() }.st_size as usize)
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        }
+    };
+    let len = len.and_then(|len| match len >= size_of::<Header>() {
+        true => Ok(len),
+        false => Err(invalid_data("Shared memory segment is shorter than a queue header")),
+    });
+    let len = match len {
+        Ok(len) => len,
+        Err(e) => {
+            unsafe { libc::close(fd) };
+            if create_len.is_some() {
+                unsafe { libc::shm_unlink(name.as_ptr()) };
+            }
+            return Err(e);
+        }
+    };
+
+    let base = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            fd,
+            0,
+        )
+    };
+    unsafe { libc::close(fd) };
+    if base == libc::MAP_FAILED {
+        let e = io::Error::last_os_error();
+        if create_len.is_some() {
+            unsafe { libc::shm_unlink(name.as_ptr()) };
+        }
+        return Err(e);
+    }
+
+    Ok((base as *mut u8, len))
+}
+
+#[cfg(test)]
+mod shm_ipq_tests {
+    use crate::limit::IpqError;
+    use crate::shm::{MaxShmIpq, MinShmIpq};
+    use std::sync::Arc;
+    use std::thread;
+
+    fn segment_name(test: &str) -> String {
+        format!("/ipq-{}-{}", test, std::process::id())
+    }
+
+    #[test]
+    fn two_mappings_should_share_one_queue() {
+        let name = segment_name("share");
+        let producer = MaxShmIpq::<(u32, u32)>::create(&name, 8).unwrap();
+        let consumer = MaxShmIpq::<(u32, u32)>::open(&name).unwrap();
+        assert!(MaxShmIpq::<(u32, u32)>::create(&name, 8).is_err());
+        assert!(MinShmIpq::<(u32, u32)>::open(&name).is_err());
+        assert!(MaxShmIpq::<u8>::open(&name).is_err());
+        assert!(MinShmIpq::<u64>::create(&segment_name("huge"), 1 << 61).is_err());
+        assert!(MinShmIpq::<u64>::open(&segment_name("huge")).is_err());
+        MaxShmIpq::<(u32, u32)>::unlink(&name).unwrap();
+
+        (0..8).for_each(|key_index| producer.insert(key_index, (key_index as u32 % 3, 0)));
+        assert_eq!(consumer.capacity(), 8);
+        assert_eq!(producer.try_insert(8, (0, 0)), Err(IpqError::KeyTooLarge {
+            key_index: 8,
+            max_key_bound: 8,
+        }));
+        assert_eq!(consumer.try_insert(3, (0, 0)), Err(IpqError::KeyInUse(3)));
+
+        assert_eq!(consumer.update(4, (9, 9)), (1, 0));
+        assert_eq!(producer.peek_min(), Some((4, (9, 9))));
+        assert_eq!(consumer.delete(5), Some((2, 0)));
+        assert_eq!(producer.value_of(5), None);
+        let polled = std::iter::from_fn(|| consumer.poll_min()).map(|(_, (rank, _))| rank);
+        assert_eq!(polled.collect::<Vec<u32>>(), vec![9, 2, 1, 1, 0, 0, 0]);
+        assert!(producer.is_empty());
+    }
+
+    #[test]
+    fn values_not_comparable_with_themselves_should_be_refused() {
+        let name = segment_name("nan");
+        let queue = MinShmIpq::<f64>::create(&name, 4).unwrap();
+        MinShmIpq::<f64>::unlink(&name).unwrap();
+        queue.insert(0, 1.0);
+
+        assert_eq!(queue.try_insert(1, f64::NAN), Err(IpqError::Incomparable(1)));
+        assert_eq!(queue.try_update(0, f64::NAN), Err(IpqError::Incomparable(0)));
+        assert_eq!(queue.try_update(2, 0.5), Err(IpqError::KeyNotFound(2)));
+        assert_eq!(queue.try_update(0, 0.5), Ok(1.0));
+        assert_eq!(queue.poll_min(), Some((0, 0.5)));
+    }
+
+    #[test]
+    fn concurrent_producers_and_consumer_should_see_every_entry_once() {
+        let name = segment_name("threads");
+        let queue = Arc::new(MinShmIpq::<u64>::create(&name, 4_000).unwrap());
+        MinShmIpq::<u64>::unlink(&name).unwrap();
+
+        let producers = (0..4)
+            .map(|producer| {
+                let queue = Arc::clone(&queue);
+                thread::spawn(move || {
+                    for key_index in (producer..4_000).step_by(4) {
+                        queue.insert(key_index, key_index as u64 * 7 % 1_000);
+                        if let Some((key_index, value)) = queue.peek_min() {
+                            assert_eq!(value, key_index as u64 * 7 % 1_000);
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut polled = Vec::new();
+        while polled.len() < 4_000 {
+            if let Some((key_index, _)) = queue.poll_min() {
+                polled.push(key_index);
+            }
+        }
+        producers.into_iter().for_each(|producer| producer.join().unwrap());
+
+        polled.sort_unstable();
+        assert!(polled.into_iter().eq(0..4_000));
+        assert_eq!(queue.size(), 0);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::index::NodeIdx;
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+
+/// When a queue gives back the mapping memory it no longer needs.
+///
+/// The inverse map only ever grows on its own, so a queue that once held a million entries
+/// keeps a million-slot mapping after being drained. A shrink policy is checked after
+/// `delete`, `drain` and `clear`, and trims the inverse map down to the next power of two of
+/// the remaining size when occupancy falls low enough. The position map releases its pages on
+/// its own as keys leave them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShrinkPolicy {
+    /// Keep the peak mapping capacity forever, the historical behavior.
+    Never,
+    /// Shrink once fewer than `percent`% of the mapping slots are in use.
+    BelowOccupancy { percent: u8 },
+}
+
+impl Default for ShrinkPolicy {
+    fn default() -> Self {
+        ShrinkPolicy::Never
+    }
+}
+
+impl ShrinkPolicy {
+    /// Shrinks once occupancy drops below a quarter of the mapping capacity.
+    pub const QUARTER: ShrinkPolicy = ShrinkPolicy::BelowOccupancy { percent: 25 };
+
+    pub(crate) fn should_shrink(&self, size: usize, capacity: usize) -> bool {
+        match *self {
+            ShrinkPolicy::Never => false,
+            ShrinkPolicy::BelowOccupancy { percent } => {
+                size.next_power_of_two() < capacity
+                    && (size as u128) * 100 < (capacity as u128) * percent as u128
+            }
+        }
+    }
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Sets the [`ShrinkPolicy`] applied after removals, consuming and returning the queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::shrink::ShrinkPolicy;
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = (0..1000).collect::<Vec<u32>>();
+    /// let mut ipq = MinIndexedPriorityQueue::from(&mut values).with_shrink_policy(ShrinkPolicy::QUARTER);
+    /// assert_eq!(ipq.mapping_capacity(), 1024);
+    ///
+    /// ipq.try_drain(10..1_000).unwrap();
+    /// assert_eq!(ipq.mapping_capacity(), 16);
+    /// ```
+    pub fn with_shrink_policy(mut self, shrink_policy: ShrinkPolicy) -> Self {
+        self.set_shrink_policy(shrink_policy);
+        self
+    }
+
+    pub fn set_shrink_policy(&mut self, shrink_policy: ShrinkPolicy) {
+        self.shrink_policy = shrink_policy;
+        self.apply_shrink_policy();
+    }
+
+    pub fn shrink_policy(&self) -> ShrinkPolicy {
+        self.shrink_policy
+    }
+
+    /// Number of entries the index maps can hold before having to grow.
+    pub fn mapping_capacity(&self) -> usize {
+        self.maps.node_capacity()
+    }
+
+    /// Trims the index maps down to the next power of two of the current size, and whatever
+    /// the remaining keys do not need, e.g. the trailing position map pages, whatever the
+    /// policy.
+    pub fn shrink_to_fit(&mut self) {
+        self.maps.shrink_to_fit(self.size().next_power_of_two());
+    }
+
+    /// Removes every value, leaving the mapping capacity to the [`ShrinkPolicy`].
+    ///
+    /// Key indexes start over from `0` afterwards.
+    pub fn clear(&mut self) {
+        if let Some(observer) = self.observer.as_deref_mut() {
+            for i in (0..self.values.len()).rev() {
+                observer.on_remove(self.maps.key_at(NodeIdx(i)).unwrap().0, NodeIdx(i));
+            }
+        }
+        self.values.clear();
+        self.maps.clear();
+        self.metadata.clear();
+        self.held.clear();
+
+        self.apply_shrink_policy();
+        self.publish_min();
+    }
+
+    pub(crate) fn apply_shrink_policy(&mut self) {
+        if self
+            .shrink_policy
+            .should_shrink(self.size(), self.maps.node_capacity())
+        {
+            self.shrink_to_fit();
+        }
+    }
+}
+
+#[cfg(test)]
+mod shrink_tests {
+    use crate::shrink::ShrinkPolicy;
+    use crate::MinIndexedPriorityQueue;
+
+    #[test]
+    fn never_policy_should_keep_the_peak_capacity() {
+        let mut values = (0..100).collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        ipq.drain(..99);
+        ipq.clear();
+
+        assert!(ipq.is_empty());
+        assert_eq!(ipq.mapping_capacity(), 128);
+    }
+
+    #[test]
+    fn quarter_policy_should_shrink_after_polling_most_values() {
+        let mut values = (0..64).rev().collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values).with_shrink_policy(ShrinkPolicy::QUARTER);
+
+        (0..48).for_each(|i| assert_eq!(ipq.poll_min_value(), Some(i)));
+        assert_eq!(ipq.mapping_capacity(), 64);
+
+        ipq.poll_min_value();
+        assert_eq!(ipq.size(), 15);
+        assert_eq!(ipq.mapping_capacity(), 16);
+        assert!(ipq.has_consistent_mappings());
+        (49..64).for_each(|i| assert_eq!(ipq.poll_min_value(), Some(i)));
+    }
+
+    #[test]
+    fn clear_should_shrink_to_a_single_slot_and_allow_reuse() {
+        let mut values = (0..20).collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values).with_shrink_policy(ShrinkPolicy::QUARTER);
+
+        ipq.clear();
+        assert_eq!(ipq.mapping_capacity(), 1);
+        assert_eq!(ipq.peek_min_value(), None);
+
+        ipq.push(3);
+        ipq.push(1);
+        assert_eq!(ipq.peek_min_value(), Some(1));
+    }
+
+    #[test]
+    fn shrink_to_fit_should_ignore_the_policy() {
+        let mut values = (0..9).collect::<Vec<i32>>();
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        ipq.drain(0..=5);
+
+        ipq.shrink_to_fit();
+        assert_eq!(ipq.mapping_capacity(), 4);
+        assert_eq!(ipq.shrink_policy(), ShrinkPolicy::Never);
+    }
+}
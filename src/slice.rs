@@ -0,0 +1,319 @@
+use crate::{check_all_comparable, check_comparable};
+use crate::index::NodeIdx;
+use crate::ipq::IndexedBinaryHeap;
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use std::marker::PhantomData;
+
+const NO_NODE: usize = usize::MAX;
+
+/// Reusable buffers for the index maps of a [`SliceIpq`].
+///
+/// Building a queue only clears and refills them, so once they have grown to the largest
+/// slice seen, building queues over slices of that size or smaller never allocates.
+#[derive(Clone, Debug, Default)]
+pub struct MappingScratch {
+    position_map: Vec<usize>,
+    inverse_map: Vec<usize>,
+}
+
+impl MappingScratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates both maps up front for slices of up to `capacity` values.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            position_map: Vec::with_capacity(capacity),
+            inverse_map: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Largest slice the buffers can map without growing.
+    pub fn capacity(&self) -> usize {
+        self.position_map.capacity().min(self.inverse_map.capacity())
+    }
+}
+
+/// An indexed priority queue over a caller's slice, keyed by `0..values.len()`, whose index
+/// maps live in a caller's [`MappingScratch`].
+///
+/// The slice is heapified in place and entries are only ever moved within it, so the queue
+/// never copies the values nor allocates once the scratch is warm: it suits per-frame work
+/// such as pathfinding, which starts every node at some bound, lowers them with
+/// [`try_decrease`](Self::try_decrease) and polls them. Polled and deleted values stay at the
+/// end of the slice, past [`size`](Self::size), and no key can be added back.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::slice::{MappingScratch, MinSliceIpq};
+///
+/// let mut scratch = MappingScratch::with_capacity(4);
+/// let mut distances = [u32::MAX; 4];
+/// for _frame in 0..2 {
+///     distances.fill(u32::MAX);
+///     let mut frontier = MinSliceIpq::from_slice_in(&mut distances, &mut scratch);
+///     frontier.try_decrease(2, 0).unwrap();
+///     frontier.try_decrease(0, 5).unwrap();
+///     assert_eq!(frontier.poll_min(), Some((2, 0)));
+///     assert_eq!(frontier.poll_min(), Some((0, 5)));
+/// }
+/// assert_eq!(scratch.capacity(), 4);
+/// ```
+pub struct SliceIpq<'a, T, O> {
+    values: &'a mut [T],
+    scratch: &'a mut MappingScratch,
+    size: usize,
+    order: PhantomData<O>,
+}
+
+pub type MinSliceIpq<'a, T> = SliceIpq<'a, T, Min>;
+
+pub type MaxSliceIpq<'a, T> = SliceIpq<'a, T, Max>;
+
+impl<'a, T, O> SliceIpq<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Builds a queue holding `values[i]` under the key index `i`, heapifying the slice in
+    /// place in `O(n)` and mapping keys through `scratch`, which only grows if it is smaller
+    /// than the slice.
+    ///
+    /// # Panics
+    ///
+    /// If a value is not comparable with itself, see
+    /// [`try_from_slice_in`](Self::try_from_slice_in).
+    pub fn from_slice_in(values: &'a mut [T], scratch: &'a mut MappingScratch) -> Self {
+        Self::try_from_slice_in(values, scratch).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`from_slice_in`](Self::from_slice_in), returning an error instead of
+    /// panicking; neither the slice nor `scratch` is touched then.
+    pub fn try_from_slice_in(
+        values: &'a mut [T],
+        scratch: &'a mut MappingScratch,
+    ) -> Result<Self, IpqError> {
+        check_all_comparable(values)?;
+        let size = values.len();
+        scratch.position_map.clear();
+        scratch.position_map.extend(0..size);
+        scratch.inverse_map.clear();
+        scratch.inverse_map.extend(0..size);
+
+        let mut ipq = Self {
+            values,
+            scratch,
+            size,
+            order: PhantomData,
+        };
+        (0..size / 2).rev().for_each(|i| ipq.sink(NodeIdx(i)));
+
+        Ok(ipq)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.scratch
+            .position_map
+            .get(key_index)
+            .map_or(false, |&i| i != NO_NODE)
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Option<&T> {
+        if !self.contains(key_index) {
+            return None;
+        }
+
+        Some(&self.values[self.scratch.position_map[key_index]])
+    }
+
+    pub fn peek_min_key_index(&self) -> Option<usize> {
+        (!self.is_empty()).then(|| self.scratch.inverse_map[0])
+    }
+
+    pub fn peek_min_value(&self) -> Option<&T> {
+        self.values[..self.size].first()
+    }
+
+    pub fn poll_min(&mut self) -> Option<(usize, T)> {
+        let key_index = self.peek_min_key_index()?;
+
+        Some((key_index, self.delete(key_index)?))
+    }
+
+    /// Replaces `key_index`'s value, returning the previous one.
+    pub fn try_update(&mut self, key_index: usize, value: T) -> Result<T, IpqError> {
+        let i = self.node_index(key_index)?;
+        check_comparable(key_index, &value)?;
+        let old_value = std::mem::replace(&mut self.values[i.0], value);
+
+        self.sink(i);
+        self.swim(i);
+
+        Ok(old_value)
+    }
+
+    /// Lowers `key_index`'s value to `value` if it is smaller.
+    pub fn try_decrease(&mut self, key_index: usize, value: T) -> Result<(), IpqError> {
+        let i = self.node_index(key_index)?;
+        if value < self.values[i.0] {
+            self.try_update(key_index, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Raises `key_index`'s value to `value` if it is greater.
+    pub fn try_increase(&mut self, key_index: usize, value: T) -> Result<(), IpqError> {
+        let i = self.node_index(key_index)?;
+        if self.values[i.0] < value {
+            self.try_update(key_index, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Takes `key_index` out of the queue, leaving its value right past the remaining
+    /// entries of the slice, and returns a clone of it.
+    pub fn delete(&mut self, key_index: usize) -> Option<T> {
+        if !self.contains(key_index) {
+            return None;
+        }
+
+        let i = NodeIdx(self.scratch.position_map[key_index]);
+        let last = NodeIdx(self.size - 1);
+        self.swap(i, last);
+        self.size -= 1;
+        self.scratch.position_map[key_index] = NO_NODE;
+
+        if i < last {
+            self.sink(i);
+            self.swim(i);
+        }
+
+        Some(self.values[last.0].clone())
+    }
+
+    fn node_index(&self, key_index: usize) -> Result<NodeIdx, IpqError> {
+        if !self.contains(key_index) {
+            return Err(IpqError::KeyNotFound(key_index));
+        }
+
+        Ok(NodeIdx(self.scratch.position_map[key_index]))
+    }
+}
+
+impl<'a, T, O> IndexedBinaryHeap for SliceIpq<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    fn less(&self, i: NodeIdx, j: NodeIdx) -> bool {
+        O::precedes(&self.values[i.0], &self.values[j.0])
+    }
+
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn swap(&mut self, i: NodeIdx, j: NodeIdx) {
+        self.values.swap(i.0, j.0);
+        self.scratch.inverse_map.swap(i.0, j.0);
+        self.scratch.position_map[self.scratch.inverse_map[i.0]] = i.0;
+        self.scratch.position_map[self.scratch.inverse_map[j.0]] = j.0;
+    }
+}
+
+#[cfg(test)]
+mod slice_ipq_tests {
+    use crate::limit::IpqError;
+    use crate::slice::{MappingScratch, MaxSliceIpq, MinSliceIpq};
+
+    #[test]
+    fn slice_ipq_should_keep_polled_values_at_the_end_of_the_slice() {
+        let mut scratch = MappingScratch::new();
+        let mut values = [4, 9, 1, 7, 3];
+        let mut ipq = MaxSliceIpq::from_slice_in(&mut values, &mut scratch);
+
+        assert_eq!(ipq.peek_min_key_index(), Some(1));
+        assert_eq!(ipq.try_increase(4, 8), Ok(()));
+        assert_eq!(ipq.try_update(1, 0), Ok(9));
+        assert_eq!(ipq.delete(3), Some(7));
+        assert_eq!(ipq.try_decrease(3, 0), Err(IpqError::KeyNotFound(3)));
+        assert_eq!(ipq.poll_min(), Some((4, 8)));
+        assert_eq!(ipq.value_of(0), Some(&4));
+        assert_eq!(ipq.size(), 3);
+
+        values[3..].sort_unstable();
+        assert_eq!(values[3..], [7, 8]);
+        assert!(scratch.capacity() >= 5);
+    }
+
+    #[test]
+    fn slice_ipq_should_poll_in_order_and_refuse_deleted_keys() {
+        let mut scratch = MappingScratch::new();
+        let mut values = [5, 3, 8, 1, 9, 2];
+        let mut ipq = MinSliceIpq::try_from_slice_in(&mut values, &mut scratch).unwrap();
+
+        assert_eq!(ipq.delete(3), Some(1));
+        assert_eq!(ipq.delete(3), None);
+        assert_eq!(ipq.try_update(3, 0), Err(IpqError::KeyNotFound(3)));
+        assert_eq!(ipq.try_decrease(4, 0), Ok(()));
+        assert_eq!(ipq.try_increase(4, 9), Ok(()));
+        assert_eq!(ipq.try_increase(5, 1), Ok(()));
+
+        let polled = std::iter::from_fn(|| ipq.poll_min()).collect::<Vec<(usize, i32)>>();
+        assert_eq!(polled, vec![(5, 2), (1, 3), (0, 5), (2, 8), (4, 9)]);
+        assert!(ipq.is_empty());
+        assert_eq!(ipq.peek_min_key_index(), None);
+        assert!((0..6).all(|k| !ipq.contains(k)));
+    }
+
+    #[test]
+    fn scratch_should_be_reused_across_slices_of_any_size() {
+        let mut scratch = MappingScratch::with_capacity(8);
+        let mut large = [7, 6, 5, 4, 3, 2, 1, 0];
+        let mut small = [2, 0, 1];
+
+        let mut ipq = MaxSliceIpq::try_from_slice_in(&mut large, &mut scratch).unwrap();
+        assert_eq!(ipq.poll_min(), Some((0, 7)));
+        assert_eq!(ipq.delete(7), Some(0));
+
+        let mut ipq = MinSliceIpq::try_from_slice_in(&mut small, &mut scratch).unwrap();
+        assert!(!ipq.contains(5));
+        assert_eq!(ipq.try_update(5, 0), Err(IpqError::KeyNotFound(5)));
+        assert_eq!(ipq.poll_min(), Some((1, 0)));
+        assert_eq!(ipq.value_of(2), Some(&1));
+
+        large.sort_unstable();
+        let ipq = MinSliceIpq::try_from_slice_in(&mut large, &mut scratch).unwrap();
+        assert_eq!(ipq.size(), 8);
+        assert!((0..8).all(|k| ipq.value_of(k) == Some(&(k as i32))));
+        assert_eq!(scratch.capacity(), 8);
+    }
+
+    #[test]
+    fn try_from_slice_in_should_refuse_incomparable_values() {
+        let mut scratch = MappingScratch::new();
+        let mut values = [2.0, f64::NAN, 1.0];
+
+        let ipq = MinSliceIpq::try_from_slice_in(&mut values, &mut scratch);
+        assert_eq!(ipq.err(), Some(IpqError::Incomparable(1)));
+        assert_eq!(values[0], 2.0);
+        assert_eq!(scratch.capacity(), 0);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::index::NodeIdx;
+use crate::order::{Min, Order};
+use crate::shrink::ShrinkPolicy;
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Invariant found broken by [`check_invariants`](OrderedIndexedPriorityQueue::check_invariants).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// The key to node and node to key maps disagree, or a padding slot is still mapped.
+    Mappings,
+    /// A node ranks before its parent.
+    HeapOrder { node_index: NodeIdx },
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvariantViolation::Mappings => write!(f, "Position and inverse maps disagree"),
+            InvariantViolation::HeapOrder { node_index } => {
+                write!(f, "Heap order broken; received: {} ranked before its parent", node_index)
+            }
+        }
+    }
+}
+
+impl Error for InvariantViolation {}
+
+/// Memory a queue holds on to, in slots rather than bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Footprint {
+    pub values_capacity: usize,
+    pub mapping_capacity: usize,
+    pub position_pages: usize,
+    pub position_directory_len: usize,
+}
+
+impl Footprint {
+    /// Returns `true` if the footprint is no larger than a queue whose key indexes all stayed
+    /// below `key_space` ever needs, i.e. nothing grew past what the keys account for.
+    pub fn is_bounded_by(&self, key_space: usize) -> bool {
+        let pages = (key_space + 1023) / 1024;
+
+        self.values_capacity <= 2 * key_space.max(4)
+            && self.mapping_capacity <= key_space.next_power_of_two()
+            && self.position_pages <= pages
+            && self.position_directory_len <= pages
+    }
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Checks that both index maps agree with each other and that no node ranks before its
+    /// parent, in `O(n)`.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        if !self.has_consistent_mappings() {
+            return Err(InvariantViolation::Mappings);
+        }
+
+        match (1..self.values.len())
+            .map(NodeIdx)
+            .find(|&i| O::precedes(&self.values[i.0], &self.values[i.parent().0]))
+        {
+            Some(node_index) => Err(InvariantViolation::HeapOrder { node_index }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn footprint(&self) -> Footprint {
+        Footprint {
+            values_capacity: self.values.capacity(),
+            mapping_capacity: self.mapping_capacity(),
+            position_pages: self.maps.allocated_pages(),
+            position_directory_len: self.maps.directory_len(),
+        }
+    }
+}
+
+/// Parameters of a [`soak`] run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoakConfig {
+    pub operations: u64,
+    /// Key indexes are drawn from `0..key_space`.
+    pub key_space: usize,
+    /// Number of operations between two invariant and footprint checks.
+    pub sample_every: u64,
+    pub seed: u64,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            operations: 1_000_000,
+            key_space: 4096,
+            sample_every: 100_000,
+            seed: 1,
+        }
+    }
+}
+
+/// State of a [`soak`] run at one of its checks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoakSample {
+    pub operation: u64,
+    pub size: usize,
+    pub footprint: Footprint,
+}
+
+/// First problem a [`soak`] run ran into, with the number of operations applied by then.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoakError {
+    Invariant { operation: u64, violation: InvariantViolation },
+    /// The queue's content differs from the model's, first at `key_index`.
+    Divergence { operation: u64, key_index: usize },
+    /// The queue holds more memory than its keys account for.
+    Growth { operation: u64, footprint: Footprint },
+}
+
+impl Display for SoakError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SoakError::Invariant {
+                operation,
+                violation,
+            } => write!(f, "{} after {} operations", violation, operation),
+            SoakError::Divergence {
+                operation,
+                key_index,
+            } => write!(
+                f,
+                "Queue diverged from the model after {} operations; received: key {}",
+                operation, key_index
+            ),
+            SoakError::Growth {
+                operation,
+                footprint,
+            } => write!(
+                f,
+                "Queue outgrew its key space after {} operations; received: {:?}",
+                operation, footprint
+            ),
+        }
+    }
+}
+
+impl Error for SoakError {}
+
+/// Runs `config.operations` random inserts, updates, decreases, increases, deletes and polls
+/// on an owned minimum queue that shrinks its mapping at a quarter occupancy, next to a
+/// plain vector model.
+///
+/// Polled values are checked against the model as they come. Every `config.sample_every`
+/// operations and at the end, the invariants, the whole content and the [`Footprint`] are
+/// checked too, and `on_sample` is told how the queue looks, e.g. to log its growth. Long runs
+/// catch slow corruption and maps that keep growing, which short unit tests cannot.
+///
+/// # Panics
+///
+/// If `config.key_space` is `0`.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::soak::{soak, SoakConfig};
+///
+/// let config = SoakConfig { operations: 20_000, sample_every: 5_000, ..SoakConfig::default() };
+/// let mut samples = 0;
+///
+/// assert_eq!(soak(&config, |_| samples += 1), Ok(()));
+/// assert_eq!(samples, 4);
+/// ```
+pub fn soak<F>(config: &SoakConfig, mut on_sample: F) -> Result<(), SoakError>
+where
+    F: FnMut(&SoakSample),
+{
+    if config.key_space == 0 {
+        panic!("Soak key space must be positive; received: 0");
+    }
+
+    let mut ipq: OrderedIndexedPriorityQueue<'static, u32, Min> =
+        OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new()))
+            .with_shrink_policy(ShrinkPolicy::QUARTER);
+    let mut model: Vec<Option<u32>> = vec![None; config.key_space];
+    let mut state = config.seed | 1;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for operation in 1..=config.operations {
+        let draw = random();
+        let key_index = (draw >> 32) as usize % config.key_space;
+        let value = (draw >> 8) as u32 % 1_000_000;
+        match draw % 20 {
+            0..=7 if model[key_index].is_some() => {
+                ipq.update(key_index, value);
+                model[key_index] = Some(value);
+            }
+            0..=7 => {
+                ipq.insert(key_index, value);
+                model[key_index] = Some(value);
+            }
+            8..=10 => {
+                if ipq.delete(key_index) != model[key_index].take() {
+                    return Err(SoakError::Divergence {
+                        operation,
+                        key_index,
+                    });
+                }
+            }
+            11..=13 => {
+                let polled_key_index = ipq.peek_min_key_index();
+                let polled = ipq.poll_min_value();
+                if let Some(polled_key_index) = polled_key_index {
+                    if polled != model[polled_key_index].take() {
+                        return Err(SoakError::Divergence {
+                            operation,
+                            key_index: polled_key_index,
+                        });
+                    }
+                }
+            }
+            _ => {
+                if let Some(current) = model[key_index] {
+                    if draw % 2 == 0 {
+                        ipq.decrease(key_index, value);
+                        model[key_index] = Some(current.min(value));
+                    } else {
+                        ipq.increase(key_index, value);
+                        model[key_index] = Some(current.max(value));
+                    }
+                }
+            }
+        }
+
+        if operation % config.sample_every.max(1) == 0 || operation == config.operations {
+            ipq.check_invariants()
+                .map_err(|violation| SoakError::Invariant {
+                    operation,
+                    violation,
+                })?;
+            if let Some(key_index) =
+                (0..config.key_space).find(|&k| ipq.value_ref(k) != model[k].as_ref())
+            {
+                return Err(SoakError::Divergence {
+                    operation,
+                    key_index,
+                });
+            }
+
+            let footprint = ipq.footprint();
+            if !footprint.is_bounded_by(config.key_space) {
+                return Err(SoakError::Growth {
+                    operation,
+                    footprint,
+                });
+            }
+            on_sample(&SoakSample {
+                operation,
+                size: ipq.size(),
+                footprint,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod soak_tests {
+    use crate::index::NodeIdx;
+    use crate::soak::{soak, Footprint, InvariantViolation, SoakConfig};
+    use crate::MinIndexedPriorityQueue;
+
+    #[test]
+    fn short_soak_should_stay_consistent_and_bounded() {
+        let config = SoakConfig {
+            operations: 200_000,
+            key_space: 3000,
+            sample_every: 10_000,
+            seed: 7,
+        };
+        let mut peak = Footprint::default();
+
+        assert_eq!(
+            soak(&config, |sample| peak.mapping_capacity =
+                peak.mapping_capacity.max(sample.footprint.mapping_capacity)),
+            Ok(())
+        );
+        assert!(peak.mapping_capacity > 0);
+    }
+
+    #[test]
+    fn check_invariants_should_catch_values_edited_out_of_order() {
+        let mut values = vec![1, 2, 3];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        assert_eq!(ipq.check_invariants(), Ok(()));
+
+        ipq.iter_mut().for_each(|value| *value = 10 - *value);
+        assert_eq!(
+            ipq.check_invariants(),
+            Err(InvariantViolation::HeapOrder { node_index: NodeIdx(1) })
+        );
+    }
+}
@@ -0,0 +1,344 @@
+use crate::cursor::PriorityCursor;
+use crate::index::NodeIdx;
+use crate::ipq::IndexedBinaryHeap;
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::iter::FusedIterator;
+
+/// Iterator over the `(key_index, &value)` pairs in the order they would be polled, created by
+/// [`OrderedIndexedPriorityQueue::sorted_iter`].
+///
+/// The queue is left untouched: a side cursor heap of node indexes holds the frontier of the
+/// nodes yielded so far, so the first `k` items cost `O(k log k)` comparisons and at most
+/// `k + 1` indexes of memory, whatever the size of the queue.
+pub struct SortedIter<'q, 'a, T, O>
+where
+    T: Clone,
+{
+    queue: &'q OrderedIndexedPriorityQueue<'a, T, O>,
+    cursor: PriorityCursor,
+    remaining: usize,
+}
+
+impl<'q, 'a, T, O> Iterator for SortedIter<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    type Item = (usize, &'q T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let queue = self.queue;
+        let i = self.cursor.next(queue.size(), |i, j| queue.less(i, j))?;
+        self.remaining -= 1;
+
+        Some((queue.maps.key_at(i).unwrap().0, &queue.values[i.0]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'q, 'a, T, O> ExactSizeIterator for SortedIter<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+}
+
+/// Draining iterator over the `(key_index, value)` pairs in polling order, created by
+/// [`OrderedIndexedPriorityQueue::drain_sorted`].
+///
+/// Every `next` polls the entry ranked first in `O(log n)`, held entries included. Dropping the
+/// iterator clears whatever it did not yield.
+pub struct DrainSorted<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    queue: &'q mut OrderedIndexedPriorityQueue<'a, T, O>,
+}
+
+impl<'q, 'a, T, O> Iterator for DrainSorted<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.queue.swap_remove(NodeIdx::ROOT)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.queue.size(), Some(self.queue.size()))
+    }
+}
+
+impl<'q, 'a, T, O> ExactSizeIterator for DrainSorted<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+}
+
+impl<'q, 'a, T, O> FusedIterator for DrainSorted<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+}
+
+impl<'q, 'a, T, O> Drop for DrainSorted<'q, 'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn drop(&mut self) {
+        if !self.queue.is_empty() {
+            self.queue.clear();
+        }
+    }
+}
+
+/// Error returned by a [`SortedCursor`] whose queue was mutated since the cursor was created.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Invalidated;
+
+impl Display for Invalidated {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Queue was mutated since the cursor was created")
+    }
+}
+
+impl Error for Invalidated {}
+
+/// Detached counterpart of [`SortedIter`], created by
+/// [`OrderedIndexedPriorityQueue::sorted_cursor`], that does not borrow the queue between
+/// steps, e.g. when the queue sits behind a `RefCell` or a lock shared with other handles.
+///
+/// The cursor remembers the queue's [`version`](OrderedIndexedPriorityQueue::version): once
+/// any mutation went through, every further step fails with [`Invalidated`] instead of
+/// walking node indexes that no longer mean the same entries.
+pub struct SortedCursor {
+    cursor: PriorityCursor,
+    version: u64,
+    remaining: usize,
+}
+
+impl SortedCursor {
+    /// Returns the next `(key_index, &value)` pair in polling order, `queue` being the queue
+    /// the cursor was created from.
+    pub fn next<'q, T, O>(
+        &mut self,
+        queue: &'q OrderedIndexedPriorityQueue<'_, T, O>,
+    ) -> Result<Option<(usize, &'q T)>, Invalidated>
+    where
+        T: Clone + PartialOrd,
+        O: Order,
+    {
+        if queue.version != self.version {
+            return Err(Invalidated);
+        }
+        let i = match self.cursor.next(queue.size(), |i, j| queue.less(i, j)) {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+        self.remaining -= 1;
+
+        Ok(Some((queue.maps.key_at(i).unwrap().0, &queue.values[i.0])))
+    }
+
+    /// Number of entries left to yield, as long as the queue is not mutated.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Iterates over every entry, held ones included, from the one ranked first on, without
+    /// cloning nor modifying the queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut values = vec![40, 10, 30, 20, 50];
+    /// let ipq = MinIndexedPriorityQueue::from(&mut values);
+    ///
+    /// let next_three = ipq.sorted_iter().take(3).collect::<Vec<(usize, &i32)>>();
+    /// assert_eq!(next_three, vec![(1, &10), (3, &20), (2, &30)]);
+    /// ```
+    pub fn sorted_iter(&self) -> SortedIter<'_, 'a, T, O> {
+        SortedIter {
+            queue: self,
+            cursor: PriorityCursor::new(self.size()),
+            remaining: self.size(),
+        }
+    }
+
+    /// Consumes the queue, returning its values in polling order, i.e. ascending for a
+    /// minimum queue and descending for a maximum one, in `O(n log n)`.
+    ///
+    /// Values are moved out rather than cloned, which leaves a borrowed vector empty. Values
+    /// comparing neither way keep their heap order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::OwnedMaxIpq;
+    ///
+    /// let ipq = vec![40, 10, 30, 20].into_iter().collect::<OwnedMaxIpq<i32>>();
+    ///
+    /// assert_eq!(ipq.into_sorted_vec(), vec![40, 30, 20, 10]);
+    /// ```
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut values = std::mem::take(&mut *self.values);
+        values.sort_by(|a, b| {
+            if O::precedes(a, b) {
+                Ordering::Less
+            } else if O::precedes(b, a) {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        });
+
+        values
+    }
+
+    /// Polls the `(key_index, value)` pairs lazily, one at each `next`, from the one ranked
+    /// first on, held ones included. The queue is left empty once the iterator is dropped,
+    /// even if it was not run to the end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let mut ipq = vec![40, 10, 30, 20].into_iter().collect::<OwnedMinIpq<i32>>();
+    ///
+    /// let first_two = ipq.drain_sorted().take(2).collect::<Vec<(usize, i32)>>();
+    /// assert_eq!(first_two, vec![(1, 10), (3, 20)]);
+    /// assert!(ipq.is_empty());
+    /// ```
+    pub fn drain_sorted(&mut self) -> DrainSorted<'_, 'a, T, O> {
+        DrainSorted { queue: self }
+    }
+
+    /// Starts a [`SortedCursor`] over every entry, held ones included, in polling order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::sorted::Invalidated;
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    /// use std::cell::RefCell;
+    ///
+    /// let mut values = vec![30, 10, 20];
+    /// let shared = RefCell::new(MinIndexedPriorityQueue::from(&mut values));
+    ///
+    /// let mut cursor = shared.borrow().sorted_cursor();
+    /// assert_eq!(cursor.next(&shared.borrow()), Ok(Some((1, &10))));
+    ///
+    /// shared.borrow_mut().try_push(0).unwrap();
+    /// assert_eq!(cursor.next(&shared.borrow()), Err(Invalidated));
+    /// ```
+    pub fn sorted_cursor(&self) -> SortedCursor {
+        SortedCursor {
+            cursor: PriorityCursor::new(self.size()),
+            version: self.version,
+            remaining: self.size(),
+        }
+    }
+
+    /// Counter bumped by every mutation of the queue, including lending its values through
+    /// [`iter_mut`](Self::iter_mut); two reads of equal versions saw the same entries.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub(crate) fn bump_version(&mut self) {
+        self.version = self.version.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod sorted_tests {
+    use crate::sorted::Invalidated;
+    use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue};
+
+    #[test]
+    fn sorted_iter_should_match_polling_order() {
+        let mut values = vec![5, 3, 9, 1, 3, 7, 0, 8, 3];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let sorted = ipq.sorted_iter().map(|(_, v)| *v).collect::<Vec<i32>>();
+        assert_eq!(ipq.sorted_iter().len(), 9);
+        let mut polled = Vec::new();
+        while let Some(v) = ipq.poll_min_value() {
+            polled.push(v);
+        }
+        assert_eq!(sorted, polled);
+    }
+
+    #[test]
+    fn sorted_cursor_should_fail_once_the_queue_is_mutated() {
+        let mut values = vec![4, 1, 3, 2];
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        let mut cursor = ipq.sorted_cursor();
+        assert_eq!(cursor.next(&ipq), Ok(Some((0, &4))));
+        assert_eq!(cursor.next(&ipq), Ok(Some((2, &3))));
+        assert_eq!(cursor.remaining(), 2);
+
+        let version = ipq.version();
+        ipq.update(3, 9);
+        assert!(ipq.version() > version);
+        assert_eq!(cursor.next(&ipq), Err(Invalidated));
+        assert_eq!(cursor.next(&ipq), Err(Invalidated));
+
+        let mut cursor = ipq.sorted_cursor();
+        ipq.iter_mut().for_each(|_| {});
+        assert_eq!(cursor.next(&ipq), Err(Invalidated));
+    }
+
+    #[test]
+    fn into_sorted_vec_and_drain_sorted_should_match_polling_order() {
+        let values = vec![5, 3, 9, 1, 3, 7, 0, 8, 3];
+        let mut polled = values.clone();
+        polled.sort_unstable();
+
+        let mut borrowed = values.clone();
+        assert_eq!(MinIndexedPriorityQueue::from(&mut borrowed).into_sorted_vec(), polled);
+        assert!(borrowed.is_empty());
+
+        let mut ipq = MaxIndexedPriorityQueue::from(&mut borrowed);
+        ipq.append(&mut values.clone());
+        let mut drain = ipq.drain_sorted();
+        assert_eq!(drain.len(), 9);
+        assert_eq!(drain.next(), Some((2, 9)));
+        let rest = drain.map(|(_, v)| v).collect::<Vec<i32>>();
+        assert!(rest.iter().rev().eq(polled.iter().take(8)));
+        assert_eq!(ipq.drain_sorted().next(), None);
+    }
+
+    #[test]
+    fn sorted_iter_should_follow_the_queue_order() {
+        let mut values = vec![2, 6, 4];
+        let ipq = MaxIndexedPriorityQueue::from(&mut values);
+
+        let keys = ipq.sorted_iter().map(|(k, _)| k).collect::<Vec<usize>>();
+        assert_eq!(keys, vec![1, 2, 0]);
+        assert!(ipq.satisfies_heap_invariant());
+    }
+}
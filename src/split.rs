@@ -0,0 +1,320 @@
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::collections::HashMap;
+
+/// An indexed priority queue keeping priorities and payloads apart.
+///
+/// Priorities live in the heap itself, a dense array that every sift walks through, while
+/// payloads sit in a separate map by key index that is only read when an entry is handed
+/// back. Heap maintenance therefore never drags payload bytes through the cache, which pays
+/// off when `V` is large compared to the priority `P`.
+///
+/// Keys are stable: deleting an entry leaves every other key untouched, and its key is handed
+/// out again by a later [`push`](Self::push). Callers that already number
+/// their entries, e.g. graph nodes, can [`insert`](Self::insert) under their own keys instead.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::split::MinPayloadIndexedPriorityQueue;
+///
+/// // Dijkstra's frontier: a tentative distance and the predecessor of each reached node.
+/// let mut frontier = MinPayloadIndexedPriorityQueue::new();
+/// frontier.try_insert(7, 12, Some(0)).unwrap();
+/// frontier.try_insert(3, 15, Some(0)).unwrap();
+///
+/// assert_eq!(frontier.set_priority(3, 4), Some(15));
+/// *frontier.payload_of_mut(3).unwrap() = Some(7);
+/// assert_eq!(frontier.peek_min(), Some((3, &4, &Some(7))));
+/// assert_eq!(frontier.poll_min(), Some((3, 4, Some(7))));
+/// ```
+pub struct PayloadIndexedPriorityQueue<P, V, O>
+where
+    P: Clone + 'static,
+{
+    heap: OrderedIndexedPriorityQueue<'static, P, O>,
+    payloads: HashMap<usize, V>,
+    vacant_keys: Vec<usize>,
+    /// Every key below it is in use or vacated: `push` scans up from it, once no vacated key
+    /// is left, for keys a caller's [`insert`](Self::insert) skipped over, so that a large key
+    /// costs nothing up front.
+    scanned_to: usize,
+}
+
+pub type MinPayloadIndexedPriorityQueue<P, V> = PayloadIndexedPriorityQueue<P, V, Min>;
+
+pub type MaxPayloadIndexedPriorityQueue<P, V> = PayloadIndexedPriorityQueue<P, V, Max>;
+
+impl<P, V, O> Default for PayloadIndexedPriorityQueue<P, V, O>
+where
+    P: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P, V, O> FromIterator<(P, V)> for PayloadIndexedPriorityQueue<P, V, O>
+where
+    P: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    /// Builds the queue with a single heapify pass, keys following the iteration order.
+    fn from_iter<I: IntoIterator<Item = (P, V)>>(entries: I) -> Self {
+        let (priorities, payloads): (Vec<P>, Vec<V>) = entries.into_iter().unzip();
+        let scanned_to = payloads.len();
+
+        Self {
+            heap: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(priorities)),
+            payloads: payloads.into_iter().enumerate().collect(),
+            vacant_keys: Vec::new(),
+            scanned_to,
+        }
+    }
+}
+
+impl<P, V, O> PayloadIndexedPriorityQueue<P, V, O>
+where
+    P: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    pub fn new() -> Self {
+        Self::from_iter(Vec::new())
+    }
+
+    pub fn size(&self) -> usize {
+        self.heap.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.heap.contains(key_index)
+    }
+
+    /// Adds an entry under the most recently vacated key, else the lowest key skipped over by
+    /// [`insert`](Self::insert), else a fresh one, and returns it.
+    ///
+    /// # Panics
+    ///
+    /// If `priority` is not comparable with itself, see [`try_push`](Self::try_push).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn push(&mut self, priority: P, payload: V) -> usize {
+        self.try_push(priority, payload).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`push`](Self::push), returning an error instead of panicking. The key it
+    /// would have used is handed out by the next push.
+    pub fn try_push(&mut self, priority: P, payload: V) -> Result<usize, IpqError> {
+        let key_index = loop {
+            match self.vacant_keys.pop() {
+                Some(key_index) if self.payloads.contains_key(&key_index) => continue,
+                Some(key_index) => break key_index,
+                None => {
+                    while self.payloads.contains_key(&self.scanned_to) {
+                        self.scanned_to += 1;
+                    }
+                    self.scanned_to += 1;
+                    break self.scanned_to - 1;
+                }
+            }
+        };
+        if let Err(e) = self.heap.try_insert(key_index, priority) {
+            self.vacant_keys.push(key_index);
+            return Err(e);
+        }
+        self.payloads.insert(key_index, payload);
+
+        Ok(key_index)
+    }
+
+    /// Adds an entry under `key_index`, chosen by the caller.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is in use or past the key bound, see [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&mut self, key_index: usize, priority: P, payload: V) {
+        if let Err(e) = self.try_insert(key_index, priority, payload) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Same as [`insert`](Self::insert), returning an error instead of panicking. Keys skipped
+    /// over by a large `key_index` are handed out by later pushes, one at a time.
+    pub fn try_insert(
+        &mut self,
+        key_index: usize,
+        priority: P,
+        payload: V,
+    ) -> Result<(), IpqError> {
+        self.heap.try_insert(key_index, priority)?;
+        self.payloads.insert(key_index, payload);
+
+        Ok(())
+    }
+
+    /// Returns the entry ranked first, as its key, priority and payload.
+    pub fn peek_min(&self) -> Option<(usize, &P, &V)> {
+        let key_index = self.peek_min_key_index()?;
+
+        Some((key_index, self.priority_of(key_index)?, self.payload_of(key_index)?))
+    }
+
+    pub fn peek_min_key_index(&self) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        self.heap.peek_min_key_index()
+    }
+
+    pub fn priority_of(&self, key_index: usize) -> Option<&P> {
+        self.heap.value_ref(key_index)
+    }
+
+    pub fn payload_of(&self, key_index: usize) -> Option<&V> {
+        self.payloads.get(&key_index)
+    }
+
+    /// Payloads take no part in the ordering, so they can be freely mutated in place.
+    pub fn payload_of_mut(&mut self, key_index: usize) -> Option<&mut V> {
+        self.payloads.get_mut(&key_index)
+    }
+
+    /// Replaces the priority of `key_index`, returning the previous one.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue or `priority` is not comparable with itself, see
+    /// [`try_update_priority`](Self::try_update_priority).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn update_priority(&mut self, key_index: usize, priority: P) -> P {
+        self.try_update_priority(key_index, priority).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`update_priority`](Self::update_priority), returning an error instead of
+    /// panicking.
+    pub fn try_update_priority(&mut self, key_index: usize, priority: P) -> Result<P, IpqError> {
+        self.heap.try_update(key_index, priority)
+    }
+
+    /// Replaces the priority of `key_index`, returning the previous one, or `None` if the key
+    /// is not in the queue.
+    pub fn set_priority(&mut self, key_index: usize, priority: P) -> Option<P> {
+        self.heap.try_update(key_index, priority).ok()
+    }
+
+    pub fn delete(&mut self, key_index: usize) -> Option<(P, V)> {
+        let priority = self.heap.delete(key_index)?;
+        let payload = self.payloads.remove(&key_index)?;
+        self.vacant_keys.push(key_index);
+
+        Some((priority, payload))
+    }
+
+    /// Removes the entry ranked first, returning its key alongside its priority and payload.
+    pub fn poll_min(&mut self) -> Option<(usize, P, V)> {
+        let key_index = self.peek_min_key_index()?;
+        let (priority, payload) = self.delete(key_index)?;
+
+        Some((key_index, priority, payload))
+    }
+}
+
+#[cfg(test)]
+mod payload_indexed_pq_tests {
+    use crate::limit::IpqError;
+    use crate::split::{MaxPayloadIndexedPriorityQueue, MinPayloadIndexedPriorityQueue};
+
+    #[test]
+    fn poll_min_should_hand_back_payloads_in_priority_order() {
+        let mut ipq = [(3, "c"), (1, "a"), (2, "b")]
+            .into_iter()
+            .collect::<MinPayloadIndexedPriorityQueue<i32, &str>>();
+
+        assert_eq!(ipq.poll_min(), Some((1, 1, "a")));
+        assert_eq!(ipq.poll_min(), Some((2, 2, "b")));
+        assert_eq!(ipq.poll_min(), Some((0, 3, "c")));
+        assert_eq!(ipq.poll_min(), None);
+    }
+
+    #[test]
+    fn update_priority_should_reorder_without_touching_the_payload() {
+        let mut ipq = MinPayloadIndexedPriorityQueue::new();
+        let big = ipq.try_push(10, vec![0u8; 1024]).unwrap();
+        let small = ipq.try_push(5, vec![1u8; 2]).unwrap();
+
+        assert_eq!(ipq.peek_min_key_index(), Some(small));
+        assert_eq!(ipq.try_update_priority(big, 1), Ok(10));
+        assert_eq!(ipq.peek_min_key_index(), Some(big));
+        assert_eq!(ipq.priority_of(big), Some(&1));
+        assert_eq!(ipq.payload_of(big).map(Vec::len), Some(1024));
+
+        ipq.payload_of_mut(small).unwrap().push(2);
+        let (_, _, payload) = ipq.poll_min().unwrap();
+        assert_eq!(payload.len(), 1024);
+        assert_eq!(ipq.poll_min(), Some((small, 5, vec![1, 1, 2])));
+    }
+
+    #[test]
+    fn delete_should_leave_other_keys_untouched_and_recycle_its_own() {
+        let mut ipq = [(4, 'd'), (1, 'a'), (3, 'c'), (2, 'b')]
+            .into_iter()
+            .collect::<MaxPayloadIndexedPriorityQueue<i32, char>>();
+
+        assert_eq!(ipq.delete(1), Some((1, 'a')));
+        assert_eq!(ipq.delete(1), None);
+        assert_eq!(ipq.size(), 3);
+        assert_eq!(ipq.payload_of(1), None);
+        assert_eq!(ipq.priority_of(2), Some(&3));
+        assert_eq!(ipq.payload_of(2), Some(&'c'));
+        assert!(ipq.contains(3));
+
+        assert_eq!(ipq.try_push(0, 'z').unwrap(), 1);
+        assert_eq!(ipq.poll_min(), Some((0, 4, 'd')));
+        assert_eq!(ipq.poll_min(), Some((2, 3, 'c')));
+        assert_eq!(ipq.poll_min(), Some((3, 2, 'b')));
+        assert_eq!(ipq.poll_min(), Some((1, 0, 'z')));
+        assert!(ipq.is_empty());
+    }
+
+    #[test]
+    fn insert_should_use_the_callers_keys_alongside_pushed_ones() {
+        let mut ipq = MinPayloadIndexedPriorityQueue::new();
+        assert_eq!(ipq.try_insert(3, 30, "three"), Ok(()));
+        assert_eq!(ipq.try_push(20, "pushed").unwrap(), 0);
+        assert_eq!(ipq.try_insert(1, 10, "one"), Ok(()));
+        assert_eq!(ipq.try_insert(3, 0, "again"), Err(IpqError::KeyInUse(3)));
+
+        assert_eq!(ipq.try_push(40, "pushed").unwrap(), 2);
+        assert_eq!(ipq.try_push(50, "pushed").unwrap(), 4);
+        assert_eq!(ipq.set_priority(4, 5), Some(50));
+        assert_eq!(ipq.set_priority(9, 5), None);
+        assert_eq!(ipq.peek_min(), Some((4, &5, &"pushed")));
+        assert_eq!(ipq.poll_min(), Some((4, 5, "pushed")));
+        assert_eq!(ipq.poll_min(), Some((1, 10, "one")));
+        assert_eq!(ipq.size(), 3);
+
+        let mut sparse = MinPayloadIndexedPriorityQueue::new();
+        assert_eq!(sparse.try_insert(1 << 29, 0, ()), Ok(()));
+        assert_eq!(sparse.try_push(1, ()).unwrap(), 0);
+        assert!(sparse.payloads.capacity() < 1024);
+    }
+
+    #[test]
+    fn a_refused_push_should_leave_its_key_to_the_next_one() {
+        let mut ipq = MinPayloadIndexedPriorityQueue::new();
+        assert_eq!(ipq.try_push(1.0, 'a'), Ok(0));
+        assert_eq!(ipq.try_push(f64::NAN, 'b'), Err(IpqError::Incomparable(1)));
+        assert_eq!(ipq.try_update_priority(0, f64::NAN), Err(IpqError::Incomparable(0)));
+        assert_eq!(ipq.payload_of(1), None);
+
+        assert_eq!(ipq.try_push(2.0, 'c'), Ok(1));
+        assert_eq!(ipq.poll_min(), Some((0, 1.0, 'a')));
+    }
+}
@@ -0,0 +1,367 @@
+use crate::fairness::{FairnessReport, PollLog};
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::storage::Storage;
+use crate::OrderedIndexedPriorityQueue;
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+/// A value stamped with its insertion sequence, ties being ranked first-in first-out whatever
+/// the order `O`.
+#[derive(Debug)]
+struct Sequenced<T, O> {
+    value: T,
+    sequence: u64,
+    order: PhantomData<O>,
+}
+
+impl<T, O> Clone for Sequenced<T, O>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            sequence: self.sequence,
+            order: PhantomData,
+        }
+    }
+}
+
+impl<T, O> PartialEq for Sequenced<T, O>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.sequence == other.sequence
+    }
+}
+
+impl<T, O> PartialOrd for Sequenced<T, O>
+where
+    T: PartialOrd,
+    O: Order,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.value.partial_cmp(&other.value)? {
+            Ordering::Equal => {
+                let arrival = self.sequence.cmp(&other.sequence);
+                if O::precedes(&0, &1) {
+                    Some(arrival)
+                } else {
+                    Some(arrival.reverse())
+                }
+            }
+            ordering => Some(ordering),
+        }
+    }
+}
+
+/// An indexed priority queue with stable ordering: entries of equal priority are polled in
+/// the order they arrived.
+///
+/// Every insertion is stamped with a sequence number, starting at `0` and never reused, that
+/// [`sequence_of`](Self::sequence_of) and [`iter`](Self::iter) expose so that consumers can
+/// rebuild the arrival order, audit scheduling decisions or replay them deterministically.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::stable::MinStableIndexedPriorityQueue;
+///
+/// let mut jobs = MinStableIndexedPriorityQueue::new();
+/// let late = jobs.try_push(1).unwrap();
+/// let early = jobs.try_push(0).unwrap();
+/// let later = jobs.try_push(1).unwrap();
+///
+/// assert_eq!(jobs.sequence_of(later), Some(2));
+/// assert_eq!(jobs.poll_min(), Some((early, 0)));
+/// assert_eq!(jobs.poll_min(), Some((late, 1)));
+/// assert_eq!(jobs.poll_min(), Some((later, 1)));
+/// ```
+pub struct StableIndexedPriorityQueue<T, O>
+where
+    T: Clone + 'static,
+    O: 'static,
+{
+    heap: OrderedIndexedPriorityQueue<'static, Sequenced<T, O>, O>,
+    next_sequence: u64,
+    poll_log: Option<PollLog<T>>,
+}
+
+pub type MinStableIndexedPriorityQueue<T> = StableIndexedPriorityQueue<T, Min>;
+
+pub type MaxStableIndexedPriorityQueue<T> = StableIndexedPriorityQueue<T, Max>;
+
+impl<T, O> Default for StableIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, O> StableIndexedPriorityQueue<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            heap: OrderedIndexedPriorityQueue::with_storage(Storage::Owned(Vec::new())),
+            next_sequence: 0,
+            poll_log: None,
+        }
+    }
+
+    /// Starts recording the value and wait of the latest `capacity` polls for
+    /// [`fairness_report`](Self::fairness_report), consuming and returning the queue.
+    pub fn with_fairness_tracking(mut self, capacity: usize) -> Self {
+        self.set_fairness_tracking(capacity);
+        self
+    }
+
+    /// Same as [`with_fairness_tracking`](Self::with_fairness_tracking), forgetting the polls
+    /// recorded so far.
+    pub fn set_fairness_tracking(&mut self, capacity: usize) {
+        self.poll_log = Some(PollLog::new(capacity));
+    }
+
+    /// Reports how long the entries of each priority waited over the latest `window` polls,
+    /// or `None` unless fairness tracking is enabled.
+    ///
+    /// Waits are counted in insertions, from an entry's sequence number to the next one at
+    /// its poll, so they do not depend on wall-clock time. Recording a poll costs `O(1)` and
+    /// the report `O(w log w)`, letting schedulers check empirically that no priority starves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::stable::MinStableIndexedPriorityQueue;
+    ///
+    /// let mut jobs = MinStableIndexedPriorityQueue::new().with_fairness_tracking(100);
+    /// jobs.try_push(5).unwrap();
+    /// for _ in 0..3 {
+    ///     jobs.try_push(1).unwrap();
+    ///     jobs.poll_min();
+    /// }
+    /// jobs.poll_min();
+    ///
+    /// let report = jobs.fairness_report(10).unwrap();
+    /// assert_eq!(report.polls, 4);
+    /// assert_eq!(report.classes[0].class, 1);
+    /// assert_eq!(report.classes[0].mean_wait, 1.0);
+    /// assert_eq!((report.classes[1].class, report.classes[1].max_wait), (5, 4));
+    /// ```
+    pub fn fairness_report(&self, window: usize) -> Option<FairnessReport<T>> {
+        Some(self.poll_log.as_ref()?.report::<O>(window))
+    }
+
+    pub fn size(&self) -> usize {
+        self.heap.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.heap.contains(key_index)
+    }
+
+    /// Adds `value` under the queue's next key index, which is returned.
+    ///
+    /// # Panics
+    ///
+    /// If `value` is not comparable with itself, see [`try_push`](Self::try_push).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn push(&mut self, value: T) -> usize {
+        self.try_push(value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`push`](Self::push), returning an error instead of panicking.
+    pub fn try_push(&mut self, value: T) -> Result<usize, IpqError> {
+        let key_index = self.heap.next_key_index();
+        self.try_insert(key_index, value)?;
+
+        Ok(key_index)
+    }
+
+    /// Adds `value` under `key_index`, stamped with the next sequence number.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is already in the queue or `value` is not comparable with itself, see
+    /// [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&mut self, key_index: usize, value: T) {
+        if let Err(e) = self.try_insert(key_index, value) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Same as [`insert`](Self::insert), returning an error instead of panicking. A refused
+    /// value uses up no sequence number.
+    pub fn try_insert(&mut self, key_index: usize, value: T) -> Result<(), IpqError> {
+        let sequence = self.next_sequence;
+        self.heap.try_insert(
+            key_index,
+            Sequenced {
+                value,
+                sequence,
+                order: PhantomData,
+            },
+        )?;
+        self.next_sequence += 1;
+
+        Ok(())
+    }
+
+    /// Replaces the value of `key_index`, which keeps its place in the arrival order, and
+    /// returns the previous one.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is not in the queue or `value` is not comparable with itself, see
+    /// [`try_update`](Self::try_update).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn update(&mut self, key_index: usize, value: T) -> T {
+        self.try_update(key_index, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as [`update`](Self::update), returning an error instead of panicking.
+    pub fn try_update(&mut self, key_index: usize, value: T) -> Result<T, IpqError> {
+        let sequence = self.sequence_of(key_index).ok_or(IpqError::KeyNotFound(key_index))?;
+        let entry = Sequenced {
+            value,
+            sequence,
+            order: PhantomData,
+        };
+
+        Ok(self.heap.try_update(key_index, entry)?.value)
+    }
+
+    pub fn delete(&mut self, key_index: usize) -> Option<T> {
+        self.heap.delete(key_index).map(|entry| entry.value)
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Option<&T> {
+        self.heap.value_ref(key_index).map(|entry| &entry.value)
+    }
+
+    /// Returns the insertion sequence number of `key_index`.
+    pub fn sequence_of(&self, key_index: usize) -> Option<u64> {
+        self.heap.value_ref(key_index).map(|entry| entry.sequence)
+    }
+
+    pub fn peek_min_key_index(&self) -> Option<usize> {
+        if self.is_empty() {
+            return None;
+        }
+        self.heap.peek_min_key_index()
+    }
+
+    /// Removes the entry ranked first, the earliest one among equals.
+    pub fn poll_min(&mut self) -> Option<(usize, T)> {
+        let (key_index, entry) = self.heap.swap_remove_min()?;
+        if let Some(poll_log) = &mut self.poll_log {
+            poll_log.record(&entry.value, self.next_sequence - entry.sequence);
+        }
+
+        Some((key_index, entry.value))
+    }
+
+    /// Iterates over the `(key_index, sequence, &value)` triples in heap order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, u64, &T)> + '_ {
+        self.heap
+            .maps
+            .keys_by_node(self.heap.values.len())
+            .zip(self.heap.values.iter())
+            .map(|(key_index, entry)| (key_index.0, entry.sequence, &entry.value))
+    }
+}
+
+#[cfg(test)]
+mod stable_indexed_pq_tests {
+    use crate::limit::IpqError;
+    use crate::stable::{MaxStableIndexedPriorityQueue, MinStableIndexedPriorityQueue};
+
+    #[test]
+    fn ties_should_be_polled_in_arrival_order_in_both_orders() {
+        let mut min_ipq = MinStableIndexedPriorityQueue::new();
+        let mut max_ipq = MaxStableIndexedPriorityQueue::new();
+        (0..20).for_each(|i| {
+            min_ipq.try_push(i % 2).unwrap();
+            max_ipq.try_push(i % 2).unwrap();
+        });
+
+        let min_keys = (0..20).map(|_| min_ipq.poll_min().unwrap().0).collect::<Vec<usize>>();
+        let max_keys = (0..20).map(|_| max_ipq.poll_min().unwrap().0).collect::<Vec<usize>>();
+
+        let evens = (0..20).step_by(2);
+        let odds = (1..20).step_by(2);
+        assert_eq!(min_keys, evens.clone().chain(odds.clone()).collect::<Vec<usize>>());
+        assert_eq!(max_keys, odds.chain(evens).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn update_should_keep_the_original_sequence() {
+        let mut ipq = MinStableIndexedPriorityQueue::new();
+        ipq.try_insert(7, 'b').unwrap();
+        ipq.try_insert(3, 'c').unwrap();
+        assert_eq!(ipq.try_update(3, 'b'), Ok('c'));
+        assert_eq!(ipq.try_update(4, 'a'), Err(IpqError::KeyNotFound(4)));
+
+        assert_eq!(ipq.sequence_of(3), Some(1));
+        assert_eq!(ipq.poll_min(), Some((7, 'b')));
+        assert_eq!(ipq.poll_min(), Some((3, 'b')));
+    }
+
+    #[test]
+    fn iter_should_expose_sequences_to_rebuild_the_arrival_order() {
+        let mut ipq = MinStableIndexedPriorityQueue::new();
+        [5, 1, 4, 1, 3].into_iter().for_each(|v| {
+            ipq.try_push(v).unwrap();
+        });
+        ipq.delete(1);
+
+        let mut arrivals = ipq.iter().map(|(k, s, v)| (s, k, *v)).collect::<Vec<(u64, usize, i32)>>();
+        arrivals.sort();
+        assert_eq!(arrivals, vec![(0, 0, 5), (2, 2, 4), (3, 3, 1), (4, 4, 3)]);
+        assert_eq!(ipq.try_push(0), Ok(5));
+        assert_eq!(ipq.sequence_of(5), Some(5));
+    }
+
+    #[test]
+    fn fairness_report_should_cover_the_latest_polls_only() {
+        let mut ipq = MaxStableIndexedPriorityQueue::new();
+        assert_eq!(ipq.fairness_report(10), None);
+
+        ipq.set_fairness_tracking(4);
+        [1, 2, 1, 3, 2, 1].into_iter().for_each(|v| {
+            ipq.try_push(v).unwrap();
+        });
+        while ipq.poll_min().is_some() {}
+
+        let report = ipq.fairness_report(10).unwrap();
+        assert_eq!(report.polls, 4);
+        let classes = report.classes.iter().map(|c| (c.class, c.polls)).collect::<Vec<_>>();
+        assert_eq!(classes, vec![(2, 1), (1, 3)]);
+        assert_eq!(report.max_wait(), Some(6));
+        assert_eq!(report.classes[1].mean_wait, 11.0 / 3.0);
+        assert_eq!(ipq.fairness_report(1).unwrap().classes[0].max_wait, 1);
+    }
+
+    #[test]
+    fn refused_values_should_use_up_no_sequence_number() {
+        let mut ipq = MinStableIndexedPriorityQueue::new();
+        assert_eq!(ipq.try_push(f64::NAN), Err(IpqError::Incomparable(0)));
+        assert_eq!(ipq.try_push(1.0), Ok(0));
+        assert_eq!(ipq.try_update(0, f64::NAN), Err(IpqError::Incomparable(0)));
+
+        assert_eq!(ipq.sequence_of(0), Some(0));
+        assert_eq!(ipq.poll_min(), Some((0, 1.0)));
+    }
+}
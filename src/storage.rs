@@ -0,0 +1,27 @@
+use std::ops::{Deref, DerefMut};
+
+/// Backing vector of a queue's values, either borrowed from the caller or owned by the queue.
+pub(crate) enum Storage<'a, T> {
+    Borrowed(&'a mut Vec<T>),
+    Owned(Vec<T>),
+}
+
+impl<'a, T> Deref for Storage<'a, T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Storage::Borrowed(values) => values,
+            Storage::Owned(values) => values,
+        }
+    }
+}
+
+impl<'a, T> DerefMut for Storage<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Storage::Borrowed(values) => values,
+            Storage::Owned(values) => values,
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use crate::limit::IpqError;
+use crate::order::{Max, Min, Order};
+use crate::OwnedIpq;
+use futures_core::Stream;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+struct State<T, O>
+where
+    T: Clone + 'static,
+{
+    queue: OwnedIpq<T, O>,
+    waiting: Vec<Waker>,
+    closed: bool,
+}
+
+/// A queue shared between tasks, whose polls can be awaited until an entry exists.
+///
+/// Clones are handles to the same queue, so producers insert through theirs while a consumer
+/// awaits [`poll_min`](Self::poll_min) or reads the queue as a [`Stream`] of `(key, value)`
+/// pairs, the one ranked first each time. Waiting only relies on `std::task`, so tokio or any
+/// other executor drives it; [`close`](Self::close) ends the stream once the queue is drained.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::stream::MinAsyncIpq;
+/// use std::future::Future;
+/// use std::pin::Pin;
+/// use std::sync::Arc;
+/// use std::task::{Context, Poll, Wake};
+///
+/// struct Noop;
+///
+/// impl Wake for Noop {
+///     fn wake(self: Arc<Self>) {}
+/// }
+///
+/// let timers = MinAsyncIpq::new();
+/// let waker = Arc::new(Noop).into();
+/// let mut cx = Context::from_waker(&waker);
+///
+/// let mut due = timers.poll_min_value();
+/// assert_eq!(Pin::new(&mut due).poll(&mut cx), Poll::Pending);
+/// timers.clone().insert(3, 250u64);
+/// assert_eq!(Pin::new(&mut due).poll(&mut cx), Poll::Ready(Some(250)));
+/// ```
+pub struct AsyncIpq<T, O>
+where
+    T: Clone + 'static,
+{
+    shared: Arc<Mutex<State<T, O>>>,
+}
+
+pub type MinAsyncIpq<T> = AsyncIpq<T, Min>;
+
+pub type MaxAsyncIpq<T> = AsyncIpq<T, Max>;
+
+impl<T, O> Clone for AsyncIpq<T, O>
+where
+    T: Clone + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T, O> Default for AsyncIpq<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, O> AsyncIpq<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(State {
+                queue: OwnedIpq::default(),
+                waiting: Vec::new(),
+                closed: false,
+            })),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.lock().queue.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().queue.is_empty()
+    }
+
+    pub fn contains(&self, key_index: usize) -> bool {
+        self.lock().queue.contains(key_index)
+    }
+
+    pub fn value_of(&self, key_index: usize) -> Option<T> {
+        self.lock().queue.value_of(key_index)
+    }
+
+    /// Adds `value` under `key_index`, waking the tasks awaiting an entry.
+    ///
+    /// # Panics
+    ///
+    /// If `key_index` is already in use or out of the key space, see
+    /// [`try_insert`](Self::try_insert).
+    #[cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+    pub fn insert(&self, key_index: usize, value: T) {
+        if let Err(e) = self.try_insert(key_index, value) {
+            panic!("{}", e);
+        }
+    }
+
+    pub fn try_insert(&self, key_index: usize, value: T) -> Result<(), IpqError> {
+        let mut state = self.lock();
+        state.queue.try_insert(key_index, value)?;
+        state.waiting.drain(..).for_each(Waker::wake);
+
+        Ok(())
+    }
+
+    /// Replaces the value of `key_index` and returns the previous one, or returns `None` if
+    /// the key is not in the queue.
+    pub fn update(&self, key_index: usize, value: T) -> Option<T> {
+        self.lock().queue.try_update(key_index, value).ok()
+    }
+
+    pub fn delete(&self, key_index: usize) -> Option<T> {
+        self.lock().queue.delete(key_index)
+    }
+
+    /// Removes and returns the entry ranked first, or `None` at once if the queue is empty.
+    pub fn try_poll_min(&self) -> Option<(usize, T)> {
+        self.lock().queue.swap_remove_min()
+    }
+
+    /// Waits for an entry and removes and returns the one ranked first, or `None` once the
+    /// queue is [closed](Self::close) and empty.
+    pub fn poll_min(&self) -> PollMin<'_, T, O> {
+        PollMin { queue: self }
+    }
+
+    /// Same as [`poll_min`](Self::poll_min), returning the value alone.
+    pub fn poll_min_value(&self) -> PollMinValue<'_, T, O> {
+        PollMinValue { queue: self }
+    }
+
+    /// Wakes every waiting task and ends polls and the stream once the queue is empty.
+    /// Entries can still be inserted and polled.
+    pub fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        state.waiting.drain(..).for_each(Waker::wake);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    fn poll_entry(&self, cx: &mut Context<'_>) -> Poll<Option<(usize, T)>> {
+        let mut state = self.lock();
+        if let Some(entry) = state.queue.swap_remove_min() {
+            return Poll::Ready(Some(entry));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        if !state.waiting.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.waiting.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T, O>> {
+        self.shared.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Future returned by [`AsyncIpq::poll_min`].
+pub struct PollMin<'q, T, O>
+where
+    T: Clone + 'static,
+{
+    queue: &'q AsyncIpq<T, O>,
+}
+
+impl<'q, T, O> Future for PollMin<'q, T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    type Output = Option<(usize, T)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.queue.poll_entry(cx)
+    }
+}
+
+/// Future returned by [`AsyncIpq::poll_min_value`].
+pub struct PollMinValue<'q, T, O>
+where
+    T: Clone + 'static,
+{
+    queue: &'q AsyncIpq<T, O>,
+}
+
+impl<'q, T, O> Future for PollMinValue<'q, T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.queue.poll_entry(cx).map(|entry| entry.map(|(_, value)| value))
+    }
+}
+
+/// Yields the entry ranked first each time one exists, ending once the queue is closed and
+/// empty.
+impl<T, O> Stream for AsyncIpq<T, O>
+where
+    T: Clone + PartialOrd + 'static,
+    O: Order,
+{
+    type Item = (usize, T);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_entry(cx)
+    }
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use crate::stream::{MaxAsyncIpq, MinAsyncIpq};
+    use crate::testing::block_on;
+    use futures_core::Stream;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::thread;
+
+    struct Next<'s, S>(&'s mut S);
+
+    impl<'s, S: Stream + Unpin> Future for Next<'s, S> {
+        type Output = Option<S::Item>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            Pin::new(&mut *self.0).poll_next(cx)
+        }
+    }
+
+    #[test]
+    fn the_stream_should_wait_for_producers_and_end_once_closed() {
+        let mut ipq = MinAsyncIpq::new();
+        let producer = {
+            let ipq = ipq.clone();
+            thread::spawn(move || {
+                (0..500).for_each(|key_index| ipq.insert(key_index, key_index % 11));
+                ipq.close();
+            })
+        };
+
+        let mut polled = Vec::new();
+        while let Some((key_index, _)) = block_on(Next(&mut ipq)) {
+            polled.push(key_index);
+        }
+        producer.join().unwrap();
+
+        polled.sort_unstable();
+        assert_eq!(polled, (0..500).collect::<Vec<usize>>());
+        assert!(ipq.is_closed());
+        assert_eq!(block_on(ipq.poll_min()), None);
+    }
+
+    #[test]
+    fn polls_should_follow_the_queue_order() {
+        let ipq = MaxAsyncIpq::new();
+        ipq.insert(0, 'b');
+        ipq.insert(1, 'y');
+        ipq.insert(2, 'm');
+        assert_eq!(ipq.update(2, 'z'), Some('m'));
+        assert_eq!(ipq.update(7, 'a'), None);
+        assert_eq!(ipq.delete(0), Some('b'));
+        assert!(ipq.try_insert(1, 'c').is_err());
+
+        assert_eq!(block_on(ipq.poll_min()), Some((2, 'z')));
+        assert_eq!(block_on(ipq.poll_min_value()), Some('y'));
+        assert_eq!(ipq.try_poll_min(), None);
+    }
+}
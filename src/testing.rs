@@ -0,0 +1,429 @@
+use crate::ipq::IndexedPriorityQueue;
+use crate::order::Order;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+/// One call to an [`IndexedPriorityQueue`], as replayed by [`ApplyOps::apply_ops`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Op<T> {
+    Insert(usize, T),
+    Push(T),
+    Update(usize, T),
+    Decrease(usize, T),
+    Increase(usize, T),
+    Delete(usize),
+    PollMinKeyIndex,
+    PollMinValue,
+    PeekMinKeyIndex,
+    PeekMinValue,
+    Contains(usize),
+    ValueOf(usize),
+}
+
+const OP_CODES: u8 = 12;
+
+impl<T> Op<T>
+where
+    T: From<u8>,
+{
+    /// Decodes raw fuzzer input, three bytes per operation: an op-code, a key index and a
+    /// value. Keys and values stay below 256 so that they often collide, and trailing bytes
+    /// short of an operation are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::testing::Op;
+    ///
+    /// let ops = Op::<u16>::decode(&[0, 4, 40, 6, 0, 0, 9]);
+    /// assert_eq!(ops, vec![Op::Insert(4, 40), Op::PollMinKeyIndex]);
+    /// ```
+    pub fn decode(bytes: &[u8]) -> Vec<Op<T>> {
+        bytes
+            .chunks_exact(3)
+            .map(|op| {
+                let (key_index, value) = (op[1] as usize, op[2]);
+                match op[0] % OP_CODES {
+                    0 => Op::Insert(key_index, value.into()),
+                    1 => Op::Push(value.into()),
+                    2 => Op::Update(key_index, value.into()),
+                    3 => Op::Decrease(key_index, value.into()),
+                    4 => Op::Increase(key_index, value.into()),
+                    5 => Op::Delete(key_index),
+                    6 => Op::PollMinKeyIndex,
+                    7 => Op::PollMinValue,
+                    8 => Op::PeekMinKeyIndex,
+                    9 => Op::PeekMinValue,
+                    10 => Op::Contains(key_index),
+                    _ => Op::ValueOf(key_index),
+                }
+            })
+            .collect()
+    }
+}
+
+/// What an [`Op`] returned.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Outcome<T> {
+    Done,
+    /// The operation would have panicked, e.g. inserting under a key in use, so it was not run.
+    Skipped,
+    Bool(bool),
+    Key(Option<usize>),
+    Value(Option<T>),
+}
+
+/// Replays [`Op`]s against any [`IndexedPriorityQueue`], so wrappers around this crate's
+/// queues can be fuzzed with the same machinery.
+pub trait ApplyOps<T> {
+    /// Runs `ops` in order, returning the outcome of each. Operations that would panic are
+    /// skipped rather than run.
+    fn apply_ops(&mut self, ops: &[Op<T>]) -> Vec<Outcome<T>>;
+}
+
+impl<T, Q> ApplyOps<T> for Q
+where
+    T: Clone,
+    Q: IndexedPriorityQueue<T>,
+{
+    fn apply_ops(&mut self, ops: &[Op<T>]) -> Vec<Outcome<T>> {
+        ops.iter().map(|op| apply_op(self, op.clone())).collect()
+    }
+}
+
+fn apply_op<T, Q>(queue: &mut Q, op: Op<T>) -> Outcome<T>
+where
+    Q: IndexedPriorityQueue<T> + ?Sized,
+{
+    match op {
+        Op::Insert(key_index, _) if queue.contains(key_index) => Outcome::Skipped,
+        Op::Insert(key_index, value) => {
+            queue.insert(key_index, value);
+            Outcome::Done
+        }
+        Op::Push(value) => {
+            queue.push(value);
+            Outcome::Done
+        }
+        Op::Update(key_index, _)
+        | Op::Decrease(key_index, _)
+        | Op::Increase(key_index, _)
+            if !queue.contains(key_index) =>
+        {
+            Outcome::Skipped
+        }
+        Op::Update(key_index, value) => Outcome::Value(Some(queue.update(key_index, value))),
+        Op::Decrease(key_index, value) => {
+            queue.decrease(key_index, value);
+            Outcome::Done
+        }
+        Op::Increase(key_index, value) => {
+            queue.increase(key_index, value);
+            Outcome::Done
+        }
+        Op::Delete(key_index) => Outcome::Value(queue.delete(key_index)),
+        Op::PollMinKeyIndex => Outcome::Key(queue.poll_min_key_index()),
+        Op::PollMinValue => Outcome::Value(queue.poll_min_value()),
+        Op::PeekMinKeyIndex => Outcome::Key(queue.peek_min_key_index()),
+        Op::PeekMinValue => Outcome::Value(queue.peek_min_value()),
+        Op::Contains(key_index) => Outcome::Bool(queue.contains(key_index)),
+        Op::ValueOf(key_index) => Outcome::Value(queue.value_of(key_index)),
+    }
+}
+
+/// First operation of a [`check_ops`] run where the queue under test disagreed with the
+/// reference model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence<T> {
+    pub step: usize,
+    pub op: Op<T>,
+    pub expected: Outcome<T>,
+    pub actual: Outcome<T>,
+}
+
+/// Naive model of an indexed priority queue that scans all its entries to find the first.
+struct Reference<T, O> {
+    entries: BTreeMap<usize, T>,
+    next_key_index: usize,
+    order: PhantomData<O>,
+}
+
+impl<T, O> Reference<T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    fn first_value(&self) -> Option<&T> {
+        self.entries
+            .values()
+            .reduce(|first, value| if O::precedes(value, first) { value } else { first })
+    }
+
+    /// Returns `true` when the key's value ties with the first, i.e. a poll may return it.
+    fn may_come_first(&self, key_index: usize) -> bool {
+        match (self.entries.get(&key_index), self.first_value()) {
+            (Some(value), Some(first)) => !O::precedes(first, value),
+            _ => false,
+        }
+    }
+
+    fn set(&mut self, key_index: usize, value: T) -> Option<T> {
+        self.next_key_index = self.next_key_index.max(key_index + 1);
+        self.entries.insert(key_index, value)
+    }
+}
+
+/// Replays `ops` against `queue`, which must start empty, and against a naive reference
+/// model ranking values by `O`, stopping at the first outcome they disagree on.
+///
+/// Equal values may be polled in any order, so a key returned by a peek or a poll is accepted
+/// whenever its value ties with the first, and the model then follows the queue's choice.
+///
+/// # Examples
+///
+/// ```
+/// use indexed_priority_queue::order::Min;
+/// use indexed_priority_queue::testing::{check_ops, Op};
+/// use indexed_priority_queue::MinIndexedPriorityQueue;
+///
+/// let mut values: Vec<u8> = Vec::new();
+/// let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+/// let ops = Op::decode(b"fuzzer-provided bytes, replayed op by op");
+///
+/// assert_eq!(check_ops::<_, _, Min>(&mut ipq, &ops), Ok(()));
+/// ```
+pub fn check_ops<T, Q, O>(queue: &mut Q, ops: &[Op<T>]) -> Result<(), Divergence<T>>
+where
+    T: Clone + PartialOrd,
+    Q: IndexedPriorityQueue<T> + ?Sized,
+    O: Order,
+{
+    let mut reference = Reference::<T, O> {
+        entries: BTreeMap::new(),
+        next_key_index: 0,
+        order: PhantomData,
+    };
+
+    for (step, op) in ops.iter().enumerate() {
+        let first_key_index = queue.peek_min_key_index();
+        let actual = apply_op(queue, op.clone());
+        let expected = match op {
+            Op::Insert(key_index, _) if reference.entries.contains_key(key_index) => {
+                Outcome::Skipped
+            }
+            Op::Insert(key_index, value) => {
+                reference.set(*key_index, value.clone());
+                Outcome::Done
+            }
+            Op::Push(value) => {
+                reference.set(reference.next_key_index, value.clone());
+                Outcome::Done
+            }
+            Op::Update(key_index, _) | Op::Decrease(key_index, _) | Op::Increase(key_index, _)
+                if !reference.entries.contains_key(key_index) =>
+            {
+                Outcome::Skipped
+            }
+            Op::Update(key_index, value) => {
+                Outcome::Value(reference.set(*key_index, value.clone()))
+            }
+            Op::Decrease(key_index, value) | Op::Increase(key_index, value) => {
+                let current = &reference.entries[key_index];
+                let applies = match op {
+                    Op::Decrease(..) => value < current,
+                    _ => value > current,
+                };
+                if applies {
+                    reference.set(*key_index, value.clone());
+                }
+                Outcome::Done
+            }
+            Op::Delete(key_index) => Outcome::Value(reference.entries.remove(key_index)),
+            Op::PeekMinKeyIndex | Op::PollMinKeyIndex | Op::PollMinValue => {
+                match first_key_index.filter(|&k| reference.may_come_first(k)) {
+                    Some(key_index) if *op == Op::PeekMinKeyIndex => Outcome::Key(Some(key_index)),
+                    Some(key_index) if *op == Op::PollMinKeyIndex => {
+                        reference.entries.remove(&key_index);
+                        Outcome::Key(Some(key_index))
+                    }
+                    Some(key_index) => Outcome::Value(reference.entries.remove(&key_index)),
+                    None if *op == Op::PollMinValue => {
+                        Outcome::Value(reference.first_value().cloned())
+                    }
+                    None => Outcome::Key(reference.entries.keys().next().copied()),
+                }
+            }
+            Op::PeekMinValue => Outcome::Value(reference.first_value().cloned()),
+            Op::Contains(key_index) => Outcome::Bool(reference.entries.contains_key(key_index)),
+            Op::ValueOf(key_index) => Outcome::Value(reference.entries.get(key_index).cloned()),
+        };
+
+        if actual != expected {
+            return Err(Divergence {
+                step,
+                op: op.clone(),
+                expected,
+                actual,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+struct Unpark(std::thread::Thread);
+
+#[cfg(test)]
+impl std::task::Wake for Unpark {
+    fn wake(self: std::sync::Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Waker unparking the current thread, as [`block_on`] polls with.
+#[cfg(test)]
+pub(crate) fn thread_waker() -> std::task::Waker {
+    std::task::Waker::from(std::sync::Arc::new(Unpark(std::thread::current())))
+}
+
+/// Minimal executor for the async tests, parking the current thread until the future's waker
+/// is called.
+#[cfg(test)]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = thread_waker();
+    let mut cx = std::task::Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            std::task::Poll::Ready(output) => return output,
+            std::task::Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod testing_tests {
+    use crate::index::KeyIdx;
+    use crate::ipq::IndexedPriorityQueue;
+    use crate::order::{Max, Min};
+    use crate::testing::{check_ops, ApplyOps, Op, Outcome};
+    use crate::{MaxIndexedPriorityQueue, MinIndexedPriorityQueue};
+    use std::ops::RangeBounds;
+    use std::vec::IntoIter;
+
+    fn pseudo_random_bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 33) as u8 % 16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn queues_should_agree_with_the_reference_on_random_ops() {
+        for seed in 0..50 {
+            let ops = Op::<u8>::decode(&pseudo_random_bytes(seed, 3 * 200));
+
+            let mut values = Vec::new();
+            let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+            assert_eq!(check_ops::<_, _, Min>(&mut ipq, &ops), Ok(()), "seed {}", seed);
+
+            let mut values = Vec::new();
+            let mut ipq = MaxIndexedPriorityQueue::from(&mut values);
+            assert_eq!(check_ops::<_, _, Max>(&mut ipq, &ops), Ok(()), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn apply_ops_should_skip_operations_that_would_panic() {
+        let mut values = vec![5];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+        let outcomes = ipq.apply_ops(&[
+            Op::Insert(0, 1),
+            Op::Update(3, 1),
+            Op::Push(2),
+            Op::PollMinKeyIndex,
+            Op::Delete(0),
+            Op::Contains(0),
+        ]);
+        assert_eq!(
+            outcomes,
+            vec![
+                Outcome::Skipped,
+                Outcome::Skipped,
+                Outcome::Done,
+                Outcome::Key(Some(1)),
+                Outcome::Value(Some(5)),
+                Outcome::Bool(false),
+            ]
+        );
+    }
+
+    /// A wrapper that forgets to forward deletions.
+    struct Leaky<'a>(MinIndexedPriorityQueue<'a, u8>);
+
+    impl<'a> IndexedPriorityQueue<u8> for Leaky<'a> {
+        fn append(&mut self, extra_values: &mut Vec<u8>) {
+            self.0.append(extra_values)
+        }
+        fn contains(&self, key_index: impl Into<KeyIdx>) -> bool {
+            self.0.contains(key_index)
+        }
+        fn decrease(&mut self, key_index: impl Into<KeyIdx>, value: u8) {
+            self.0.decrease(key_index, value)
+        }
+        fn delete(&mut self, key_index: impl Into<KeyIdx>) -> Option<u8> {
+            self.0.value_of(key_index)
+        }
+        fn drain<R: RangeBounds<usize>>(&mut self, key_indexes: R) -> IntoIter<u8> {
+            self.0.drain(key_indexes)
+        }
+        fn insert(&mut self, key_index: impl Into<KeyIdx>, value: u8) {
+            self.0.insert(key_index, value)
+        }
+        fn increase(&mut self, key_index: impl Into<KeyIdx>, value: u8) {
+            self.0.increase(key_index, value)
+        }
+        fn peek_min_key_index(&self) -> Option<usize> {
+            self.0.peek_min_key_index()
+        }
+        fn peek_min_value(&self) -> Option<u8> {
+            self.0.peek_min_value()
+        }
+        fn poll_min_key_index(&mut self) -> Option<usize> {
+            self.0.poll_min_key_index()
+        }
+        fn poll_min_value(&mut self) -> Option<u8> {
+            self.0.poll_min_value()
+        }
+        fn push(&mut self, value: u8) {
+            self.0.push(value)
+        }
+        fn update(&mut self, key_index: impl Into<KeyIdx>, value: u8) -> u8 {
+            self.0.update(key_index, value)
+        }
+        fn value_of(&self, key_index: impl Into<KeyIdx>) -> Option<u8> {
+            self.0.value_of(key_index)
+        }
+    }
+
+    #[test]
+    fn check_ops_should_report_the_first_divergence() {
+        let mut values = Vec::new();
+        let mut leaky = Leaky(MinIndexedPriorityQueue::from(&mut values));
+        let ops = [Op::Push(3), Op::Delete(0), Op::Contains(0)];
+
+        let divergence = check_ops::<_, _, Min>(&mut leaky, &ops).unwrap_err();
+        assert_eq!(divergence.step, 2);
+        assert_eq!(divergence.expected, Outcome::Bool(false));
+        assert_eq!(divergence.actual, Outcome::Bool(true));
+    }
+}
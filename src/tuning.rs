@@ -0,0 +1,223 @@
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+
+/// Point at which a bulk operation stops sifting the entries it touches one by one and
+/// rebuilds the whole heap in a single `O(n)` pass instead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RebuildThreshold {
+    /// Rebuild once the touched entries times the heap height reach the size, i.e. once `k`
+    /// sifts of `O(log n)` each would cost about as much as the rebuild.
+    SiftCost,
+    /// Rebuild once the touched entries reach this fraction of the size, e.g. `0.25`; `0.0`
+    /// always rebuilds and `f64::INFINITY` never does.
+    Ratio(f64),
+}
+
+impl RebuildThreshold {
+    /// Returns `true` if touching `touched` of the `size` entries of a queue is better done by
+    /// a rebuild, `size` counting the entries after the operation for growing ones.
+    pub fn should_rebuild(&self, touched: usize, size: usize) -> bool {
+        match *self {
+            RebuildThreshold::SiftCost => {
+                let height = (usize::BITS - size.leading_zeros()) as usize;
+
+                touched.saturating_mul(height) >= size
+            }
+            RebuildThreshold::Ratio(ratio) => touched as f64 >= ratio * size as f64,
+        }
+    }
+}
+
+/// Rebuild thresholds of the bulk operations, one per kind of sift they would otherwise do.
+///
+/// The defaults come from the `bulk strategies` benchmarks on 100K entries. Appended values
+/// mostly stay near the leaves, so swimming them in is cheap until about half the queue is new.
+/// Decreased entries break even around 60% of the queue. Removing one by one beat the rebuild,
+/// which remaps every surviving entry, all the way up to emptying the queue, so removals never
+/// rebuild unless told to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BulkTuning {
+    /// Used by [`append`](crate::ipq::IndexedPriorityQueue::append), the appended values being
+    /// the touched ones.
+    pub append: RebuildThreshold,
+    /// Used by [`remove_all`](OrderedIndexedPriorityQueue::remove_all) and
+    /// [`drain`](crate::ipq::IndexedPriorityQueue::drain).
+    pub remove: RebuildThreshold,
+    /// Used by [`decrease_many`](OrderedIndexedPriorityQueue::decrease_many).
+    pub decrease: RebuildThreshold,
+}
+
+impl Default for BulkTuning {
+    fn default() -> Self {
+        Self {
+            append: RebuildThreshold::Ratio(0.5),
+            remove: RebuildThreshold::Ratio(f64::INFINITY),
+            decrease: RebuildThreshold::Ratio(0.6),
+        }
+    }
+}
+
+/// How a queue lays out its key to node and node to key maps.
+///
+/// The default paged layout keeps a paged key to node map apart from its node to key vector,
+/// so a swap touches both. The interleaved one keeps a single array of `(key_index, node_index)`
+/// slots, slot `i` holding the key at node `i` next to the node of key `i`: as long as key
+/// indexes stay close to node indexes, as with [`push`](crate::ipq::IndexedPriorityQueue::push),
+/// a swap's four map accesses mostly share cache lines instead of touching two arrays. The slots
+/// are paged like the paged layout's key to node map, so a far key costs one page rather than
+/// every slot before it, but with sparse keys a key and its node sit on different pages and the
+/// gain is lost. The `map layouts` benchmarks compare both on the same keyed workload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapLayout {
+    Paged,
+    Interleaved,
+}
+
+impl Default for MapLayout {
+    fn default() -> Self {
+        MapLayout::Paged
+    }
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Sets the [`BulkTuning`] bulk operations pick their strategy by, consuming and returning
+    /// the queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::tuning::{BulkTuning, RebuildThreshold};
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let always_rebuild = RebuildThreshold::Ratio(0.0);
+    /// let tuning = BulkTuning { remove: always_rebuild, ..BulkTuning::default() };
+    /// let mut ipq = MinIndexedPriorityQueue::from_sorted_iter(0..100).with_bulk_tuning(tuning);
+    ///
+    /// assert_eq!(ipq.remove_all([7]), vec![(7, 7)]);
+    /// assert_eq!(ipq.bulk_tuning().remove, always_rebuild);
+    /// ```
+    pub fn with_bulk_tuning(mut self, bulk_tuning: BulkTuning) -> Self {
+        self.set_bulk_tuning(bulk_tuning);
+        self
+    }
+
+    pub fn set_bulk_tuning(&mut self, bulk_tuning: BulkTuning) {
+        self.bulk_tuning = bulk_tuning;
+    }
+
+    pub fn bulk_tuning(&self) -> BulkTuning {
+        self.bulk_tuning
+    }
+
+    /// Lays the index maps out as `map_layout` says, consuming and returning the queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::tuning::MapLayout;
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// let mut ipq = OwnedMinIpq::new().with_map_layout(MapLayout::Interleaved);
+    /// ipq.try_insert_all([(2, 'c'), (0, 'b')]);
+    /// ipq.try_decrease(2, 'a').unwrap();
+    ///
+    /// assert_eq!(ipq.poll_min_key_index(), Some(2));
+    /// assert_eq!(ipq.map_layout(), MapLayout::Interleaved);
+    /// ```
+    pub fn with_map_layout(mut self, map_layout: MapLayout) -> Self {
+        self.set_map_layout(map_layout);
+        self
+    }
+
+    /// Moves the mappings over to `map_layout` in `O(n)`, unless the queue already uses it.
+    pub fn set_map_layout(&mut self, map_layout: MapLayout) {
+        if self.maps.layout() != map_layout {
+            self.maps = self.maps.relaid(map_layout, self.size());
+        }
+    }
+
+    pub fn map_layout(&self) -> MapLayout {
+        self.maps.layout()
+    }
+}
+
+#[cfg(test)]
+mod tuning_tests {
+    use crate::order::Min;
+    use crate::testing::{check_ops, Op};
+    use crate::tuning::{BulkTuning, MapLayout, RebuildThreshold};
+    use crate::{MaxIndexedPriorityQueue, OwnedMinIpq};
+
+    #[test]
+    fn thresholds_should_compare_touched_entries_to_the_size() {
+        assert!(!RebuildThreshold::SiftCost.should_rebuild(10, 1024));
+        assert!(RebuildThreshold::SiftCost.should_rebuild(94, 1024));
+        assert!(RebuildThreshold::SiftCost.should_rebuild(0, 0));
+        assert!(RebuildThreshold::Ratio(0.25).should_rebuild(25, 100));
+        assert!(!RebuildThreshold::Ratio(0.25).should_rebuild(24, 100));
+        assert!(RebuildThreshold::Ratio(0.0).should_rebuild(0, 100));
+        assert!(!RebuildThreshold::Ratio(f64::INFINITY).should_rebuild(100, 100));
+    }
+
+    #[test]
+    fn append_and_drain_should_agree_across_thresholds() {
+        let thresholds = [RebuildThreshold::Ratio(0.0), RebuildThreshold::Ratio(f64::INFINITY)];
+        for threshold in thresholds {
+            let tuning = BulkTuning {
+                append: threshold,
+                remove: threshold,
+                decrease: threshold,
+            };
+            let mut ipq = MaxIndexedPriorityQueue::from_sorted_iter(vec![9, 5, 1]);
+            ipq.set_bulk_tuning(tuning);
+
+            ipq.append(&mut vec![4, 10, 0, 7]);
+            assert_eq!(ipq.drain(1..5).collect::<Vec<i32>>(), vec![5, 1, 4, 10]);
+            assert_eq!(ipq.check_invariants(), Ok(()));
+            assert_eq!(ipq.poll_min_key_index(), Some(0));
+            assert_eq!(ipq.poll_min_key_index(), Some(6));
+        }
+    }
+
+    #[test]
+    fn interleaved_layout_should_match_the_reference_model() {
+        let bytes = (0..3_000u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect::<Vec<u8>>();
+        let ops = Op::<u8>::decode(&bytes);
+
+        let mut ipq = OwnedMinIpq::new().with_map_layout(MapLayout::Interleaved);
+        assert_eq!(check_ops::<_, _, Min>(&mut ipq, &ops), Ok(()));
+        assert_eq!(ipq.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn switching_layouts_should_keep_entries_and_key_bound() {
+        let mut ipq = MaxIndexedPriorityQueue::from_sorted_iter(0..6);
+        assert_eq!(ipq.map_layout(), MapLayout::Paged);
+        ipq.set_map_layout(MapLayout::Interleaved);
+        ipq.append(&mut vec![10, -1]);
+
+        assert_eq!(ipq.drain(2..=6).collect::<Vec<i32>>(), vec![2, 3, 4, 5, 10]);
+        assert_eq!(ipq.poll_min_key_index(), Some(1));
+        ipq.set_map_layout(MapLayout::Paged);
+        assert_eq!(ipq.next_key_index(), 8);
+        assert_eq!(ipq.check_invariants(), Ok(()));
+        assert_eq!(ipq.keys().collect::<Vec<usize>>(), vec![0, 7]);
+    }
+
+    #[test]
+    fn interleaved_layout_should_take_the_greatest_default_key_without_a_dense_map() {
+        let mut ipq = OwnedMinIpq::new().with_map_layout(MapLayout::Interleaved);
+        let greatest = ipq.max_key_bound() - 1;
+
+        assert_eq!(ipq.try_insert(greatest, 1), Ok(()));
+        assert_eq!(ipq.try_insert(0, 2), Ok(()));
+        assert_eq!(ipq.poll_min_key_index(), Some(greatest));
+        assert_eq!(ipq.footprint().position_pages, 1);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::index::KeyIdx;
+use crate::iter::Iter;
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+
+/// A shared borrow of a queue exposing its reads only, built by
+/// [`as_view`](OrderedIndexedPriorityQueue::as_view).
+///
+/// A component given a view can peek, look keys up and rank entries, but has no method to
+/// change the queue, not even one taking `&self` such as
+/// [`min_watch`](OrderedIndexedPriorityQueue::min_watch). Views are `Copy`, and `Send` when
+/// the queue is `Sync`, so one can be handed to other threads as long as the borrow lasts.
+pub struct IpqView<'q, T, O>
+where
+    T: Clone,
+{
+    queue: &'q OrderedIndexedPriorityQueue<'q, T, O>,
+}
+
+impl<'q, T, O> Clone for IpqView<'q, T, O>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'q, T, O> Copy for IpqView<'q, T, O> where T: Clone {}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Borrows the queue as an [`IpqView`], restricting the borrower to reads.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::view::IpqView;
+    /// use indexed_priority_queue::OwnedMinIpq;
+    ///
+    /// fn report(jobs: IpqView<'_, u32, indexed_priority_queue::order::Min>) -> String {
+    ///     format!("{} jobs, next {:?}", jobs.size(), jobs.peek_min())
+    /// }
+    ///
+    /// let mut jobs = OwnedMinIpq::new();
+    /// jobs.try_insert_all([(4, 40), (7, 10)]);
+    ///
+    /// assert_eq!(report(jobs.as_view()), "2 jobs, next Some((7, 10))");
+    /// ```
+    pub fn as_view(&self) -> IpqView<'_, T, O> {
+        IpqView { queue: self }
+    }
+}
+
+impl<'q, T, O> IpqView<'q, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    pub fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn contains(&self, key_index: impl Into<KeyIdx>) -> bool {
+        self.queue.contains(key_index)
+    }
+
+    pub fn value_of(&self, key_index: impl Into<KeyIdx>) -> Option<&'q T> {
+        let KeyIdx(key_index) = key_index.into();
+        self.queue.value_ref(key_index)
+    }
+
+    /// Returns the key index and value ranked first.
+    pub fn peek_min(&self) -> Option<(usize, &'q T)> {
+        let key_index = self.queue.peek_min_key_index()?;
+
+        Some((key_index, self.queue.value_ref(key_index)?))
+    }
+
+    /// Iterates over the `(key_index, &value)` pairs in heap order, starting with the entry
+    /// ranked first.
+    pub fn iter(&self) -> Iter<'q, T> {
+        self.queue.iter()
+    }
+
+    /// Returns the entry that would be polled after `k` others, `k` counting from `0`, entries
+    /// ranked equal coming in no particular order.
+    ///
+    /// Walks the heap best first from the root through
+    /// [`sorted_iter`](OrderedIndexedPriorityQueue::sorted_iter), in `O(k log k)` time and
+    /// `O(k)` space, so small ranks are cheap however large the queue is; use a
+    /// [`FrozenIpq`](crate::frozen::FrozenIpq) for many rank queries on a queue at rest.
+    pub fn kth_smallest(&self, k: usize) -> Option<(usize, &'q T)> {
+        if k >= self.size() {
+            return None;
+        }
+
+        self.queue.sorted_iter().nth(k)
+    }
+}
+
+#[cfg(test)]
+mod view_tests {
+    use crate::{OwnedMaxIpq, OwnedMinIpq};
+
+    fn assert_send<S: Send>(sendable: S) -> S {
+        sendable
+    }
+
+    #[test]
+    fn kth_smallest_should_follow_the_polling_order() {
+        let mut ipq = (0..300).map(|i| (i * 37 % 101) as u32).collect::<OwnedMinIpq<u32>>();
+        ipq.delete(11);
+        let view = ipq.as_view();
+
+        let mut sorted = view.iter().map(|(_, &value)| value).collect::<Vec<u32>>();
+        sorted.sort_unstable();
+        for (k, &value) in sorted.iter().enumerate() {
+            let (key_index, &ranked) = view.kth_smallest(k).unwrap();
+            assert_eq!(ranked, value);
+            assert_eq!(view.value_of(key_index), Some(&value));
+        }
+        assert_eq!(view.kth_smallest(sorted.len()), None);
+        assert_eq!(view.kth_smallest(usize::MAX), None);
+        assert!(!view.contains(11));
+    }
+
+    #[test]
+    fn views_should_be_sendable_copies() {
+        let mut ipq = OwnedMaxIpq::new();
+        (0..8).for_each(|key_index| ipq.insert(key_index, key_index * 3));
+        let view = assert_send(ipq.as_view());
+        let copy = view;
+
+        assert_eq!(copy.peek_min(), Some((7, &21)));
+        assert_eq!(view.kth_smallest(2), Some((5, &15)));
+        assert_eq!(view.size(), 8);
+    }
+}
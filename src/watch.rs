@@ -0,0 +1,247 @@
+use crate::order::Order;
+use crate::OrderedIndexedPriorityQueue;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+struct State<T> {
+    current: Option<(usize, T)>,
+    version: u64,
+    closed: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    changed: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Receiving end of [`OrderedIndexedPriorityQueue::min_watch`], told about every change of the
+/// entry ranked first.
+///
+/// Like a `tokio::sync::watch` receiver, it only ever holds the latest `(key_index, value)`
+/// pair: intermediate minimums published while nobody looked are skipped. Receivers can be
+/// cloned and moved to other threads, each one tracking what it has already seen.
+pub struct MinWatch<T> {
+    shared: Arc<Shared<T>>,
+    seen_version: u64,
+}
+
+impl<T> Clone for MinWatch<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+            seen_version: self.seen_version,
+        }
+    }
+}
+
+impl<T> MinWatch<T>
+where
+    T: Clone,
+{
+    /// Returns the current minimum, `None` for an empty queue, and marks it as seen.
+    pub fn latest(&mut self) -> Option<(usize, T)> {
+        let state = self.shared.lock();
+        self.seen_version = state.version;
+
+        state.current.clone()
+    }
+
+    /// Returns `true` when a minimum not yet returned by [`latest`](Self::latest) was published.
+    pub fn has_changed(&self) -> bool {
+        self.shared.lock().version != self.seen_version
+    }
+
+    /// Blocks until a minimum not yet seen is published, returning `false` instead once the
+    /// queue has been dropped.
+    pub fn changed(&mut self) -> bool {
+        let state = self
+            .shared
+            .changed
+            .wait_while(self.shared.lock(), |state| {
+                state.version == self.seen_version && !state.closed
+            })
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        state.version != self.seen_version
+    }
+
+    /// Returns `true` once the watched queue has been dropped; [`latest`](Self::latest) then
+    /// keeps answering with the last published minimum.
+    pub fn is_closed(&self) -> bool {
+        self.shared.lock().closed
+    }
+}
+
+/// Sending end kept by the queue, closing every receiver when dropped along with it.
+pub(crate) struct MinPublisher<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> MinPublisher<T>
+where
+    T: PartialEq + Clone,
+{
+    fn new(current: Option<(usize, T)>) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    current,
+                    version: 0,
+                    closed: false,
+                }),
+                changed: Condvar::new(),
+            }),
+        }
+    }
+
+    fn publish(&self, minimum: Option<(usize, &T)>) {
+        let mut state = self.shared.lock();
+        let unchanged = match (&state.current, minimum) {
+            (Some((k, v)), Some((key_index, value))) => *k == key_index && v == value,
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged {
+            return;
+        }
+
+        state.current = minimum.map(|(key_index, value)| (key_index, value.clone()));
+        state.version += 1;
+        self.shared.changed.notify_all();
+    }
+}
+
+impl<T> Drop for MinPublisher<T> {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+impl<'a, T, O> OrderedIndexedPriorityQueue<'a, T, O>
+where
+    T: Clone + PartialOrd,
+    O: Order,
+{
+    /// Subscribes to the entry ranked first, see [`MinWatch`]; the current one counts as seen.
+    ///
+    /// Every queue operation changing the key or value at the top publishes the new pair;
+    /// values mutated behind the queue's back through [`iter_mut`](Self::iter_mut) are not
+    /// noticed. All receivers of a queue share the same channel, which costs nothing until the
+    /// first call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexed_priority_queue::MinIndexedPriorityQueue;
+    ///
+    /// let mut deadlines = vec![30, 10, 20];
+    /// let mut timers = MinIndexedPriorityQueue::from(&mut deadlines);
+    /// let mut next_deadline = timers.min_watch();
+    /// assert_eq!(next_deadline.latest(), Some((1, 10)));
+    ///
+    /// timers.try_push(40).unwrap();
+    /// assert!(!next_deadline.has_changed());
+    ///
+    /// timers.poll_min_value();
+    /// assert!(next_deadline.has_changed());
+    /// assert_eq!(next_deadline.latest(), Some((2, 20)));
+    /// ```
+    pub fn min_watch(&mut self) -> MinWatch<T> {
+        let minimum = self.peek_min_key_index().zip(self.peek_min_value());
+        let publisher = self
+            .min_publisher
+            .get_or_insert_with(|| MinPublisher::new(minimum));
+
+        let seen_version = publisher.shared.lock().version;
+
+        MinWatch {
+            shared: Arc::clone(&publisher.shared),
+            seen_version,
+        }
+    }
+
+    /// Called after every mutation: bumps the queue's [`version`](Self::version) and tells the
+    /// receivers about the current minimum, if it changed since last time.
+    pub(crate) fn publish_min(&mut self) {
+        self.bump_version();
+        if let Some(publisher) = &self.min_publisher {
+            let minimum = self
+                .first_unheld_node()
+                .map(|i| (self.maps.key_at(i).unwrap().0, &self.values[i.0]));
+            publisher.publish(minimum);
+        }
+    }
+}
+
+#[cfg(test)]
+mod watch_tests {
+    use crate::MinIndexedPriorityQueue;
+    use std::thread;
+
+    #[test]
+    fn min_watch_should_only_report_changes_of_the_top_entry() {
+        let mut values = vec![5, 3, 8];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        let mut watch = ipq.min_watch();
+        assert!(!watch.has_changed());
+
+        ipq.update(2, 9);
+        ipq.delete(0);
+        assert!(!watch.has_changed());
+
+        ipq.decrease(2, 1);
+        assert_eq!(watch.latest(), Some((2, 1)));
+        ipq.update(2, 1);
+        assert!(!watch.has_changed());
+
+        ipq.drain(1..=2);
+        assert!(watch.has_changed());
+        assert_eq!(watch.latest(), None);
+    }
+
+    #[test]
+    fn every_receiver_should_track_what_it_has_seen() {
+        let mut values = vec![2, 1];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        let mut first = ipq.min_watch();
+        ipq.push(0);
+
+        let mut second = ipq.min_watch();
+        assert!(first.has_changed());
+        assert!(!second.has_changed());
+        assert_eq!(first.latest(), Some((2, 0)));
+        assert!(!first.has_changed());
+
+        ipq.decrease(1, -1);
+        assert!(second.clone().has_changed());
+        assert_eq!(second.latest(), Some((1, -1)));
+        assert_eq!(first.latest(), Some((1, -1)));
+    }
+
+    #[test]
+    fn changed_should_wake_up_on_publication_and_on_drop() {
+        let mut values = vec![4, 2];
+        let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+        let mut watch = ipq.min_watch();
+
+        let waiter = thread::spawn(move || {
+            let mut minimums = Vec::new();
+            while watch.changed() {
+                minimums.push(watch.latest());
+            }
+            assert!(watch.is_closed());
+            minimums
+        });
+
+        ipq.push(1);
+        drop(ipq);
+
+        assert_eq!(waiter.join().unwrap(), vec![Some((2, 1))]);
+    }
+}
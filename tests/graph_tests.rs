@@ -0,0 +1,127 @@
+use indexed_priority_queue::graph::{dijkstra, prim_mst};
+
+/// Deterministic xorshift generator, so that failures can be replayed.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, bound: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound
+    }
+}
+
+fn bellman_ford(graph: &[Vec<(usize, u64)>], source: usize) -> Vec<Option<u64>> {
+    let mut distances = vec![None; graph.len()];
+    distances[source] = Some(0);
+    for _ in 0..graph.len() {
+        for (node, edges) in graph.iter().enumerate() {
+            let distance = match distances[node] {
+                Some(distance) => distance,
+                None => continue,
+            };
+            for &(next, weight) in edges {
+                let candidate: u64 = distance + weight;
+                if distances[next].map_or(true, |known| candidate < known) {
+                    distances[next] = Some(candidate);
+                }
+            }
+        }
+    }
+
+    distances
+}
+
+/// Total weight and edge count of a minimum spanning forest, by Kruskal's algorithm.
+fn kruskal(graph: &[Vec<(usize, u64)>]) -> (u64, usize) {
+    fn root(parents: &mut [usize], mut node: usize) -> usize {
+        while parents[node] != node {
+            parents[node] = parents[parents[node]];
+            node = parents[node];
+        }
+        node
+    }
+
+    let mut edges = graph
+        .iter()
+        .enumerate()
+        .flat_map(|(node, edges)| {
+            edges
+                .iter()
+                .map(move |&(next, weight)| (weight, node, next))
+        })
+        .collect::<Vec<_>>();
+    edges.sort_unstable();
+    let mut parents = (0..graph.len()).collect::<Vec<usize>>();
+    let (mut total, mut count) = (0, 0);
+    for (weight, a, b) in edges {
+        let (a, b) = (root(&mut parents, a), root(&mut parents, b));
+        if a != b {
+            parents[a] = b;
+            total += weight;
+            count += 1;
+        }
+    }
+
+    (total, count)
+}
+
+#[test]
+fn dijkstra_should_agree_with_bellman_ford_on_random_graphs() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for round in 0..60 {
+        let nodes = 1 + rng.below(120) as usize;
+        let graph = (0..nodes)
+            .map(|_| {
+                let degree = rng.below(6);
+                (0..degree)
+                    .map(|_| (rng.below(nodes as u64) as usize, rng.below(1 + round)))
+                    .collect()
+            })
+            .collect::<Vec<Vec<(usize, u64)>>>();
+        let source = rng.below(nodes as u64) as usize;
+
+        let paths = dijkstra(&graph, source);
+        assert_eq!(paths.distances, bellman_ford(&graph, source));
+        for path in (0..nodes).filter_map(|target| paths.path_to(target)) {
+            let target = path[path.len() - 1];
+            let length = path
+                .windows(2)
+                .map(|hop| {
+                    graph[hop[0]]
+                        .iter()
+                        .filter(|e| e.0 == hop[1])
+                        .map(|e| e.1)
+                        .min()
+                })
+                .sum::<Option<u64>>();
+            assert_eq!((path[0], length), (source, paths.distances[target]));
+        }
+    }
+}
+
+#[test]
+fn prim_mst_should_weigh_as_much_as_kruskal_on_random_graphs() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..60 {
+        let nodes = 1 + rng.below(100) as usize;
+        let mut graph = vec![Vec::new(); nodes];
+        for _ in 0..rng.below(4 * nodes as u64) {
+            let (a, b) = (
+                rng.below(nodes as u64) as usize,
+                rng.below(nodes as u64) as usize,
+            );
+            let weight = rng.below(50);
+            graph[a].push((b, weight));
+            graph[b].push((a, weight));
+        }
+
+        let forest = prim_mst(&graph);
+        let total = forest.iter().map(|&(_, _, weight)| weight).sum::<u64>();
+        assert_eq!((total, forest.len()), kruskal(&graph));
+        for &(parent, node, weight) in &forest {
+            assert!(graph[parent].contains(&(node, weight)));
+        }
+    }
+}
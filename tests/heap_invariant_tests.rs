@@ -0,0 +1,28 @@
+use indexed_priority_queue::MinIndexedPriorityQueue;
+
+fn poll_all(ipq: &mut MinIndexedPriorityQueue<i32>) -> Vec<i32> {
+    std::iter::from_fn(|| ipq.poll_min_value()).collect()
+}
+
+#[test]
+fn heap_construction_should_order_every_subtree() {
+    let mut values = vec![9, 8, 8, 6, 1, 7, 2, 2, 2, 3, 4, 0];
+    let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+    // A large value left above smaller children would surface too late here.
+    assert_eq!(poll_all(&mut ipq), [0, 1, 2, 2, 2, 3, 4, 6, 7, 8, 8, 9]);
+}
+
+#[test]
+fn delete_then_insert_should_keep_keys_and_order_consistent() {
+    let mut values = vec![9, 8, 7, 6, 5, 1, 2, 2, 2, 3, 4, 0];
+    let mut ipq = MinIndexedPriorityQueue::from(&mut values);
+
+    assert_eq!(ipq.delete(3), Some(6));
+    assert_eq!(ipq.delete(0), Some(9));
+    assert_eq!(ipq.try_insert(3, -1), Ok(()));
+
+    assert_eq!(ipq.value_of(3), Some(-1));
+    assert_eq!(ipq.peek_min_value(), Some(-1));
+    assert_eq!(poll_all(&mut ipq), [-1, 0, 1, 2, 2, 2, 3, 4, 5, 7, 8]);
+}
@@ -0,0 +1,78 @@
+use indexed_priority_queue::cache::PriorityCache;
+use std::collections::HashMap;
+
+/// Naive LRU model: every entry remembers when it was last touched, its expiry and its value.
+#[derive(Default)]
+struct Model {
+    entries: HashMap<u8, (u64, Option<u64>, u32)>,
+    clock: u64,
+}
+
+impl Model {
+    fn least_recent(&self) -> Option<u8> {
+        self.entries
+            .iter()
+            .min_by_key(|(_, (touched, _, _))| *touched)
+            .map(|(&key, _)| key)
+    }
+}
+
+#[test]
+fn cache_should_match_a_naive_lru_model_under_churn() {
+    let capacity = 8;
+    let mut cache = PriorityCache::new(capacity);
+    let mut model = Model::default();
+    let mut state = 7u64;
+
+    for step in 0..5_000u64 {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let key = (state >> 40) as u8 % 16;
+        let value = (state >> 20) as u32;
+
+        match (state >> 60) % 4 {
+            0 | 1 => {
+                let expires_at = ((state >> 59) % 2 == 0).then(|| step + (state >> 8) % 64);
+                let pushed_out = match expires_at {
+                    Some(expires_at) => cache.insert_with_ttl(key, value, expires_at),
+                    None => cache.insert(key, value),
+                };
+
+                let expected = match model.entries.remove(&key) {
+                    Some((_, _, previous)) => Some((key, previous)),
+                    None if model.entries.len() == capacity => {
+                        let evicted = model.least_recent().unwrap();
+                        Some((evicted, model.entries.remove(&evicted).unwrap().2))
+                    }
+                    None => None,
+                };
+                model.clock += 1;
+                model.entries.insert(key, (model.clock, expires_at, value));
+                assert_eq!(pushed_out, expected, "step {}", step);
+            }
+            2 => {
+                let expected = model.entries.get(&key).map(|entry| entry.2);
+                if expected.is_some() {
+                    model.clock += 1;
+                    model.entries.get_mut(&key).unwrap().0 = model.clock;
+                }
+                assert_eq!(cache.get(&key).copied(), expected, "step {}", step);
+            }
+            _ => {
+                let mut expired = cache.purge_expired(step);
+                expired.sort_unstable();
+                let mut expected = model
+                    .entries
+                    .iter()
+                    .filter(|(_, (_, expires_at, _))| matches!(expires_at, Some(t) if *t <= step))
+                    .map(|(&key, &(_, _, value))| (key, value))
+                    .collect::<Vec<(u8, u32)>>();
+                expected.sort_unstable();
+                expected.iter().for_each(|(key, _)| {
+                    model.entries.remove(key);
+                });
+                assert_eq!(expired, expected, "step {}", step);
+            }
+        }
+        assert_eq!(cache.len(), model.entries.len());
+    }
+}
@@ -1,5 +1,8 @@
-use indexed_priority_queue::ipq::{IndexedBinaryHeap, IndexedPriorityQueue};
-use indexed_priority_queue::MinIndexedPriorityQueue;
+#![cfg(any(feature = "panic-on-misuse", not(feature = "deny-panics")))]
+
+use indexed_priority_queue::ipq::IndexedPriorityQueue;
+use indexed_priority_queue::tuning::MapLayout;
+use indexed_priority_queue::{MinIndexedPriorityQueue, OwnedMinIpq};
 
 #[test]
 fn test_insertion_and_polling_until_emptiness() {
@@ -36,3 +39,32 @@ fn test_decrease_and_polling_until_emptiness() {
     assert_eq!(ipq.poll_min_value(), None);
     assert!(ipq.is_empty());
 }
+
+/// Uses nothing but the trait, as downstream code generic over the queue would.
+fn exercise_trait_surface<Q: IndexedPriorityQueue<i32>>(mut ipq: Q) {
+    ipq.push(5);
+    ipq.push(3);
+    ipq.append(&mut vec![8, 1]);
+    ipq.insert(9, 4);
+
+    assert_eq!(ipq.peek_min_key_index(), Some(3));
+    assert_eq!(ipq.peek_min_value(), Some(1));
+    assert_eq!(ipq.update(0, 6), 5);
+    ipq.increase(3, 7);
+    ipq.decrease(2, 2);
+    assert_eq!(ipq.value_of(3), Some(7));
+    assert_eq!(ipq.delete(4), None);
+    assert_eq!(ipq.delete(9), Some(4));
+    assert_eq!(ipq.drain(0..2).collect::<Vec<i32>>(), vec![6, 3]);
+    assert!(ipq.contains(2));
+    assert_eq!(ipq.poll_min_key_index(), Some(2));
+    assert_eq!(ipq.poll_min_value(), Some(7));
+    assert_eq!(ipq.poll_min_value(), None);
+    assert_eq!(ipq.peek_min_key_index(), None);
+}
+
+#[test]
+fn every_map_layout_should_implement_the_same_trait_surface() {
+    exercise_trait_surface(OwnedMinIpq::new());
+    exercise_trait_surface(OwnedMinIpq::new().with_map_layout(MapLayout::Interleaved));
+}
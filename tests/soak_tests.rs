@@ -0,0 +1,18 @@
+use indexed_priority_queue::soak::{soak, SoakConfig};
+
+#[test]
+#[ignore = "runs tens of millions of operations; use `cargo test --release --test soak_tests -- --ignored`"]
+fn queue_should_survive_tens_of_millions_of_random_operations() {
+    let config = SoakConfig {
+        operations: 30_000_000,
+        key_space: 1 << 16,
+        sample_every: 1_000_000,
+        seed: 0x5eed,
+    };
+    let mut peak_size = 0;
+
+    let outcome = soak(&config, |sample| peak_size = peak_size.max(sample.size));
+
+    assert_eq!(outcome, Ok(()));
+    assert!(peak_size > config.key_space / 4);
+}
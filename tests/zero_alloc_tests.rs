@@ -0,0 +1,59 @@
+use indexed_priority_queue::slice::{MappingScratch, MinSliceIpq};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local! {
+    // Per thread, so that the test harness allocating on its own threads is not counted.
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Relaxes a ring of `distances.len()` nodes from node `0`, one frame's worth of pathfinding.
+fn shortest_ring_distances(distances: &mut [u32], scratch: &mut MappingScratch) -> u32 {
+    let n = distances.len();
+    distances.fill(u32::MAX);
+    let mut frontier = MinSliceIpq::from_slice_in(distances, scratch);
+    frontier.try_decrease(0, 0).unwrap();
+
+    let mut farthest = 0;
+    while let Some((node, distance)) = frontier.poll_min() {
+        farthest = distance;
+        for (neighbour, weight) in [((node + 1) % n, 3), ((node + n - 1) % n, 5)] {
+            if frontier.contains(neighbour) {
+                frontier.try_decrease(neighbour, distance + weight).unwrap();
+            }
+        }
+    }
+
+    farthest
+}
+
+#[test]
+fn slice_ipq_should_not_allocate_once_its_scratch_is_warm() {
+    let mut scratch = MappingScratch::new();
+    let mut distances = vec![0; 1000];
+    let warm_up = shortest_ring_distances(&mut distances, &mut scratch);
+
+    let before = ALLOCATIONS.with(Cell::get);
+    for _frame in 0..100 {
+        assert_eq!(shortest_ring_distances(&mut distances, &mut scratch), warm_up);
+        assert_eq!(shortest_ring_distances(&mut distances[..600], &mut scratch), 1125);
+    }
+
+    assert_eq!(ALLOCATIONS.with(Cell::get), before);
+}